@@ -0,0 +1,148 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{Data, DeriveInput, Fields, Member, Path, Type};
+
+// Generated code refers to the runtime crate by its absolute path
+fn hash_mod() -> TokenStream {
+    quote! { ::cpp_types::msvc::hash }
+}
+
+/// How the field hashes of a struct are combined, picked with #[cpp_hash(...)]
+pub enum HashStrategy {
+    /// #[cpp_hash(fnv)] (default): _Fnv1a_append_value over each field in order
+    Fnv,
+    /// #[cpp_hash(hash_combine)]: boost::hash_combine over std::hash of each field
+    HashCombine,
+    /// #[cpp_hash(with = "path::to::function")]: fn(&Self) -> u64
+    With(Path)
+}
+
+impl HashStrategy {
+    fn from_attributes(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut strategy = None;
+        for attr in attrs.iter().filter(|a| a.path().is_ident("cpp_hash")) {
+            attr.parse_nested_meta(|meta| {
+                let new = if meta.path.is_ident("fnv") {
+                    HashStrategy::Fnv
+                } else if meta.path.is_ident("hash_combine") {
+                    HashStrategy::HashCombine
+                } else if meta.path.is_ident("with") {
+                    let path = meta.value()?.parse::<syn::LitStr>()?;
+                    HashStrategy::With(path.parse::<Path>()?)
+                } else {
+                    return Err(meta.error("Expected one of `fnv`, `hash_combine` or `with = \"path\"`"));
+                };
+                if strategy.is_some() {
+                    return Err(meta.error("Only one hash strategy can be specified"));
+                }
+                strategy = Some(new);
+                Ok(())
+            })?;
+        }
+        Ok(strategy.unwrap_or(HashStrategy::Fnv))
+    }
+}
+
+// Fieldless enums are hashed by their discriminant, so they need a primitive representation
+fn enum_repr(input: &DeriveInput) -> syn::Result<Type> {
+    const INTEGERS: [&str; 10] = ["u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize"];
+    let mut repr = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if INTEGERS.iter().any(|i| meta.path.is_ident(i)) {
+                repr = Some(meta.path.clone());
+            }
+            Ok(())
+        })?;
+    }
+    match repr {
+        Some(r) => Ok(Type::Verbatim(r.into_token_stream())),
+        None => Err(syn::Error::new(Span::call_site(), "CppHash on enums requires an integer #[repr(...)]"))
+    }
+}
+
+fn struct_members(fields: &Fields) -> (Vec<Member>, Vec<Type>) {
+    fields.iter().enumerate().map(|(i, f)| {
+        let member = match &f.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(i.into())
+        };
+        (member, f.ty.clone())
+    }).unzip()
+}
+
+pub fn derive_cpp_hash(input: TokenStream) -> TokenStream {
+    let input = match syn::parse2::<DeriveInput>(input) {
+        Ok(i) => i, Err(e) => return e.to_compile_error()
+    };
+    match derive_cpp_hash_inner(&input) {
+        Ok(t) => t, Err(e) => e.to_compile_error()
+    }
+}
+
+fn derive_cpp_hash_inner(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let hash = hash_mod();
+    let name = &input.ident;
+    let strategy = HashStrategy::from_attributes(&input.attrs)?;
+    let mut generics = input.generics.clone();
+    let (members, types) = match &input.data {
+        Data::Struct(s) => struct_members(&s.fields),
+        Data::Enum(e) => {
+            if e.variants.iter().any(|v| !v.fields.is_empty()) {
+                return Err(syn::Error::new(Span::call_site(), "CppHash can only be derived for fieldless enums"));
+            }
+            if let HashStrategy::HashCombine = strategy {
+                return Err(syn::Error::new(Span::call_site(), "hash_combine requires a struct"));
+            }
+            (vec![], vec![])
+        },
+        Data::Union(_) => return Err(syn::Error::new(Span::call_site(), "CppHash can't be derived for unions"))
+    };
+    let where_clause = generics.make_where_clause();
+    let append = match (&strategy, &input.data) {
+        (HashStrategy::Fnv, Data::Enum(_)) => {
+            let repr = enum_repr(input)?;
+            Some(quote! {
+                let discriminant = unsafe { *(self as *const Self as *const #repr) };
+                #hash::FnvAppend::fnv1a_append(&discriminant, val)
+            })
+        },
+        (HashStrategy::Fnv, _) => {
+            for ty in &types {
+                where_clause.predicates.push(syn::parse_quote! { #ty: #hash::FnvAppend });
+            }
+            Some(quote! {
+                #( let val = #hash::FnvAppend::fnv1a_append(&self.#members, val); )*
+                val
+            })
+        },
+        (HashStrategy::HashCombine, _) => {
+            for ty in &types {
+                where_clause.predicates.push(syn::parse_quote! { #ty: #hash::CppHash });
+            }
+            None
+        },
+        (HashStrategy::With(_), _) => None
+    };
+    let body = match &strategy {
+        HashStrategy::Fnv => quote! { #hash::FnvAppend::fnv1a_append(self, #hash::FNV_OFFSET_BASIS) },
+        HashStrategy::HashCombine => quote! {
+            let seed = 0u64;
+            #( let seed = #hash::hash_combine(seed, #hash::CppHash::cpp_hash(&self.#members)); )*
+            seed
+        },
+        HashStrategy::With(path) => quote! { #path(self) }
+    };
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let append_impl = append.map(|append| quote! {
+        impl #impl_generics #hash::FnvAppend for #name #ty_generics #where_clause {
+            fn fnv1a_append(&self, val: u64) -> u64 { #append }
+        }
+    });
+    Ok(quote! {
+        #append_impl
+        impl #impl_generics #hash::CppHash for #name #ty_generics #where_clause {
+            fn cpp_hash(&self) -> u64 { #body }
+        }
+    })
+}
@@ -1 +1,2 @@
+pub mod hash;
 pub mod param;
\ No newline at end of file
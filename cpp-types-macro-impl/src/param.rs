@@ -25,6 +25,7 @@ fn make_struct_name(index: usize) -> String {
     format!("With{}Param{}", index, plural)
 }
 
+#[allow(clippy::needless_range_loop)]
 pub fn create_function_param_structs(input: TokenStream) -> TokenStream {
     let params = match FunctionParam::parse.parse2(input) {
         Ok(p) => p, Err(e) => return e.to_compile_error()
@@ -3,4 +3,12 @@ use proc_macro::TokenStream;
 #[proc_macro]
 pub fn create_function_param_structs(input: TokenStream) -> TokenStream {
     cpp_types_macro_impl::param::create_function_param_structs(input.into()).into()
-}
\ No newline at end of file
+}
+
+/// Implements `cpp_types::msvc::hash::CppHash` to match a C++ std::hash specialization.
+/// The strategy is picked with `#[cpp_hash(fnv)]` (default), `#[cpp_hash(hash_combine)]` or
+/// `#[cpp_hash(with = "path::to::function")]`.
+#[proc_macro_derive(CppHash, attributes(cpp_hash))]
+pub fn derive_cpp_hash(input: TokenStream) -> TokenStream {
+    cpp_types_macro_impl::hash::derive_cpp_hash(input.into()).into()
+}
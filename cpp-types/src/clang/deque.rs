@@ -0,0 +1,561 @@
+//! Rust reimplementation of libc++'s std::deque implementation
+
+#![allow(dead_code)]
+use allocator_api2::alloc::{ Allocator, Global };
+use std::{
+    alloc::Layout,
+    fmt::Display,
+    marker::PhantomData,
+    ops::{ Index, IndexMut },
+    ptr::NonNull
+};
+
+// https://github.com/llvm/llvm-project/blob/main/libcxx/include/deque
+// https://github.com/llvm/llvm-project/blob/main/libcxx/include/__split_buffer
+
+/// A buffer with spare capacity at both ends, which libc++ uses for the deque's block map
+#[repr(C)]
+struct SplitBuffer<T, A = Global> // __split_buffer
+where T: Copy,
+      A: Allocator + Clone
+{
+    first: *mut T, // __first_
+    begin: *mut T, // __begin_
+    end: *mut T, // __end_
+    end_cap: *mut T, // __end_cap_
+    _allocator: A
+}
+
+impl<T, A> SplitBuffer<T, A>
+where T: Copy,
+      A: Allocator + Clone
+{
+    fn new_in(alloc: A) -> Self {
+        Self {
+            first: std::ptr::null_mut(),
+            begin: std::ptr::null_mut(),
+            end: std::ptr::null_mut(),
+            end_cap: std::ptr::null_mut(),
+            _allocator: alloc
+        }
+    }
+    // __split_buffer(__cap, __start, __a)
+    fn with_capacity_in(cap: usize, start: usize, alloc: A) -> Self {
+        let first = match cap {
+            0 => std::ptr::null_mut(),
+            _ => alloc.allocate(Layout::array::<T>(cap).unwrap()).unwrap().as_ptr() as *mut T
+        };
+        unsafe {
+            Self {
+                first,
+                begin: first.add(start),
+                end: first.add(start),
+                end_cap: first.add(cap),
+                _allocator: alloc
+            }
+        }
+    }
+    fn len(&self) -> usize { unsafe { self.end.offset_from(self.begin) as usize } }
+    fn is_empty(&self) -> bool { self.begin == self.end }
+    fn capacity(&self) -> usize { unsafe { self.end_cap.offset_from(self.first) as usize } }
+    fn front_spare(&self) -> usize { unsafe { self.begin.offset_from(self.first) as usize } }
+    fn back_spare(&self) -> usize { unsafe { self.end_cap.offset_from(self.end) as usize } }
+    fn as_slice(&self) -> &[T] {
+        if self.begin.is_null() { return &[] }
+        unsafe { std::slice::from_raw_parts(self.begin, self.len()) }
+    }
+    fn front(&self) -> T { unsafe { *self.begin } }
+    fn back(&self) -> T { unsafe { *self.end.sub(1) } }
+
+    // Moves the contents into a new buffer with start spare elements at the front
+    fn reallocate(&mut self, cap: usize, start: usize) {
+        let mut new = Self::with_capacity_in(cap, start, self._allocator.clone());
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.begin, new.begin, self.len());
+            new.end = new.begin.add(self.len());
+        }
+        std::mem::swap(self, &mut new);
+    }
+    fn push_back(&mut self, value: T) {
+        if self.end == self.end_cap {
+            if self.begin > self.first {
+                // slide the contents halfway into the front spare
+                let shift = self.front_spare().div_ceil(2);
+                let len = self.len();
+                unsafe {
+                    std::ptr::copy(self.begin, self.begin.sub(shift), len);
+                    self.begin = self.begin.sub(shift);
+                    self.end = self.begin.add(len);
+                }
+            } else {
+                let cap = (2 * self.capacity()).max(1);
+                self.reallocate(cap, cap / 4);
+            }
+        }
+        unsafe {
+            std::ptr::write(self.end, value);
+            self.end = self.end.add(1);
+        }
+    }
+    fn push_front(&mut self, value: T) {
+        if self.begin == self.first {
+            if self.end < self.end_cap {
+                // slide the contents halfway into the back spare
+                let shift = self.back_spare().div_ceil(2);
+                let len = self.len();
+                unsafe {
+                    std::ptr::copy(self.begin, self.begin.add(shift), len);
+                    self.begin = self.begin.add(shift);
+                    self.end = self.end.add(shift);
+                }
+            } else {
+                let cap = (2 * self.capacity()).max(1);
+                self.reallocate(cap, cap.div_ceil(4));
+            }
+        }
+        unsafe {
+            self.begin = self.begin.sub(1);
+            std::ptr::write(self.begin, value);
+        }
+    }
+    fn pop_front(&mut self) -> T {
+        let value = self.front();
+        self.begin = unsafe { self.begin.add(1) };
+        value
+    }
+    fn pop_back(&mut self) -> T {
+        self.end = unsafe { self.end.sub(1) };
+        unsafe { *self.end }
+    }
+}
+
+impl<T, A> Drop for SplitBuffer<T, A>
+where T: Copy,
+      A: Allocator + Clone
+{
+    fn drop(&mut self) {
+        if self.first.is_null() { return }
+        unsafe {
+            self._allocator.deallocate(NonNull::new_unchecked(self.first as *mut u8), Layout::array::<T>(self.capacity()).unwrap());
+        }
+    }
+}
+
+/// A double ended queue made of fixed size blocks. Elements are indexed from start, an offset
+/// into the first block of the map, so pushing to the front of an empty deque starts in the
+/// middle of a block. Spare blocks are moved between the ends of the map before new ones are
+/// allocated.
+#[repr(C)]
+pub struct Deque<T, A = Global> // std::deque
+where A: Allocator + Clone
+{
+    map: SplitBuffer<*mut T, A>, // __map_
+    start: usize, // __start_
+    size: usize, // __size_
+    _allocator: A
+}
+
+impl<T> Deque<T, Global> {
+    pub fn new() -> Self { Self::new_in(Global) }
+    pub fn from_vec(vec: Vec<T>) -> Self { Self::from_vec_in(vec, Global) }
+}
+
+impl<T> Default for Deque<T, Global> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T, A> Deque<T, A>
+where A: Allocator + Clone
+{
+    /// Number of elements per block (__block_size), which is 4096 bytes of elements with at
+    /// least 16 elements per block
+    pub const BLOCK_SIZE: usize = match std::mem::size_of::<T>() {
+        0 => 4096,
+        s if s < 256 => 4096 / s,
+        _ => 16
+    };
+
+    pub fn new_in(alloc: A) -> Self {
+        assert!(std::mem::size_of::<A>() == 0, "Allocator must be zero-sized!");
+        Self {
+            map: SplitBuffer::new_in(alloc.clone()),
+            start: 0,
+            size: 0,
+            _allocator: alloc
+        }
+    }
+    pub fn from_vec_in(vec: Vec<T>, alloc: A) -> Self {
+        let mut new = Self::new_in(alloc);
+        for v in vec { new.push_back(v); }
+        new
+    }
+    pub fn len(&self) -> usize { self.size }
+    pub fn is_empty(&self) -> bool { self.size == 0 }
+    /// Returns the number of blocks in the map
+    pub fn block_count(&self) -> usize { self.map.len() }
+
+    fn block_layout() -> Layout { Layout::array::<T>(Self::BLOCK_SIZE).unwrap() }
+    fn allocate_block(&self) -> *mut T {
+        self._allocator.allocate(Self::block_layout()).unwrap().as_ptr() as *mut T
+    }
+    unsafe fn deallocate_block(&self, block: *mut T) {
+        self._allocator.deallocate(NonNull::new_unchecked(block as *mut u8), Self::block_layout());
+    }
+
+    // __capacity: one slot is always left unused, so that end() is inside of the map
+    fn capacity(&self) -> usize {
+        match self.map.is_empty() {
+            true => 0,
+            false => self.map.len() * Self::BLOCK_SIZE - 1
+        }
+    }
+    // __front_spare
+    fn front_spare(&self) -> usize { self.start }
+    // __back_spare
+    fn back_spare(&self) -> usize { self.capacity() - (self.start + self.size) }
+
+    // __add_back_capacity
+    fn add_back_capacity(&mut self) {
+        if self.front_spare() >= Self::BLOCK_SIZE {
+            // rotate a spare block from the front to the back
+            self.start -= Self::BLOCK_SIZE;
+            let block = self.map.pop_front();
+            self.map.push_back(block);
+        } else if self.map.len() < self.map.capacity() {
+            let block = self.allocate_block();
+            if self.map.back_spare() > 0 {
+                self.map.push_back(block);
+            } else {
+                self.map.push_front(block);
+                let block = self.map.pop_front();
+                self.map.push_back(block);
+            }
+        } else {
+            let mut map = SplitBuffer::with_capacity_in((2 * self.map.capacity()).max(1), self.map.len(), self._allocator.clone());
+            map.push_back(self.allocate_block());
+            for block in self.map.as_slice().iter().rev() { map.push_front(*block); }
+            std::mem::swap(&mut self.map, &mut map);
+        }
+    }
+    // __add_front_capacity
+    fn add_front_capacity(&mut self) {
+        if self.back_spare() >= Self::BLOCK_SIZE {
+            // rotate a spare block from the back to the front
+            self.start += Self::BLOCK_SIZE;
+            let block = self.map.pop_back();
+            self.map.push_front(block);
+            return
+        }
+        if self.map.len() < self.map.capacity() {
+            let block = self.allocate_block();
+            if self.map.front_spare() > 0 {
+                self.map.push_front(block);
+            } else {
+                self.map.push_back(block);
+                let block = self.map.pop_back();
+                self.map.push_front(block);
+            }
+        } else {
+            let mut map = SplitBuffer::with_capacity_in((2 * self.map.capacity()).max(1), 0, self._allocator.clone());
+            map.push_back(self.allocate_block());
+            for block in self.map.as_slice() { map.push_back(*block); }
+            std::mem::swap(&mut self.map, &mut map);
+        }
+        // a lone block starts from its middle, so both ends have room
+        self.start = match self.map.len() {
+            1 => Self::BLOCK_SIZE / 2,
+            _ => self.start + Self::BLOCK_SIZE
+        };
+    }
+    // __maybe_remove_front_spare
+    fn maybe_remove_front_spare(&mut self) {
+        if self.front_spare() / Self::BLOCK_SIZE >= 2 {
+            let block = self.map.pop_front();
+            unsafe { self.deallocate_block(block) };
+            self.start -= Self::BLOCK_SIZE;
+        }
+    }
+    // __maybe_remove_back_spare
+    fn maybe_remove_back_spare(&mut self) {
+        if self.back_spare() / Self::BLOCK_SIZE >= 2 {
+            let block = self.map.pop_back();
+            unsafe { self.deallocate_block(block) };
+        }
+    }
+
+    // Pointer to the element at an offset from the start of the first block
+    fn slot(&self, offset: usize) -> *mut T {
+        unsafe { (*self.map.begin.add(offset / Self::BLOCK_SIZE)).add(offset % Self::BLOCK_SIZE) }
+    }
+    fn element(&self, index: usize) -> *mut T { self.slot(self.start + index) }
+
+    pub fn push_back(&mut self, value: T) {
+        if self.back_spare() == 0 { self.add_back_capacity(); }
+        unsafe { std::ptr::write(self.slot(self.start + self.size), value) };
+        self.size += 1;
+    }
+    pub fn push_front(&mut self, value: T) {
+        if self.front_spare() == 0 { self.add_front_capacity(); }
+        unsafe { std::ptr::write(self.slot(self.start - 1), value) };
+        self.start -= 1;
+        self.size += 1;
+    }
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() { return None }
+        let value = unsafe { std::ptr::read(self.element(self.size - 1)) };
+        self.size -= 1;
+        self.maybe_remove_back_spare();
+        Some(value)
+    }
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() { return None }
+        let value = unsafe { std::ptr::read(self.element(0)) };
+        self.size -= 1;
+        self.start += 1;
+        self.maybe_remove_front_spare();
+        Some(value)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.size { return None }
+        Some(unsafe { &*self.element(index) })
+    }
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.size { return None }
+        Some(unsafe { &mut *self.element(index) })
+    }
+
+    pub fn iter(&self) -> DequeIterator<'_, T, A> { self.into_iter() }
+    pub fn iter_mut(&mut self) -> DequeIteratorMut<'_, T, A> { self.into_iter() }
+}
+
+// C++ API
+impl<T, A> Deque<T, A>
+where A: Allocator + Clone
+{
+    /// Checks if the container has no elements
+    pub fn empty(&self) -> bool { self.is_empty() }
+    /// Returns the number of elements in the container
+    pub fn size(&self) -> usize { self.size }
+    /// Erases all elements from the container. Up to two blocks are kept, and start is reset to
+    /// the middle of them.
+    pub fn clear(&mut self) {
+        for i in 0..self.size {
+            unsafe { std::ptr::drop_in_place(self.element(i)) };
+        }
+        self.size = 0;
+        while self.map.len() > 2 {
+            let block = self.map.pop_front();
+            unsafe { self.deallocate_block(block) };
+        }
+        match self.map.len() {
+            1 => self.start = Self::BLOCK_SIZE / 2,
+            2 => self.start = Self::BLOCK_SIZE,
+            _ => ()
+        }
+    }
+    /// Returns a reference to the element at specified location index, with bounds checking.
+    pub fn at(&self, index: usize) -> Option<&T> { self.get(index) }
+    /// Returns a mutable reference to the element at specified location index, with bounds checking.
+    pub fn at_mut(&mut self, index: usize) -> Option<&mut T> { self.get_mut(index) }
+    /// Returns a reference to the first element, or None if the container is empty
+    pub fn front(&self) -> Option<&T> { self.get(0) }
+    /// Returns a mutable reference to the first element, or None if the container is empty
+    pub fn front_mut(&mut self) -> Option<&mut T> { self.get_mut(0) }
+    /// Returns a reference to the last element, or None if the container is empty
+    pub fn back(&self) -> Option<&T> { self.get(self.size.wrapping_sub(1)) }
+    /// Returns a mutable reference to the last element, or None if the container is empty
+    pub fn back_mut(&mut self) -> Option<&mut T> { self.get_mut(self.size.wrapping_sub(1)) }
+}
+
+impl<T, A> Drop for Deque<T, A>
+where A: Allocator + Clone
+{
+    fn drop(&mut self) {
+        self.clear();
+        for block in self.map.as_slice() {
+            unsafe { self.deallocate_block(*block) };
+        }
+    }
+}
+
+impl<T, A> Index<usize> for Deque<T, A>
+where A: Allocator + Clone
+{
+    type Output = T;
+    fn index(&self, index: usize) -> &Self::Output {
+        assert!(index < self.size, "Tried to index a deque out of bounds");
+        unsafe { &*self.element(index) }
+    }
+}
+
+impl<T, A> IndexMut<usize> for Deque<T, A>
+where A: Allocator + Clone
+{
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        assert!(index < self.size, "Tried to index a deque out of bounds");
+        unsafe { &mut *self.element(index) }
+    }
+}
+
+impl<T, A> Display for Deque<T, A>
+where T: Display,
+      A: Allocator + Clone
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values: Vec<std::string::String> = self.iter().map(|v| format!("{}", v)).collect();
+        write!(f, "Deque [ {} ]", values.join(", "))
+    }
+}
+
+pub struct DequeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    deque: &'a Deque<T, A>,
+    front: usize,
+    back: usize
+}
+
+impl<'a, T, A> Iterator for DequeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back { return None }
+        self.front += 1;
+        Some(unsafe { &*self.deque.element(self.front - 1) })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.back - self.front, Some(self.back - self.front)) }
+}
+
+impl<T, A> DoubleEndedIterator for DequeIterator<'_, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back { return None }
+        self.back -= 1;
+        Some(unsafe { &*self.deque.element(self.back) })
+    }
+}
+
+impl<T, A> ExactSizeIterator for DequeIterator<'_, T, A>
+where A: Allocator + Clone {}
+
+pub struct DequeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    deque: *mut Deque<T, A>,
+    front: usize,
+    back: usize,
+    _marker: PhantomData<&'a mut T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for DequeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back { return None }
+        self.front += 1;
+        Some(unsafe { &mut *(*self.deque).element(self.front - 1) })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.back - self.front, Some(self.back - self.front)) }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for DequeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back { return None }
+        self.back -= 1;
+        Some(unsafe { &mut *(*self.deque).element(self.back) })
+    }
+}
+
+impl<'a, T: 'a, A: 'a> ExactSizeIterator for DequeIteratorMut<'a, T, A>
+where A: Allocator + Clone {}
+
+impl<'a, T, A> IntoIterator for &'a Deque<T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    type IntoIter = DequeIterator<'a, T, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        DequeIterator { deque: self, front: 0, back: self.size }
+    }
+}
+
+impl<'a, T, A> IntoIterator for &'a mut Deque<T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    type IntoIter = DequeIteratorMut<'a, T, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        DequeIteratorMut { front: 0, back: self.size, deque: self, _marker: PhantomData }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::generic::deque::check_against_vec_deque;
+    use super::Deque;
+    use std::error::Error;
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    #[test]
+    pub fn deque_layout() -> TestReturn {
+        assert!(std::mem::size_of::<Deque<u32>>() == 48, "Deque should be the map split buffer, start and size");
+        let sizes = [Deque::<u8>::BLOCK_SIZE, Deque::<u64>::BLOCK_SIZE];
+        assert!(sizes == [4096, 512], "Blocks should hold 4096 bytes, got {:?}", sizes);
+        let sizes = [Deque::<[u8; 255]>::BLOCK_SIZE, Deque::<[u8; 1000]>::BLOCK_SIZE];
+        assert!(sizes == [16, 16], "Blocks should hold at least 16 elements, got {:?}", sizes);
+        let deque: Deque<u64> = Deque::new();
+        assert!(deque.map.first.is_null() && deque.start == 0, "Empty deque shouldn't allocate");
+        Ok(())
+    }
+
+    #[test]
+    pub fn deque_front_spare() -> TestReturn {
+        let mut deque = Deque::new();
+        deque.push_front(0u64);
+        assert!(deque.start == 255 && deque.block_count() == 1, "First front push should start in the middle of a block");
+        for i in 1..256 { deque.push_front(i); }
+        assert!(deque.start == 0 && deque.block_count() == 1, "Front of the block should be full");
+        deque.push_front(256);
+        assert!(deque.start == 511 && deque.block_count() == 2, "New front block should be added");
+        assert!(deque.iter().copied().eq((0..257).rev()), "Values should be in reverse order");
+        assert!(deque[0] == 256 && deque[256] == 0, "Indices should count from start");
+
+        // pushing to the back leaves a spare slot so end() stays inside the map
+        let mut deque = Deque::new();
+        for i in 0..511u64 { deque.push_back(i); }
+        assert!(deque.start == 0 && deque.block_count() == 1, "Back pushes should start from the front of the block");
+        deque.push_back(511);
+        assert!(deque.block_count() == 2 && deque.map.capacity() == 2, "Last slot should need a new block");
+        for i in 512..1536 { deque.push_back(i); }
+        assert!(deque.block_count() == 4 && deque.map.capacity() == 4, "Map should double");
+
+        // popping keeps one spare block at each end, then rotates it when the other end grows
+        for _ in 0..1024 { deque.pop_front(); }
+        assert!(deque.start == 512 && deque.block_count() == 3, "Front blocks past the first spare should be freed");
+        let spare = unsafe { *deque.map.begin };
+        for i in 1536..2048 { deque.push_back(i); }
+        assert!(deque.block_count() == 3 && unsafe { *deque.map.end.sub(1) } == spare, "Spare front block should move to the back");
+        assert!(deque.start == 0 && deque.iter().copied().eq(1024..2048), "Values should follow the rotated block");
+
+        deque.clear();
+        assert!(deque.is_empty() && deque.block_count() == 2 && deque.start == 512, "Clear should keep two blocks");
+        deque.push_back(1);
+        deque.push_front(0);
+        assert!(deque.iter().copied().eq(0..2), "Cleared deque should be usable from both ends");
+        Ok(())
+    }
+
+    #[test]
+    pub fn deque_matches_vec_deque() -> TestReturn {
+        check_against_vec_deque!(Deque::new(), 5000, |v| [v; 80], |v: &mut [u32; 80]| v[0] += 1,
+            |deque: &Deque<[u32; 80]>| assert!(deque.start + deque.size <= deque.capacity(), "Elements should fit inside the map"));
+        Ok(())
+    }
+}
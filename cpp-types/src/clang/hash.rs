@@ -0,0 +1,257 @@
+pub use crate::generic::hash::HasherInit;
+use crate::generic::hash::HashedValue;
+use std::hash::{ Hash, Hasher };
+
+// https://github.com/llvm/llvm-project/blob/main/libcxx/include/__functional/hash.h
+
+/// libc++'s std::hash. Integral types (including bool, char and enums) hash to their own
+/// value, with signed types being sign extended to size_t. Byte sequences (strings) are hashed
+/// with std::__murmur2_or_cityhash. Like std::hash, this hashes a single value, so `str` and
+/// `String` hash their bytes, while keys that write more than once (tuples, slices and most
+/// derived Hash impls) need their own hasher and trip a debug assertion.
+pub struct StdHash {
+    hash: u64,
+    value: HashedValue
+}
+
+crate::generic::hash::single_value_hasher!(StdHash, murmur2_or_cityhash);
+
+/// std::__murmur2_or_cityhash for the target's size_t
+#[cfg(target_pointer_width = "64")]
+pub fn murmur2_or_cityhash(bytes: &[u8]) -> usize { cityhash64(bytes) as usize }
+
+/// std::__murmur2_or_cityhash for the target's size_t
+#[cfg(target_pointer_width = "32")]
+pub fn murmur2_or_cityhash(bytes: &[u8]) -> usize { murmur2_32(bytes) as usize }
+
+/// std::__murmur2_or_cityhash<size_t, 32>
+pub fn murmur2_32(bytes: &[u8]) -> u32 {
+    const M: u32 = 0x5bd1e995;
+    const R: u32 = 24;
+    let mut h = bytes.len() as u32;
+    let mut chunks = bytes.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes(chunk.try_into().unwrap());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+    let rem = chunks.remainder();
+    if !rem.is_empty() {
+        for (i, b) in rem.iter().enumerate() {
+            h ^= (*b as u32) << (i * 8);
+        }
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^ h >> 15
+}
+
+const K0: u64 = 0xc3a5c85c97cb3127;
+const K1: u64 = 0xb492b66fbe98f273;
+const K2: u64 = 0x9ae16a3b2f90404f;
+const K3: u64 = 0xc949d7c7509e6557;
+
+fn load64(s: &[u8], off: usize) -> u64 { u64::from_le_bytes(s[off..off + 8].try_into().unwrap()) }
+fn load32(s: &[u8], off: usize) -> u32 { u32::from_le_bytes(s[off..off + 4].try_into().unwrap()) }
+fn shift_mix(v: u64) -> u64 { v ^ v >> 47 }
+
+fn hash_len_16(u: u64, v: u64) -> u64 {
+    const MUL: u64 = 0x9ddfea08eb382d69;
+    let mut a = (u ^ v).wrapping_mul(MUL);
+    a ^= a >> 47;
+    let mut b = (v ^ a).wrapping_mul(MUL);
+    b ^= b >> 47;
+    b.wrapping_mul(MUL)
+}
+
+fn hash_len_0_to_16(s: &[u8]) -> u64 {
+    let len = s.len();
+    if len > 8 {
+        let a = load64(s, 0);
+        let b = load64(s, len - 8);
+        return hash_len_16(a, b.wrapping_add(len as u64).rotate_right(len as u32)) ^ b;
+    }
+    if len >= 4 {
+        let a = load32(s, 0);
+        let b = load32(s, len - 4);
+        // without _LIBCPP_ABI_FIX_CITYHASH_IMPLEMENTATION the shift is done in 32 bits
+        return hash_len_16((len as u64).wrapping_add(a.wrapping_shl(3) as u64), b as u64);
+    }
+    if len > 0 {
+        let y = s[0] as u32 + ((s[len >> 1] as u32) << 8);
+        let z = len as u32 + ((s[len - 1] as u32) << 2);
+        return shift_mix((y as u64).wrapping_mul(K2) ^ (z as u64).wrapping_mul(K3)).wrapping_mul(K2);
+    }
+    K2
+}
+
+fn hash_len_17_to_32(s: &[u8]) -> u64 {
+    let len = s.len();
+    let a = load64(s, 0).wrapping_mul(K1);
+    let b = load64(s, 8);
+    let c = load64(s, len - 8).wrapping_mul(K2);
+    let d = load64(s, len - 16).wrapping_mul(K0);
+    hash_len_16(
+        a.wrapping_sub(b).rotate_right(43).wrapping_add(c.rotate_right(30)).wrapping_add(d),
+        a.wrapping_add((b ^ K3).rotate_right(20)).wrapping_sub(c).wrapping_add(len as u64)
+    )
+}
+
+fn weak_hash_len_32_with_seeds(s: &[u8], off: usize, mut a: u64, mut b: u64) -> (u64, u64) {
+    let w = load64(s, off);
+    let x = load64(s, off + 8);
+    let y = load64(s, off + 16);
+    let z = load64(s, off + 24);
+    a = a.wrapping_add(w);
+    b = b.wrapping_add(a).wrapping_add(z).rotate_right(21);
+    let c = a;
+    a = a.wrapping_add(x).wrapping_add(y);
+    b = b.wrapping_add(a.rotate_right(44));
+    (a.wrapping_add(z), b.wrapping_add(c))
+}
+
+fn hash_len_33_to_64(s: &[u8]) -> u64 {
+    let len = s.len();
+    let mut z = load64(s, 24);
+    let mut a = load64(s, 0).wrapping_add((len as u64).wrapping_add(load64(s, len - 16)).wrapping_mul(K0));
+    let mut b = a.wrapping_add(z).rotate_right(52);
+    let mut c = a.rotate_right(37);
+    a = a.wrapping_add(load64(s, 8));
+    c = c.wrapping_add(a.rotate_right(7));
+    a = a.wrapping_add(load64(s, 16));
+    let vf = a.wrapping_add(z);
+    let vs = b.wrapping_add(a.rotate_right(31)).wrapping_add(c);
+    a = load64(s, 16).wrapping_add(load64(s, len - 32));
+    z = z.wrapping_add(load64(s, len - 8));
+    b = a.wrapping_add(z).rotate_right(52);
+    c = a.rotate_right(37);
+    a = a.wrapping_add(load64(s, len - 24));
+    c = c.wrapping_add(a.rotate_right(7));
+    a = a.wrapping_add(load64(s, len - 16));
+    let wf = a.wrapping_add(z);
+    let ws = b.wrapping_add(a.rotate_right(31)).wrapping_add(c);
+    let r = shift_mix(vf.wrapping_add(ws).wrapping_mul(K2).wrapping_add(wf.wrapping_add(vs).wrapping_mul(K0)));
+    shift_mix(r.wrapping_mul(K0).wrapping_add(vs)).wrapping_mul(K2)
+}
+
+/// std::__murmur2_or_cityhash<size_t, 64> (CityHash64)
+pub fn cityhash64(s: &[u8]) -> u64 {
+    let len = s.len();
+    if len <= 16 {
+        return hash_len_0_to_16(s);
+    } else if len <= 32 {
+        return hash_len_17_to_32(s);
+    } else if len <= 64 {
+        return hash_len_33_to_64(s);
+    }
+    // for strings over 64 bytes the end is hashed first, then 56 bytes of state are kept
+    // while looping over 64 byte chunks
+    let mut x = load64(s, len - 40);
+    let mut y = load64(s, len - 16).wrapping_add(load64(s, len - 56));
+    let mut z = hash_len_16(load64(s, len - 48).wrapping_add(len as u64), load64(s, len - 24));
+    let mut v = weak_hash_len_32_with_seeds(s, len - 64, len as u64, z);
+    let mut w = weak_hash_len_32_with_seeds(s, len - 32, y.wrapping_add(K1), x);
+    x = x.wrapping_mul(K1).wrapping_add(load64(s, 0));
+    let mut off = 0;
+    let mut remaining = (len - 1) & !63;
+    while remaining != 0 {
+        x = x.wrapping_add(y).wrapping_add(v.0).wrapping_add(load64(s, off + 8)).rotate_right(37).wrapping_mul(K1);
+        y = y.wrapping_add(v.1).wrapping_add(load64(s, off + 48)).rotate_right(42).wrapping_mul(K1);
+        x ^= w.1;
+        y = y.wrapping_add(v.0).wrapping_add(load64(s, off + 40));
+        z = z.wrapping_add(w.0).rotate_right(33).wrapping_mul(K1);
+        v = weak_hash_len_32_with_seeds(s, off, v.1.wrapping_mul(K1), x.wrapping_add(w.0));
+        w = weak_hash_len_32_with_seeds(s, off + 32, z.wrapping_add(w.1), y.wrapping_add(load64(s, off + 16)));
+        std::mem::swap(&mut z, &mut x);
+        off += 64;
+        remaining -= 64;
+    }
+    hash_len_16(
+        hash_len_16(v.0, w.0).wrapping_add(shift_mix(y).wrapping_mul(K1)).wrapping_add(z),
+        hash_len_16(v.1, w.1).wrapping_add(x)
+    )
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{ cityhash64, murmur2_32, murmur2_or_cityhash, HasherInit, StdHash };
+    use std::{
+        error::Error,
+        hash::Hasher
+    };
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    const LOREM: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam";
+
+    // (input, __murmur2_or_cityhash<size_t, 64>, __murmur2_or_cityhash<size_t, 32>)
+    // covers every length class: 0, 1-3, 4-8, 9-16, 17-32, 33-64 and over 64 bytes
+    fn expected() -> [(&'static str, u64, u32); 13] {
+        [
+            ("", 0x9ae16a3b2f90404f, 0x00000000),
+            ("A", 0x2f27714d501639e9, 0x25f31569),
+            ("Door", 0x36718cb35123a258, 0x6d7ef7ea),
+            ("Chest", 0x9b0afd20d637cbf8, 0x6ea3a389),
+            ("Player", 0x1f7479e79536cd79, 0x0306e114),
+            ("Lorem ip", 0xec08d099de42d273, 0x43264eb1),
+            ("Lorem ips", 0x1e855e1f5782ee2e, 0x18a430c8),
+            ("Lorem ipsum dolo", 0x70e5a1a633af9633, 0x9c1f773f),
+            ("Lorem ipsum dolor", 0x08a040a77956f362, 0x76f42984),
+            (&LOREM[..32], 0xef398cc1220b9b1d, 0x1b66d6d9),
+            (&LOREM[..33], 0x0ef0059dab23b3e7, 0x36746eb5),
+            (&LOREM[..64], 0x32f1db564a74b512, 0x8313d526),
+            (LOREM, 0x73f86b943746db5f, 0x8ca6f425)
+        ]
+    }
+
+    #[test]
+    pub fn hash_integers() -> TestReturn {
+        assert!(StdHash::get_hash(&5u32) == 5, "std::hash<unsigned int> should be identity");
+        assert!(StdHash::get_hash(&0x1234_5678_9abc_def0u64) == 0x1234_5678_9abc_def0, "std::hash<unsigned long> should be identity");
+        assert!(StdHash::get_hash(&-1i32) == u64::MAX, "std::hash<int> should sign extend");
+        assert!(StdHash::get_hash(&-2i8) == u64::MAX - 1, "std::hash<signed char> should sign extend");
+        assert!(StdHash::get_hash(&true) == 1, "std::hash<bool> should be identity");
+        assert!(StdHash::get_hash(&'A') == 0x41, "std::hash<char32_t> should be identity");
+        Ok(())
+    }
+
+    #[test]
+    pub fn hash_bytes_64_bit() -> TestReturn {
+        for (s, hash, _) in expected() {
+            let res = cityhash64(s.as_bytes());
+            assert!(res == hash, "Hash of {:?} should be {:#x} instead of {:#x}", s, hash, res);
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn hash_bytes_32_bit() -> TestReturn {
+        for (s, _, hash) in expected() {
+            let res = murmur2_32(s.as_bytes());
+            assert!(res == hash, "Hash of {:?} should be {:#x} instead of {:#x}", s, hash, res);
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    pub fn hasher_writes_bytes() -> TestReturn {
+        let mut hasher = StdHash::new();
+        hasher.write(b"Player");
+        assert!(hasher.finish() == 0x1f7479e79536cd79, "Hasher::write should use __murmur2_or_cityhash");
+        Ok(())
+    }
+
+    #[test]
+    pub fn hash_rust_strings() -> TestReturn {
+        let expected = murmur2_or_cityhash(b"Player") as u64;
+        assert!(StdHash::get_hash(&"Player") == expected, "str should hash its bytes with __murmur2_or_cityhash");
+        assert!(StdHash::get_hash(&String::from("Player")) == expected, "String should hash its bytes with __murmur2_or_cityhash");
+        assert!(StdHash::get_hash(&"Door") != expected, "Different strings should hash differently");
+        Ok(())
+    }
+}
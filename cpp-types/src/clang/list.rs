@@ -0,0 +1,720 @@
+//! Rust reimplementation of libc++'s std::list implementation
+
+#![allow(dead_code, unused_imports)]
+use allocator_api2::{
+    alloc::{ Allocator, Global },
+    boxed::Box as ABox
+};
+use std::{
+    alloc::Layout,
+    fmt::Display,
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ops::{ Index, IndexMut },
+    ptr::NonNull
+};
+
+// https://github.com/llvm/llvm-project/blob/main/libcxx/include/list
+
+#[repr(C)]
+pub struct ListNodeBase<T, A = Global> // __list_node_base
+where A: Allocator + Clone
+{
+    prev: *mut ListNode<T, A>, // __prev_
+    next: *mut ListNode<T, A>, // __next_
+}
+
+#[repr(C)]
+pub struct ListNode<T, A = Global> // __list_node
+where A: Allocator + Clone
+{
+    prev: *mut Self, // __prev_
+    next: *mut Self, // __next_
+    val: ManuallyDrop<T>, // __value_
+    _allocator: A
+}
+
+#[repr(C)]
+pub struct List<T, A = Global> // std::list (__list_imp)
+where A: Allocator + Clone
+{
+    // Unlike MSVC, the sentinel node is embedded inside of the list, so an empty list points to
+    // itself. Like the libstdc++ string, a list created on the stack must call setup_pointers()
+    // once it's been moved to it's final location.
+    end: ListNodeBase<T, A>, // __end_
+    size: usize, // __size_
+    _allocator: A
+}
+
+impl<T> List<T, Global> {
+    /// # Safety
+    ///
+    /// The list is unusable until [`List::setup_pointers`] is called at its final address.
+    pub unsafe fn new() -> Self { Self::new_in(Global) }
+    pub fn new_standalone() -> ABox<Self, Global> { Self::new_standalone_in(Global) }
+    pub fn from_vec_standalone(vec: Vec<T>) -> ABox<Self, Global> { Self::from_vec_standalone_in(vec, Global) }
+}
+
+impl<T, A> List<T, A>
+where A: Allocator + Clone
+{
+    /// # Safety
+    ///
+    /// The list is unusable until [`List::setup_pointers`] is called at its final address.
+    pub unsafe fn new_in(alloc: A) -> Self {
+        assert!(std::mem::size_of::<A>() == 0, "Allocator must be zero-sized!");
+        Self {
+            // these should point to self.end, but we can't do that until the list has settled
+            end: ListNodeBase { prev: std::ptr::null_mut(), next: std::ptr::null_mut() },
+            size: 0,
+            _allocator: alloc
+        }
+    }
+
+    pub fn new_standalone_in(alloc: A) -> ABox<Self, A> {
+        let mut new = ABox::new_in(unsafe { Self::new_in(alloc.clone()) }, alloc);
+        unsafe { new.setup_pointers() };
+        new
+    }
+
+    pub fn from_vec_standalone_in(vec: Vec<T>, alloc: A) -> ABox<Self, A> {
+        let mut new = Self::new_standalone_in(alloc);
+        for el in vec { new.push(el) }
+        new
+    }
+
+    /// Point the sentinel node (and the nodes on either end of the list) to the list's current
+    /// address. This must be called after the list is moved.
+    ///
+    /// # Safety
+    ///
+    /// Any nodes in the list must still be valid, and the list must not be moved afterwards.
+    pub unsafe fn setup_pointers(&mut self) {
+        let nil = self.get_nil();
+        if self.size == 0 {
+            self.end.prev = nil;
+            self.end.next = nil;
+        } else {
+            (*self.end.next).prev = nil;
+            (*self.end.prev).next = nil;
+        }
+    }
+
+    pub fn len(&self) -> usize { self.size }
+    pub fn is_empty(&self) -> bool { self.size == 0 }
+
+    pub fn get_nil(&mut self) -> *mut ListNode<T, A> { &raw mut self.end as *mut ListNode<T, A> }
+
+    pub fn first(&self) -> Option<&ListNode<T, A>> {
+        if self.is_empty() { return None }
+        Some(unsafe { &*self.end.next })
+    }
+    pub fn first_mut(&mut self) -> Option<&mut ListNode<T, A>> {
+        if self.is_empty() { return None }
+        Some(unsafe { &mut *self.end.next })
+    }
+    pub fn first_ptr(&mut self) -> Option<NonNull<ListNode<T, A>>> {
+        self.first_mut().map(|f| unsafe { NonNull::new_unchecked(&raw mut *f) })
+    }
+    pub fn last(&self) -> Option<&ListNode<T, A>> {
+        if self.is_empty() { return None }
+        Some(unsafe { &*self.end.prev })
+    }
+    pub fn last_mut(&mut self) -> Option<&mut ListNode<T, A>> {
+        if self.is_empty() { return None }
+        Some(unsafe { &mut *self.end.prev })
+    }
+    pub fn last_ptr(&mut self) -> Option<NonNull<ListNode<T, A>>> {
+        self.last_mut().map(|f| unsafe { NonNull::new_unchecked(&raw mut *f) })
+    }
+
+    pub fn push(&mut self, val: T) {
+        let nil = self.get_nil();
+        unsafe { self.insert_at_unchecked(nil, val) }
+    }
+
+    pub fn push_front(&mut self, val: T) {
+        let first = self.end.next;
+        unsafe { self.insert_at_unchecked(first, val) }
+    }
+
+    // std::list::insert inserts the value before the index position
+    // additionally, if index is set to self.len(), call push instead
+    pub fn insert(&mut self, after_index: usize, val: T) {
+        assert!(after_index <= self.len(), "Tried to insert value out of bounds");
+        if self.len() == after_index { self.push(val) }
+        else {
+            let node = &raw mut *self.get_unchecked_mut(after_index);
+            unsafe { self.insert_at_unchecked(node, val) }
+        }
+    }
+
+    pub(crate) unsafe fn insert_at_unchecked(&mut self, node: *mut ListNode<T, A>, val: T) {
+        let new = ListNode::new(val, self._allocator.clone());
+        self.link_before(node, new);
+        self.size += 1;
+    }
+
+    pub(crate) unsafe fn insert_after_unchecked(&mut self, insert_after: *mut ListNode<T, A>, val: T) {
+        let new = ListNode::new(val, self._allocator.clone());
+        self.link_before((*insert_after).next, new);
+        self.size += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() { return None }
+        let last = self.end.prev;
+        Some(unsafe { self.remove_unchecked(last) })
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() { return None }
+        let first = self.end.next;
+        Some(unsafe { self.remove_unchecked(first) })
+    }
+
+    pub fn get(&self, index: usize) -> Option<&ListNode<T, A>> {
+        if index >= self.len() { return None; }
+        Some(self.get_unchecked(index))
+    }
+
+    pub fn get_unchecked(&self, index: usize) -> &ListNode<T, A> {
+        unsafe { &*self.get_node_ptr(index) }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut ListNode<T, A>> {
+        if index >= self.len() { return None; }
+        Some(self.get_unchecked_mut(index))
+    }
+
+    pub fn get_unchecked_mut(&mut self, index: usize) -> &mut ListNode<T, A> {
+        unsafe { &mut *self.get_node_ptr(index) }
+    }
+
+    // walk from whichever end of the list is closest
+    fn get_node_ptr(&self, index: usize) -> *mut ListNode<T, A> {
+        unsafe {
+            match index <= (self.len() - 1) / 2 {
+                true => {
+                    let mut curr = self.end.next;
+                    for _ in 0..index { curr = (*curr).next; }
+                    curr
+                },
+                false => {
+                    let mut curr = self.end.prev;
+                    for _ in 0..(self.len() - 1 - index) { curr = (*curr).prev; }
+                    curr
+                }
+            }
+        }
+    }
+
+    pub fn iter(&self) -> ListIterator<'_, T, A> { self.into_iter() }
+    pub fn iter_mut(&mut self) -> ListIteratorMut<'_, T, A> { self.into_iter() }
+
+    pub fn index_of_by_predicate<F>(&self, cb: F) -> Option<usize>
+    where F: Fn(&T) -> bool
+    {
+        for (i, v) in self.iter().enumerate() {
+            if cb(v) { return Some(i)}
+        }
+        None
+    }
+
+    pub fn contains_by_predicate<F>(&self, cb: F) -> bool
+    where F: Fn(&T) -> bool { self.find_by_predicate(cb).is_some() }
+
+    pub fn find_by_predicate<F>(&self, cb: F) -> Option<&T>
+    where F: Fn(&T) -> bool
+    {
+        for v in self { if cb(v) { return Some(v) } }
+        None
+    }
+
+    pub fn clear(&mut self) {
+        let nil = self.get_nil();
+        let mut node = self.end.next;
+        while !std::ptr::eq(node, nil) {
+            unsafe {
+                let next = (&*node).next;
+                ManuallyDrop::drop(&mut (&mut *node).val);
+                ListNode::delete(node, self._allocator.clone());
+                node = next;
+            }
+        }
+        self.size = 0;
+        self.end.prev = nil;
+        self.end.next = nil;
+    }
+}
+
+impl<T, A> List<T, A>
+where A: Allocator + Clone
+{
+    // __link_nodes
+    pub(crate) unsafe fn link_before(&mut self, before: *mut ListNode<T, A>, new: *mut ListNode<T, A>) {
+        // `before` may be the base-only sentinel, so only its link fields are touched
+        (*new).prev = (*before).prev;
+        (*new).next = before;
+        (*(*before).prev).next = new;
+        (*before).prev = new;
+    }
+
+    // __unlink_nodes
+    pub(crate) unsafe fn unlink(&mut self, p_element: *mut ListNode<T, A>) {
+        // remove curr from the linked list without deallocating it
+        (*(*p_element).prev).next = (*p_element).next;
+        (*(*p_element).next).prev = (*p_element).prev;
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(self.len() > index, "Tried to remove value out of bounds");
+        let el_ptr = self.get_node_ptr(index);
+        unsafe { self.remove_unchecked(el_ptr) }
+    }
+
+    pub fn remove_checked(&mut self, index: usize) -> Option<T> {
+        if self.len() > index { Some(self.remove(index)) }
+        else { None }
+    }
+
+    pub(crate) unsafe fn remove_unchecked(&mut self, p_element: *mut ListNode<T, A>) -> T {
+        self.size -= 1;
+        self.unlink(p_element);
+        let val_out = ManuallyDrop::take(&mut (&mut *p_element).val);
+        ListNode::delete(p_element, self._allocator.clone());
+        val_out
+    }
+
+    pub(crate) fn move_node(&mut self, old: usize, new: usize) -> bool {
+        if self.len() <= old || self.len() < new { return false; }
+        unsafe {
+            let val = self.get_node_ptr(old);
+            let attach_to = match self.len() == new {
+                true => self.get_nil(),
+                false => self.get_node_ptr(new)
+            };
+            if std::ptr::eq(val, attach_to) { return true; }
+            self.unlink(val);
+            self.link_before(attach_to, val);
+        }
+        true
+    }
+    pub(crate) unsafe fn move_node_before_unchecked(&mut self, target: &mut ListNode<T, A>, attach: &mut ListNode<T, A>) {
+        self.unlink(target);
+        self.link_before(attach, target);
+    }
+    pub(crate) unsafe fn move_node_after_unchecked(&mut self, target: &mut ListNode<T, A>, attach: &mut ListNode<T, A>) {
+        self.unlink(target);
+        self.link_before(attach.next, target);
+    }
+}
+
+impl<T, A> List<T, A>
+where T: PartialEq,
+      A: Allocator + Clone
+{
+    pub fn index_of(&self, val: T) -> Option<usize> {
+        for (i, v) in self.iter().enumerate() {
+            if *v == val { return Some(i)}
+        }
+        None
+    }
+
+    pub fn find(&self, val: T) -> Option<&T> {
+        for v in self {
+            if *v == val { return Some(v)}
+        }
+        None
+    }
+
+    pub fn find_mut(&mut self, val: T) -> Option<&mut T> {
+        for v in self {
+            if *v == val { return Some(v)}
+        }
+        None
+    }
+
+    pub fn contains(&self, val: T) -> bool { self.find(val).is_some() }
+}
+
+impl<T, A> ListNode<T, A>
+where A: Allocator + Clone
+{
+    fn new(val: T, alloc: A) -> *mut Self {
+        let new = alloc.allocate(Layout::new::<Self>()).unwrap().as_ptr() as *mut Self;
+        unsafe {
+            std::ptr::write(new, Self {
+                prev: std::ptr::null_mut(),
+                next: std::ptr::null_mut(),
+                val: ManuallyDrop::new(val),
+                _allocator: alloc
+            });
+        }
+        new
+    }
+
+    // SAFETY: The node's value must be dropped or moved out beforehand
+    unsafe fn delete(node: *mut Self, alloc: A) {
+        alloc.deallocate(NonNull::new_unchecked(node as *mut u8), Layout::new::<Self>());
+    }
+
+    pub fn next(&self, nil: *mut Self) -> Option<&Self> {
+        match std::ptr::eq(self.next, nil) {
+            true => None,
+            false => Some(unsafe { &*self.next })
+        }
+    }
+    pub fn next_mut(&mut self, nil: *mut Self) -> Option<&mut Self> {
+        match std::ptr::eq(self.next, nil) {
+            true => None,
+            false => Some(unsafe { &mut *self.next })
+        }
+    }
+    pub fn next_ptr(&self, nil: *mut Self) -> Option<NonNull<Self>> {
+        match std::ptr::eq(self.next, nil) {
+            true => None,
+            false => Some(unsafe { NonNull::new_unchecked(self.next) })
+        }
+    }
+    pub fn prev(&self, nil: *mut Self) -> Option<&Self> {
+        match std::ptr::eq(self.prev, nil) {
+            true => None,
+            false => Some(unsafe { &*self.prev })
+        }
+    }
+    pub fn prev_mut(&mut self, nil: *mut Self) -> Option<&mut Self> {
+        match std::ptr::eq(self.prev, nil) {
+            true => None,
+            false => Some(unsafe { &mut *self.prev })
+        }
+    }
+    pub fn prev_ptr(&self, nil: *mut Self) -> Option<NonNull<Self>> {
+        match std::ptr::eq(self.prev, nil) {
+            true => None,
+            false => Some(unsafe { NonNull::new_unchecked(self.prev) })
+        }
+    }
+    pub fn value(&self) -> &T { &self.val }
+    pub fn value_mut(&mut self) -> &mut T { &mut self.val }
+}
+
+impl<'a, T, A> IntoIterator for &'a List<T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    type IntoIter = ListIterator<'a, T, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        Self::IntoIter {
+            curr: self.end.next,
+            curr_rev: self.end.prev,
+            remaining: self.len(),
+            _type_marker: PhantomData
+        }
+    }
+}
+
+impl<'a, T, A> IntoIterator for &'a mut List<T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    type IntoIter = ListIteratorMut<'a, T, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        Self::IntoIter {
+            curr: self.end.next,
+            curr_rev: self.end.prev,
+            remaining: self.len(),
+            _type_marker: PhantomData
+        }
+    }
+}
+
+// Since the sentinel node lives inside of the list, iterators keep track of how many nodes are
+// left instead of checking for the nil node
+pub struct ListIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    curr: *mut ListNode<T, A>,
+    curr_rev: *mut ListNode<T, A>,
+    remaining: usize,
+    _type_marker: PhantomData<&'a T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for ListIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 { return None }
+        self.remaining -= 1;
+        let v = unsafe { &*self.curr };
+        self.curr = v.next;
+        Some(v.value())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.remaining, Some(self.remaining)) }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for ListIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 { return None }
+        self.remaining -= 1;
+        let v = unsafe { &*self.curr_rev };
+        self.curr_rev = v.prev;
+        Some(v.value())
+    }
+}
+
+pub struct ListIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    curr: *mut ListNode<T, A>,
+    curr_rev: *mut ListNode<T, A>,
+    remaining: usize,
+    _type_marker: PhantomData<&'a mut T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for ListIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 { return None }
+        self.remaining -= 1;
+        let v = unsafe { &mut *self.curr };
+        self.curr = v.next;
+        Some(v.value_mut())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.remaining, Some(self.remaining)) }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for ListIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 { return None }
+        self.remaining -= 1;
+        let v = unsafe { &mut *self.curr_rev };
+        self.curr_rev = v.prev;
+        Some(v.value_mut())
+    }
+}
+
+impl<T, A> Drop for List<T, A>
+where A: Allocator + Clone
+{
+    fn drop(&mut self) {
+        let mut node = self.end.next;
+        for _ in 0..self.size {
+            unsafe {
+                let next = (&*node).next;
+                ManuallyDrop::drop(&mut (&mut *node).val);
+                ListNode::delete(node, self._allocator.clone());
+                node = next;
+            }
+        }
+    }
+}
+
+impl<T, A> Index<usize> for List<T, A>
+where A: Allocator + Clone
+{
+    type Output = T;
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get_unchecked(index).value()
+    }
+}
+
+impl<T, A> IndexMut<usize> for List<T, A>
+where A: Allocator + Clone
+{
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_unchecked_mut(index).value_mut()
+    }
+}
+
+impl<T, A> Display for List<T, A>
+where T: Display,
+      A: Allocator + Clone
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut buf = String::from("List [ ");
+        for (i, v) in self.iter().enumerate() {
+            buf.push_str(&format!("{}", v));
+            if i < self.len() - 1 { buf.push_str(", ") }
+        }
+        buf.push_str(" ]");
+        write!(f, "{}", &buf)
+    }
+}
+
+impl<T, A> From<List<T, A>> for Vec<T>
+where A: Allocator + Clone,
+{
+    fn from(mut value: List<T, A>) -> Self {
+        // the list was moved to get here
+        unsafe { value.setup_pointers() };
+        let mut vec: Vec<T> = Vec::with_capacity(value.len());
+        while let Some(v) = value.pop_front() { vec.push(v) }
+        vec
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use allocator_api2::{
+        alloc::{ Allocator, Global },
+        boxed::Box as ABox
+    };
+    use super::{ List, ListNode };
+    use std::{
+        fmt::Debug,
+        error::Error,
+        rc::Rc
+    };
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+impl<T, A> List<T, A>
+where T: Debug + PartialEq,
+      A: Allocator + Clone
+{
+    pub(crate) fn check_list_iterator(&self, values: &[T]) {
+        assert!(self.len() == values.len(), "List should have {} items instead of {}", values.len(), self.len());
+        for (i, v) in self.iter().enumerate() {
+            assert!(values[i] == *v,
+                "Index {} should have item {:?} instead of {:?}",
+                i, values[i], *v);
+        }
+        for (i, v) in self.iter().rev().enumerate() {
+            let index = values.len() - 1 - i;
+            assert!(values[index] == *v,
+                "Reverse index {} should have item {:?} instead of {:?}",
+                index, values[index], *v);
+        }
+    }
+}
+
+    #[test]
+    pub fn create_blank_list() -> TestReturn {
+        let list = List::<u32>::new_standalone();
+        assert!(list.is_empty(), "List should be blank");
+        assert!(list.first().is_none(), "First element should be blank");
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_layout() -> TestReturn {
+        assert!(size_of::<List<u32>>() == 0x18, "std::list should be 0x18 bytes");
+        assert!(size_of::<ListNode<u64>>() == 0x18, "Node should be 0x18 bytes");
+        let mut list = List::new_standalone();
+        let nil = list.get_nil() as usize;
+        assert!(nil == &raw const *list as usize, "__end_ should be at the start of the list");
+        let raw = unsafe { std::slice::from_raw_parts(nil as *const usize, 3) };
+        assert!(raw == [nil, nil, 0], "Blank list should point to itself");
+        list.push(10u64);
+        let node = list.first().unwrap();
+        let raw = unsafe { std::slice::from_raw_parts(nil as *const usize, 3) };
+        assert!(raw == [&raw const *node as usize, &raw const *node as usize, 1], "__end_ should point to the only node");
+        let raw_node = unsafe { std::slice::from_raw_parts(&raw const *node as *const usize, 3) };
+        assert!(raw_node == [nil, nil, 10], "Node should contain (__prev_, __next_, __value_)");
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_push_pop() -> TestReturn {
+        let mut list = List::new_standalone();
+        list.push(2);
+        list.push(3);
+        list.push_front(1);
+        list.check_list_iterator(&[1, 2, 3]);
+        assert!(list.pop() == Some(3), "Element should be 3");
+        assert!(list.pop_front() == Some(1), "Element should be 1");
+        assert!(list.pop() == Some(2), "Element should be 2");
+        assert!(list.pop().is_none(), "List should be empty");
+        assert!(list.pop_front().is_none(), "List should be empty");
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_iterator() -> TestReturn {
+        let mut list = List::new_standalone();
+        for i in 5..10 { list.push(i) }
+        for (i, v) in list.iter_mut().enumerate() {
+            *v *= 2;
+            assert!(*v == (i + 5) * 2, "Element {} should have item {} instead of {}", i, (i + 5) * 2, *v);
+        }
+        let mut iter = list.iter();
+        assert!(iter.next() == Some(&10), "First element should be 10");
+        assert!(iter.next_back() == Some(&18), "Last element should be 18");
+        assert!(iter.collect::<Vec<_>>() == [&12, &14, &16], "Iterator should stop when both ends meet");
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_insertion_removal() -> TestReturn {
+        let mut list = List::from_vec_standalone(vec![1, 3, 5, 7, 9]);
+        list.insert(1, 2);
+        list.insert(3, 4);
+        list.insert(7, 10);
+        list.check_list_iterator(&[1, 2, 3, 4, 5, 7, 9, 10]);
+        assert!(list.remove(0) == 1, "The first element removed should be 1");
+        assert!(list.remove(6) == 10, "The last element removed should be 10");
+        assert!(list.remove(2) == 4, "The third element removed should be 4");
+        assert!(list.remove_checked(5).is_none(), "Index 5 is out of bounds");
+        list.check_list_iterator(&[2, 3, 5, 7, 9]);
+        assert!(list.index_of(7) == Some(3), "7 should be the fourth element");
+        assert!(list.contains_by_predicate(|f| *f == 9), "List should contain 9");
+        let out: Vec<i32> = ABox::into_inner(list).into();
+        assert!(out == [2, 3, 5, 7, 9], "Output vec should be the same as the list");
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_move_by_index() -> TestReturn {
+        let mut list = List::new_standalone();
+        for i in 0..10 { list.push(i * 2) }
+        let len = list.len();
+        list.move_node(0, len);
+        list.check_list_iterator(&[2, 4, 6, 8, 10, 12, 14, 16, 18, 0]);
+        list.move_node(9, 0);
+        list.check_list_iterator(&[0, 2, 4, 6, 8, 10, 12, 14, 16, 18]);
+        list.move_node(0, 2);
+        list.check_list_iterator(&[2, 0, 4, 6, 8, 10, 12, 14, 16, 18]);
+        list.move_node(4, 4);
+        list.check_list_iterator(&[2, 0, 4, 6, 8, 10, 12, 14, 16, 18]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_relocate() -> TestReturn {
+        let mut list = unsafe { List::new() };
+        unsafe { list.setup_pointers() };
+        for i in 0..4 { list.push(i) }
+        let mut moved = Box::new(list);
+        unsafe { moved.setup_pointers() };
+        moved.push(4);
+        moved.check_list_iterator(&[0, 1, 2, 3, 4]);
+        moved.clear();
+        assert!(moved.is_empty(), "List should be empty after clearing");
+        assert!(moved.iter().next().is_none(), "Cleared list shouldn't iterate");
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_drops_values() -> TestReturn {
+        let value = Rc::new(0);
+        let mut list = List::new_standalone();
+        list.push(value.clone());
+        list.push(value.clone());
+        list.clear();
+        assert!(Rc::strong_count(&value) == 1, "Clearing should drop every value");
+        list.push(value.clone());
+        list.push_front(value.clone());
+        drop(list);
+        assert!(Rc::strong_count(&value) == 1, "Dropping the list should drop every value");
+        Ok(())
+    }
+}
@@ -95,6 +95,7 @@ where T: CharBehavior + PartialEq,
 }
 
 impl String<u8, Global> {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self { Self::new_using(Global) }
     // pub fn from_str(text: &str) -> Self { Self::from_str_in(text, Global) }
 }
@@ -146,6 +147,7 @@ where T: CharBehavior + PartialEq,
         }
     }
 
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         self._impl.get_size()
     }
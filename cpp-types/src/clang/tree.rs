@@ -0,0 +1,894 @@
+//! Rust reimplementation of libc++'s std::map/std::set implementation (__tree)
+
+#![allow(dead_code, unused_imports)]
+use allocator_api2::{
+    alloc::{ Allocator, Global },
+    boxed::Box as ABox
+};
+pub use crate::generic::tree::{ CompareGreater, CompareLess, MapPair, TreeCompare };
+use std::{
+    alloc::Layout,
+    fmt::Debug,
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ptr::NonNull
+};
+
+// https://github.com/llvm/llvm-project/blob/main/libcxx/include/__tree
+
+#[repr(C)]
+pub struct EndNode<T, A = Global> // __tree_end_node
+where A: Allocator + Clone
+{
+    left: *mut TreeNode<T, A> // __left_
+}
+
+#[repr(C)]
+pub struct TreeNode<T, A = Global> // __tree_node
+where A: Allocator + Clone
+{
+    left: *mut Self, // __left_
+    right: *mut Self, // __right_
+    // For the root node, this points to the tree's end node, which only has a left field. Only
+    // access fields through raw pointers so we never make a reference to the end node as a node.
+    parent: *mut Self, // __parent_
+    is_black: bool, // __is_black_
+    data: ManuallyDrop<T>, // __value_
+    _allocator: A
+}
+
+#[repr(C)]
+pub struct Tree<C, T0, T1, A = Global>
+where C: TreeCompare<T0, T1>,
+      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    // Leftmost node, or the end node if the tree is empty
+    begin_node: *mut TreeNode<T0, A>, // __begin_node_
+    // The end node's left is the root. Since the root (and begin_node for empty trees) points to
+    // it, call setup_pointers() after moving the tree.
+    end_node: EndNode<T0, A>, // __end_node_
+    size: usize, // __size_
+    // value_compare is compressed with the size, so empty comparators like std::less take no space
+    compare: C, // __value_comp_
+    _allocator: A,
+    _key_ty: PhantomData<T1>
+}
+
+impl<C, T0, T1, A> Tree<C, T0, T1, A>
+where C: TreeCompare<T0, T1>,
+      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    /// # Safety
+    ///
+    /// The tree is unusable until [`Tree::setup_pointers`] is called at its final address.
+    pub unsafe fn new_inner(alloc: A) -> Self
+    where C: Default
+    {
+        assert!(std::mem::size_of::<A>() == 0, "Allocator must be zero-sized!");
+        Self {
+            // begin_node should point to the end node, but we can't do that until the tree has
+            // settled. Call setup_pointers() after this
+            begin_node: std::ptr::null_mut(),
+            end_node: EndNode { left: std::ptr::null_mut() },
+            size: 0,
+            compare: C::default(),
+            _allocator: alloc,
+            _key_ty: PhantomData
+        }
+    }
+
+    pub fn new_standalone_inner(alloc: A) -> ABox<Self, A>
+    where C: Default
+    {
+        let mut new = ABox::new_in(unsafe { Self::new_inner(alloc.clone()) }, alloc);
+        unsafe { new.setup_pointers() };
+        new
+    }
+
+    /// Point begin_node (for empty trees) and the root node to the tree's current address. This
+    /// must be called after the tree is moved.
+    ///
+    /// # Safety
+    ///
+    /// Any nodes in the tree must still be valid, and the tree must not be moved afterwards.
+    pub unsafe fn setup_pointers(&mut self) {
+        let end = self.get_end();
+        match self.end_node.left.is_null() {
+            true => self.begin_node = end,
+            false => (*self.end_node.left).parent = end
+        }
+    }
+
+    pub fn len(&self) -> usize { self.size }
+    pub fn is_empty(&self) -> bool { self.size == 0 }
+
+    fn get_end(&self) -> *mut TreeNode<T0, A> { &raw const self.end_node as *mut TreeNode<T0, A> }
+
+    fn get_root(&self) -> *mut TreeNode<T0, A> { self.end_node.left }
+
+    pub fn first(&self) -> Option<&T0> {
+        if self.is_empty() { return None }
+        Some(unsafe { &(*self.begin_node).data })
+    }
+
+    pub fn last(&self) -> Option<&T0> {
+        if self.is_empty() { return None }
+        Some(unsafe { &(*TreeNode::prev(self.get_end())).data })
+    }
+
+    // __find_equal. Returns the parent for the new node and if it goes on the left, or None if
+    // the value already exists
+    unsafe fn find_equal(&self, value: &T0) -> Option<(*mut TreeNode<T0, A>, bool)> {
+        let mut nd = self.get_root();
+        if nd.is_null() { return Some((self.get_end(), true)) }
+        loop {
+            if self.compare.compare_aa(value, &(*nd).data) {
+                if (*nd).left.is_null() { return Some((nd, true)) }
+                nd = (*nd).left;
+            } else if self.compare.compare_aa(&(*nd).data, value) {
+                if (*nd).right.is_null() { return Some((nd, false)) }
+                nd = (*nd).right;
+            } else {
+                return None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, value: T0) -> bool {
+        unsafe {
+            let (parent, left) = match self.find_equal(&value) {
+                Some(v) => v,
+                // this fails if attempting to add a duplicate key
+                None => return false
+            };
+            let node = TreeNode::new(value, self._allocator.clone());
+            self.insert_node_at(parent, left, node);
+        }
+        true
+    }
+
+    // __insert_node_at
+    unsafe fn insert_node_at(&mut self, parent: *mut TreeNode<T0, A>, left: bool, node: *mut TreeNode<T0, A>) {
+        (*node).left = std::ptr::null_mut();
+        (*node).right = std::ptr::null_mut();
+        (*node).parent = parent;
+        match left {
+            true => (*parent).left = node,
+            false => (*parent).right = node
+        };
+        if !(*self.begin_node).left.is_null() {
+            self.begin_node = (*self.begin_node).left;
+        }
+        balance_after_insert(self.get_root(), node);
+        self.size += 1;
+    }
+
+    // __lower_bound: first node that doesn't go before value
+    fn lower_bound_node(&self, value: &T1) -> *mut TreeNode<T0, A> {
+        let mut root = self.get_root();
+        let mut result = self.get_end();
+        while !root.is_null() {
+            let data = unsafe { &(*root).data };
+            if !self.compare.compare_ab(data, value) {
+                result = root;
+                root = unsafe { (*root).left };
+            } else {
+                root = unsafe { (*root).right };
+            }
+        }
+        result
+    }
+
+    // __upper_bound: first node that goes after value
+    fn upper_bound_node(&self, value: &T1) -> *mut TreeNode<T0, A> {
+        let mut root = self.get_root();
+        let mut result = self.get_end();
+        while !root.is_null() {
+            let data = unsafe { &(*root).data };
+            if self.compare.compare_ba(value, data) {
+                result = root;
+                root = unsafe { (*root).left };
+            } else {
+                root = unsafe { (*root).right };
+            }
+        }
+        result
+    }
+
+    // find: lower bound, unless value goes before the node it found
+    fn find_node(&self, value: &T1) -> Option<*mut TreeNode<T0, A>> {
+        let p = self.lower_bound_node(value);
+        if std::ptr::eq(p, self.get_end()) { return None }
+        match unsafe { !self.compare.compare_ba(value, &(*p).data) } {
+            true => Some(p),
+            false => None
+        }
+    }
+
+    pub fn contains(&self, value: T1) -> bool { self.find_node(&value).is_some() }
+
+    pub fn find(&self, value: T1) -> Option<&T0> {
+        self.find_node(&value).map(|n| unsafe { &*(*n).data })
+    }
+
+    pub fn find_mut(&mut self, value: T1) -> Option<&mut T0> {
+        self.find_node(&value).map(|n| unsafe { &mut *(*n).data })
+    }
+
+    /// Returns an iterator starting from the first element that is not ordered before value
+    pub fn lower_bound(&self, value: T1) -> TreeIterator<'_, T0, A> {
+        let start = self.lower_bound_node(&value);
+        self.iter_from(start)
+    }
+
+    /// Returns an iterator starting from the first element that is ordered after value
+    pub fn upper_bound(&self, value: T1) -> TreeIterator<'_, T0, A> {
+        let start = self.upper_bound_node(&value);
+        self.iter_from(start)
+    }
+
+    fn iter_from(&self, start: *mut TreeNode<T0, A>) -> TreeIterator<'_, T0, A> {
+        TreeIterator {
+            front: start,
+            back: self.get_end(),
+            _marker: PhantomData
+        }
+    }
+
+    pub fn iter(&self) -> TreeIterator<'_, T0, A> { self.into_iter() }
+    pub fn iter_mut(&mut self) -> TreeIteratorMut<'_, T0, A> { self.into_iter() }
+
+    // __remove_node_pointer
+    unsafe fn erase_node(&mut self, node: *mut TreeNode<T0, A>) -> T0 {
+        if std::ptr::eq(self.begin_node, node) {
+            self.begin_node = TreeNode::next(node);
+        }
+        self.size -= 1;
+        tree_remove(self.get_root(), node);
+        let value = ManuallyDrop::take(&mut (*node).data);
+        TreeNode::delete(node, self._allocator.clone());
+        value
+    }
+
+    // remove type can be different from storage type (e.g for maps, store as MapPair(K, V),
+    // but find based on K which is PartialEq<MapPair>
+    pub fn remove(&mut self, value: T1) -> bool { self.take(value).is_some() }
+
+    pub fn take(&mut self, value: T1) -> Option<T0> {
+        let node = self.find_node(&value)?;
+        Some(unsafe { self.erase_node(node) })
+    }
+
+    pub fn clear(&mut self) {
+        unsafe { self.destroy(self.get_root()) };
+        self.begin_node = self.get_end();
+        self.end_node.left = std::ptr::null_mut();
+        self.size = 0;
+    }
+
+    // destroy: free a subtree without rebalancing
+    unsafe fn destroy(&mut self, node: *mut TreeNode<T0, A>) {
+        if node.is_null() { return }
+        self.destroy((*node).left);
+        self.destroy((*node).right);
+        ManuallyDrop::drop(&mut (*node).data);
+        TreeNode::delete(node, self._allocator.clone());
+    }
+}
+
+impl<C, T0, T1, A> Drop for Tree<C, T0, T1, A>
+where C: TreeCompare<T0, T1>,
+      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    fn drop(&mut self) {
+        unsafe { self.destroy(self.get_root()) }
+    }
+}
+
+impl<T, A> TreeNode<T, A>
+where A: Allocator + Clone
+{
+    // __construct_node
+    fn new(value: T, alloc: A) -> *mut Self {
+        let new = alloc.allocate(Layout::new::<Self>()).unwrap().as_ptr() as *mut Self;
+        unsafe {
+            std::ptr::write(new, Self {
+                left: std::ptr::null_mut(),
+                right: std::ptr::null_mut(),
+                parent: std::ptr::null_mut(),
+                is_black: false,
+                data: ManuallyDrop::new(value),
+                _allocator: alloc
+            });
+        }
+        new
+    }
+
+    // SAFETY: The node's value must be dropped or moved out beforehand
+    unsafe fn delete(node: *mut Self, alloc: A) {
+        alloc.deallocate(NonNull::new_unchecked(node as *mut u8), Layout::new::<Self>());
+    }
+
+    pub fn value(&self) -> &T { &self.data }
+    pub fn value_mut(&mut self) -> &mut T { &mut self.data }
+
+    // __tree_is_left_child
+    unsafe fn is_left_child(x: *mut Self) -> bool {
+        std::ptr::eq(x, (*(*x).parent).left)
+    }
+
+    // __tree_min
+    unsafe fn minimum(mut x: *mut Self) -> *mut Self {
+        while !(*x).left.is_null() { x = (*x).left; }
+        x
+    }
+
+    // __tree_max
+    unsafe fn maximum(mut x: *mut Self) -> *mut Self {
+        while !(*x).right.is_null() { x = (*x).right; }
+        x
+    }
+
+    // __tree_next_iter
+    unsafe fn next(mut x: *mut Self) -> *mut Self {
+        if !(*x).right.is_null() {
+            return Self::minimum((*x).right);
+        }
+        // the root is the end node's left child, so this always stops there
+        while !Self::is_left_child(x) { x = (*x).parent; }
+        (*x).parent
+    }
+
+    // __tree_prev_iter. Also works for the end node, since it's left is the root
+    unsafe fn prev(x: *mut Self) -> *mut Self {
+        if !(*x).left.is_null() {
+            return Self::maximum((*x).left);
+        }
+        let mut nx = x;
+        while Self::is_left_child(nx) { nx = (*nx).parent; }
+        (*nx).parent
+    }
+}
+
+fn is_black<T, A>(x: *mut TreeNode<T, A>) -> bool
+where A: Allocator + Clone
+{
+    x.is_null() || unsafe { (*x).is_black }
+}
+
+// __tree_left_rotate. The root's parent is the end node, so it doesn't need special handling
+unsafe fn rotate_left<T, A>(x: *mut TreeNode<T, A>)
+where A: Allocator + Clone
+{
+    let y = (*x).right;
+    (*x).right = (*y).left;
+    if !(*x).right.is_null() { (*(*x).right).parent = x; }
+    (*y).parent = (*x).parent;
+    match TreeNode::is_left_child(x) {
+        true => (*(*x).parent).left = y,
+        false => (*(*x).parent).right = y
+    };
+    (*y).left = x;
+    (*x).parent = y;
+}
+
+// __tree_right_rotate
+unsafe fn rotate_right<T, A>(x: *mut TreeNode<T, A>)
+where A: Allocator + Clone
+{
+    let y = (*x).left;
+    (*x).left = (*y).right;
+    if !(*x).left.is_null() { (*(*x).left).parent = x; }
+    (*y).parent = (*x).parent;
+    match TreeNode::is_left_child(x) {
+        true => (*(*x).parent).left = y,
+        false => (*(*x).parent).right = y
+    };
+    (*y).right = x;
+    (*x).parent = y;
+}
+
+// __tree_balance_after_insert
+unsafe fn balance_after_insert<T, A>(root: *mut TreeNode<T, A>, mut x: *mut TreeNode<T, A>)
+where A: Allocator + Clone
+{
+    (*x).is_black = std::ptr::eq(x, root);
+    while !std::ptr::eq(x, root) && !(*(*x).parent).is_black {
+        // x.parent != root because x.parent.is_black == false
+        if TreeNode::is_left_child((*x).parent) {
+            let y = (*(*(*x).parent).parent).right;
+            if !is_black(y) {
+                x = (*x).parent;
+                (*x).is_black = true;
+                x = (*x).parent;
+                (*x).is_black = std::ptr::eq(x, root);
+                (*y).is_black = true;
+            } else {
+                if !TreeNode::is_left_child(x) {
+                    x = (*x).parent;
+                    rotate_left(x);
+                }
+                x = (*x).parent;
+                (*x).is_black = true;
+                x = (*x).parent;
+                (*x).is_black = false;
+                rotate_right(x);
+                break;
+            }
+        } else {
+            let y = (*(*(*x).parent).parent).left;
+            if !is_black(y) {
+                x = (*x).parent;
+                (*x).is_black = true;
+                x = (*x).parent;
+                (*x).is_black = std::ptr::eq(x, root);
+                (*y).is_black = true;
+            } else {
+                if TreeNode::is_left_child(x) {
+                    x = (*x).parent;
+                    rotate_right(x);
+                }
+                x = (*x).parent;
+                (*x).is_black = true;
+                x = (*x).parent;
+                (*x).is_black = false;
+                rotate_left(x);
+                break;
+            }
+        }
+    }
+}
+
+// __tree_remove. Unlinks z from the tree and rebalances it. root is a local copy, the real root
+// is kept up to date through the end node since it's always the end node's left child
+unsafe fn tree_remove<T, A>(mut root: *mut TreeNode<T, A>, z: *mut TreeNode<T, A>)
+where A: Allocator + Clone
+{
+    // y will have at most one child. y will be the initial hole in the tree (make the hole at a
+    // leaf)
+    let y = match (*z).left.is_null() || (*z).right.is_null() {
+        true => z,
+        false => TreeNode::next(z)
+    };
+    // x is y's possibly null single child
+    let mut x = match (*y).left.is_null() {
+        true => (*y).right,
+        false => (*y).left
+    };
+    // w is x's possibly null uncle (will become x's sibling)
+    let mut w = std::ptr::null_mut();
+    // link x to y's parent, and find w
+    if !x.is_null() { (*x).parent = (*y).parent; }
+    if TreeNode::is_left_child(y) {
+        (*(*y).parent).left = x;
+        match std::ptr::eq(y, root) {
+            true => root = x, // w == null
+            false => w = (*(*y).parent).right
+        };
+    } else {
+        (*(*y).parent).right = x;
+        // y can't be root if it is a right child
+        w = (*(*y).parent).left;
+    }
+    let removed_black = (*y).is_black;
+    // If we didn't remove z, do so now by splicing in y for z, but copy z's color. This does not
+    // impact x or w.
+    if !std::ptr::eq(y, z) {
+        (*y).parent = (*z).parent;
+        match TreeNode::is_left_child(z) {
+            true => (*(*y).parent).left = y,
+            false => (*(*y).parent).right = y
+        };
+        (*y).left = (*z).left;
+        (*(*y).left).parent = y;
+        (*y).right = (*z).right;
+        if !(*y).right.is_null() { (*(*y).right).parent = y; }
+        (*y).is_black = (*z).is_black;
+        if std::ptr::eq(root, z) { root = y; }
+    }
+    // There is no need to rebalance if we removed a red, or if we removed the last node.
+    if !removed_black || root.is_null() { return }
+    // Rebalance: x has an implicit black color (transferred from the removed y) associated with
+    // it, no matter what its color is. If x is root (in which case it can't be null), it is
+    // supposed to be black anyway, and if it is doubly black, then the double can just be
+    // ignored. If x is red (in which case it can't be null), then it can absorb the implicit
+    // black just by setting its color to black. Since y was black and only had one child (which
+    // x points to), x is either red with no children, else null, otherwise y would have
+    // different black heights under left and right pointers.
+    if !x.is_null() {
+        (*x).is_black = true;
+        return
+    }
+    // Else x isn't root, and is "doubly black", even though it may be null. w can not be null
+    // here, else the parent would see a black height >= 2 on the x side and a black height of 1
+    // on the w side (w must be a non-null black or a red with a non-null black child).
+    loop {
+        if !TreeNode::is_left_child(w) { // if x is left child
+            if !(*w).is_black {
+                (*w).is_black = true;
+                (*(*w).parent).is_black = false;
+                rotate_left((*w).parent);
+                // x is still valid. reset root only if necessary
+                if std::ptr::eq(root, (*w).left) { root = w; }
+                // reset sibling, and it still can't be null
+                w = (*(*w).left).right;
+            }
+            // w->__is_black_ is now true, w may have null children
+            if is_black((*w).left) && is_black((*w).right) {
+                (*w).is_black = false;
+                x = (*w).parent;
+                // x can no longer be null
+                if std::ptr::eq(x, root) || !(*x).is_black {
+                    (*x).is_black = true;
+                    break;
+                }
+                // reset sibling, and it still can't be null
+                w = match TreeNode::is_left_child(x) {
+                    true => (*(*x).parent).right,
+                    false => (*(*x).parent).left
+                };
+                // continue;
+            } else { // w has a red child
+                if is_black((*w).right) {
+                    // w left child is non-null and red
+                    (*(*w).left).is_black = true;
+                    (*w).is_black = false;
+                    rotate_right(w);
+                    // w is known not to be root, so root hasn't changed. reset sibling, and it
+                    // still can't be null
+                    w = (*w).parent;
+                }
+                // w has a right red child, left child may be null
+                (*w).is_black = (*(*w).parent).is_black;
+                (*(*w).parent).is_black = true;
+                (*(*w).right).is_black = true;
+                rotate_left((*w).parent);
+                break;
+            }
+        } else {
+            if !(*w).is_black {
+                (*w).is_black = true;
+                (*(*w).parent).is_black = false;
+                rotate_right((*w).parent);
+                // x is still valid. reset root only if necessary
+                if std::ptr::eq(root, (*w).right) { root = w; }
+                // reset sibling, and it still can't be null
+                w = (*(*w).right).left;
+            }
+            // w->__is_black_ is now true, w may have null children
+            if is_black((*w).left) && is_black((*w).right) {
+                (*w).is_black = false;
+                x = (*w).parent;
+                // x can no longer be null
+                if !(*x).is_black || std::ptr::eq(x, root) {
+                    (*x).is_black = true;
+                    break;
+                }
+                // reset sibling, and it still can't be null
+                w = match TreeNode::is_left_child(x) {
+                    true => (*(*x).parent).right,
+                    false => (*(*x).parent).left
+                };
+                // continue;
+            } else { // w has a red child
+                if is_black((*w).left) {
+                    // w right child is non-null and red
+                    (*(*w).right).is_black = true;
+                    (*w).is_black = false;
+                    rotate_left(w);
+                    // w is known not to be root, so root hasn't changed. reset sibling, and it
+                    // still can't be null
+                    w = (*w).parent;
+                }
+                // w has a left red child, right child may be null
+                (*w).is_black = (*(*w).parent).is_black;
+                (*(*w).parent).is_black = true;
+                (*(*w).left).is_black = true;
+                rotate_right((*w).parent);
+                break;
+            }
+        }
+    }
+}
+
+impl<'a, C, T0, T1, A> IntoIterator for &'a Tree<C, T0, T1, A>
+where C: TreeCompare<T0, T1>,
+      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    type Item = &'a T0;
+    type IntoIter = TreeIterator<'a, T0, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_from(self.begin_node)
+    }
+}
+
+impl<'a, C, T0, T1, A> IntoIterator for &'a mut Tree<C, T0, T1, A>
+where C: TreeCompare<T0, T1>,
+      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    type Item = &'a mut T0;
+    type IntoIter = TreeIteratorMut<'a, T0, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        TreeIteratorMut {
+            front: self.begin_node,
+            back: self.get_end(),
+            _marker: PhantomData
+        }
+    }
+}
+
+// Half-open [front, back) range of nodes, like a pair of C++ iterators
+pub struct TreeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    front: *mut TreeNode<T, A>,
+    back: *mut TreeNode<T, A>,
+    _marker: PhantomData<&'a T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for TreeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if std::ptr::eq(self.front, self.back) { return None }
+        let out = unsafe { &*(*self.front).data };
+        self.front = unsafe { TreeNode::next(self.front) };
+        Some(out)
+    }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for TreeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if std::ptr::eq(self.front, self.back) { return None }
+        self.back = unsafe { TreeNode::prev(self.back) };
+        Some(unsafe { &*(*self.back).data })
+    }
+}
+
+pub struct TreeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    front: *mut TreeNode<T, A>,
+    back: *mut TreeNode<T, A>,
+    _marker: PhantomData<&'a mut T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for TreeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if std::ptr::eq(self.front, self.back) { return None }
+        let out = unsafe { &mut *(*self.front).data };
+        self.front = unsafe { TreeNode::next(self.front) };
+        Some(out)
+    }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for TreeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if std::ptr::eq(self.front, self.back) { return None }
+        self.back = unsafe { TreeNode::prev(self.back) };
+        Some(unsafe { &mut *(*self.back).data })
+    }
+}
+
+crate::generic::tree::value_lookup_wrappers!();
+
+#[cfg(test)]
+pub mod tests {
+    use allocator_api2::alloc::{ Allocator, Global };
+    use super::{
+        CompareLess,
+        Tree,
+        TreeCompare,
+        TreeNode
+    };
+    use std::{
+        error::Error,
+        fmt::Debug
+    };
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    impl<C, T0, T1, A> Tree<C, T0, T1, A>
+    where C: TreeCompare<T0, T1>,
+          T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1> + Debug,
+          T1: PartialEq + PartialOrd,
+          A: Allocator + Clone
+    {
+        // Check every red-black tree property, then return the black height
+        unsafe fn check_node(&self, n: *mut TreeNode<T0, A>, parent: *mut TreeNode<T0, A>) -> usize {
+            if n.is_null() { return 1 }
+            let node = &*n;
+            assert!(std::ptr::eq(node.parent, parent), "Parent pointer for {:?} is wrong", *node.data);
+            if !node.is_black {
+                assert!(super::is_black(node.left) && super::is_black(node.right),
+                    "Red node {:?} shouldn't have a red child", *node.data);
+            }
+            if !node.left.is_null() {
+                assert!(self.compare.compare_aa(&(&*node.left).data, &node.data), "Left child of {:?} is out of order", *node.data);
+            }
+            if !node.right.is_null() {
+                assert!(self.compare.compare_aa(&node.data, &(&*node.right).data), "Right child of {:?} is out of order", *node.data);
+            }
+            let left = self.check_node(node.left, n);
+            let right = self.check_node(node.right, n);
+            assert!(left == right, "Black height for {:?} doesn't match ({} != {})", *node.data, left, right);
+            left + if node.is_black { 1 } else { 0 }
+        }
+
+        pub(crate) fn check_tree(&self) {
+            let root = self.get_root();
+            match root.is_null() {
+                false => unsafe {
+                    assert!((*root).is_black, "Root should be black");
+                    self.check_node(root, self.get_end());
+                    assert!(std::ptr::eq(self.begin_node, TreeNode::minimum(root)), "Begin node should point to leftmost node");
+                },
+                true => assert!(std::ptr::eq(self.begin_node, self.get_end()), "Begin node should point to the end node")
+            }
+            assert!(self.iter().count() == self.len(), "Tree should contain {} nodes", self.len());
+        }
+    }
+
+    #[test]
+    pub fn tree_layout() -> TestReturn {
+        assert!(size_of::<Tree<CompareLess, u32, u32, Global>>() == 0x18, "std::set should be 0x18 bytes");
+        let mut tree = Tree::<CompareLess, u64, u64, Global>::new_standalone_inner(Global);
+        let base = &raw const *tree as usize;
+        let raw = unsafe { std::slice::from_raw_parts(base as *const usize, 3) };
+        assert!(raw == [base + 8, 0, 0], "Blank tree should be (end, null, 0)");
+        tree.insert(5);
+        let node = tree.get_root() as usize;
+        let raw = unsafe { std::slice::from_raw_parts(base as *const usize, 3) };
+        assert!(raw == [node, node, 1], "Tree should be (begin, root, size)");
+        // __is_black_ is a bool followed by padding, so the value is read on its own
+        let raw_node = unsafe { std::slice::from_raw_parts(node as *const usize, 3) };
+        let value = unsafe { *((node + 0x20) as *const u64) };
+        assert!(raw_node == [0, 0, base + 8] && value == 5, "Node should be (left, right, parent, is_black, value)");
+        assert!(unsafe { *((node + 0x18) as *const u8) } == 1, "Root node should be black");
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_begin_node_cache() -> TestReturn {
+        let mut tree = Tree::<CompareLess, u32, u32, Global>::new_standalone_inner(Global);
+        assert!(std::ptr::eq(tree.begin_node, tree.get_end()), "Empty tree should begin at the end node");
+        // __tree caches the leftmost node, which only changes when a smaller value is inserted
+        for i in [40, 50, 30, 45, 35, 20, 60, 10] {
+            let before = tree.begin_node;
+            tree.insert(i);
+            let first = tree.iter().next().unwrap() as *const u32;
+            assert!(std::ptr::eq(unsafe { &*(*tree.begin_node).data } as *const u32, first), "Begin node should be the first value after inserting {}", i);
+            match tree.len() == 1 || i < unsafe { *(*before).data } {
+                true => assert!(!std::ptr::eq(before, tree.begin_node), "Inserting {} should move the begin node", i),
+                false => assert!(std::ptr::eq(before, tree.begin_node), "Inserting {} shouldn't move the begin node", i)
+            }
+        }
+        // Erasing the begin node advances it to its successor, while other erasures leave it
+        let begin = tree.begin_node;
+        tree.remove(45);
+        assert!(std::ptr::eq(begin, tree.begin_node), "Erasing 45 shouldn't move the begin node");
+        for expected in [20, 30, 35, 40] {
+            let first = tree.first().copied();
+            tree.remove(first.unwrap());
+            assert!(unsafe { *(*tree.begin_node).data } == expected, "Begin node should advance to {}", expected);
+            tree.check_tree();
+        }
+        tree.clear();
+        assert!(std::ptr::eq(tree.begin_node, tree.get_end()), "Clearing should reset the begin node to the end node");
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_end_node_root() -> TestReturn {
+        let mut tree = Tree::<CompareLess, u32, u32, Global>::new_standalone_inner(Global);
+        let end = tree.get_end();
+        assert!(tree.end_node.left.is_null(), "Empty tree should have no root");
+        // Ascending inserts rotate at the root, so end_node.left has to follow each new root
+        for i in 0..16 {
+            tree.insert(i);
+            let root = tree.end_node.left;
+            unsafe {
+                assert!(std::ptr::eq((*root).parent, end), "Root's parent should be the end node after inserting {}", i);
+                assert!(std::ptr::eq(TreeNode::minimum(root), tree.begin_node), "Leftmost node under the root should be the begin node");
+            }
+        }
+        unsafe { assert!(*(*tree.end_node.left).data == 3, "Root of 0..16 should be 3"); }
+        // Leaves have null children rather than pointing at a sentinel like libstdc++ and MSVC
+        let leaf = tree.begin_node;
+        unsafe { assert!((*leaf).left.is_null() && (*leaf).right.is_null(), "Leftmost leaf should have null children"); }
+        while !tree.is_empty() {
+            let root = unsafe { *(*tree.end_node.left).data };
+            tree.remove(root);
+            match tree.is_empty() {
+                true => assert!(tree.end_node.left.is_null(), "Removing the last node should clear the root"),
+                false => unsafe { assert!(std::ptr::eq((*tree.end_node.left).parent, end), "New root's parent should be the end node") }
+            };
+            tree.check_tree();
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_black_bit_layout() -> TestReturn {
+        let mut tree = Tree::<CompareLess, u64, u64, Global>::new_standalone_inner(Global);
+        // __is_black_ is the byte after the parent pointer, with the value starting at the next
+        // 8 byte boundary
+        let is_black = |n: *mut TreeNode<u64, Global>| unsafe { *((n as usize + 0x18) as *const u8) };
+        for i in [1, 2, 3] { tree.insert(i); }
+        let root = tree.get_root();
+        unsafe {
+            assert!(*(*root).data == 2 && is_black(root) == 1, "Root 2 should be black");
+            assert!(is_black((*root).left) == 0 && is_black((*root).right) == 0, "1 and 3 should be red");
+        }
+        // Inserting 4 recolors 1 and 3 black, leaving 4 red
+        tree.insert(4);
+        unsafe {
+            assert!(is_black((*root).left) == 1 && is_black((*root).right) == 1, "1 and 3 should be black");
+            let four = (*(*root).right).right;
+            assert!(*(*four).data == 4 && is_black(four) == 0, "4 should be red");
+        }
+        for i in 5..64 { tree.insert(i); }
+        let mut node = tree.begin_node;
+        while node != tree.get_end() {
+            let byte = is_black(node);
+            assert!(byte <= 1 && (byte == 1) == unsafe { (*node).is_black }, "Black bit should be a bool at 0x18");
+            node = unsafe { TreeNode::next(node) };
+        }
+        tree.check_tree();
+        Ok(())
+    }
+
+    // Orders values by their last digit, so equivalent values aren't always equal
+    #[derive(Default)]
+    struct CompareLastDigit;
+    impl TreeCompare<u32, u32> for CompareLastDigit {
+        fn compare_aa(&self, d0: &u32, d1: &u32) -> bool { d0 % 10 < d1 % 10 }
+        fn compare_ab(&self, d0: &u32, d1: &u32) -> bool { d0 % 10 < d1 % 10 }
+        fn compare_ba(&self, d0: &u32, d1: &u32) -> bool { d0 % 10 < d1 % 10 }
+    }
+
+    #[test]
+    pub fn tree_equivalent_keys() -> TestReturn {
+        let mut tree = Tree::<CompareLastDigit, u32, u32, Global>::new_standalone_inner(Global);
+        for i in [12, 25, 31] { tree.insert(i); }
+        assert!(!tree.insert(45), "45 is equivalent to 25, so it shouldn't be inserted");
+        assert!(tree.find(15) == Some(&25), "find(15) should find the equivalent 25");
+        assert!(tree.upper_bound(5).next().is_none(), "upper_bound(5) should skip the equivalent 25");
+        assert!(tree.upper_bound(2).next() == Some(&25), "upper_bound(2) should skip the equivalent 12");
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_relocate() -> TestReturn {
+        let mut tree: Tree<CompareLess, u32, u32, Global> = unsafe { Tree::new_inner(Global) };
+        unsafe { tree.setup_pointers() };
+        for i in 0..8 { tree.insert(i); }
+        let mut moved = Box::new(tree);
+        unsafe { moved.setup_pointers() };
+        moved.check_tree();
+        moved.insert(8);
+        moved.remove(0);
+        moved.check_tree();
+        moved.clear();
+        moved.check_tree();
+        Ok(())
+    }
+}
@@ -0,0 +1,869 @@
+//! Rust reimplementation of libc++'s std::unordered_map/std::unordered_set implementation
+//! (__hash_table)
+
+#![allow(dead_code, unused_imports)]
+use allocator_api2::{
+    alloc::{ Allocator, Global },
+    boxed::Box as ABox
+};
+use crate::clang::hash::{ HasherInit, StdHash };
+pub use crate::generic::tree::MapPair;
+use std::{
+    alloc::Layout,
+    fmt::Debug,
+    hash::{ Hash, Hasher },
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ops::{ Index, IndexMut },
+    ptr::NonNull
+};
+
+// https://github.com/llvm/llvm-project/blob/main/libcxx/include/__hash_table
+// https://github.com/llvm/llvm-project/blob/main/libcxx/src/hash.cpp
+
+// small_primes (from hash.cpp)
+const SMALL_PRIMES: [usize; 48] = [
+    0, 2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83,
+    89, 97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181,
+    191, 193, 197, 199, 211
+];
+
+// __next_prime: smallest prime that's at least n
+fn next_prime(n: usize) -> usize {
+    if n <= SMALL_PRIMES[SMALL_PRIMES.len() - 1] {
+        return SMALL_PRIMES[SMALL_PRIMES.partition_point(|p| *p < n)];
+    }
+    // libc++ only tests candidates that are coprime to 210, which finds the same prime
+    let is_prime = |v: usize| (2..).take_while(|d| d * d <= v).all(|d| !v.is_multiple_of(d));
+    (n..).find(|v| is_prime(*v)).unwrap()
+}
+
+// __is_hash_power2
+fn is_hash_power2(bc: usize) -> bool { bc > 2 && bc & (bc - 1) == 0 }
+
+// __next_hash_pow2
+fn next_hash_pow2(n: usize) -> usize {
+    if n < 2 { return n }
+    1 << (usize::BITS - (n - 1).leading_zeros())
+}
+
+// __constrain_hash
+fn constrain_hash(h: u64, bc: usize) -> usize {
+    let h = h as usize;
+    match bc & bc.wrapping_sub(1) == 0 {
+        true => h & bc.wrapping_sub(1),
+        false => if h < bc { h } else { h % bc }
+    }
+}
+
+#[repr(C)]
+pub struct NodeBase<T, A = Global> // __hash_node_base
+where A: Allocator + Clone
+{
+    next: *mut HashNode<T, A> // __next_
+}
+
+#[repr(C)]
+pub struct HashNode<T, A = Global> // __hash_node
+where A: Allocator + Clone
+{
+    // Buckets point to __p1_ for the first node, so only access fields through raw pointers so
+    // we never make a reference to a node base as a full node.
+    next: *mut Self, // __next_
+    hash: usize, // __hash_
+    data: ManuallyDrop<T>, // __value_
+    _allocator: A
+}
+
+#[repr(C)]
+pub struct HashTable<H, T0, T1, A = Global>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      A: Allocator + Clone
+{
+    // Each bucket points to the node *before* the bucket's first node, which is first_node for
+    // the bucket containing the first node. Since that points into the table, call
+    // setup_pointers() after moving it.
+    buckets: *mut *mut HashNode<T0, A>, // __bucket_list_
+    bucket_count: usize, // __bucket_list_.get_deleter().size()
+    first_node: NodeBase<T0, A>, // __p1_
+    size: usize, // __p2_
+    // std::hash and std::equal_to are empty so they take no space
+    max_load_factor: f32, // __p3_
+    _allocator: A,
+    _hash: PhantomData<H>,
+    _key_ty: PhantomData<T1>
+}
+
+impl<H, T0, T1, A> HashTable<H, T0, T1, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      A: Allocator + Clone
+{
+    /// # Safety
+    ///
+    /// The table is unusable until [`HashTable::setup_pointers`] is called at its final address.
+    pub unsafe fn new_inner(alloc: A) -> Self {
+        assert!(std::mem::size_of::<A>() == 0, "Allocator must be zero-sized!");
+        Self {
+            buckets: std::ptr::null_mut(),
+            bucket_count: 0,
+            first_node: NodeBase { next: std::ptr::null_mut() },
+            size: 0,
+            max_load_factor: 1.0,
+            _allocator: alloc,
+            _hash: PhantomData,
+            _key_ty: PhantomData
+        }
+    }
+
+    pub fn new_standalone_inner(alloc: A) -> ABox<Self, A> {
+        let mut new = ABox::new_in(unsafe { Self::new_inner(alloc.clone()) }, alloc);
+        unsafe { new.setup_pointers() };
+        new
+    }
+
+    /// Point the first node's bucket to the table's current address. This must be called after
+    /// the table is moved.
+    ///
+    /// # Safety
+    ///
+    /// Any nodes in the table must still be valid, and the table must not be moved afterwards.
+    pub unsafe fn setup_pointers(&mut self) {
+        let first = self.first_node.next;
+        if !first.is_null() {
+            let bucket = constrain_hash((*first).hash as u64, self.bucket_count);
+            let first_node = self.get_first_node();
+            self.set_bucket(bucket, first_node);
+        }
+    }
+
+    pub fn len(&self) -> usize { self.size }
+    pub fn is_empty(&self) -> bool { self.size == 0 }
+    pub fn bucket_count(&self) -> usize { self.bucket_count }
+    pub fn load_factor(&self) -> f32 {
+        match self.bucket_count {
+            0 => 0.0,
+            v => self.size as f32 / v as f32
+        }
+    }
+    pub fn max_load_factor(&self) -> f32 { self.max_load_factor }
+    /// Change the maximum load factor. Like libc++, it can't go below the current load factor,
+    /// and this doesn't rehash until the next insert.
+    pub fn set_max_load_factor(&mut self, max_load_factor: f32) {
+        self.max_load_factor = max_load_factor.max(self.load_factor());
+    }
+
+    fn get_first_node(&mut self) -> *mut HashNode<T0, A> {
+        &raw mut self.first_node as *mut HashNode<T0, A>
+    }
+
+    fn get_bucket(&self, bucket: usize) -> *mut HashNode<T0, A> {
+        unsafe { *self.buckets.add(bucket) }
+    }
+
+    fn set_bucket(&mut self, bucket: usize, node: *mut HashNode<T0, A>) {
+        unsafe { *self.buckets.add(bucket) = node }
+    }
+
+    unsafe fn node_bucket(&self, node: *mut HashNode<T0, A>) -> usize {
+        constrain_hash((*node).hash as u64, self.bucket_count)
+    }
+
+    /// Returns the index of the bucket that the key would be stored in
+    pub fn bucket(&self, key: &T1) -> usize { constrain_hash(H::get_hash(key), self.bucket_count) }
+
+    /// Returns the number of elements in the bucket
+    pub fn bucket_size(&self, bucket: usize) -> usize { self.bucket_iter(bucket).count() }
+
+    /// Iterate through each element in the bucket
+    pub fn bucket_iter(&self, bucket: usize) -> BucketIterator<'_, H, T0, T1, A> {
+        // tables without any buckets don't have a bucket array
+        let prev = match self.buckets.is_null() {
+            true => std::ptr::null_mut(),
+            false => self.get_bucket(bucket)
+        };
+        BucketIterator {
+            table: self,
+            curr: if prev.is_null() { prev } else { unsafe { (*prev).next } },
+            bucket
+        }
+    }
+
+    // find, or the node search in __emplace_unique_key_args
+    unsafe fn find_node_hashed<F>(&self, hash: u64, eq: F) -> Option<*mut HashNode<T0, A>>
+    where F: Fn(&T0) -> bool
+    {
+        let bc = self.bucket_count;
+        if bc == 0 { return None }
+        let chash = constrain_hash(hash, bc);
+        let nd = self.get_bucket(chash);
+        if nd.is_null() { return None }
+        let mut nd = (*nd).next;
+        while !nd.is_null() && ((*nd).hash == hash as usize || self.node_bucket(nd) == chash) {
+            if (*nd).hash == hash as usize && eq(&(*nd).data) { return Some(nd) }
+            nd = (*nd).next;
+        }
+        None
+    }
+
+    fn find_node(&self, key: &T1) -> Option<*mut HashNode<T0, A>> {
+        unsafe { self.find_node_hashed(H::get_hash(key), |v| *v == *key) }
+    }
+
+    pub fn find(&self, key: &T1) -> Option<&T0> {
+        self.find_node(key).map(|n| unsafe { &*(*n).data })
+    }
+    pub fn find_mut(&mut self, key: &T1) -> Option<&mut T0> {
+        self.find_node(key).map(|n| unsafe { &mut *(*n).data })
+    }
+    pub fn contains(&self, key: &T1) -> bool { self.find_node(key).is_some() }
+
+    pub fn iter(&self) -> HashIterator<'_, T0, A> { self.into_iter() }
+    pub fn iter_mut(&mut self) -> HashIteratorMut<'_, T0, A> { self.into_iter() }
+
+    /// Inserts the value if there isn't an equivalent element already, returning true if the
+    /// insertion took place (__emplace_unique_key_args)
+    pub fn insert(&mut self, value: T0) -> bool {
+        let hash = H::get_hash(&value);
+        if unsafe { self.find_node_hashed(hash, |v| *v == value) }.is_some() { return false }
+        let node = HashNode::new(value, hash as usize, self._allocator.clone());
+        let bc = self.bucket_count;
+        if (self.size + 1) as f32 > bc as f32 * self.max_load_factor || bc == 0 {
+            let grow = 2 * bc + if is_hash_power2(bc) { 0 } else { 1 };
+            let required = ((self.size + 1) as f32 / self.max_load_factor).ceil() as usize;
+            self.rehash_unique(grow.max(required));
+        }
+        unsafe { self.insert_node(node) };
+        true
+    }
+
+    // insert_after __bucket_list_[__chash], or __first_node if bucket is null
+    unsafe fn insert_node(&mut self, node: *mut HashNode<T0, A>) {
+        let bc = self.bucket_count;
+        let chash = self.node_bucket(node);
+        let pn = self.get_bucket(chash);
+        if pn.is_null() {
+            let pn = self.get_first_node();
+            (*node).next = (*pn).next;
+            (*pn).next = node;
+            // fix up __bucket_list_
+            self.set_bucket(chash, pn);
+            if !(*node).next.is_null() {
+                self.set_bucket(constrain_hash((*(*node).next).hash as u64, bc), node);
+            }
+        } else {
+            (*node).next = (*pn).next;
+            (*pn).next = node;
+        }
+        self.size += 1;
+    }
+
+    /// Sets the number of buckets to at least count, rehashing the table (__rehash_unique)
+    pub fn rehash(&mut self, count: usize) { self.rehash_unique(count) }
+
+    /// Sets the number of buckets to the amount needed for count elements
+    pub fn reserve(&mut self, count: usize) {
+        self.rehash_unique((count as f32 / self.max_load_factor).ceil() as usize)
+    }
+
+    // __rehash<true>
+    fn rehash_unique(&mut self, mut n: usize) {
+        if n == 1 {
+            n = 2;
+        } else if n != 0 && n & (n - 1) != 0 {
+            n = next_prime(n);
+        }
+        let bc = self.bucket_count;
+        if n > bc {
+            unsafe { self.do_rehash(n) };
+        } else if n < bc {
+            let required = (self.size as f32 / self.max_load_factor).ceil() as usize;
+            n = n.max(match is_hash_power2(bc) {
+                true => next_hash_pow2(required),
+                false => next_prime(required)
+            });
+            if n < bc { unsafe { self.do_rehash(n) }; }
+        }
+    }
+
+    // __do_rehash<true>
+    unsafe fn do_rehash(&mut self, nbc: usize) {
+        self.deallocate_buckets();
+        self.buckets = match nbc > 0 {
+            true => {
+                let layout = Layout::array::<*mut HashNode<T0, A>>(nbc).unwrap();
+                self._allocator.allocate_zeroed(layout).unwrap().as_ptr() as *mut *mut HashNode<T0, A>
+            },
+            false => std::ptr::null_mut()
+        };
+        self.bucket_count = nbc;
+        if nbc == 0 { return }
+        let mut pp = self.get_first_node();
+        let mut cp = (*pp).next;
+        if cp.is_null() { return }
+        let mut chash = constrain_hash((*cp).hash as u64, nbc);
+        self.set_bucket(chash, pp);
+        let mut phash = chash;
+        pp = cp;
+        cp = (*cp).next;
+        while !cp.is_null() {
+            chash = constrain_hash((*cp).hash as u64, nbc);
+            if chash == phash {
+                pp = cp;
+            } else if self.get_bucket(chash).is_null() {
+                self.set_bucket(chash, pp);
+                pp = cp;
+                phash = chash;
+            } else {
+                // splice the node to the start of the bucket it belongs to
+                let np = cp;
+                (*pp).next = (*np).next;
+                (*np).next = (*self.get_bucket(chash)).next;
+                (*self.get_bucket(chash)).next = cp;
+            }
+            cp = (*pp).next;
+        }
+    }
+
+    unsafe fn deallocate_buckets(&mut self) {
+        if self.buckets.is_null() { return }
+        let layout = Layout::array::<*mut HashNode<T0, A>>(self.bucket_count).unwrap();
+        self._allocator.deallocate(NonNull::new_unchecked(self.buckets as *mut u8), layout);
+    }
+
+    // remove(const_iterator)
+    unsafe fn remove_node(&mut self, cn: *mut HashNode<T0, A>) -> T0 {
+        let bc = self.bucket_count;
+        let chash = self.node_bucket(cn);
+        // find previous node
+        let mut pn = self.get_bucket(chash);
+        while !std::ptr::eq((*pn).next, cn) { pn = (*pn).next; }
+        let next = (*cn).next;
+        // Fix up __bucket_list_. If pn is not in same bucket (before begin is not in same
+        // bucket) and cn.next is not in same bucket (nullptr is not in same bucket)
+        if (std::ptr::eq(pn, self.get_first_node()) || self.node_bucket(pn) != chash)
+            && (next.is_null() || constrain_hash((*next).hash as u64, bc) != chash) {
+            self.set_bucket(chash, std::ptr::null_mut());
+        }
+        // if cn.next is not in same bucket (nullptr is in same bucket)
+        if !next.is_null() {
+            let nhash = constrain_hash((*next).hash as u64, bc);
+            if nhash != chash { self.set_bucket(nhash, pn); }
+        }
+        (*pn).next = next;
+        self.size -= 1;
+        let value = ManuallyDrop::take(&mut (*cn).data);
+        HashNode::delete(cn, self._allocator.clone());
+        value
+    }
+
+    /// Removes the element matching the key, returning it if it existed
+    pub fn take(&mut self, key: &T1) -> Option<T0> {
+        let node = self.find_node(key)?;
+        Some(unsafe { self.remove_node(node) })
+    }
+
+    pub fn erase(&mut self, key: &T1) -> bool { self.take(key).is_some() }
+
+    pub fn clear(&mut self) {
+        if self.size == 0 { return }
+        unsafe { self.deallocate_nodes() };
+        self.first_node.next = std::ptr::null_mut();
+        for i in 0..self.bucket_count { self.set_bucket(i, std::ptr::null_mut()) }
+        self.size = 0;
+    }
+
+    // __deallocate_node
+    unsafe fn deallocate_nodes(&mut self) {
+        let mut n = self.first_node.next;
+        while !n.is_null() {
+            let next = (*n).next;
+            ManuallyDrop::drop(&mut (*n).data);
+            HashNode::delete(n, self._allocator.clone());
+            n = next;
+        }
+    }
+}
+
+impl<H, T0, T1, A> Drop for HashTable<H, T0, T1, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      A: Allocator + Clone
+{
+    fn drop(&mut self) {
+        unsafe {
+            self.deallocate_nodes();
+            self.deallocate_buckets();
+        }
+    }
+}
+
+impl<T, A> HashNode<T, A>
+where A: Allocator + Clone
+{
+    // __construct_node_hash
+    fn new(value: T, hash: usize, alloc: A) -> *mut Self {
+        let new = alloc.allocate(Layout::new::<Self>()).unwrap().as_ptr() as *mut Self;
+        unsafe {
+            std::ptr::write(new, Self {
+                next: std::ptr::null_mut(),
+                hash,
+                data: ManuallyDrop::new(value),
+                _allocator: alloc
+            });
+        }
+        new
+    }
+
+    // SAFETY: The node's value must be dropped or moved out beforehand
+    unsafe fn delete(node: *mut Self, alloc: A) {
+        alloc.deallocate(NonNull::new_unchecked(node as *mut u8), Layout::new::<Self>());
+    }
+
+    pub fn value(&self) -> &T { &self.data }
+    pub fn value_mut(&mut self) -> &mut T { &mut self.data }
+}
+
+impl<'a, H, T0, T1, A> IntoIterator for &'a HashTable<H, T0, T1, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      A: Allocator + Clone
+{
+    type Item = &'a T0;
+    type IntoIter = HashIterator<'a, T0, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        HashIterator { curr: self.first_node.next, _marker: PhantomData }
+    }
+}
+
+impl<'a, H, T0, T1, A> IntoIterator for &'a mut HashTable<H, T0, T1, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      A: Allocator + Clone
+{
+    type Item = &'a mut T0;
+    type IntoIter = HashIteratorMut<'a, T0, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        HashIteratorMut { curr: self.first_node.next, _marker: PhantomData }
+    }
+}
+
+impl<H, T0, T1, A> Index<&T1> for HashTable<H, T0, T1, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      A: Allocator + Clone
+{
+    type Output = T0;
+    fn index(&self, index: &T1) -> &Self::Output { self.find(index).unwrap() }
+}
+
+impl<H, T0, T1, A> IndexMut<&T1> for HashTable<H, T0, T1, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      A: Allocator + Clone
+{
+    fn index_mut(&mut self, index: &T1) -> &mut Self::Output { self.find_mut(index).unwrap() }
+}
+
+// Walks the singly linked list starting from __p1_
+pub struct HashIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    curr: *mut HashNode<T, A>,
+    _marker: PhantomData<&'a T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for HashIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.curr.is_null() { return None }
+        let out = unsafe { &*(*self.curr).data };
+        self.curr = unsafe { (*self.curr).next };
+        Some(out)
+    }
+}
+
+pub struct HashIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    curr: *mut HashNode<T, A>,
+    _marker: PhantomData<&'a mut T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for HashIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.curr.is_null() { return None }
+        let out = unsafe { &mut *(*self.curr).data };
+        self.curr = unsafe { (*self.curr).next };
+        Some(out)
+    }
+}
+
+// Iterates through a single bucket (local_iterator), stopping once the next node belongs to
+// another bucket
+pub struct BucketIterator<'a, H, T0, T1, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      A: Allocator + Clone
+{
+    table: &'a HashTable<H, T0, T1, A>,
+    curr: *mut HashNode<T0, A>,
+    bucket: usize
+}
+
+impl<'a, H, T0, T1, A> Iterator for BucketIterator<'a, H, T0, T1, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      A: Allocator + Clone
+{
+    type Item = &'a T0;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.curr.is_null() { return None }
+        let out = unsafe { &*(*self.curr).data };
+        let next = unsafe { (*self.curr).next };
+        self.curr = match next.is_null() || unsafe { self.table.node_bucket(next) } != self.bucket {
+            true => std::ptr::null_mut(),
+            false => next
+        };
+        Some(out)
+    }
+}
+
+// ========================================================
+
+// https://en.cppreference.com/w/cpp/container/unordered_set
+#[repr(C)]
+pub struct Set<H, T, A = Global>
+where H: Hasher + HasherInit,
+      T: Hash + PartialEq,
+      A: Allocator + Clone
+{ _impl: HashTable<H, T, T, A> }
+
+impl<T> Set<StdHash, T, Global>
+where T: Hash + PartialEq
+{
+    pub fn new_standalone() -> ABox<Self, Global> { Self::new_standalone_in(Global) }
+}
+
+impl<H, T, A> Set<H, T, A>
+where H: Hasher + HasherInit,
+      T: Hash + PartialEq,
+      A: Allocator + Clone
+{
+    /// Constructs the unordered_set. Call setup_pointers() once it's in its final location
+    ///
+    /// # Safety
+    ///
+    /// It's unusable until setup_pointers() is called.
+    pub unsafe fn new_in(alloc: A) -> Self {
+        Self { _impl: HashTable::new_inner(alloc) }
+    }
+    pub fn new_standalone_in(alloc: A) -> ABox<Self, A> {
+        let mut new = ABox::new_in(unsafe { Self::new_in(alloc.clone()) }, alloc);
+        unsafe { new.setup_pointers() };
+        new
+    }
+    /// # Safety
+    ///
+    /// Same as the table's setup_pointers.
+    pub unsafe fn setup_pointers(&mut self) { self._impl.setup_pointers() }
+    /// Checks if the container has no elements
+    pub fn empty(&self) -> bool { self._impl.is_empty() }
+    /// Returns the number of elements in the container,
+    pub fn size(&self) -> usize { self._impl.len() }
+    /// Returns the number of buckets in the container
+    pub fn bucket_count(&self) -> usize { self._impl.bucket_count() }
+    /// Erases all elements from the container. After this call, size() returns zero.
+    pub fn clear(&mut self) { self._impl.clear() }
+    /// Inserts an element into the container, if the container doesn't already contain an
+    /// element with an equivalent key. This returns a bool that notes if the insertion took place
+    pub fn insert(&mut self, value: T) -> bool { self._impl.insert(value) }
+    pub fn erase(&mut self, val: &T) -> bool { self._impl.erase(val) }
+    pub fn contains(&self, val: &T) -> bool { self._impl.contains(val) }
+    pub fn find(&self, val: &T) -> Option<&T> { self._impl.find(val) }
+    pub fn rehash(&mut self, count: usize) { self._impl.rehash(count) }
+    pub fn reserve(&mut self, count: usize) { self._impl.reserve(count) }
+    pub fn iter(&self) -> HashIterator<'_, T, A> { self._impl.iter() }
+}
+
+impl<'a, H, T, A> IntoIterator for &'a Set<H, T, A>
+where H: Hasher + HasherInit,
+      T: Hash + PartialEq,
+      A: Allocator + Clone
+{
+    type Item = &'a T;
+    type IntoIter = HashIterator<'a, T, A>;
+    fn into_iter(self) -> Self::IntoIter { self._impl.iter() }
+}
+
+// https://en.cppreference.com/w/cpp/container/unordered_map
+
+#[repr(C)]
+pub struct Map<H, K, V, A = Global>
+where H: Hasher + HasherInit,
+      K: PartialEq + Hash,
+      A: Allocator + Clone
+{ _impl: HashTable<H, MapPair<K, V>, K, A> }
+
+impl<K, V> Map<StdHash, K, V, Global>
+where K: PartialEq + Hash
+{
+    pub fn new_standalone() -> ABox<Self, Global> { Self::new_standalone_in(Global) }
+}
+
+impl<H, K, V, A> Map<H, K, V, A>
+where H: Hasher + HasherInit,
+      K: PartialEq + Hash,
+      A: Allocator + Clone
+{
+    /// Constructs the unordered_map. Call setup_pointers() once it's in its final location
+    ///
+    /// # Safety
+    ///
+    /// It's unusable until setup_pointers() is called.
+    pub unsafe fn new_in(alloc: A) -> Self {
+        Self { _impl: HashTable::new_inner(alloc) }
+    }
+    pub fn new_standalone_in(alloc: A) -> ABox<Self, A> {
+        let mut new = ABox::new_in(unsafe { Self::new_in(alloc.clone()) }, alloc);
+        unsafe { new.setup_pointers() };
+        new
+    }
+    /// # Safety
+    ///
+    /// Same as the table's setup_pointers.
+    pub unsafe fn setup_pointers(&mut self) { self._impl.setup_pointers() }
+    /// Checks if the container has no elements
+    pub fn empty(&self) -> bool { self._impl.is_empty() }
+    /// Returns the number of elements in the container,
+    pub fn size(&self) -> usize { self._impl.len() }
+    /// Returns the number of buckets in the container
+    pub fn bucket_count(&self) -> usize { self._impl.bucket_count() }
+    /// Erases all elements from the container. After this call, size() returns zero.
+    pub fn clear(&mut self) { self._impl.clear() }
+    /// Inserts an element into the container, if the container doesn't already contain an
+    /// element with an equivalent key. This returns a bool that notes if the insertion took place
+    pub fn insert(&mut self, key: K, value: V) -> bool {
+        let pair = MapPair::new(key, value);
+        self._impl.insert(pair)
+    }
+    pub fn iter(&self) -> HashIterator<'_, MapPair<K, V>, A> { self._impl.iter() }
+    pub fn iter_mut(&mut self) -> HashIteratorMut<'_, MapPair<K, V>, A> { self._impl.iter_mut() }
+    pub fn find(&self, key: &K) -> Option<&MapPair<K, V>> { self._impl.find(key) }
+    pub fn find_mut(&mut self, key: &K) -> Option<&mut MapPair<K, V>> { self._impl.find_mut(key) }
+    pub fn contains(&self, key: &K) -> bool { self._impl.contains(key) }
+    pub fn erase(&mut self, key: &K) -> bool { self._impl.erase(key) }
+    pub fn rehash(&mut self, count: usize) { self._impl.rehash(count) }
+    pub fn reserve(&mut self, count: usize) { self._impl.reserve(count) }
+}
+
+impl<'a, H, K, V, A> IntoIterator for &'a Map<H, K, V, A>
+where H: Hasher + HasherInit,
+      K: PartialEq + Hash,
+      A: Allocator + Clone
+{
+    type Item = &'a MapPair<K, V>;
+    type IntoIter = HashIterator<'a, MapPair<K, V>, A>;
+    fn into_iter(self) -> Self::IntoIter { self._impl.iter() }
+}
+
+impl<'a, H, K, V, A> IntoIterator for &'a mut Map<H, K, V, A>
+where H: Hasher + HasherInit,
+      K: PartialEq + Hash,
+      A: Allocator + Clone
+{
+    type Item = &'a mut MapPair<K, V>;
+    type IntoIter = HashIteratorMut<'a, MapPair<K, V>, A>;
+    fn into_iter(self) -> Self::IntoIter { self._impl.iter_mut() }
+}
+
+impl<H, K, V, A> Index<&K> for Map<H, K, V, A>
+where H: Hasher + HasherInit,
+      K: PartialEq + Hash,
+      A: Allocator + Clone
+{
+    type Output = MapPair<K, V>;
+    fn index(&self, index: &K) -> &Self::Output { &self._impl[index] }
+}
+
+impl<H, K, V, A> IndexMut<&K> for Map<H, K, V, A>
+where H: Hasher + HasherInit,
+      K: PartialEq + Hash,
+      A: Allocator + Clone
+{
+    fn index_mut(&mut self, index: &K) -> &mut Self::Output { &mut self._impl[index] }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use allocator_api2::alloc::Global;
+    use crate::clang::hash::StdHash;
+    use super::{ HashTable, Map, Set };
+    use std::error::Error;
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    fn check_buckets(table: &HashTable<StdHash, i32, i32, Global>, order: &[i32], buckets: &[(usize, &[i32])]) {
+        let values: Vec<i32> = table.iter().copied().collect();
+        assert!(values == order, "Iteration order should be {:?} instead of {:?}", order, values);
+        let mut expected = buckets.iter();
+        for i in 0..table.bucket_count() {
+            let contents: Vec<i32> = table.bucket_iter(i).copied().collect();
+            if contents.is_empty() { continue }
+            let (bucket, values) = expected.next().unwrap();
+            assert!(i == *bucket && contents == *values, "Bucket {} should be {:?} instead of {:?}", bucket, values, contents);
+        }
+        assert!(expected.next().is_none(), "Some buckets were empty");
+    }
+
+    #[test]
+    pub fn hash_table_layout() -> TestReturn {
+        assert!(size_of::<HashTable<StdHash, i32, i32, Global>>() == 0x28, "std::unordered_set should be 0x28 bytes");
+        let mut table = HashTable::<StdHash, u64, u64, Global>::new_standalone_inner(Global);
+        let base = &raw const *table as usize;
+        // max_load_factor is a float followed by padding, so only the first four fields are read
+        let raw = unsafe { std::slice::from_raw_parts(base as *const usize, 4) };
+        assert!(raw == [0, 0, 0, 0], "Empty table shouldn't allocate any buckets");
+        assert!(table.max_load_factor() == 1.0, "Default max load factor should be 1.0");
+        table.insert(7);
+        let raw = unsafe { std::slice::from_raw_parts(base as *const usize, 4) };
+        assert!(raw[1] == 2 && raw[3] == 1, "Table should have 2 buckets and 1 element");
+        let node = unsafe { std::slice::from_raw_parts(raw[2] as *const usize, 3) };
+        assert!(node == [0, 7, 7], "Node should be (next, hash, value)");
+        let buckets = unsafe { std::slice::from_raw_parts(raw[0] as *const usize, 2) };
+        assert!(buckets == [0, base + 0x10], "First node's bucket should point to __p1_");
+        Ok(())
+    }
+
+    #[test]
+    pub fn bucket_growth() -> TestReturn {
+        let mut table = HashTable::<StdHash, i32, i32, Global>::new_standalone_inner(Global);
+        let mut counts = vec![];
+        for i in 0..100 {
+            table.insert(i);
+            if counts.last() != Some(&table.bucket_count()) { counts.push(table.bucket_count()); }
+        }
+        assert!(counts == [2, 5, 11, 23, 47, 97, 197], "Bucket counts should grow to the next prime after doubling");
+        // Power of 2 bucket counts use a mask and keep growing by powers of 2
+        let mut table = HashTable::<StdHash, i32, i32, Global>::new_standalone_inner(Global);
+        table.rehash(8);
+        for i in 0..9 { table.insert(i * 3); }
+        assert!(table.bucket_count() == 16, "Table should double to 16 buckets");
+        assert!(table.bucket(&45) == 13, "45 should go into bucket 45 & 15");
+        Ok(())
+    }
+
+    #[test]
+    pub fn insert_into_hash_table() -> TestReturn {
+        let mut table = HashTable::<StdHash, i32, i32, Global>::new_standalone_inner(Global);
+        table.insert(1);
+        table.insert(2);
+        check_buckets(&table, &[2, 1], &[(0, &[2]), (1, &[1])]);
+        // rehash to 5 buckets: 2 goes into bucket 2, then 1 into bucket 1 after it
+        table.insert(3);
+        check_buckets(&table, &[3, 2, 1], &[(1, &[1]), (2, &[2]), (3, &[3])]);
+        // colliding values are added to the start of the bucket
+        table.insert(6);
+        table.insert(8);
+        assert!(!table.insert(8), "Duplicate keys should be rejected");
+        check_buckets(&table, &[8, 3, 2, 6, 1], &[(1, &[6, 1]), (2, &[2]), (3, &[8, 3])]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn erase_from_hash_table() -> TestReturn {
+        let mut table = HashTable::<StdHash, i32, i32, Global>::new_standalone_inner(Global);
+        for v in [1, 2, 3, 6, 8] { table.insert(v); }
+        // removing the first node of a bucket that's at the start of the list
+        assert!(table.erase(&3), "Erasing 3 should succeed");
+        check_buckets(&table, &[8, 2, 6, 1], &[(1, &[6, 1]), (2, &[2]), (3, &[8])]);
+        // removing the only node in a bucket
+        assert!(table.erase(&2), "Erasing 2 should succeed");
+        check_buckets(&table, &[8, 6, 1], &[(1, &[6, 1]), (3, &[8])]);
+        assert!(!table.erase(&2), "2 was already erased");
+        assert!(table.erase(&8), "Erasing 8 should succeed");
+        check_buckets(&table, &[6, 1], &[(1, &[6, 1])]);
+        assert!(table.erase(&1) && table.erase(&6), "Erasing the rest should succeed");
+        assert!(table.is_empty() && table.iter().next().is_none(), "Table should be empty");
+        table.insert(-1);
+        // std::hash<int> sign extends, so this goes into bucket 0xffffffffffffffff % 5
+        assert!(table.bucket(&-1) == 0, "-1 should go into bucket 0");
+        assert!(table.contains(&-1), "Table should contain -1");
+        Ok(())
+    }
+
+    #[test]
+    pub fn rehash_hash_table() -> TestReturn {
+        let mut table = HashTable::<StdHash, i32, i32, Global>::new_standalone_inner(Global);
+        for i in 0..20 { table.insert(i * 5); }
+        assert!(table.bucket_count() == 23, "Table should have 23 buckets");
+        // shrinking can't go below the number of elements
+        table.rehash(2);
+        assert!(table.bucket_count() == 23, "Rehashing to 2 shouldn't shrink the table");
+        table.set_max_load_factor(4.0);
+        let before: Vec<i32> = table.iter().copied().collect();
+        table.rehash(2);
+        assert!(table.bucket_count() == 5, "Table should shrink to 5 buckets");
+        // every element is a multiple of 5, so they all end up in bucket 0 in the same order
+        check_buckets(&table, &before, &[(0, &before)]);
+        table.set_max_load_factor(0.5);
+        assert!(table.max_load_factor() == 4.0, "Max load factor can't go below the load factor");
+        table.reserve(100);
+        assert!(table.bucket_count() == 29, "Table should reserve 29 buckets for 100 elements at a 4.0 load factor");
+        assert!((0..20).all(|i| table.contains(&(i * 5))), "Every element should still exist");
+        // rehash(0) shrinks as far as the elements allow
+        table.rehash(0);
+        assert!(table.bucket_count() == 5, "Rehashing to 0 should shrink the table to 5 buckets");
+        table.clear();
+        table.rehash(0);
+        assert!(table.bucket_count() == 0, "Rehashing an empty table to 0 should free the buckets");
+        table.reserve(0);
+        table.insert(7);
+        assert!(table.contains(&7) && table.bucket_count() == 2, "Table should grow again after freeing the buckets");
+        Ok(())
+    }
+
+    #[test]
+    pub fn hash_table_as_map() -> TestReturn {
+        let mut map = Map::<StdHash, u32, &str>::new_standalone();
+        map.insert(1, "Player");
+        map.insert(2, "Enemy");
+        assert!(!map.insert(1, "Chest"), "Key 1 should already exist");
+        assert!(*map[&1].get_value() == "Player", "Key 1 should be Player");
+        *map.find_mut(&2).unwrap().get_value_mut() = "Ally";
+        assert!(*map.find(&2).unwrap().get_value() == "Ally", "Key 2 should be Ally");
+        assert!(map.erase(&1) && !map.contains(&1), "Key 1 should be erased");
+        assert!(map.size() == 1, "Map should have 1 entry");
+        let mut set = Set::<StdHash, u64>::new_standalone();
+        for i in 0..100 { set.insert(i); }
+        assert!(set.size() == 100 && set.bucket_count() == 197, "Set should have 100 entries in 197 buckets");
+        Ok(())
+    }
+
+    #[test]
+    pub fn relocate_hash_table() -> TestReturn {
+        let mut table: HashTable<StdHash, i32, i32, Global> = unsafe { HashTable::new_inner(Global) };
+        for i in 0..4 { table.insert(i); }
+        let mut moved = Box::new(table);
+        unsafe { moved.setup_pointers() };
+        moved.erase(&3);
+        moved.insert(4);
+        assert!(moved.iter().copied().collect::<Vec<_>>() == [4, 2, 1, 0], "Iteration order doesn't match");
+        Ok(())
+    }
+}
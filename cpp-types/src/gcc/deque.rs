@@ -0,0 +1,509 @@
+//! Rust reimplementation of libstdc++'s std::deque implementation
+
+#![allow(dead_code)]
+use allocator_api2::alloc::{ Allocator, Global };
+use std::{
+    alloc::Layout,
+    fmt::Display,
+    marker::PhantomData,
+    ops::{ Index, IndexMut },
+    ptr::NonNull
+};
+
+// https://github.com/gcc-mirror/gcc/blob/master/libstdc++-v3/include/bits/stl_deque.h
+// https://github.com/gcc-mirror/gcc/blob/master/libstdc++-v3/include/bits/deque.tcc
+
+// _GLIBCXX_DEQUE_BUF_SIZE
+const DEQUE_BUF_SIZE: usize = 512;
+// _S_initial_map_size
+const INITIAL_MAP_SIZE: usize = 8;
+
+#[repr(C)]
+struct DequePosition<T> // _Deque_iterator
+{
+    cur: *mut T, // _M_cur
+    first: *mut T, // _M_first
+    last: *mut T, // _M_last
+    node: *mut *mut T // _M_node
+}
+
+impl<T> Clone for DequePosition<T> {
+    fn clone(&self) -> Self { *self }
+}
+impl<T> Copy for DequePosition<T> {}
+
+impl<T> DequePosition<T> {
+    // _M_set_node
+    unsafe fn set_node(&mut self, node: *mut *mut T, buffer_size: usize) {
+        self.node = node;
+        self.first = *node;
+        self.last = self.first.add(buffer_size);
+    }
+    fn empty() -> Self {
+        Self {
+            cur: std::ptr::null_mut(),
+            first: std::ptr::null_mut(),
+            last: std::ptr::null_mut(),
+            node: std::ptr::null_mut()
+        }
+    }
+}
+
+/// A double ended queue made of fixed size buffers, with the used buffers kept in the middle of
+/// a map of buffer pointers. Map entries outside of start and finish are left uninitialized.
+/// Unlike MSVC's deque, buffers are freed as soon as they're emptied by a pop.
+///
+/// Elements are found by pointer differences within a buffer, so zero-sized types (which can't
+/// exist in C++) are rejected at compile time:
+/// ```compile_fail
+/// let mut deque = cpp_types::gcc::deque::Deque::<()>::new();
+/// deque.push_back(());
+/// ```
+#[repr(C)]
+pub struct Deque<T, A = Global> // std::deque
+where A: Allocator + Clone
+{
+    map: *mut *mut T, // _M_map
+    map_size: usize, // _M_map_size
+    start: DequePosition<T>, // _M_start
+    finish: DequePosition<T>, // _M_finish
+    _allocator: A
+}
+
+impl<T> Deque<T, Global> {
+    pub fn new() -> Self { Self::new_in(Global) }
+    pub fn from_vec(vec: Vec<T>) -> Self { Self::from_vec_in(vec, Global) }
+}
+
+impl<T> Default for Deque<T, Global> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T, A> Deque<T, A>
+where A: Allocator + Clone
+{
+    /// Number of elements per buffer (__deque_buf_size), which fits 512 bytes of elements with
+    /// at least one element per buffer
+    pub const BUFFER_SIZE: usize = match std::mem::size_of::<T>() {
+        s if s < DEQUE_BUF_SIZE => DEQUE_BUF_SIZE / s,
+        _ => 1
+    };
+    const NOT_ZERO_SIZED: () = assert!(std::mem::size_of::<T>() != 0, "Element type can't be zero-sized!");
+
+    pub fn new_in(alloc: A) -> Self {
+        let () = Self::NOT_ZERO_SIZED;
+        assert!(std::mem::size_of::<A>() == 0, "Allocator must be zero-sized!");
+        let mut new = Self {
+            map: std::ptr::null_mut(),
+            map_size: 0,
+            start: DequePosition::empty(),
+            finish: DequePosition::empty(),
+            _allocator: alloc
+        };
+        new.initialize_map(0);
+        new
+    }
+    pub fn from_vec_in(vec: Vec<T>, alloc: A) -> Self {
+        let mut new = Self::new_in(alloc);
+        for v in vec { new.push_back(v); }
+        new
+    }
+
+    fn buffer_layout() -> Layout { Layout::array::<T>(Self::BUFFER_SIZE).unwrap() }
+    fn map_layout(size: usize) -> Layout { Layout::array::<*mut T>(size).unwrap() }
+    // _M_allocate_node
+    fn allocate_node(&self) -> *mut T {
+        self._allocator.allocate(Self::buffer_layout()).unwrap().as_ptr() as *mut T
+    }
+    // _M_deallocate_node
+    unsafe fn deallocate_node(&self, node: *mut T) {
+        self._allocator.deallocate(NonNull::new_unchecked(node as *mut u8), Self::buffer_layout());
+    }
+    // _M_allocate_map
+    fn allocate_map(&self, size: usize) -> *mut *mut T {
+        self._allocator.allocate(Self::map_layout(size)).unwrap().as_ptr() as *mut *mut T
+    }
+
+    // _M_initialize_map: even an empty deque has a map and one buffer
+    fn initialize_map(&mut self, num_elements: usize) {
+        let num_nodes = num_elements / Self::BUFFER_SIZE + 1;
+        self.map_size = INITIAL_MAP_SIZE.max(num_nodes + 2);
+        self.map = self.allocate_map(self.map_size);
+        unsafe {
+            let start = self.map.add((self.map_size - num_nodes) / 2);
+            let finish = start.add(num_nodes);
+            let mut node = start;
+            while node < finish {
+                *node = self.allocate_node();
+                node = node.add(1);
+            }
+            self.start.set_node(start, Self::BUFFER_SIZE);
+            self.finish.set_node(finish.sub(1), Self::BUFFER_SIZE);
+            self.start.cur = self.start.first;
+            self.finish.cur = self.finish.first.add(num_elements % Self::BUFFER_SIZE);
+        }
+    }
+
+    // _M_reallocate_map: makes room for nodes_to_add more buffers at one end of the map, either
+    // by recentering the used nodes or by moving them into a larger map
+    fn reallocate_map(&mut self, nodes_to_add: usize, add_at_front: bool) {
+        unsafe {
+            let old_num_nodes = self.finish.node.offset_from(self.start.node) as usize + 1;
+            let new_num_nodes = old_num_nodes + nodes_to_add;
+            let front_offset = if add_at_front { nodes_to_add } else { 0 };
+            let new_start;
+            if self.map_size > 2 * new_num_nodes {
+                new_start = self.map.add((self.map_size - new_num_nodes) / 2 + front_offset);
+                std::ptr::copy(self.start.node, new_start, old_num_nodes);
+            } else {
+                let new_map_size = self.map_size + self.map_size.max(nodes_to_add) + 2;
+                let new_map = self.allocate_map(new_map_size);
+                new_start = new_map.add((new_map_size - new_num_nodes) / 2 + front_offset);
+                std::ptr::copy_nonoverlapping(self.start.node, new_start, old_num_nodes);
+                self._allocator.deallocate(NonNull::new_unchecked(self.map as *mut u8), Self::map_layout(self.map_size));
+                self.map = new_map;
+                self.map_size = new_map_size;
+            }
+            self.start.set_node(new_start, Self::BUFFER_SIZE);
+            self.finish.set_node(new_start.add(old_num_nodes - 1), Self::BUFFER_SIZE);
+        }
+    }
+    // _M_reserve_map_at_back
+    fn reserve_map_at_back(&mut self, nodes_to_add: usize) {
+        let finish_node = unsafe { self.finish.node.offset_from(self.map) as usize };
+        if nodes_to_add + 1 > self.map_size - finish_node {
+            self.reallocate_map(nodes_to_add, false);
+        }
+    }
+    // _M_reserve_map_at_front
+    fn reserve_map_at_front(&mut self, nodes_to_add: usize) {
+        let start_node = unsafe { self.start.node.offset_from(self.map) as usize };
+        if nodes_to_add > start_node {
+            self.reallocate_map(nodes_to_add, true);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe {
+            let nodes = self.finish.node.offset_from(self.start.node);
+            let len = (Self::BUFFER_SIZE as isize) * (nodes - 1)
+                + self.finish.cur.offset_from(self.finish.first)
+                + self.start.last.offset_from(self.start.cur);
+            len as usize
+        }
+    }
+    pub fn is_empty(&self) -> bool { self.start.cur == self.finish.cur }
+    /// Returns the number of buffer pointers in the map
+    pub fn map_size(&self) -> usize { self.map_size }
+
+    pub fn push_back(&mut self, value: T) {
+        unsafe {
+            if self.finish.cur != self.finish.last.sub(1) {
+                std::ptr::write(self.finish.cur, value);
+                self.finish.cur = self.finish.cur.add(1);
+            } else {
+                // _M_push_back_aux
+                self.reserve_map_at_back(1);
+                *self.finish.node.add(1) = self.allocate_node();
+                std::ptr::write(self.finish.cur, value);
+                self.finish.set_node(self.finish.node.add(1), Self::BUFFER_SIZE);
+                self.finish.cur = self.finish.first;
+            }
+        }
+    }
+    pub fn push_front(&mut self, value: T) {
+        unsafe {
+            if self.start.cur != self.start.first {
+                std::ptr::write(self.start.cur.sub(1), value);
+                self.start.cur = self.start.cur.sub(1);
+            } else {
+                // _M_push_front_aux
+                self.reserve_map_at_front(1);
+                *self.start.node.sub(1) = self.allocate_node();
+                self.start.set_node(self.start.node.sub(1), Self::BUFFER_SIZE);
+                self.start.cur = self.start.last.sub(1);
+                std::ptr::write(self.start.cur, value);
+            }
+        }
+    }
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() { return None }
+        unsafe {
+            if self.finish.cur == self.finish.first {
+                // _M_pop_back_aux
+                self.deallocate_node(self.finish.first);
+                self.finish.set_node(self.finish.node.sub(1), Self::BUFFER_SIZE);
+                self.finish.cur = self.finish.last;
+            }
+            self.finish.cur = self.finish.cur.sub(1);
+            Some(std::ptr::read(self.finish.cur))
+        }
+    }
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() { return None }
+        unsafe {
+            let value = std::ptr::read(self.start.cur);
+            if self.start.cur != self.start.last.sub(1) {
+                self.start.cur = self.start.cur.add(1);
+            } else {
+                // _M_pop_front_aux
+                self.deallocate_node(self.start.first);
+                self.start.set_node(self.start.node.add(1), Self::BUFFER_SIZE);
+                self.start.cur = self.start.first;
+            }
+            Some(value)
+        }
+    }
+
+    // Same as _M_start + index
+    fn element(&self, index: usize) -> *mut T {
+        unsafe {
+            let offset = index + self.start.cur.offset_from(self.start.first) as usize;
+            (*self.start.node.add(offset / Self::BUFFER_SIZE)).add(offset % Self::BUFFER_SIZE)
+        }
+    }
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len() { return None }
+        Some(unsafe { &*self.element(index) })
+    }
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len() { return None }
+        Some(unsafe { &mut *self.element(index) })
+    }
+
+    pub fn iter(&self) -> DequeIterator<'_, T, A> { self.into_iter() }
+    pub fn iter_mut(&mut self) -> DequeIteratorMut<'_, T, A> { self.into_iter() }
+
+    // _M_destroy_data and _M_destroy_nodes for every buffer after the first
+    fn erase_at_begin(&mut self) {
+        unsafe {
+            for i in 0..self.len() { std::ptr::drop_in_place(self.element(i)); }
+            let mut node = self.start.node.add(1);
+            while node <= self.finish.node {
+                self.deallocate_node(*node);
+                node = node.add(1);
+            }
+        }
+        self.finish = self.start;
+    }
+}
+
+// C++ API
+impl<T, A> Deque<T, A>
+where A: Allocator + Clone
+{
+    /// Checks if the container has no elements
+    pub fn empty(&self) -> bool { self.is_empty() }
+    /// Returns the number of elements in the container
+    pub fn size(&self) -> usize { self.len() }
+    /// Erases all elements from the container. The map and the first buffer are kept.
+    pub fn clear(&mut self) { self.erase_at_begin() }
+    /// Returns a reference to the element at specified location index, with bounds checking.
+    pub fn at(&self, index: usize) -> Option<&T> { self.get(index) }
+    /// Returns a mutable reference to the element at specified location index, with bounds checking.
+    pub fn at_mut(&mut self, index: usize) -> Option<&mut T> { self.get_mut(index) }
+    /// Returns a reference to the first element, or None if the container is empty
+    pub fn front(&self) -> Option<&T> { self.get(0) }
+    /// Returns a mutable reference to the first element, or None if the container is empty
+    pub fn front_mut(&mut self) -> Option<&mut T> { self.get_mut(0) }
+    /// Returns a reference to the last element, or None if the container is empty
+    pub fn back(&self) -> Option<&T> { self.get(self.len().wrapping_sub(1)) }
+    /// Returns a mutable reference to the last element, or None if the container is empty
+    pub fn back_mut(&mut self) -> Option<&mut T> { self.get_mut(self.len().wrapping_sub(1)) }
+}
+
+impl<T, A> Drop for Deque<T, A>
+where A: Allocator + Clone
+{
+    fn drop(&mut self) {
+        if self.map.is_null() { return }
+        self.erase_at_begin();
+        unsafe {
+            self.deallocate_node(self.start.first);
+            self._allocator.deallocate(NonNull::new_unchecked(self.map as *mut u8), Self::map_layout(self.map_size));
+        }
+    }
+}
+
+impl<T, A> Index<usize> for Deque<T, A>
+where A: Allocator + Clone
+{
+    type Output = T;
+    fn index(&self, index: usize) -> &Self::Output {
+        assert!(index < self.len(), "Tried to index a deque out of bounds");
+        unsafe { &*self.element(index) }
+    }
+}
+
+impl<T, A> IndexMut<usize> for Deque<T, A>
+where A: Allocator + Clone
+{
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        assert!(index < self.len(), "Tried to index a deque out of bounds");
+        unsafe { &mut *self.element(index) }
+    }
+}
+
+impl<T, A> Display for Deque<T, A>
+where T: Display,
+      A: Allocator + Clone
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values: Vec<std::string::String> = self.iter().map(|v| format!("{}", v)).collect();
+        write!(f, "Deque [ {} ]", values.join(", "))
+    }
+}
+
+pub struct DequeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    deque: &'a Deque<T, A>,
+    front: usize,
+    back: usize
+}
+
+impl<'a, T, A> Iterator for DequeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back { return None }
+        self.front += 1;
+        Some(unsafe { &*self.deque.element(self.front - 1) })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.back - self.front, Some(self.back - self.front)) }
+}
+
+impl<T, A> DoubleEndedIterator for DequeIterator<'_, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back { return None }
+        self.back -= 1;
+        Some(unsafe { &*self.deque.element(self.back) })
+    }
+}
+
+impl<T, A> ExactSizeIterator for DequeIterator<'_, T, A>
+where A: Allocator + Clone {}
+
+pub struct DequeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    deque: *mut Deque<T, A>,
+    front: usize,
+    back: usize,
+    _marker: PhantomData<&'a mut T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for DequeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back { return None }
+        self.front += 1;
+        Some(unsafe { &mut *(*self.deque).element(self.front - 1) })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.back - self.front, Some(self.back - self.front)) }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for DequeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back { return None }
+        self.back -= 1;
+        Some(unsafe { &mut *(*self.deque).element(self.back) })
+    }
+}
+
+impl<'a, T: 'a, A: 'a> ExactSizeIterator for DequeIteratorMut<'a, T, A>
+where A: Allocator + Clone {}
+
+impl<'a, T, A> IntoIterator for &'a Deque<T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    type IntoIter = DequeIterator<'a, T, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        DequeIterator { deque: self, front: 0, back: self.len() }
+    }
+}
+
+impl<'a, T, A> IntoIterator for &'a mut Deque<T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    type IntoIter = DequeIteratorMut<'a, T, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        DequeIteratorMut { front: 0, back: self.len(), deque: self, _marker: PhantomData }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::generic::deque::check_against_vec_deque;
+    use super::Deque;
+    use std::error::Error;
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    // Index of the start and finish buffers in the map, and the offset of each cursor in its
+    // buffer
+    fn positions<T>(deque: &Deque<T>) -> (usize, usize, usize, usize) {
+        unsafe {(
+            deque.start.node.offset_from(deque.map) as usize,
+            deque.finish.node.offset_from(deque.map) as usize,
+            deque.start.cur.offset_from(deque.start.first) as usize,
+            deque.finish.cur.offset_from(deque.finish.first) as usize
+        )}
+    }
+
+    #[test]
+    pub fn deque_layout() -> TestReturn {
+        assert!(std::mem::size_of::<Deque<u32>>() == 80, "Deque should be the map, map size and two iterators");
+        let sizes = [Deque::<u8>::BUFFER_SIZE, Deque::<u64>::BUFFER_SIZE];
+        assert!(sizes == [512, 64], "Buffers should hold 512 bytes, got {:?}", sizes);
+        let sizes = [Deque::<[u8; 200]>::BUFFER_SIZE, Deque::<[u8; 600]>::BUFFER_SIZE];
+        assert!(sizes == [2, 1], "Large elements should be one per buffer, got {:?}", sizes);
+        let deque: Deque<u64> = Deque::new();
+        assert!(deque.map_size == 8 && positions(&deque) == (3, 3, 0, 0), "Empty deque should have one buffer in the middle of the map");
+        Ok(())
+    }
+
+    // Positions were taken from the same operations on a std::deque<uint64_t> built with g++
+    #[test]
+    pub fn deque_reallocate_map() -> TestReturn {
+        let mut deque = Deque::new();
+        for i in 0..319u64 { deque.push_back(i); }
+        assert!(deque.map_size == 8 && positions(&deque) == (3, 7, 0, 63), "Map should fill up to the end");
+        deque.push_back(319);
+        assert!(deque.map_size == 18 && positions(&deque) == (6, 11, 0, 0), "Map should grow to 18 and recenter");
+        assert!(deque.iter().copied().eq(0..320), "Values should survive reallocation");
+
+        let mut deque = Deque::new();
+        for i in 0..193u64 { deque.push_front(i); }
+        assert!(deque.map_size == 18 && positions(&deque) == (6, 10, 63, 0), "Map should grow from the front");
+        assert!(deque.iter().copied().eq((0..193).rev()), "Front values should be in reverse order");
+
+        let mut deque = Deque::new();
+        for i in 0..256u64 { deque.push_back(i); }
+        for _ in 0..192 { deque.pop_front(); }
+        assert!(deque.map_size == 8 && positions(&deque) == (6, 7, 0, 0), "Popped buffers should be freed");
+        let map = deque.map;
+        for i in 0..64 { deque.push_back(i); }
+        assert!(deque.map == map && positions(&deque) == (2, 4, 0, 0), "Nodes should be recentered in the same map");
+        deque.clear();
+        assert!(deque.is_empty() && positions(&deque) == (2, 2, 0, 0), "Clear should keep the first buffer");
+        deque.push_front(1);
+        assert!(deque.pop_back() == Some(1) && deque.pop_back().is_none(), "Cleared deque should still be usable");
+        Ok(())
+    }
+
+    #[test]
+    pub fn deque_matches_vec_deque() -> TestReturn {
+        check_against_vec_deque!(Deque::new(), 5000, |v| [v; 20], |v: &mut [u32; 20]| v[0] += 1, |_| ());
+        Ok(())
+    }
+}
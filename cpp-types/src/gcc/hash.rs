@@ -0,0 +1,157 @@
+pub use crate::generic::hash::HasherInit;
+use crate::generic::hash::HashedValue;
+use std::hash::{ Hash, Hasher };
+
+// https://github.com/gcc-mirror/gcc/blob/master/libstdc++-v3/include/bits/functional_hash.h
+// https://github.com/gcc-mirror/gcc/blob/master/libstdc++-v3/libsupc++/hash_bytes.cc
+
+/// Seed used by std::_Hash_impl, which backs std::hash for strings and floating point types
+pub const HASH_SEED: usize = 0xc70f6907;
+
+/// libstdc++'s std::hash. Integral types (including bool, char and enums) hash to their own
+/// value, with signed types being sign extended to size_t. Byte sequences (strings) are hashed
+/// with std::_Hash_bytes using [`HASH_SEED`]. Like std::hash, this hashes a single value, so
+/// `str` and `String` hash their bytes, while keys that write more than once (tuples, slices and
+/// most derived Hash impls) need their own hasher and trip a debug assertion.
+pub struct StdHash {
+    hash: u64,
+    value: HashedValue
+}
+
+crate::generic::hash::single_value_hasher!(StdHash, |bytes| hash_bytes(bytes, HASH_SEED));
+
+/// std::_Hash_bytes for the target's size_t
+#[cfg(target_pointer_width = "64")]
+pub fn hash_bytes(bytes: &[u8], seed: usize) -> usize { hash_bytes_64(bytes, seed as u64) as usize }
+
+/// std::_Hash_bytes for the target's size_t
+#[cfg(target_pointer_width = "32")]
+pub fn hash_bytes(bytes: &[u8], seed: usize) -> usize { hash_bytes_32(bytes, seed as u32) as usize }
+
+fn shift_mix(v: u64) -> u64 { v ^ v >> 47 }
+
+/// std::_Hash_bytes when size_t is 8 bytes (64-bit MurmurHash2 variant)
+pub fn hash_bytes_64(bytes: &[u8], seed: u64) -> u64 {
+    const MUL: u64 = (0xc6a4a793 << 32) + 0x5bd1e995;
+    let mut hash = seed ^ (bytes.len() as u64).wrapping_mul(MUL);
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+        let data = shift_mix(u64::from_le_bytes(chunk.try_into().unwrap()).wrapping_mul(MUL)).wrapping_mul(MUL);
+        hash ^= data;
+        hash = hash.wrapping_mul(MUL);
+    }
+    let rem = chunks.remainder();
+    if !rem.is_empty() {
+        // load_bytes: the remaining bytes as a little endian integer
+        let data = rem.iter().rev().fold(0u64, |acc, b| (acc << 8) + *b as u64);
+        hash ^= data;
+        hash = hash.wrapping_mul(MUL);
+    }
+    hash = shift_mix(hash).wrapping_mul(MUL);
+    shift_mix(hash)
+}
+
+/// std::_Hash_bytes when size_t is 4 bytes (32-bit MurmurHash2)
+pub fn hash_bytes_32(bytes: &[u8], seed: u32) -> u32 {
+    const M: u32 = 0x5bd1e995;
+    let mut hash = seed ^ bytes.len() as u32;
+    let mut chunks = bytes.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes(chunk.try_into().unwrap());
+        k = k.wrapping_mul(M);
+        k ^= k >> 24;
+        k = k.wrapping_mul(M);
+        hash = hash.wrapping_mul(M);
+        hash ^= k;
+    }
+    let rem = chunks.remainder();
+    if !rem.is_empty() {
+        for (i, b) in rem.iter().enumerate() {
+            hash ^= (*b as u32) << (i * 8);
+        }
+        hash = hash.wrapping_mul(M);
+    }
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(M);
+    hash ^ hash >> 15
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::gcc::string::String;
+    use super::{ hash_bytes, hash_bytes_32, hash_bytes_64, HasherInit, StdHash, HASH_SEED };
+    use std::error::Error;
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    const LOREM: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam";
+
+    #[test]
+    pub fn hash_integers() -> TestReturn {
+        assert!(StdHash::get_hash(&5u32) == 5, "std::hash<unsigned int> should be identity");
+        assert!(StdHash::get_hash(&0x1234_5678_9abc_def0u64) == 0x1234_5678_9abc_def0, "std::hash<unsigned long> should be identity");
+        assert!(StdHash::get_hash(&-1i32) == u64::MAX, "std::hash<int> should sign extend");
+        assert!(StdHash::get_hash(&-2i8) == u64::MAX - 1, "std::hash<signed char> should sign extend");
+        assert!(StdHash::get_hash(&true) == 1, "std::hash<bool> should be identity");
+        assert!(StdHash::get_hash(&'A') == 0x41, "std::hash<char32_t> should be identity");
+        Ok(())
+    }
+
+    #[test]
+    pub fn hash_bytes_64_bit() -> TestReturn {
+        // values from std::hash<std::string> on x86_64 libstdc++
+        let expected = [
+            ("", 0x553e93901e462a6e),
+            ("A", 0x600668de4345e18e),
+            ("Door", 0xfbb371c54a44a2f5),
+            ("Chest", 0x95cc172e95f6c686),
+            ("Player", 0x320a147ffd3f7948),
+            ("Lorem ip", 0xcd1f5102dd12da80),
+            ("Lorem ips", 0x6be955c48daedf73),
+            ("Lorem ipsum dolor", 0xe4871ba71dc933b2),
+            (&LOREM[..64], 0xf44f07770a14f6b1),
+            (LOREM, 0x71a076d8953a4d33)
+        ];
+        for (s, hash) in expected {
+            let res = hash_bytes_64(s.as_bytes(), HASH_SEED as u64);
+            assert!(res == hash, "Hash of {:?} should be {:#x} instead of {:#x}", s, hash, res);
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn hash_bytes_32_bit() -> TestReturn {
+        let expected = [
+            ("", 0xedd39288),
+            ("A", 0x168ebef9),
+            ("Door", 0x575aeec2),
+            ("Chest", 0xb2b88e3a),
+            ("Player", 0x3b129d36),
+            ("Lorem ipsum dolor", 0xba6dc15d)
+        ];
+        for (s, hash) in expected {
+            let res = hash_bytes_32(s.as_bytes(), HASH_SEED as u32);
+            assert!(res == hash, "Hash of {:?} should be {:#x} instead of {:#x}", s, hash, res);
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    pub fn hash_cpp_string() -> TestReturn {
+        let player = String::from_str_standalone("Player");
+        assert!(StdHash::get_hash(&*player) == 0x320a147ffd3f7948, "std::hash<std::string> should use _Hash_bytes");
+        let lorem = String::from_str_standalone(LOREM);
+        assert!(StdHash::get_hash(&*lorem) == 0x71a076d8953a4d33, "std::hash<std::string> should use _Hash_bytes");
+        Ok(())
+    }
+
+    #[test]
+    pub fn hash_rust_strings() -> TestReturn {
+        let expected = hash_bytes(b"Player", HASH_SEED) as u64;
+        assert!(StdHash::get_hash(&"Player") == expected, "str should hash its bytes with _Hash_bytes");
+        assert!(StdHash::get_hash(&std::string::String::from("Player")) == expected, "String should hash its bytes with _Hash_bytes");
+        assert!(StdHash::get_hash(&"Door") != expected, "Different strings should hash differently");
+        Ok(())
+    }
+}
@@ -54,13 +54,16 @@ where T: CharBehavior + PartialEq,
 }
 
 impl String<u8, Global> {
+    #[allow(clippy::missing_safety_doc)]
     pub unsafe fn new() -> Self { Self::new_using(Global) }
     pub fn new_standalone() -> ABox<Self, Global> { Self::new_standalone_using(Global) }
+    #[allow(clippy::missing_safety_doc)]
     pub unsafe fn from_str(text: &str) -> Self { Self::from_str_in(text, Global) }
     pub fn from_str_standalone(text: &str) -> ABox<Self, Global> { Self::from_str_in_standalone(text, Global) }
 }
 
 impl String<u16, Global> {
+    #[allow(clippy::missing_safety_doc)]
     pub unsafe fn new_wide() -> Self { Self::new_using_wide(Global) }
     pub fn new_standalone_wide() -> ABox<Self, Global> { Self::new_standalong_using_wide(Global) }
 }
@@ -68,6 +71,7 @@ impl String<u16, Global> {
 impl<A> String<u8, A>
 where A: Allocator + Clone
 {
+    #[allow(clippy::missing_safety_doc)]
     pub unsafe fn new_using(alloc: A) -> Self { Self::new_in(alloc) }
     pub fn new_standalone_using(alloc: A) -> ABox<Self, A> { Self::new_standalone_in(alloc) }
 }
@@ -75,6 +79,7 @@ where A: Allocator + Clone
 impl<A> String<u16, A>
 where A: Allocator + Clone
 {
+    #[allow(clippy::missing_safety_doc)]
     pub unsafe fn new_using_wide(alloc: A) -> Self { Self::new_in(alloc) }
     pub fn new_standalong_using_wide(alloc: A) -> ABox<Self, A> { Self::new_standalone_in(alloc) }
 }
@@ -83,6 +88,7 @@ impl<T, A> String<T, A>
 where T: CharBehavior + PartialEq,
       A: Allocator + Clone
 {
+    #[allow(clippy::missing_safety_doc)]
     pub unsafe fn new_in(alloc: A) -> Self {
         assert_eq!(size_of::<A>(), 0, "Allocator must be zero-sized!");
         Self {
@@ -103,6 +109,7 @@ where T: CharBehavior + PartialEq,
         new
     }
 
+    #[allow(clippy::missing_safety_doc)]
     pub unsafe fn setup_pointers(&mut self) {
         if self.size < MAX_STORAGE_SIZE / size_of::<T>() {
             self.ptr = unsafe { NonNull::new_unchecked(self.storage.buf.as_ptr() as _) };
@@ -120,6 +127,7 @@ where T: CharBehavior + PartialEq,
         n < MAX_STORAGE_SIZE / size_of::<T>()
     }
 
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         self.size
     }
@@ -146,6 +154,7 @@ where T: CharBehavior + PartialEq,
         )
     }
 
+    #[allow(clippy::missing_transmute_annotations, clippy::ptr_eq)]
     fn drop_inner(&mut self) {
         if self.ptr.as_ptr() != self.storage.get_buf() as _ {
             unsafe { self._allocator.deallocate(std::mem::transmute(self.ptr.as_ptr()), self.get_layout()) }
@@ -199,6 +208,7 @@ where T: CharBehavior + PartialEq,
 impl<A> String<u8, A>
 where A: Allocator + Clone
 {
+    #[allow(clippy::missing_safety_doc)]
     pub unsafe fn from_str_in(text: &str, alloc: A) -> Self {
         let mut new = Self::new_in(alloc);
         // +1 to account for null terminator
@@ -0,0 +1,926 @@
+//! Rust reimplementation of libstdc++'s std::map/std::set implementation (_Rb_tree)
+
+#![allow(dead_code, unused_imports)]
+use allocator_api2::{
+    alloc::{ Allocator, Global },
+    boxed::Box as ABox
+};
+pub use crate::generic::tree::{ CompareGreater, CompareLess, MapPair, TreeCompare };
+use std::{
+    alloc::Layout,
+    fmt::Debug,
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ptr::NonNull
+};
+
+// https://github.com/gcc-mirror/gcc/blob/master/libstdc++-v3/include/bits/stl_tree.h
+// https://github.com/gcc-mirror/gcc/blob/master/libstdc++-v3/src/c++98/tree.cc
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NodeColor { // _Rb_tree_color
+    Red = 0, // _S_red
+    Black // _S_black
+}
+
+#[repr(C)]
+pub struct TreeNodeBase<T, A = Global> // _Rb_tree_node_base
+where A: Allocator + Clone
+{
+    color: NodeColor, // _M_color
+    parent: *mut TreeNode<T, A>, // _M_parent
+    left: *mut TreeNode<T, A>, // _M_left
+    right: *mut TreeNode<T, A>, // _M_right
+}
+
+#[repr(C)]
+pub struct TreeNode<T, A = Global> // _Rb_tree_node
+where A: Allocator + Clone
+{
+    color: NodeColor, // _M_color
+    parent: *mut Self, // _M_parent
+    left: *mut Self, // _M_left
+    right: *mut Self, // _M_right
+    data: ManuallyDrop<T>, // _M_storage
+    _allocator: A
+}
+
+#[repr(C)]
+pub struct Tree<C, T0, T1, A = Global>
+where C: TreeCompare<T0, T1>,
+      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    // std::less is empty, but it's stored as a member of _Rb_tree_key_compare so it still takes
+    // up a byte before the header. Only empty comparators are supported for now, since a
+    // stateful one would replace that byte.
+    compare: C,
+    _key_compare: u8, // _M_key_compare
+    // The header is embedded in the tree: parent is the root, left is the leftmost node and right
+    // is the rightmost node. Since leaf nodes point to it, call setup_pointers() after moving
+    // the tree.
+    header: TreeNodeBase<T0, A>, // _M_header
+    size: usize, // _M_node_count
+    _allocator: A,
+    _key_ty: PhantomData<T1>
+}
+
+impl<C, T0, T1, A> Tree<C, T0, T1, A>
+where C: TreeCompare<T0, T1>,
+      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    /// # Safety
+    ///
+    /// The tree is unusable until [`Tree::setup_pointers`] is called at its final address.
+    pub unsafe fn new_inner(alloc: A) -> Self
+    where C: Default
+    {
+        assert!(std::mem::size_of::<A>() == 0, "Allocator must be zero-sized!");
+        assert!(std::mem::size_of::<C>() == 0, "Comparator must be zero-sized!");
+        Self {
+            compare: C::default(),
+            _key_compare: 0,
+            // left and right should point to the header, but we can't do that until the tree has
+            // settled. Call setup_pointers() after this
+            header: TreeNodeBase {
+                color: NodeColor::Red,
+                parent: std::ptr::null_mut(),
+                left: std::ptr::null_mut(),
+                right: std::ptr::null_mut()
+            },
+            size: 0,
+            _allocator: alloc,
+            _key_ty: PhantomData
+        }
+    }
+
+    pub fn new_standalone_inner(alloc: A) -> ABox<Self, A>
+    where C: Default
+    {
+        let mut new = ABox::new_in(unsafe { Self::new_inner(alloc.clone()) }, alloc);
+        unsafe { new.setup_pointers() };
+        new
+    }
+
+    /// Point the header (and the root node) to the tree's current address. This must be called
+    /// after the tree is moved.
+    ///
+    /// # Safety
+    ///
+    /// Any nodes in the tree must still be valid, and the tree must not be moved afterwards.
+    pub unsafe fn setup_pointers(&mut self) {
+        let header = self.get_header();
+        match self.header.parent.is_null() {
+            true => {
+                self.header.left = header;
+                self.header.right = header;
+            },
+            false => (*self.header.parent).parent = header
+        }
+    }
+
+    pub fn len(&self) -> usize { self.size }
+    pub fn is_empty(&self) -> bool { self.size == 0 }
+
+    fn get_header(&mut self) -> *mut TreeNode<T0, A> { &raw mut self.header as *mut TreeNode<T0, A> }
+
+    // Read-only pointer to the header, for comparisons. Anything that writes through it should use
+    // get_header() instead
+    fn end(&self) -> *const TreeNode<T0, A> { &raw const self.header as *const TreeNode<T0, A> }
+
+    fn get_root(&self) -> Option<&TreeNode<T0, A>> {
+        unsafe { self.header.parent.as_ref() }
+    }
+
+    pub fn first(&self) -> Option<&T0> {
+        if self.is_empty() { return None }
+        Some(unsafe { &(*self.header.left).data })
+    }
+
+    pub fn last(&self) -> Option<&T0> {
+        if self.is_empty() { return None }
+        Some(unsafe { &(*self.header.right).data })
+    }
+
+    // _M_get_insert_unique_pos. Returns the parent for the new node and if it goes on the left,
+    // or None if the value already exists
+    unsafe fn get_insert_unique_pos(&mut self, value: &T0) -> Option<(*mut TreeNode<T0, A>, bool)> {
+        let header = self.get_header();
+        let mut x = self.header.parent;
+        let mut y = header;
+        let mut comp = true;
+        while !x.is_null() {
+            y = x;
+            comp = self.compare.compare_aa(value, &(*x).data);
+            x = if comp { (*x).left } else { (*x).right };
+        }
+        let mut j = y;
+        if comp {
+            if std::ptr::eq(j, self.header.left) { return Some((y, true)) }
+            j = TreeNode::decrement(j);
+        }
+        if self.compare.compare_aa(&(*j).data, value) {
+            let left = std::ptr::eq(y, header) || self.compare.compare_aa(value, &(*y).data);
+            return Some((y, left))
+        }
+        None
+    }
+
+    pub fn insert(&mut self, value: T0) -> bool {
+        unsafe {
+            let (parent, left) = match self.get_insert_unique_pos(&value) {
+                Some(v) => v,
+                // this fails if attempting to add a duplicate key
+                None => return false
+            };
+            let node = TreeNode::new(value, self._allocator.clone());
+            let header = self.get_header();
+            insert_and_rebalance(left, node, parent, header);
+        }
+        self.size += 1;
+        true
+    }
+
+    // _M_lower_bound: first node that doesn't go before value
+    fn lower_bound_node(&self, value: &T1) -> *const TreeNode<T0, A> {
+        let mut x = self.header.parent;
+        let mut y = self.end();
+        while !x.is_null() {
+            let node = unsafe { &*x };
+            if !self.compare.compare_ab(&node.data, value) {
+                y = x;
+                x = node.left;
+            } else {
+                x = node.right;
+            }
+        }
+        y
+    }
+
+    // _M_upper_bound: first node that goes after value
+    fn upper_bound_node(&self, value: &T1) -> *const TreeNode<T0, A> {
+        let mut x = self.header.parent;
+        let mut y = self.end();
+        while !x.is_null() {
+            let node = unsafe { &*x };
+            if self.compare.compare_ba(value, &node.data) {
+                y = x;
+                x = node.left;
+            } else {
+                x = node.right;
+            }
+        }
+        y
+    }
+
+    // find: lower bound, unless value goes before the node it found
+    fn find_node(&self, value: &T1) -> Option<*mut TreeNode<T0, A>> {
+        let j = self.lower_bound_node(value);
+        if std::ptr::eq(j, self.end()) { return None }
+        match unsafe { !self.compare.compare_ba(value, &(*j).data) } {
+            // j is a real node here, reached through the tree's own *mut links
+            true => Some(j.cast_mut()),
+            false => None
+        }
+    }
+
+    pub fn contains(&self, value: T1) -> bool { self.find_node(&value).is_some() }
+
+    pub fn find(&self, value: T1) -> Option<&T0> {
+        self.find_node(&value).map(|n| unsafe { &*(*n).data })
+    }
+
+    pub fn find_mut(&mut self, value: T1) -> Option<&mut T0> {
+        self.find_node(&value).map(|n| unsafe { &mut *(*n).data })
+    }
+
+    /// Returns an iterator starting from the first element that is not ordered before value
+    pub fn lower_bound(&self, value: T1) -> TreeIterator<'_, T0, A> {
+        let start = self.lower_bound_node(&value);
+        self.iter_from(start)
+    }
+
+    /// Returns an iterator starting from the first element that is ordered after value
+    pub fn upper_bound(&self, value: T1) -> TreeIterator<'_, T0, A> {
+        let start = self.upper_bound_node(&value);
+        self.iter_from(start)
+    }
+
+    fn iter_from(&self, start: *const TreeNode<T0, A>) -> TreeIterator<'_, T0, A> {
+        TreeIterator {
+            front: start.cast_mut(),
+            back: self.header.right,
+            done: self.is_empty() || std::ptr::eq(start, self.end()),
+            _marker: PhantomData
+        }
+    }
+
+    pub fn iter(&self) -> TreeIterator<'_, T0, A> { self.into_iter() }
+    pub fn iter_mut(&mut self) -> TreeIteratorMut<'_, T0, A> { self.into_iter() }
+
+    // _M_erase_aux
+    unsafe fn erase_node(&mut self, node: *mut TreeNode<T0, A>) -> T0 {
+        let header = self.get_header();
+        let y = rebalance_for_erase(node, header);
+        self.size -= 1;
+        let value = ManuallyDrop::take(&mut (*y).data);
+        TreeNode::delete(y, self._allocator.clone());
+        value
+    }
+
+    // remove type can be different from storage type (e.g for maps, store as MapPair(K, V),
+    // but find based on K which is PartialEq<MapPair>
+    pub fn remove(&mut self, value: T1) -> bool { self.take(value).is_some() }
+
+    pub fn take(&mut self, value: T1) -> Option<T0> {
+        let node = self.find_node(&value)?;
+        Some(unsafe { self.erase_node(node) })
+    }
+
+    pub fn clear(&mut self) {
+        unsafe { self.erase_subtree(self.header.parent) };
+        self.header.parent = std::ptr::null_mut();
+        let header = self.get_header();
+        self.header.left = header;
+        self.header.right = header;
+        self.size = 0;
+    }
+
+    // _M_erase: erase without rebalancing
+    unsafe fn erase_subtree(&mut self, mut x: *mut TreeNode<T0, A>) {
+        while !x.is_null() {
+            self.erase_subtree((*x).right);
+            let y = (*x).left;
+            ManuallyDrop::drop(&mut (*x).data);
+            TreeNode::delete(x, self._allocator.clone());
+            x = y;
+        }
+    }
+}
+
+impl<C, T0, T1, A> Drop for Tree<C, T0, T1, A>
+where C: TreeCompare<T0, T1>,
+      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    fn drop(&mut self) {
+        unsafe { self.erase_subtree(self.header.parent) }
+    }
+}
+
+impl<T, A> TreeNode<T, A>
+where A: Allocator + Clone
+{
+    // _M_create_node
+    fn new(value: T, alloc: A) -> *mut Self {
+        let new = alloc.allocate(Layout::new::<Self>()).unwrap().as_ptr() as *mut Self;
+        unsafe {
+            std::ptr::write(new, Self {
+                color: NodeColor::Red,
+                parent: std::ptr::null_mut(),
+                left: std::ptr::null_mut(),
+                right: std::ptr::null_mut(),
+                data: ManuallyDrop::new(value),
+                _allocator: alloc
+            });
+        }
+        new
+    }
+
+    // SAFETY: The node's value must be dropped or moved out beforehand
+    unsafe fn delete(node: *mut Self, alloc: A) {
+        alloc.deallocate(NonNull::new_unchecked(node as *mut u8), Layout::new::<Self>());
+    }
+
+    pub fn value(&self) -> &T { &self.data }
+    pub fn value_mut(&mut self) -> &mut T { &mut self.data }
+
+    unsafe fn minimum(mut x: *mut Self) -> *mut Self {
+        while !(*x).left.is_null() { x = (*x).left; }
+        x
+    }
+
+    unsafe fn maximum(mut x: *mut Self) -> *mut Self {
+        while !(*x).right.is_null() { x = (*x).right; }
+        x
+    }
+
+    // _Rb_tree_increment
+    unsafe fn increment(mut x: *mut Self) -> *mut Self {
+        if !(*x).right.is_null() {
+            return Self::minimum((*x).right);
+        }
+        let mut y = (*x).parent;
+        while std::ptr::eq(x, (*y).right) {
+            x = y;
+            y = (*y).parent;
+        }
+        // only false when x is the root of a tree with no right subtree
+        if !std::ptr::eq((*x).right, y) { x = y; }
+        x
+    }
+
+    // _Rb_tree_decrement
+    unsafe fn decrement(mut x: *mut Self) -> *mut Self {
+        // x is the header (end()), so go to the rightmost node
+        if (*x).color == NodeColor::Red && std::ptr::eq((*(*x).parent).parent, x) {
+            return (*x).right;
+        }
+        if !(*x).left.is_null() {
+            return Self::maximum((*x).left);
+        }
+        let mut y = (*x).parent;
+        while std::ptr::eq(x, (*y).left) {
+            x = y;
+            y = (*y).parent;
+        }
+        y
+    }
+}
+
+// local_Rb_tree_rotate_left
+unsafe fn rotate_left<T, A>(x: *mut TreeNode<T, A>, root: &mut *mut TreeNode<T, A>)
+where A: Allocator + Clone
+{
+    let y = (*x).right;
+    (*x).right = (*y).left;
+    if !(*y).left.is_null() { (*(*y).left).parent = x; }
+    (*y).parent = (*x).parent;
+    if std::ptr::eq(x, *root) {
+        *root = y;
+    } else if std::ptr::eq(x, (*(*x).parent).left) {
+        (*(*x).parent).left = y;
+    } else {
+        (*(*x).parent).right = y;
+    }
+    (*y).left = x;
+    (*x).parent = y;
+}
+
+// local_Rb_tree_rotate_right
+unsafe fn rotate_right<T, A>(x: *mut TreeNode<T, A>, root: &mut *mut TreeNode<T, A>)
+where A: Allocator + Clone
+{
+    let y = (*x).left;
+    (*x).left = (*y).right;
+    if !(*y).right.is_null() { (*(*y).right).parent = x; }
+    (*y).parent = (*x).parent;
+    if std::ptr::eq(x, *root) {
+        *root = y;
+    } else if std::ptr::eq(x, (*(*x).parent).right) {
+        (*(*x).parent).right = y;
+    } else {
+        (*(*x).parent).left = y;
+    }
+    (*y).right = x;
+    (*x).parent = y;
+}
+
+fn is_black<T, A>(x: *mut TreeNode<T, A>) -> bool
+where A: Allocator + Clone
+{
+    x.is_null() || unsafe { (*x).color == NodeColor::Black }
+}
+
+// _Rb_tree_insert_and_rebalance
+unsafe fn insert_and_rebalance<T, A>(insert_left: bool, mut x: *mut TreeNode<T, A>,
+    p: *mut TreeNode<T, A>, header: *mut TreeNode<T, A>)
+where A: Allocator + Clone
+{
+    (*x).parent = p;
+    (*x).left = std::ptr::null_mut();
+    (*x).right = std::ptr::null_mut();
+    (*x).color = NodeColor::Red;
+    // Insert. Make new node child of parent and maintain root, leftmost and rightmost nodes.
+    if insert_left {
+        (*p).left = x; // also makes leftmost = x when p == header
+        if std::ptr::eq(p, header) {
+            (*header).parent = x;
+            (*header).right = x;
+        } else if std::ptr::eq(p, (*header).left) {
+            (*header).left = x; // maintain leftmost pointing to min node
+        }
+    } else {
+        (*p).right = x;
+        if std::ptr::eq(p, (*header).right) {
+            (*header).right = x; // maintain rightmost pointing to max node
+        }
+    }
+    // Rebalance
+    let root = &mut (*header).parent;
+    while !std::ptr::eq(x, *root) && (*(*x).parent).color == NodeColor::Red {
+        let xpp = (*(*x).parent).parent;
+        if std::ptr::eq((*x).parent, (*xpp).left) {
+            let y = (*xpp).right;
+            if !is_black(y) {
+                (*(*x).parent).color = NodeColor::Black;
+                (*y).color = NodeColor::Black;
+                (*xpp).color = NodeColor::Red;
+                x = xpp;
+            } else {
+                if std::ptr::eq(x, (*(*x).parent).right) {
+                    x = (*x).parent;
+                    rotate_left(x, root);
+                }
+                (*(*x).parent).color = NodeColor::Black;
+                (*xpp).color = NodeColor::Red;
+                rotate_right(xpp, root);
+            }
+        } else {
+            let y = (*xpp).left;
+            if !is_black(y) {
+                (*(*x).parent).color = NodeColor::Black;
+                (*y).color = NodeColor::Black;
+                (*xpp).color = NodeColor::Red;
+                x = xpp;
+            } else {
+                if std::ptr::eq(x, (*(*x).parent).left) {
+                    x = (*x).parent;
+                    rotate_right(x, root);
+                }
+                (*(*x).parent).color = NodeColor::Black;
+                (*xpp).color = NodeColor::Red;
+                rotate_left(xpp, root);
+            }
+        }
+    }
+    (**root).color = NodeColor::Black;
+}
+
+// _Rb_tree_rebalance_for_erase. Returns the node that was unlinked from the tree
+unsafe fn rebalance_for_erase<T, A>(z: *mut TreeNode<T, A>, header: *mut TreeNode<T, A>) -> *mut TreeNode<T, A>
+where A: Allocator + Clone
+{
+    let mut y = z;
+    let mut x;
+    let mut x_parent;
+    if (*y).left.is_null() { // z has at most one non-null child. y == z.
+        x = (*y).right; // x might be null.
+    } else if (*y).right.is_null() { // z has exactly one non-null child. y == z.
+        x = (*y).left; // x is not null.
+    } else { // z has two non-null children. Set y to z's successor. x might be null.
+        y = TreeNode::minimum((*y).right);
+        x = (*y).right;
+    }
+    if !std::ptr::eq(y, z) {
+        // relink y in place of z. y is z's successor
+        (*(*z).left).parent = y;
+        (*y).left = (*z).left;
+        if !std::ptr::eq(y, (*z).right) {
+            x_parent = (*y).parent;
+            if !x.is_null() { (*x).parent = (*y).parent; }
+            (*(*y).parent).left = x; // y must be a child of left
+            (*y).right = (*z).right;
+            (*(*z).right).parent = y;
+        } else {
+            x_parent = y;
+        }
+        if std::ptr::eq((*header).parent, z) {
+            (*header).parent = y;
+        } else if std::ptr::eq((*(*z).parent).left, z) {
+            (*(*z).parent).left = y;
+        } else {
+            (*(*z).parent).right = y;
+        }
+        (*y).parent = (*z).parent;
+        std::mem::swap(&mut (*y).color, &mut (*z).color);
+        y = z; // y now points to node to be actually deleted
+    } else { // y == z
+        x_parent = (*y).parent;
+        if !x.is_null() { (*x).parent = (*y).parent; }
+        if std::ptr::eq((*header).parent, z) {
+            (*header).parent = x;
+        } else if std::ptr::eq((*(*z).parent).left, z) {
+            (*(*z).parent).left = x;
+        } else {
+            (*(*z).parent).right = x;
+        }
+        if std::ptr::eq((*header).left, z) {
+            (*header).left = match (*z).right.is_null() {
+                true => (*z).parent, // z.left must be null also
+                false => TreeNode::minimum(x)
+            };
+        }
+        if std::ptr::eq((*header).right, z) {
+            (*header).right = match (*z).left.is_null() {
+                true => (*z).parent, // z.right must be null also
+                false => TreeNode::maximum(x) // x == z.left
+            };
+        }
+    }
+    if (*y).color != NodeColor::Red {
+        let root = &mut (*header).parent;
+        while !std::ptr::eq(x, *root) && is_black(x) {
+            if std::ptr::eq(x, (*x_parent).left) {
+                let mut w = (*x_parent).right;
+                if (*w).color == NodeColor::Red {
+                    (*w).color = NodeColor::Black;
+                    (*x_parent).color = NodeColor::Red;
+                    rotate_left(x_parent, root);
+                    w = (*x_parent).right;
+                }
+                if is_black((*w).left) && is_black((*w).right) {
+                    (*w).color = NodeColor::Red;
+                    x = x_parent;
+                    x_parent = (*x_parent).parent;
+                } else {
+                    if is_black((*w).right) {
+                        (*(*w).left).color = NodeColor::Black;
+                        (*w).color = NodeColor::Red;
+                        rotate_right(w, root);
+                        w = (*x_parent).right;
+                    }
+                    (*w).color = (*x_parent).color;
+                    (*x_parent).color = NodeColor::Black;
+                    if !(*w).right.is_null() { (*(*w).right).color = NodeColor::Black; }
+                    rotate_left(x_parent, root);
+                    break;
+                }
+            } else { // same as above, with right <-> left.
+                let mut w = (*x_parent).left;
+                if (*w).color == NodeColor::Red {
+                    (*w).color = NodeColor::Black;
+                    (*x_parent).color = NodeColor::Red;
+                    rotate_right(x_parent, root);
+                    w = (*x_parent).left;
+                }
+                if is_black((*w).right) && is_black((*w).left) {
+                    (*w).color = NodeColor::Red;
+                    x = x_parent;
+                    x_parent = (*x_parent).parent;
+                } else {
+                    if is_black((*w).left) {
+                        (*(*w).right).color = NodeColor::Black;
+                        (*w).color = NodeColor::Red;
+                        rotate_left(w, root);
+                        w = (*x_parent).left;
+                    }
+                    (*w).color = (*x_parent).color;
+                    (*x_parent).color = NodeColor::Black;
+                    if !(*w).left.is_null() { (*(*w).left).color = NodeColor::Black; }
+                    rotate_right(x_parent, root);
+                    break;
+                }
+            }
+        }
+        if !x.is_null() { (*x).color = NodeColor::Black; }
+    }
+    y
+}
+
+impl<'a, C, T0, T1, A> IntoIterator for &'a Tree<C, T0, T1, A>
+where C: TreeCompare<T0, T1>,
+      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    type Item = &'a T0;
+    type IntoIter = TreeIterator<'a, T0, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_from(self.header.left)
+    }
+}
+
+impl<'a, C, T0, T1, A> IntoIterator for &'a mut Tree<C, T0, T1, A>
+where C: TreeCompare<T0, T1>,
+      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    type Item = &'a mut T0;
+    type IntoIter = TreeIteratorMut<'a, T0, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        TreeIteratorMut {
+            front: self.header.left,
+            back: self.header.right,
+            done: self.is_empty(),
+            _marker: PhantomData
+        }
+    }
+}
+
+// Inorder traversal using _Rb_tree_increment/_Rb_tree_decrement, so no stack is needed
+pub struct TreeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    front: *mut TreeNode<T, A>,
+    back: *mut TreeNode<T, A>,
+    done: bool,
+    _marker: PhantomData<&'a T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for TreeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None }
+        let out = unsafe { &*self.front };
+        match std::ptr::eq(self.front, self.back) {
+            true => self.done = true,
+            false => self.front = unsafe { TreeNode::increment(self.front) }
+        };
+        Some(&out.data)
+    }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for TreeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.done { return None }
+        let out = unsafe { &*self.back };
+        match std::ptr::eq(self.front, self.back) {
+            true => self.done = true,
+            false => self.back = unsafe { TreeNode::decrement(self.back) }
+        };
+        Some(&out.data)
+    }
+}
+
+pub struct TreeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    front: *mut TreeNode<T, A>,
+    back: *mut TreeNode<T, A>,
+    done: bool,
+    _marker: PhantomData<&'a mut T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for TreeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None }
+        let out = unsafe { &mut *self.front };
+        match std::ptr::eq(self.front, self.back) {
+            true => self.done = true,
+            false => self.front = unsafe { TreeNode::increment(self.front) }
+        };
+        Some(&mut out.data)
+    }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for TreeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.done { return None }
+        let out = unsafe { &mut *self.back };
+        match std::ptr::eq(self.front, self.back) {
+            true => self.done = true,
+            false => self.back = unsafe { TreeNode::decrement(self.back) }
+        };
+        Some(&mut out.data)
+    }
+}
+
+crate::generic::tree::value_lookup_wrappers!();
+
+#[cfg(test)]
+pub mod tests {
+    use allocator_api2::alloc::{ Allocator, Global };
+    use super::{
+        CompareGreater,
+        CompareLess,
+        Map,
+        NodeColor,
+        Set,
+        Tree,
+        TreeCompare,
+        TreeNode
+    };
+    use std::{
+        error::Error,
+        fmt::Debug
+    };
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    impl<C, T0, T1, A> Tree<C, T0, T1, A>
+    where C: TreeCompare<T0, T1>,
+          T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1> + Debug,
+          T1: PartialEq + PartialOrd,
+          A: Allocator + Clone
+    {
+        // Check every red-black tree property, then return the black height
+        unsafe fn check_node(&self, n: *mut TreeNode<T0, A>, parent: *const TreeNode<T0, A>) -> usize {
+            if n.is_null() { return 1 }
+            let node = &*n;
+            assert!(std::ptr::eq(node.parent, parent), "Parent pointer for {:?} is wrong", *node.data);
+            if node.color == NodeColor::Red {
+                assert!(super::is_black(node.left) && super::is_black(node.right),
+                    "Red node {:?} shouldn't have a red child", *node.data);
+            }
+            if !node.left.is_null() {
+                assert!(self.compare.compare_aa(&(*node.left).data, &node.data), "Left child of {:?} is out of order", *node.data);
+            }
+            if !node.right.is_null() {
+                assert!(self.compare.compare_aa(&node.data, &(*node.right).data), "Right child of {:?} is out of order", *node.data);
+            }
+            let left = self.check_node(node.left, n);
+            let right = self.check_node(node.right, n);
+            assert!(left == right, "Black height for {:?} doesn't match ({} != {})", *node.data, left, right);
+            left + if node.color == NodeColor::Black { 1 } else { 0 }
+        }
+
+        pub(crate) fn check_tree(&self) {
+            let header = self.end();
+            match self.get_root() {
+                Some(root) => unsafe {
+                    assert!(root.color == NodeColor::Black, "Root should be black");
+                    self.check_node(self.header.parent, header);
+                    assert!(std::ptr::eq(self.header.left, TreeNode::minimum(self.header.parent)), "Header should point to leftmost node");
+                    assert!(std::ptr::eq(self.header.right, TreeNode::maximum(self.header.parent)), "Header should point to rightmost node");
+                },
+                None => {
+                    assert!(std::ptr::eq(self.header.left, header), "Header left should point to itself");
+                    assert!(std::ptr::eq(self.header.right, header), "Header right should point to itself");
+                }
+            }
+            assert!(self.iter().count() == self.len(), "Tree should contain {} nodes", self.len());
+        }
+    }
+
+    #[test]
+    pub fn tree_layout() -> TestReturn {
+        assert!(size_of::<Tree<CompareLess, u32, u32, Global>>() == 0x30, "std::set should be 0x30 bytes");
+        let mut tree = Tree::<CompareLess, u64, u64, Global>::new_standalone_inner(Global);
+        let base = &raw const *tree as usize;
+        let raw = unsafe { std::slice::from_raw_parts(base as *const usize, 6) };
+        assert!(raw[2..6] == [0, base + 8, base + 8, 0], "Header should be blank");
+        tree.insert(5);
+        let root = tree.get_root().unwrap();
+        let node = &raw const *root as usize;
+        let raw = unsafe { std::slice::from_raw_parts(base as *const usize, 6) };
+        assert!(raw[2..6] == [node, node, node, 1], "Header should point to the root");
+        // _M_color is a 4 byte enum followed by padding, so it's read separately from the pointers
+        let color = unsafe { *(node as *const u32) };
+        let raw_node = unsafe { std::slice::from_raw_parts((node + 8) as *const usize, 4) };
+        assert!(color == NodeColor::Black as u32 && raw_node == [base + 8, 0, 0, 5], "Node should be (color, parent, left, right, value)");
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_insert_entries() -> TestReturn {
+        let mut tree = Tree::<CompareLess, u32, u32, Global>::new_standalone_inner(Global);
+        for i in [4, 2, 6, 1, 3, 5, 8, 7, 9, 0, 15, 12, 11, 10, 14, 13] {
+            assert!(tree.insert(i), "Inserting {} should succeed", i);
+            tree.check_tree();
+        }
+        assert!(!tree.insert(5), "Duplicate keys should be rejected");
+        assert!(tree.len() == 16, "Tree should have 16 entries");
+        assert!(tree.iter().copied().collect::<Vec<_>>() == (0..16).collect::<Vec<_>>(), "Tree should be sorted");
+        assert!(tree.iter().rev().copied().collect::<Vec<_>>() == (0..16).rev().collect::<Vec<_>>(), "Reverse iterator should be sorted");
+        assert!(tree.first() == Some(&0) && tree.last() == Some(&15), "First and last should be 0 and 15");
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_remove_entries() -> TestReturn {
+        let mut tree = Tree::<CompareLess, u32, u32, Global>::new_standalone_inner(Global);
+        for i in 0..64 { tree.insert((i * 37) % 64); }
+        tree.check_tree();
+        // remove from both ends and the middle
+        for i in [0, 63, 32, 31, 1, 62] {
+            assert!(tree.remove(i), "Removing {} should succeed", i);
+            tree.check_tree();
+        }
+        assert!(!tree.remove(32), "32 was already removed");
+        for i in (2..62).filter(|v| v % 3 != 0 && *v != 31 && *v != 32) {
+            assert!(tree.remove(i), "Removing {} should succeed", i);
+            tree.check_tree();
+        }
+        let expected: Vec<u32> = (2..62).filter(|v| v % 3 == 0).collect();
+        assert!(tree.iter().copied().collect::<Vec<_>>() == expected, "Remaining values don't match");
+        let remaining = expected.len();
+        for (i, v) in expected.iter().enumerate() {
+            tree.remove(*v);
+            assert!(tree.len() == remaining - i - 1, "Length should go down by one");
+            tree.check_tree();
+        }
+        assert!(tree.is_empty(), "Tree should be empty");
+        tree.insert(1);
+        tree.check_tree();
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_bounds() -> TestReturn {
+        let mut set = Set::<CompareLess, u32>::new_standalone();
+        for i in (0..20).map(|v| v * 5) { set.insert(i); }
+        assert!(set.lower_bound(25).next() == Some(&25), "lower_bound(25) should be 25");
+        assert!(set.upper_bound(25).next() == Some(&30), "upper_bound(25) should be 30");
+        assert!(set.lower_bound(26).next() == Some(&30), "lower_bound(26) should be 30");
+        assert!(set.lower_bound(100).next().is_none(), "lower_bound(100) should be end()");
+        assert!(set.upper_bound(95).next().is_none(), "upper_bound(95) should be end()");
+        assert!(set.lower_bound(87).copied().collect::<Vec<_>>() == [90, 95], "Bound iterator should run until the end");
+        assert!(set.contains(45) && !set.contains(46), "Set should contain 45 but not 46");
+        let mut greater = Set::<CompareGreater, u32>::new_standalone();
+        for i in 0..5 { greater.insert(i); }
+        assert!(greater.iter().copied().collect::<Vec<_>>() == [4, 3, 2, 1, 0], "std::greater should sort in descending order");
+        assert!(greater.lower_bound(2).next() == Some(&2), "lower_bound(2) should be 2");
+        assert!(greater.upper_bound(2).next() == Some(&1), "upper_bound(2) should be 1");
+        Ok(())
+    }
+
+    // Orders values by their last digit, so equivalent values aren't always equal
+    #[derive(Default)]
+    struct CompareLastDigit;
+    impl TreeCompare<u32, u32> for CompareLastDigit {
+        fn compare_aa(&self, d0: &u32, d1: &u32) -> bool { d0 % 10 < d1 % 10 }
+        fn compare_ab(&self, d0: &u32, d1: &u32) -> bool { d0 % 10 < d1 % 10 }
+        fn compare_ba(&self, d0: &u32, d1: &u32) -> bool { d0 % 10 < d1 % 10 }
+    }
+
+    #[test]
+    pub fn tree_equivalent_keys() -> TestReturn {
+        let mut set = Set::<CompareLastDigit, u32>::new_standalone();
+        for i in [12, 25, 31] { set.insert(i); }
+        assert!(!set.insert(45), "45 is equivalent to 25, so it shouldn't be inserted");
+        assert!(set.find(15) == Some(&25), "find(15) should find the equivalent 25");
+        assert!(set.upper_bound(5).next().is_none(), "upper_bound(5) should skip the equivalent 25");
+        assert!(set.upper_bound(2).next() == Some(&25), "upper_bound(2) should skip the equivalent 12");
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_as_map() -> TestReturn {
+        let mut map = Map::<CompareLess, u32, &str>::new_standalone();
+        map.insert(30, "Chest");
+        map.insert(10, "Player");
+        map.insert(20, "Enemy");
+        assert!(map.find(10) == Some(&"Player"), "Key 10 should be Player");
+        *map.find_mut(20).unwrap() = "Ally";
+        for v in map.iter_mut() { if *v.get_key() == 30 { *v.get_value_mut() = "Door" } }
+        let values: Vec<_> = map.iter().map(|v| (*v.get_key(), *v.get_value())).collect();
+        assert!(values == [(10, "Player"), (20, "Ally"), (30, "Door")], "Map entries don't match");
+        assert!(map.remove(10) == Some("Player"), "Removing key 10 should return Player");
+        assert!(map.remove(10).is_none(), "Key 10 was already removed");
+        assert!(map.len() == 2, "Map should have 2 entries");
+        map._impl.check_tree();
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_relocate() -> TestReturn {
+        let mut tree: Tree<CompareLess, u32, u32, Global> = unsafe { Tree::new_inner(Global) };
+        unsafe { tree.setup_pointers() };
+        for i in 0..8 { tree.insert(i); }
+        let mut moved = Box::new(tree);
+        unsafe { moved.setup_pointers() };
+        moved.check_tree();
+        moved.insert(8);
+        moved.remove(3);
+        moved.check_tree();
+        moved.clear();
+        moved.check_tree();
+        Ok(())
+    }
+}
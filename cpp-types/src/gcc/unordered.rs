@@ -0,0 +1,999 @@
+//! Rust reimplementation of libstdc++'s std::unordered_map/std::unordered_set implementation
+//! (_Hashtable)
+
+#![allow(dead_code, unused_imports)]
+use allocator_api2::{
+    alloc::{ Allocator, Global },
+    boxed::Box as ABox
+};
+use crate::gcc::hash::{ HasherInit, StdHash };
+pub use crate::generic::tree::MapPair;
+use std::{
+    alloc::Layout,
+    fmt::Debug,
+    hash::{ Hash, Hasher },
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ops::{ Index, IndexMut },
+    ptr::NonNull
+};
+
+// https://github.com/gcc-mirror/gcc/blob/master/libstdc++-v3/include/bits/hashtable.h
+// https://github.com/gcc-mirror/gcc/blob/master/libstdc++-v3/include/bits/hashtable_policy.h
+// https://github.com/gcc-mirror/gcc/blob/master/libstdc++-v3/src/c++11/hashtable_c++0x.cc
+
+// __fast_bkt
+const FAST_BUCKETS: [u8; 14] = [ 2, 2, 2, 3, 5, 5, 7, 7, 11, 11, 11, 11, 13, 13 ];
+
+// __prime_list (from hashtable-aux.cc). The last prime is repeated as a sentinel
+const PRIME_LIST: [usize; 256 + 48 + 1] = [
+    2, 3, 5, 7, 11, 13,
+    17, 19, 23, 29, 31, 37,
+    41, 43, 47, 53, 59, 61,
+    67, 71, 73, 79, 83, 89,
+    97, 103, 109, 113, 127, 137,
+    139, 149, 157, 167, 179, 193,
+    199, 211, 227, 241, 257, 277,
+    293, 313, 337, 359, 383, 409,
+    439, 467, 503, 541, 577, 619,
+    661, 709, 761, 823, 887, 953,
+    1031, 1109, 1193, 1289, 1381, 1493,
+    1613, 1741, 1879, 2029, 2179, 2357,
+    2549, 2753, 2971, 3209, 3469, 3739,
+    4027, 4349, 4703, 5087, 5503, 5953,
+    6427, 6949, 7517, 8123, 8783, 9497,
+    10273, 11113, 12011, 12983, 14033, 15173,
+    16411, 17749, 19183, 20753, 22447, 24281,
+    26267, 28411, 30727, 33223, 35933, 38873,
+    42043, 45481, 49201, 53201, 57557, 62233,
+    67307, 72817, 78779, 85229, 92203, 99733,
+    107897, 116731, 126271, 136607, 147793, 159871,
+    172933, 187091, 202409, 218971, 236897, 256279,
+    277261, 299951, 324503, 351061, 379787, 410857,
+    444487, 480881, 520241, 562841, 608903, 658753,
+    712697, 771049, 834181, 902483, 976369, 1056323,
+    1142821, 1236397, 1337629, 1447153, 1565659, 1693859,
+    1832561, 1982627, 2144977, 2320627, 2510653, 2716249,
+    2938679, 3179303, 3439651, 3721303, 4026031, 4355707,
+    4712381, 5098259, 5515729, 5967347, 6456007, 6984629,
+    7556579, 8175383, 8844859, 9569143, 10352717, 11200489,
+    12117689, 13109983, 14183539, 15345007, 16601593, 17961079,
+    19431899, 21023161, 22744717, 24607243, 26622317, 28802401,
+    31160981, 33712729, 36473443, 39460231, 42691603, 46187573,
+    49969847, 54061849, 58488943, 63278561, 68460391, 74066549,
+    80131819, 86693767, 93793069, 101473717, 109783337, 118773397,
+    128499677, 139022417, 150406843, 162723577, 176048909, 190465427,
+    206062531, 222936881, 241193053, 260944219, 282312799, 305431229,
+    330442829, 357502601, 386778277, 418451333, 452718089, 489790921,
+    529899637, 573292817, 620239453, 671030513, 725980837, 785430967,
+    849749479, 919334987, 994618837, 1076067617, 1164186217, 1259520799,
+    1362662261, 1474249943, 1594975441, 1725587117, 1866894511, 2019773507,
+    2185171673, 2364114217, 2557710269, 2767159799, 2993761039, 3238918481,
+    3504151727, 3791104843, 4101556399, 4294967291, 6442450933, 8589934583,
+    12884901857, 17179869143, 25769803693, 34359738337, 51539607367, 68719476731,
+    103079215087, 137438953447, 206158430123, 274877906899, 412316860387, 549755813881,
+    824633720731, 1099511627689, 1649267441579, 2199023255531, 3298534883309, 4398046511093,
+    6597069766607, 8796093022151, 13194139533241, 17592186044399, 26388279066581, 35184372088777,
+    52776558133177, 70368744177643, 105553116266399, 140737488355213, 211106232532861, 281474976710597,
+    562949953421231, 1125899906842597, 2251799813685119, 4503599627370449, 9007199254740881, 18014398509481951,
+    36028797018963913, 72057594037927931, 144115188075855859, 288230376151711717, 576460752303423433, 1152921504606846883,
+    2305843009213693951, 4611686018427387847, 9223372036854775783, 18446744073709551557, 18446744073709551557,
+];
+
+/// Controls if each node stores its hash code after the value (_Hashtable_traits::__hash_cached).
+/// libstdc++ caches the hash code unless the hasher is fast and noexcept, so std::hash<int>
+/// tables use NoCache while std::hash<std::string> tables use Cache.
+pub trait HashCodeCache {
+    type Code: Copy;
+    fn store(code: u64) -> Self::Code;
+    fn load(code: &Self::Code) -> Option<u64>;
+}
+
+pub struct NoCache;
+impl HashCodeCache for NoCache {
+    type Code = ();
+    fn store(_: u64) -> Self::Code {}
+    fn load(_: &Self::Code) -> Option<u64> { None }
+}
+
+pub struct Cache;
+impl HashCodeCache for Cache {
+    type Code = usize;
+    fn store(code: u64) -> Self::Code { code as usize }
+    fn load(code: &Self::Code) -> Option<u64> { Some(*code as u64) }
+}
+
+#[repr(C)]
+pub struct NodeBase<T, C, A = Global> // _Hash_node_base
+where C: HashCodeCache,
+      A: Allocator + Clone
+{
+    next: *mut HashNode<T, C, A> // _M_nxt
+}
+
+#[repr(C)]
+pub struct HashNode<T, C, A = Global> // _Hash_node
+where C: HashCodeCache,
+      A: Allocator + Clone
+{
+    // Buckets and _M_before_begin are treated as nodes too, so only access fields through raw
+    // pointers so we never make a reference to a node base as a full node.
+    next: *mut Self, // _M_nxt
+    data: ManuallyDrop<T>, // _M_storage
+    code: C::Code, // _M_hash_code
+    _allocator: A
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RehashPolicy { // _Prime_rehash_policy
+    max_load_factor: f32, // _M_max_load_factor
+    next_resize: usize // _M_next_resize
+}
+
+impl RehashPolicy {
+    pub fn new(max_load_factor: f32) -> Self {
+        Self { max_load_factor, next_resize: 0 }
+    }
+
+    // _M_next_bkt: smallest prime bucket count that's at least n
+    fn next_bucket(&mut self, n: usize) -> usize {
+        if n < FAST_BUCKETS.len() {
+            // Special case on container 1st initialization with 0 bucket count hint. We keep
+            // next_resize to 0 to make sure that next time we want to add an element allocation
+            // will take place.
+            if n == 0 { return 1 }
+            self.next_resize = (FAST_BUCKETS[n] as f64 * self.max_load_factor as f64).floor() as usize;
+            return FAST_BUCKETS[n] as usize;
+        }
+        let last_prime = PRIME_LIST.len() - 2;
+        let next = 6 + PRIME_LIST[6..last_prime].partition_point(|p| *p < n);
+        self.next_resize = match next == last_prime {
+            // Set next resize to the max value so that we never try to rehash again as we
+            // already reach the biggest possible bucket number.
+            true => usize::MAX,
+            false => (PRIME_LIST[next] as f64 * self.max_load_factor as f64).floor() as usize
+        };
+        PRIME_LIST[next]
+    }
+
+    // _M_bkt_for_elements
+    fn buckets_for_elements(&self, n: usize) -> usize {
+        (n as f64 / self.max_load_factor as f64).ceil() as usize
+    }
+
+    // _M_need_rehash. Returns the new bucket count if a rehash is required
+    fn need_rehash(&mut self, buckets: usize, elements: usize, inserted: usize) -> Option<usize> {
+        if elements + inserted <= self.next_resize { return None }
+        // If next_resize is 0 it means that we have nothing allocated so far and that we start
+        // inserting elements. In this case we start with an initial bucket size of 11.
+        let min_start = if self.next_resize == 0 { 11 } else { 0 };
+        let min_buckets = (elements + inserted).max(min_start) as f64 / self.max_load_factor as f64;
+        if min_buckets >= buckets as f64 {
+            return Some(self.next_bucket((min_buckets.floor() as usize + 1).max(buckets * 2)))
+        }
+        self.next_resize = (buckets as f64 * self.max_load_factor as f64).floor() as usize;
+        None
+    }
+}
+
+#[repr(C)]
+pub struct HashTable<H, T0, T1, C = NoCache, A = Global>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    // Each bucket points to the node *before* the bucket's first node, which is before_begin
+    // for the bucket containing the first node. Tables with a single bucket use single_bucket
+    // instead of allocating. Since both of these point into the table, call setup_pointers()
+    // after moving it.
+    buckets: *mut *mut HashNode<T0, C, A>, // _M_buckets
+    bucket_count: usize, // _M_bucket_count
+    before_begin: NodeBase<T0, C, A>, // _M_before_begin
+    element_count: usize, // _M_element_count
+    rehash_policy: RehashPolicy, // _M_rehash_policy
+    single_bucket: *mut HashNode<T0, C, A>, // _M_single_bucket
+    // std::hash and std::equal_to are empty so they take no space
+    _allocator: A,
+    _hash: PhantomData<H>,
+    _key_ty: PhantomData<T1>
+}
+
+impl<H, T0, T1, C, A> HashTable<H, T0, T1, C, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    /// # Safety
+    ///
+    /// The table is unusable until [`HashTable::setup_pointers`] is called at its final address.
+    pub unsafe fn new_inner(alloc: A) -> Self {
+        assert!(std::mem::size_of::<A>() == 0, "Allocator must be zero-sized!");
+        Self {
+            // buckets should point to single_bucket, but we can't do that until the table has
+            // settled. Call setup_pointers() after this
+            buckets: std::ptr::null_mut(),
+            bucket_count: 1,
+            before_begin: NodeBase { next: std::ptr::null_mut() },
+            element_count: 0,
+            rehash_policy: RehashPolicy::new(1.0),
+            single_bucket: std::ptr::null_mut(),
+            _allocator: alloc,
+            _hash: PhantomData,
+            _key_ty: PhantomData
+        }
+    }
+
+    pub fn new_standalone_inner(alloc: A) -> ABox<Self, A> {
+        let mut new = ABox::new_in(unsafe { Self::new_inner(alloc.clone()) }, alloc);
+        unsafe { new.setup_pointers() };
+        new
+    }
+
+    /// Point the bucket array (for single bucket tables) and the first node's bucket to the
+    /// table's current address. This must be called after the table is moved.
+    ///
+    /// # Safety
+    ///
+    /// Any nodes in the table must still be valid, and the table must not be moved afterwards.
+    pub unsafe fn setup_pointers(&mut self) {
+        if self.bucket_count == 1 {
+            self.buckets = &raw mut self.single_bucket;
+        }
+        let first = self.before_begin.next;
+        if !first.is_null() {
+            let bucket = self.node_bucket(first);
+            *self.buckets.add(bucket) = self.get_before_begin();
+        }
+    }
+
+    pub fn len(&self) -> usize { self.element_count }
+    pub fn is_empty(&self) -> bool { self.element_count == 0 }
+    pub fn bucket_count(&self) -> usize { self.bucket_count }
+    pub fn load_factor(&self) -> f32 { self.element_count as f32 / self.bucket_count as f32 }
+    pub fn max_load_factor(&self) -> f32 { self.rehash_policy.max_load_factor }
+    /// Change the maximum load factor. Like libstdc++, this doesn't rehash until the next insert.
+    pub fn set_max_load_factor(&mut self, max_load_factor: f32) {
+        self.rehash_policy = RehashPolicy::new(max_load_factor);
+    }
+
+    fn get_before_begin(&mut self) -> *mut HashNode<T0, C, A> {
+        &raw mut self.before_begin as *mut HashNode<T0, C, A>
+    }
+
+    fn get_bucket(&self, bucket: usize) -> *mut HashNode<T0, C, A> {
+        unsafe { *self.buckets.add(bucket) }
+    }
+
+    fn set_bucket(&mut self, bucket: usize, node: *mut HashNode<T0, C, A>) {
+        unsafe { *self.buckets.add(bucket) = node }
+    }
+
+    // _M_bucket_index (_Mod_range_hashing)
+    fn bucket_index(&self, code: u64) -> usize { code as usize % self.bucket_count }
+
+    // _M_hash_code. Uses the cached code if the node has one
+    unsafe fn node_code(&self, node: *mut HashNode<T0, C, A>) -> u64 {
+        match C::load(&(*node).code) {
+            Some(v) => v,
+            None => H::get_hash(&*(*node).data)
+        }
+    }
+
+    unsafe fn node_bucket(&self, node: *mut HashNode<T0, C, A>) -> usize {
+        self.bucket_index(self.node_code(node))
+    }
+
+    /// Returns the index of the bucket that the key would be stored in
+    pub fn bucket(&self, key: &T1) -> usize { self.bucket_index(H::get_hash(key)) }
+
+    /// Returns the number of elements in the bucket
+    pub fn bucket_size(&self, bucket: usize) -> usize { self.bucket_iter(bucket).count() }
+
+    /// Iterate through each element in the bucket
+    pub fn bucket_iter(&self, bucket: usize) -> BucketIterator<'_, H, T0, T1, C, A> {
+        let prev = self.get_bucket(bucket);
+        BucketIterator {
+            table: self,
+            curr: if prev.is_null() { prev } else { unsafe { (*prev).next } },
+            bucket
+        }
+    }
+
+    // _M_allocate_buckets
+    fn allocate_buckets(&mut self, count: usize) -> *mut *mut HashNode<T0, C, A> {
+        if count == 1 {
+            self.single_bucket = std::ptr::null_mut();
+            return &raw mut self.single_bucket;
+        }
+        let layout = Layout::array::<*mut HashNode<T0, C, A>>(count).unwrap();
+        self._allocator.allocate_zeroed(layout).unwrap().as_ptr() as *mut *mut HashNode<T0, C, A>
+    }
+
+    // _M_deallocate_buckets
+    unsafe fn deallocate_buckets(&mut self) {
+        if std::ptr::eq(self.buckets, &raw const self.single_bucket) { return }
+        let layout = Layout::array::<*mut HashNode<T0, C, A>>(self.bucket_count).unwrap();
+        self._allocator.deallocate(NonNull::new_unchecked(self.buckets as *mut u8), layout);
+    }
+
+    // _M_find_before_node: find the node before the one matching the key in the bucket
+    unsafe fn find_before_node(&self, bucket: usize, key: &T1, code: u64) -> Option<*mut HashNode<T0, C, A>> {
+        let mut prev = self.get_bucket(bucket);
+        if prev.is_null() { return None }
+        let mut p = (*prev).next;
+        loop {
+            // compare the cached hash code first, if there is one (_M_equals)
+            if C::load(&(*p).code).is_none_or(|c| c == code) && *(*p).data == *key {
+                return Some(prev)
+            }
+            if (*p).next.is_null() || self.node_bucket((*p).next) != bucket { return None }
+            prev = p;
+            p = (*p).next;
+        }
+    }
+
+    unsafe fn find_before_value(&self, bucket: usize, value: &T0, code: u64) -> Option<*mut HashNode<T0, C, A>> {
+        let mut prev = self.get_bucket(bucket);
+        if prev.is_null() { return None }
+        let mut p = (*prev).next;
+        loop {
+            if C::load(&(*p).code).is_none_or(|c| c == code) && *(*p).data == *value {
+                return Some(prev)
+            }
+            if (*p).next.is_null() || self.node_bucket((*p).next) != bucket { return None }
+            prev = p;
+            p = (*p).next;
+        }
+    }
+
+    fn find_node(&self, key: &T1) -> Option<*mut HashNode<T0, C, A>> {
+        let code = H::get_hash(key);
+        let bucket = self.bucket_index(code);
+        unsafe { self.find_before_node(bucket, key, code).map(|p| (*p).next) }
+    }
+
+    pub fn find(&self, key: &T1) -> Option<&T0> {
+        self.find_node(key).map(|n| unsafe { &*(*n).data })
+    }
+    pub fn find_mut(&mut self, key: &T1) -> Option<&mut T0> {
+        self.find_node(key).map(|n| unsafe { &mut *(*n).data })
+    }
+    pub fn contains(&self, key: &T1) -> bool { self.find_node(key).is_some() }
+
+    pub fn iter(&self) -> HashIterator<'_, T0, C, A> { self.into_iter() }
+    pub fn iter_mut(&mut self) -> HashIteratorMut<'_, T0, C, A> { self.into_iter() }
+
+    /// Inserts the value if there isn't an equivalent element already, returning true if the
+    /// insertion took place
+    pub fn insert(&mut self, value: T0) -> bool {
+        let code = H::get_hash(&value);
+        let bucket = self.bucket_index(code);
+        if unsafe { self.find_before_value(bucket, &value, code) }.is_some() { return false }
+        let node = HashNode::new(value, self._allocator.clone());
+        unsafe { self.insert_unique_node(bucket, code, node) };
+        true
+    }
+
+    // _M_insert_unique_node
+    unsafe fn insert_unique_node(&mut self, mut bucket: usize, code: u64, node: *mut HashNode<T0, C, A>) {
+        let saved_state = self.rehash_policy.next_resize;
+        if let Some(count) = self.rehash_policy.need_rehash(self.bucket_count, self.element_count, 1) {
+            self.rehash_aux(count, saved_state);
+            bucket = self.bucket_index(code);
+        }
+        (*node).code = C::store(code);
+        self.insert_bucket_begin(bucket, node);
+        self.element_count += 1;
+    }
+
+    // _M_insert_bucket_begin
+    unsafe fn insert_bucket_begin(&mut self, bucket: usize, node: *mut HashNode<T0, C, A>) {
+        let prev = self.get_bucket(bucket);
+        if !prev.is_null() {
+            // Bucket is not empty, we just need to insert the new node after the bucket before
+            // begin.
+            (*node).next = (*prev).next;
+            (*prev).next = node;
+        } else {
+            // The bucket is empty, the new node is inserted at the beginning of the singly-linked
+            // list and the bucket will contain _M_before_begin pointer.
+            (*node).next = self.before_begin.next;
+            self.before_begin.next = node;
+            if !(*node).next.is_null() {
+                // We must update former begin bucket that is pointing to _M_before_begin.
+                let next_bucket = self.node_bucket((*node).next);
+                self.set_bucket(next_bucket, node);
+            }
+            let before_begin = self.get_before_begin();
+            self.set_bucket(bucket, before_begin);
+        }
+    }
+
+    /// Sets the number of buckets to at least count, rehashing the table
+    pub fn rehash(&mut self, count: usize) {
+        let saved_state = self.rehash_policy.next_resize;
+        let count = self.rehash_policy.buckets_for_elements(self.element_count + 1).max(count);
+        let count = self.rehash_policy.next_bucket(count);
+        match count != self.bucket_count {
+            true => unsafe { self.rehash_aux(count, saved_state) },
+            false => self.rehash_policy.next_resize = saved_state
+        }
+    }
+
+    // _M_rehash_aux (unique keys)
+    unsafe fn rehash_aux(&mut self, count: usize, _saved_state: usize) {
+        let new_buckets = self.allocate_buckets(count);
+        let before_begin = self.get_before_begin();
+        let mut p = self.before_begin.next;
+        self.before_begin.next = std::ptr::null_mut();
+        let mut bbegin_bucket = 0;
+        while !p.is_null() {
+            let next = (*p).next;
+            let bucket = self.node_code(p) as usize % count;
+            let prev = *new_buckets.add(bucket);
+            if prev.is_null() {
+                (*p).next = self.before_begin.next;
+                self.before_begin.next = p;
+                *new_buckets.add(bucket) = before_begin;
+                if !(*p).next.is_null() { *new_buckets.add(bbegin_bucket) = p; }
+                bbegin_bucket = bucket;
+            } else {
+                (*p).next = (*prev).next;
+                (*prev).next = p;
+            }
+            p = next;
+        }
+        self.deallocate_buckets();
+        self.bucket_count = count;
+        self.buckets = new_buckets;
+    }
+
+    // _M_erase(bkt, prev_n, n)
+    unsafe fn erase_node(&mut self, bucket: usize, prev: *mut HashNode<T0, C, A>, n: *mut HashNode<T0, C, A>) -> T0 {
+        let next = (*n).next;
+        if std::ptr::eq(prev, self.get_bucket(bucket)) {
+            let next_bucket = if next.is_null() { 0 } else { self.node_bucket(next) };
+            self.remove_bucket_begin(bucket, next, next_bucket);
+        } else if !next.is_null() {
+            let next_bucket = self.node_bucket(next);
+            if next_bucket != bucket { self.set_bucket(next_bucket, prev); }
+        }
+        (*prev).next = next;
+        self.element_count -= 1;
+        let value = ManuallyDrop::take(&mut (*n).data);
+        HashNode::delete(n, self._allocator.clone());
+        value
+    }
+
+    // _M_remove_bucket_begin
+    unsafe fn remove_bucket_begin(&mut self, bucket: usize, next: *mut HashNode<T0, C, A>, next_bucket: usize) {
+        if next.is_null() || next_bucket != bucket {
+            // Bucket is now empty. First update next bucket if any
+            if !next.is_null() { self.set_bucket(next_bucket, self.get_bucket(bucket)); }
+            // Second update before begin node if necessary
+            if std::ptr::eq(self.get_before_begin(), self.get_bucket(bucket)) {
+                self.before_begin.next = next;
+            }
+            self.set_bucket(bucket, std::ptr::null_mut());
+        }
+    }
+
+    /// Removes the element matching the key, returning it if it existed
+    pub fn take(&mut self, key: &T1) -> Option<T0> {
+        let code = H::get_hash(key);
+        let bucket = self.bucket_index(code);
+        unsafe {
+            let prev = self.find_before_node(bucket, key, code)?;
+            Some(self.erase_node(bucket, prev, (*prev).next))
+        }
+    }
+
+    pub fn erase(&mut self, key: &T1) -> bool { self.take(key).is_some() }
+
+    pub fn clear(&mut self) {
+        unsafe { self.deallocate_nodes() };
+        for i in 0..self.bucket_count { self.set_bucket(i, std::ptr::null_mut()) }
+        self.element_count = 0;
+        self.before_begin.next = std::ptr::null_mut();
+    }
+
+    // _M_deallocate_nodes
+    unsafe fn deallocate_nodes(&mut self) {
+        let mut n = self.before_begin.next;
+        while !n.is_null() {
+            let next = (*n).next;
+            ManuallyDrop::drop(&mut (*n).data);
+            HashNode::delete(n, self._allocator.clone());
+            n = next;
+        }
+    }
+}
+
+impl<H, T0, T1, C, A> Drop for HashTable<H, T0, T1, C, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    fn drop(&mut self) {
+        unsafe {
+            self.deallocate_nodes();
+            self.deallocate_buckets();
+        }
+    }
+}
+
+impl<T, C, A> HashNode<T, C, A>
+where C: HashCodeCache,
+      A: Allocator + Clone
+{
+    // _M_allocate_node. The hash code is stored once the node is inserted
+    fn new(value: T, alloc: A) -> *mut Self {
+        let new = alloc.allocate(Layout::new::<Self>()).unwrap().as_ptr() as *mut Self;
+        unsafe {
+            std::ptr::write(new, Self {
+                next: std::ptr::null_mut(),
+                data: ManuallyDrop::new(value),
+                code: C::store(0),
+                _allocator: alloc
+            });
+        }
+        new
+    }
+
+    // SAFETY: The node's value must be dropped or moved out beforehand
+    unsafe fn delete(node: *mut Self, alloc: A) {
+        alloc.deallocate(NonNull::new_unchecked(node as *mut u8), Layout::new::<Self>());
+    }
+
+    pub fn value(&self) -> &T { &self.data }
+    pub fn value_mut(&mut self) -> &mut T { &mut self.data }
+}
+
+impl<'a, H, T0, T1, C, A> IntoIterator for &'a HashTable<H, T0, T1, C, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    type Item = &'a T0;
+    type IntoIter = HashIterator<'a, T0, C, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        HashIterator { curr: self.before_begin.next, _marker: PhantomData }
+    }
+}
+
+impl<'a, H, T0, T1, C, A> IntoIterator for &'a mut HashTable<H, T0, T1, C, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    type Item = &'a mut T0;
+    type IntoIter = HashIteratorMut<'a, T0, C, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        HashIteratorMut { curr: self.before_begin.next, _marker: PhantomData }
+    }
+}
+
+impl<H, T0, T1, C, A> Index<&T1> for HashTable<H, T0, T1, C, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    type Output = T0;
+    fn index(&self, index: &T1) -> &Self::Output { self.find(index).unwrap() }
+}
+
+impl<H, T0, T1, C, A> IndexMut<&T1> for HashTable<H, T0, T1, C, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    fn index_mut(&mut self, index: &T1) -> &mut Self::Output { self.find_mut(index).unwrap() }
+}
+
+// Walks the singly linked list starting from _M_before_begin
+pub struct HashIterator<'a, T, C, A>
+where C: HashCodeCache,
+      A: Allocator + Clone
+{
+    curr: *mut HashNode<T, C, A>,
+    _marker: PhantomData<&'a T>
+}
+
+impl<'a, T: 'a, C: 'a, A: 'a> Iterator for HashIterator<'a, T, C, A>
+where C: HashCodeCache,
+      A: Allocator + Clone
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.curr.is_null() { return None }
+        let out = unsafe { &*(*self.curr).data };
+        self.curr = unsafe { (*self.curr).next };
+        Some(out)
+    }
+}
+
+pub struct HashIteratorMut<'a, T, C, A>
+where C: HashCodeCache,
+      A: Allocator + Clone
+{
+    curr: *mut HashNode<T, C, A>,
+    _marker: PhantomData<&'a mut T>
+}
+
+impl<'a, T: 'a, C: 'a, A: 'a> Iterator for HashIteratorMut<'a, T, C, A>
+where C: HashCodeCache,
+      A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.curr.is_null() { return None }
+        let out = unsafe { &mut *(*self.curr).data };
+        self.curr = unsafe { (*self.curr).next };
+        Some(out)
+    }
+}
+
+// Iterates through a single bucket (local_iterator), stopping once the next node belongs to
+// another bucket
+pub struct BucketIterator<'a, H, T0, T1, C, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    table: &'a HashTable<H, T0, T1, C, A>,
+    curr: *mut HashNode<T0, C, A>,
+    bucket: usize
+}
+
+impl<'a, H, T0, T1, C, A> Iterator for BucketIterator<'a, H, T0, T1, C, A>
+where H: Hasher + HasherInit,
+      T0: Hash + PartialEq<T1> + PartialEq,
+      T1: Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    type Item = &'a T0;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.curr.is_null() { return None }
+        let out = unsafe { &*(*self.curr).data };
+        let next = unsafe { (*self.curr).next };
+        self.curr = match next.is_null() || unsafe { self.table.node_bucket(next) } != self.bucket {
+            true => std::ptr::null_mut(),
+            false => next
+        };
+        Some(out)
+    }
+}
+
+// ========================================================
+
+// https://en.cppreference.com/w/cpp/container/unordered_set
+#[repr(C)]
+pub struct Set<H, T, C = NoCache, A = Global>
+where H: Hasher + HasherInit,
+      T: Hash + PartialEq,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{ _impl: HashTable<H, T, T, C, A> }
+
+impl<T> Set<StdHash, T, NoCache, Global>
+where T: Hash + PartialEq
+{
+    pub fn new_standalone() -> ABox<Self, Global> { Self::new_standalone_in(Global) }
+}
+
+impl<H, T, C, A> Set<H, T, C, A>
+where H: Hasher + HasherInit,
+      T: Hash + PartialEq,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    /// Constructs the unordered_set. Call setup_pointers() once it's in its final location
+    ///
+    /// # Safety
+    ///
+    /// It's unusable until setup_pointers() is called.
+    pub unsafe fn new_in(alloc: A) -> Self {
+        Self { _impl: HashTable::new_inner(alloc) }
+    }
+    pub fn new_standalone_in(alloc: A) -> ABox<Self, A> {
+        let mut new = ABox::new_in(unsafe { Self::new_in(alloc.clone()) }, alloc);
+        unsafe { new.setup_pointers() };
+        new
+    }
+    /// # Safety
+    ///
+    /// Same as the table's setup_pointers.
+    pub unsafe fn setup_pointers(&mut self) { self._impl.setup_pointers() }
+    /// Checks if the container has no elements
+    pub fn empty(&self) -> bool { self._impl.is_empty() }
+    /// Returns the number of elements in the container,
+    pub fn size(&self) -> usize { self._impl.len() }
+    /// Returns the number of buckets in the container
+    pub fn bucket_count(&self) -> usize { self._impl.bucket_count() }
+    /// Erases all elements from the container. After this call, size() returns zero.
+    pub fn clear(&mut self) { self._impl.clear() }
+    /// Inserts an element into the container, if the container doesn't already contain an
+    /// element with an equivalent key. This returns a bool that notes if the insertion took place
+    pub fn insert(&mut self, value: T) -> bool { self._impl.insert(value) }
+    pub fn erase(&mut self, val: &T) -> bool { self._impl.erase(val) }
+    pub fn contains(&self, val: &T) -> bool { self._impl.contains(val) }
+    pub fn find(&self, val: &T) -> Option<&T> { self._impl.find(val) }
+    pub fn rehash(&mut self, count: usize) { self._impl.rehash(count) }
+    pub fn iter(&self) -> HashIterator<'_, T, C, A> { self._impl.iter() }
+}
+
+impl<'a, H, T, C, A> IntoIterator for &'a Set<H, T, C, A>
+where H: Hasher + HasherInit,
+      T: Hash + PartialEq,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    type Item = &'a T;
+    type IntoIter = HashIterator<'a, T, C, A>;
+    fn into_iter(self) -> Self::IntoIter { self._impl.iter() }
+}
+
+// https://en.cppreference.com/w/cpp/container/unordered_map
+
+#[repr(C)]
+pub struct Map<H, K, V, C = NoCache, A = Global>
+where H: Hasher + HasherInit,
+      K: PartialEq + Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{ _impl: HashTable<H, MapPair<K, V>, K, C, A> }
+
+impl<K, V> Map<StdHash, K, V, NoCache, Global>
+where K: PartialEq + Hash
+{
+    pub fn new_standalone() -> ABox<Self, Global> { Self::new_standalone_in(Global) }
+}
+
+impl<H, K, V, C, A> Map<H, K, V, C, A>
+where H: Hasher + HasherInit,
+      K: PartialEq + Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    /// Constructs the unordered_map. Call setup_pointers() once it's in its final location
+    ///
+    /// # Safety
+    ///
+    /// It's unusable until setup_pointers() is called.
+    pub unsafe fn new_in(alloc: A) -> Self {
+        Self { _impl: HashTable::new_inner(alloc) }
+    }
+    pub fn new_standalone_in(alloc: A) -> ABox<Self, A> {
+        let mut new = ABox::new_in(unsafe { Self::new_in(alloc.clone()) }, alloc);
+        unsafe { new.setup_pointers() };
+        new
+    }
+    /// # Safety
+    ///
+    /// Same as the table's setup_pointers.
+    pub unsafe fn setup_pointers(&mut self) { self._impl.setup_pointers() }
+    /// Checks if the container has no elements
+    pub fn empty(&self) -> bool { self._impl.is_empty() }
+    /// Returns the number of elements in the container,
+    pub fn size(&self) -> usize { self._impl.len() }
+    /// Returns the number of buckets in the container
+    pub fn bucket_count(&self) -> usize { self._impl.bucket_count() }
+    /// Erases all elements from the container. After this call, size() returns zero.
+    pub fn clear(&mut self) { self._impl.clear() }
+    /// Inserts an element into the container, if the container doesn't already contain an
+    /// element with an equivalent key. This returns a bool that notes if the insertion took place
+    pub fn insert(&mut self, key: K, value: V) -> bool {
+        let pair = MapPair::new(key, value);
+        self._impl.insert(pair)
+    }
+    pub fn iter(&self) -> HashIterator<'_, MapPair<K, V>, C, A> { self._impl.iter() }
+    pub fn iter_mut(&mut self) -> HashIteratorMut<'_, MapPair<K, V>, C, A> { self._impl.iter_mut() }
+    pub fn find(&self, key: &K) -> Option<&MapPair<K, V>> { self._impl.find(key) }
+    pub fn find_mut(&mut self, key: &K) -> Option<&mut MapPair<K, V>> { self._impl.find_mut(key) }
+    pub fn contains(&self, key: &K) -> bool { self._impl.contains(key) }
+    pub fn erase(&mut self, key: &K) -> bool { self._impl.erase(key) }
+    pub fn rehash(&mut self, count: usize) { self._impl.rehash(count) }
+}
+
+impl<'a, H, K, V, C, A> IntoIterator for &'a Map<H, K, V, C, A>
+where H: Hasher + HasherInit,
+      K: PartialEq + Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    type Item = &'a MapPair<K, V>;
+    type IntoIter = HashIterator<'a, MapPair<K, V>, C, A>;
+    fn into_iter(self) -> Self::IntoIter { self._impl.iter() }
+}
+
+impl<'a, H, K, V, C, A> IntoIterator for &'a mut Map<H, K, V, C, A>
+where H: Hasher + HasherInit,
+      K: PartialEq + Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    type Item = &'a mut MapPair<K, V>;
+    type IntoIter = HashIteratorMut<'a, MapPair<K, V>, C, A>;
+    fn into_iter(self) -> Self::IntoIter { self._impl.iter_mut() }
+}
+
+impl<H, K, V, C, A> Index<&K> for Map<H, K, V, C, A>
+where H: Hasher + HasherInit,
+      K: PartialEq + Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    type Output = MapPair<K, V>;
+    fn index(&self, index: &K) -> &Self::Output { &self._impl[index] }
+}
+
+impl<H, K, V, C, A> IndexMut<&K> for Map<H, K, V, C, A>
+where H: Hasher + HasherInit,
+      K: PartialEq + Hash,
+      C: HashCodeCache,
+      A: Allocator + Clone
+{
+    fn index_mut(&mut self, index: &K) -> &mut Self::Output { &mut self._impl[index] }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use allocator_api2::alloc::Global;
+    use crate::gcc::hash::StdHash;
+    use super::{ Cache, HashTable, Map, NoCache, Set };
+    use std::error::Error;
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    // Expected values were taken from a libstdc++ 12 build
+    fn check_buckets<C: super::HashCodeCache>(table: &HashTable<StdHash, i32, i32, C, Global>,
+        order: &[i32], buckets: &[(usize, &[i32])]) {
+        let values: Vec<i32> = table.iter().copied().collect();
+        assert!(values == order, "Iteration order should be {:?} instead of {:?}", order, values);
+        let mut expected = buckets.iter();
+        for i in 0..table.bucket_count() {
+            let contents: Vec<i32> = table.bucket_iter(i).copied().collect();
+            if contents.is_empty() { continue }
+            let (bucket, values) = expected.next().unwrap();
+            assert!(i == *bucket && contents == *values, "Bucket {} should be {:?} instead of {:?}", bucket, values, contents);
+        }
+        assert!(expected.next().is_none(), "Some buckets were empty");
+    }
+
+    #[test]
+    pub fn hash_table_layout() -> TestReturn {
+        assert!(size_of::<HashTable<StdHash, i32, i32, NoCache, Global>>() == 0x38, "std::unordered_set should be 0x38 bytes");
+        let mut table = HashTable::<StdHash, i32, i32, Cache, Global>::new_standalone_inner(Global);
+        let base = &raw const *table as usize;
+        // _M_max_load_factor is a float followed by padding, so it's read separately from
+        // _M_next_resize and _M_single_bucket
+        let raw = unsafe { std::slice::from_raw_parts(base as *const usize, 4) };
+        let rest = unsafe { std::slice::from_raw_parts((base + 0x28) as *const usize, 2) };
+        assert!(raw == [base + 0x30, 1, 0, 0] && rest == [0, 0], "Empty table should point to single bucket");
+        assert!(table.max_load_factor() == 1.0, "Default max load factor should be 1.0");
+        table.insert(203);
+        let raw = unsafe { std::slice::from_raw_parts(base as *const usize, 4) };
+        let next_resize = unsafe { *((base + 0x28) as *const usize) };
+        assert!(raw[1] == 13 && raw[3] == 1 && next_resize == 13, "Table should have 13 buckets and resize at 13 elements");
+        // the i32 value is followed by padding before the hash code
+        let (next, value, hash) = unsafe {
+            (*(raw[2] as *const usize), *((raw[2] + 8) as *const i32), *((raw[2] + 16) as *const usize))
+        };
+        assert!((next, value, hash) == (0, 203, 203), "Cached node should be (next, value, hash code)");
+        let buckets = unsafe { std::slice::from_raw_parts(raw[0] as *const usize, 13) };
+        assert!(buckets[203 % 13] == base + 0x10, "First node's bucket should point to _M_before_begin");
+        Ok(())
+    }
+
+    #[test]
+    pub fn insert_into_hash_table() -> TestReturn {
+        let mut table = HashTable::<StdHash, i32, i32, NoCache, Global>::new_standalone_inner(Global);
+        let values = [5, 18, 31, 2, 44, 7, 13, 26, 0, 100, 12, 14, 27, 40];
+        for (i, v) in values.iter().enumerate() {
+            assert!(table.insert(*v), "Inserting {} should succeed", v);
+            if i == 12 { break }
+        }
+        assert!(!table.insert(18), "Duplicate keys should be rejected");
+        assert!(table.bucket_count() == 13, "Table should have 13 buckets");
+        check_buckets(&table, &[27, 14, 12, 100, 0, 26, 13, 7, 2, 44, 31, 18, 5],
+            &[(0, &[0, 26, 13]), (1, &[27, 14]), (2, &[2]), (5, &[44, 31, 18, 5]), (7, &[7]), (9, &[100]), (12, &[12])]);
+        // The 14th element goes past the max load factor, so the table is rehashed
+        table.insert(40);
+        assert!(table.bucket_count() == 29, "Table should have 29 buckets after rehashing");
+        check_buckets(&table, &[40, 5, 18, 44, 31, 2, 7, 26, 0, 13, 100, 12, 14, 27],
+            &[(0, &[0]), (2, &[31, 2]), (5, &[5]), (7, &[7]), (11, &[40]), (12, &[12]), (13, &[13, 100]),
+            (14, &[14]), (15, &[44]), (18, &[18]), (26, &[26]), (27, &[27])]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn erase_from_hash_table() -> TestReturn {
+        let mut table = HashTable::<StdHash, i32, i32, NoCache, Global>::new_standalone_inner(Global);
+        for v in [5, 18, 31, 2, 44, 7, 13, 26, 0, 100, 12, 14, 27, 40] { table.insert(v); }
+        for v in [31, 5, 100, 0] { assert!(table.erase(&v), "Erasing {} should succeed", v); }
+        assert!(!table.erase(&31), "31 was already erased");
+        check_buckets(&table, &[40, 18, 44, 2, 7, 26, 13, 12, 14, 27],
+            &[(2, &[2]), (7, &[7]), (11, &[40]), (12, &[12]), (13, &[13]), (14, &[14]), (15, &[44]),
+            (18, &[18]), (26, &[26]), (27, &[27])]);
+        // std::hash<int> sign extends, so this goes into bucket 0xffffffffffffffff % 29
+        table.insert(-1);
+        assert!(table.bucket(&-1) == 23, "-1 should go into bucket 23");
+        assert!(table.len() == 11, "Table should have 11 elements");
+        for v in [40, 18, 44, 2, 7, 26, 13, 12, 14, 27, -1] { table.erase(&v); }
+        assert!(table.is_empty() && table.iter().next().is_none(), "Table should be empty");
+        Ok(())
+    }
+
+    #[test]
+    pub fn cached_hash_table() -> TestReturn {
+        let mut table = HashTable::<StdHash, i32, i32, Cache, Global>::new_standalone_inner(Global);
+        for i in 0..30 { table.insert(i * 7); }
+        assert!(table.bucket_count() == 59, "Table should have 59 buckets");
+        assert!(table.iter().take(4).copied().collect::<Vec<_>>() == [203, 84, 77, 70], "Iteration order doesn't match");
+        table.rehash(5);
+        // the bucket count can't go below the number of elements
+        assert!(table.bucket_count() == 31, "Rehashed table should have 31 buckets");
+        assert!(table.iter().take(4).copied().collect::<Vec<_>>() == [196, 189, 182, 175], "Rehashed iteration order doesn't match");
+        assert!(table.bucket_size(24) == 0 && table.bucket_size(17) == 1, "Bucket 24 should be empty");
+        table.set_max_load_factor(0.5);
+        assert!(table.bucket_count() == 31, "Changing max load factor shouldn't rehash immediately");
+        table.insert(1000);
+        assert!(table.bucket_count() == 67, "Table should rehash to 67 buckets to keep a 0.5 load factor");
+        assert!((0..30).all(|i| table.contains(&(i * 7))), "Every element should still exist");
+        Ok(())
+    }
+
+    #[test]
+    pub fn hash_table_as_map() -> TestReturn {
+        let mut map = Map::<StdHash, u32, &str>::new_standalone();
+        map.insert(1, "Player");
+        map.insert(2, "Enemy");
+        assert!(!map.insert(1, "Chest"), "Key 1 should already exist");
+        assert!(*map[&1].get_value() == "Player", "Key 1 should be Player");
+        *map.find_mut(&2).unwrap().get_value_mut() = "Ally";
+        assert!(*map.find(&2).unwrap().get_value() == "Ally", "Key 2 should be Ally");
+        assert!(map.erase(&1) && !map.contains(&1), "Key 1 should be erased");
+        assert!(map.size() == 1, "Map should have 1 entry");
+        let mut set = Set::<StdHash, u64>::new_standalone();
+        for i in 0..100 { set.insert(i); }
+        assert!(set.size() == 100 && set.bucket_count() == 127, "Set should have 100 entries in 127 buckets");
+        Ok(())
+    }
+
+    #[test]
+    pub fn relocate_hash_table() -> TestReturn {
+        let mut table: HashTable<StdHash, i32, i32, NoCache, Global> = unsafe { HashTable::new_inner(Global) };
+        unsafe { table.setup_pointers() };
+        for i in 0..4 { table.insert(i); }
+        let mut moved = Box::new(table);
+        unsafe { moved.setup_pointers() };
+        moved.erase(&3);
+        moved.insert(4);
+        assert!(moved.iter().copied().collect::<Vec<_>>() == [4, 2, 1, 0], "Iteration order doesn't match");
+        let mut empty: HashTable<StdHash, i32, i32, NoCache, Global> = unsafe { HashTable::new_inner(Global) };
+        unsafe { empty.setup_pointers() };
+        let mut moved = Box::new(empty);
+        unsafe { moved.setup_pointers() };
+        moved.insert(1);
+        assert!(moved.contains(&1), "Moved empty table should be usable");
+        Ok(())
+    }
+}
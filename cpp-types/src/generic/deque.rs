@@ -0,0 +1,34 @@
+// Test support shared by every std::deque implementation
+
+/// Runs a deque through a pseudo-random mix of pushes and pops at both ends, checking it against
+/// a [`VecDeque`](std::collections::VecDeque) after every operation, then checks iteration,
+/// indexing and in-place edits. `$value` turns a random u32 into an element, `$edit` modifies an
+/// element, and `$check` checks layout invariants of the deque after each operation.
+macro_rules! check_against_vec_deque {
+    ($deque:expr, $ops:expr, $value:expr, $edit:expr, $check:expr) => {{
+        let mut deque = $deque;
+        let mut expected = std::collections::VecDeque::new();
+        let mut seed = 0x1234_5678u32;
+        for _ in 0..$ops {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            let value = $value(seed >> 16);
+            match (seed >> 16) % 5 {
+                0 | 1 => { deque.push_back(value.clone()); expected.push_back(value); },
+                2 => { deque.push_front(value.clone()); expected.push_front(value); },
+                3 => assert!(deque.pop_front() == expected.pop_front(), "Front pops should match"),
+                _ => assert!(deque.pop_back() == expected.pop_back(), "Back pops should match")
+            }
+            assert!(deque.len() == expected.len() && deque.front() == expected.front() && deque.back() == expected.back(), "Ends should match");
+            $check(&deque);
+        }
+        assert!(deque.iter().eq(expected.iter()) && deque.iter().rev().eq(expected.iter().rev()), "Iteration should match");
+        for i in 0..deque.len() { assert!(deque[i] == expected[i], "Index {} should match", i); }
+        for v in deque.iter_mut() { $edit(v); }
+        for v in expected.iter_mut() { $edit(v); }
+        assert!(deque.iter().eq(expected.iter()), "Values should be edited in place");
+        assert!(deque.get(deque.len()).is_none(), "Out of bounds index should be None");
+        deque
+    }};
+}
+
+pub(crate) use check_against_vec_deque;
@@ -0,0 +1,82 @@
+// Hasher construction shared by every std::unordered_map/std::unordered_set implementation
+
+use std::hash::Hash;
+
+pub trait HasherInit {
+    fn new() -> Self where Self: Sized;
+    fn get_hash<H>(value: &H) -> u64 where H: Hash;
+}
+
+/// What a std::hash-style hasher has been given so far. std::hash only ever hashes one value,
+/// so a second write is a key type that C++ couldn't hash this way.
+#[cfg(any(feature = "clang", feature = "gcc"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HashedValue {
+    Nothing,
+    Bytes,
+    Integer
+}
+
+// Implements HasherInit and Hasher for a libstdc++/libc++ StdHash, which hashes integers to
+// themselves and byte sequences with $bytes. The caller imports Hash, Hasher, HasherInit and
+// HashedValue.
+#[cfg(any(feature = "clang", feature = "gcc"))]
+macro_rules! single_value_hasher {
+    ($name:ident, $bytes:expr) => {
+        impl $name {
+            fn set(&mut self, hash: u64, value: HashedValue) {
+                debug_assert!(self.value == HashedValue::Nothing,
+                    "std::hash only hashes a single integer or string, so compound keys need their own hasher");
+                self.hash = hash;
+                self.value = value;
+            }
+        }
+
+        impl HasherInit for $name {
+            fn new() -> Self { Self { hash: 0, value: HashedValue::Nothing } }
+            fn get_hash<H>(value: &H) -> u64 where H: Hash {
+                let mut hasher = Self::new();
+                value.hash(&mut hasher);
+                hasher.finish()
+            }
+        }
+
+        impl Hasher for $name {
+            fn write(&mut self, bytes: &[u8]) {
+                self.set($bytes(bytes) as u64, HashedValue::Bytes)
+            }
+            fn write_u8(&mut self, i: u8) {
+                // str's Hash impl writes a 0xff terminator after the bytes, which C++ doesn't hash
+                if i == 0xff && self.value == HashedValue::Bytes { return }
+                self.set(i as u64, HashedValue::Integer)
+            }
+            fn write_u16(&mut self, i: u16) { self.set(i as u64, HashedValue::Integer) }
+            fn write_u32(&mut self, i: u32) { self.set(i as u64, HashedValue::Integer) }
+            fn write_u64(&mut self, i: u64) { self.set(i, HashedValue::Integer) }
+            fn write_usize(&mut self, i: usize) { self.set(i as u64, HashedValue::Integer) }
+            fn write_i8(&mut self, i: i8) { self.set(i as u64, HashedValue::Integer) }
+            fn write_i16(&mut self, i: i16) { self.set(i as u64, HashedValue::Integer) }
+            fn write_i32(&mut self, i: i32) { self.set(i as u64, HashedValue::Integer) }
+            fn write_i64(&mut self, i: i64) { self.set(i as u64, HashedValue::Integer) }
+            fn write_isize(&mut self, i: isize) { self.set(i as u64, HashedValue::Integer) }
+            fn finish(&self) -> u64 { self.hash }
+        }
+    };
+}
+
+#[cfg(any(feature = "clang", feature = "gcc"))]
+pub(crate) use single_value_hasher;
+
+/// Key equality function object for unordered containers (the KeyEqual template parameter).
+/// Hash tables store an instance of this, so it can carry state.
+pub trait KeyEqual<T: ?Sized> {
+    fn key_eq(&self, d0: &T, d1: &T) -> bool;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EqualTo; // std::equal_to
+impl<T: ?Sized> KeyEqual<T> for EqualTo
+where T: PartialEq
+{
+    fn key_eq(&self, d0: &T, d1: &T) -> bool { d0 == d1 }
+}
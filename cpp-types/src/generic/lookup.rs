@@ -0,0 +1,38 @@
+// Key extraction and heterogeneous lookup shared by every std::map/std::set and
+// std::unordered_map/std::unordered_set implementation
+
+/// Gets the key out of a container element, like _Kfn in MSVC's container traits. Sets use the
+/// element itself as the key, while maps use the first member of their pair.
+pub trait KeyOf<K> {
+    fn key_of(&self) -> &K;
+}
+
+impl<T> KeyOf<T> for T {
+    fn key_of(&self) -> &T { self }
+}
+
+/// A borrowed form that a key can be looked up by, modeled on transparent comparators
+/// (std::less<>) and C++20 heterogeneous unordered lookup. This fills the role of
+/// [`std::borrow::Borrow`] without its requirement that both forms hash the same way through
+/// [`Hash`](std::hash::Hash), since C++ strings hash their characters without the terminator
+/// Rust adds for str. Comparing or hashing the borrowed form must give the same result as the
+/// key it was borrowed from.
+pub trait KeyBorrow<Q: ?Sized> {
+    fn key_borrow(&self) -> &Q;
+}
+
+impl<T> KeyBorrow<T> for T {
+    fn key_borrow(&self) -> &T { self }
+}
+
+/// Creates an owned key from a borrowed lookup form, used when an entry lookup inserts a new
+/// element.
+pub trait ToKey<K> {
+    fn to_key(&self) -> K;
+}
+
+impl<K> ToKey<K> for K
+where K: Clone
+{
+    fn to_key(&self) -> K { self.clone() }
+}
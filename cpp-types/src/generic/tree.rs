@@ -0,0 +1,244 @@
+// Comparison functors and element types shared by every std::map/std::set implementation
+
+use crate::generic::lookup::KeyOf;
+use std::hash::{ Hash, Hasher };
+
+/// A std::map/std::set comparison function object. This is stored inside the tree (like C++'s
+/// key_compare), so comparators can carry state. Empty comparators such as [`CompareLess`] are
+/// zero-sized and don't change the tree's layout.
+pub trait TreeCompare<A: ?Sized, B: ?Sized>
+where A: PartialEq + PartialEq<B> + PartialOrd + PartialOrd<B>,
+      // B: PartialEq<A> + PartialOrd<A>
+{
+    fn compare_aa(&self, d0: &A, d1: &A) -> bool;
+    fn compare_ab(&self, d0: &A, d1: &B) -> bool;
+    fn compare_ba(&self, d0: &B, d1: &A) -> bool;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CompareLess; // std::less
+impl<A: ?Sized, B: ?Sized> TreeCompare<A, B> for CompareLess
+where A: PartialEq + PartialEq<B> + PartialOrd + PartialOrd<B>,
+      // B: PartialEq<A> + PartialOrd<A>
+{
+    fn compare_aa(&self, d0: &A, d1: &A) -> bool { d0 < d1 }
+    fn compare_ab(&self, d0: &A, d1: &B) -> bool { d0 < d1 }
+    fn compare_ba(&self, d0: &B, d1: &A) -> bool { d1 > d0 }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CompareGreater; // std::greater
+impl<A: ?Sized, B: ?Sized> TreeCompare<A, B> for CompareGreater
+where A: PartialEq + PartialEq<B> + PartialOrd + PartialOrd<B>,
+      // B: PartialEq<A> + PartialOrd<A>
+{
+    fn compare_aa(&self, d0: &A, d1: &A) -> bool { d0 > d1 }
+    fn compare_ab(&self, d0: &A, d1: &B) -> bool { d0 > d1 }
+    fn compare_ba(&self, d0: &B, d1: &A) -> bool { d1 < d0 }
+}
+
+/// std::pair<const K, V>, the element type of every std::map and std::unordered_map. Pairs are
+/// compared, hashed and looked up by their key alone.
+#[repr(C)]
+#[derive(Debug)]
+pub struct MapPair<K, V> {
+    pub(crate) key: K,
+    pub(crate) value: V
+}
+
+impl<K, V> MapPair<K, V> {
+    pub fn new(key: K, value: V) -> Self {
+        Self { key, value }
+    }
+    pub fn get_key(&self) -> &K { &self.key }
+    pub fn get_value(&self) -> &V { &self.value }
+    pub fn get_key_mut(&mut self) -> &mut K { &mut self.key }
+    pub fn get_value_mut(&mut self) -> &mut V { &mut self.value }
+}
+
+impl<K, V> MapPair<K, V>
+where K: Copy
+{
+    pub fn get_key_copy(&self) -> K { self.key }
+}
+
+impl<K, V> KeyOf<K> for MapPair<K, V> {
+    fn key_of(&self) -> &K { &self.key }
+}
+
+impl<K, V> Hash for MapPair<K, V>
+where K: Hash
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state)
+    }
+}
+
+impl<K, V> PartialEq for MapPair<K, V>
+where K: PartialEq
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K, V> PartialEq<K> for MapPair<K, V>
+where K: PartialEq
+{
+    fn eq(&self, other: &K) -> bool {
+        self.key == *other
+    }
+}
+
+impl<K, V> PartialOrd for MapPair<K, V>
+where K: PartialOrd
+{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.key.partial_cmp(&other.key)
+    }
+}
+
+impl<K, V> PartialOrd<K> for MapPair<K, V>
+where K: PartialOrd
+{
+    fn partial_cmp(&self, other: &K) -> Option<std::cmp::Ordering> {
+        self.key.partial_cmp(other)
+    }
+}
+
+/// Defines the std::set and std::map wrappers over a tree which looks keys up by value
+/// (libstdc++'s _Rb_tree and libc++'s __tree). The invoking module must provide `Tree`,
+/// `TreeIterator` and `TreeIteratorMut`, with `Tree::setup_pointers` fixing up the pointers into
+/// the tree after it's moved.
+#[cfg(any(feature = "clang", feature = "gcc"))]
+macro_rules! value_lookup_wrappers {
+    () => {
+        #[repr(C)]
+        pub struct Set<C, T, A = Global>
+        where C: TreeCompare<T, T>,
+              T: PartialEq + PartialOrd,
+              A: Allocator + Clone
+        {
+            _impl: Tree<C, T, T, A>
+        }
+
+        impl<C, T> Set<C, T, Global>
+        where C: TreeCompare<T, T>,
+              T: PartialEq + PartialOrd
+        {
+            pub fn new_standalone() -> ABox<Self, Global>
+            where C: Default
+            {
+                Self::new_standalone_in(Global)
+            }
+        }
+
+        impl<C, T, A> Set<C, T, A>
+        where C: TreeCompare<T, T>,
+              T: PartialEq + PartialOrd,
+              A: Allocator + Clone
+        {
+            /// # Safety
+            ///
+            /// Same as the tree's new_inner: call setup_pointers once this is at its final address.
+            pub unsafe fn new_inner(alloc: A) -> Self
+            where C: Default
+            {
+                Self { _impl: Tree::new_inner(alloc) }
+            }
+            pub fn new_standalone_in(alloc: A) -> ABox<Self, A>
+            where C: Default
+            {
+                let mut new = ABox::new_in(unsafe { Self::new_inner(alloc.clone()) }, alloc);
+                unsafe { new.setup_pointers() };
+                new
+            }
+            /// # Safety
+            ///
+            /// Same as the tree's setup_pointers.
+            pub unsafe fn setup_pointers(&mut self) { self._impl.setup_pointers() }
+            pub fn insert(&mut self, value: T) -> bool { self._impl.insert(value) }
+            pub fn remove(&mut self, value: T) -> bool { self._impl.remove(value) }
+            pub fn len(&self) -> usize { self._impl.len() }
+            pub fn is_empty(&self) -> bool { self._impl.is_empty() }
+            pub fn clear(&mut self) { self._impl.clear() }
+            pub fn contains(&self, value: T) -> bool { self._impl.contains(value) }
+            pub fn find(&self, value: T) -> Option<&T> { self._impl.find(value) }
+            pub fn first(&self) -> Option<&T> { self._impl.first() }
+            pub fn last(&self) -> Option<&T> { self._impl.last() }
+            pub fn lower_bound(&self, value: T) -> TreeIterator<'_, T, A> { self._impl.lower_bound(value) }
+            pub fn upper_bound(&self, value: T) -> TreeIterator<'_, T, A> { self._impl.upper_bound(value) }
+            pub fn iter(&self) -> TreeIterator<'_, T, A> { self._impl.iter() }
+        }
+
+        #[repr(C)]
+        pub struct Map<C, K, V, A = Global>
+        where C: TreeCompare<MapPair<K, V>, K>,
+              K: PartialEq + PartialOrd,
+              A: Allocator + Clone
+        {
+            _impl: Tree<C, MapPair<K, V>, K, A>
+        }
+
+        impl<C, K, V> Map<C, K, V, Global>
+        where C: TreeCompare<MapPair<K, V>, K>,
+              K: PartialEq + PartialOrd
+        {
+            pub fn new_standalone() -> ABox<Self, Global>
+            where C: Default
+            {
+                Self::new_standalone_in(Global)
+            }
+        }
+
+        impl<C, K, V, A> Map<C, K, V, A>
+        where C: TreeCompare<MapPair<K, V>, K>,
+              K: PartialEq + PartialOrd,
+              A: Allocator + Clone
+        {
+            /// # Safety
+            ///
+            /// Same as the tree's new_inner: call setup_pointers once this is at its final address.
+            pub unsafe fn new_inner(alloc: A) -> Self
+            where C: Default
+            {
+                Self { _impl: Tree::new_inner(alloc) }
+            }
+            pub fn new_standalone_in(alloc: A) -> ABox<Self, A>
+            where C: Default
+            {
+                let mut new = ABox::new_in(unsafe { Self::new_inner(alloc.clone()) }, alloc);
+                unsafe { new.setup_pointers() };
+                new
+            }
+            /// # Safety
+            ///
+            /// Same as the tree's setup_pointers.
+            pub unsafe fn setup_pointers(&mut self) { self._impl.setup_pointers() }
+            pub fn insert(&mut self, key: K, value: V) -> bool {
+                let pair = MapPair::new(key, value);
+                self._impl.insert(pair)
+            }
+            pub fn remove(&mut self, key: K) -> Option<V> {
+                self._impl.take(key).map(|v| v.value)
+            }
+            pub fn len(&self) -> usize { self._impl.len() }
+            pub fn is_empty(&self) -> bool { self._impl.is_empty() }
+            pub fn clear(&mut self) { self._impl.clear() }
+            pub fn contains(&self, key: K) -> bool { self._impl.contains(key) }
+            pub fn find(&self, key: K) -> Option<&V> {
+                self._impl.find(key).map(|v| v.get_value())
+            }
+            pub fn find_mut(&mut self, key: K) -> Option<&mut V> {
+                self._impl.find_mut(key).map(|v| v.get_value_mut())
+            }
+            pub fn lower_bound(&self, key: K) -> TreeIterator<'_, MapPair<K, V>, A> { self._impl.lower_bound(key) }
+            pub fn upper_bound(&self, key: K) -> TreeIterator<'_, MapPair<K, V>, A> { self._impl.upper_bound(key) }
+            pub fn iter(&self) -> TreeIterator<'_, MapPair<K, V>, A> { self._impl.iter() }
+            pub fn iter_mut(&mut self) -> TreeIteratorMut<'_, MapPair<K, V>, A> { self._impl.iter_mut() }
+        }
+    };
+}
+
+#[cfg(any(feature = "clang", feature = "gcc"))]
+pub(crate) use value_lookup_wrappers;
@@ -1,25 +1,47 @@
+// Lets derive macros refer to ::cpp_types from inside this crate
+extern crate self as cpp_types;
+
 #[cfg(feature = "clang")]
 pub mod clang {
+    pub mod deque;
+    pub mod hash;
+    pub mod list;
     pub mod string;
+    pub mod tree;
+    pub mod unordered;
     pub mod vector;
 }
 #[cfg(feature = "gcc")]
 pub mod gcc {
+    pub mod deque;
+    pub mod hash;
     pub mod string;
+    pub mod tree;
+    pub mod unordered;
     pub mod vector;
 }
 pub mod generic {
+    #[cfg(test)]
+    pub(crate) mod deque;
+    pub mod hash;
+    pub mod lookup;
     pub mod string;
+    pub mod tree;
     pub mod vector;
 }
 #[cfg(feature = "msvc")]
 pub mod msvc {
+    pub mod adapters;
+    pub mod algorithm;
+    pub mod deque;
+    pub mod forward_list;
     pub mod function;
     pub mod hash;
     pub mod list;
     #[cfg(target_os = "windows")]
     pub mod mutex;
     pub mod optional;
+    pub mod random;
     pub mod shared_ptr;
     pub mod tree;
     pub mod string;
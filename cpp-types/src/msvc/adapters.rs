@@ -0,0 +1,326 @@
+#![allow(dead_code)]
+use allocator_api2::alloc::{ Allocator, Global };
+use crate::{
+    generic::tree::{ CompareLess, TreeCompare },
+    msvc::{
+        algorithm::{ pop_heap_by, push_heap_by, make_heap_by },
+        deque::Deque,
+        vector::Vector
+    }
+};
+use std::{
+    marker::PhantomData,
+    mem::ManuallyDrop
+};
+
+// https://github.com/microsoft/STL/blob/main/stl/inc/stack
+// https://github.com/microsoft/STL/blob/main/stl/inc/queue
+
+/// A sequence container that can be used by [`Stack`]
+pub trait StackContainer<T> {
+    fn push_back(&mut self, value: T);
+    fn pop_back(&mut self) -> Option<T>;
+    fn back(&self) -> Option<&T>;
+    fn back_mut(&mut self) -> Option<&mut T>;
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool { self.len() == 0 }
+}
+
+/// A sequence container that can be used by [`Queue`]
+pub trait QueueContainer<T>: StackContainer<T> {
+    fn pop_front(&mut self) -> Option<T>;
+    fn front(&self) -> Option<&T>;
+    fn front_mut(&mut self) -> Option<&mut T>;
+}
+
+impl<T, A> StackContainer<T> for Vector<T, A>
+where A: Allocator
+{
+    fn push_back(&mut self, value: T) { self.push(value) }
+    fn pop_back(&mut self) -> Option<T> { self.pop() }
+    fn back(&self) -> Option<&T> { Vector::back(self) }
+    fn back_mut(&mut self) -> Option<&mut T> { Vector::back_mut(self) }
+    fn len(&self) -> usize { Vector::len(self) }
+}
+
+impl<T, A> StackContainer<T> for Deque<T, A>
+where A: Allocator + Clone
+{
+    fn push_back(&mut self, value: T) { Deque::push_back(self, value) }
+    fn pop_back(&mut self) -> Option<T> { Deque::pop_back(self) }
+    fn back(&self) -> Option<&T> { Deque::back(self) }
+    fn back_mut(&mut self) -> Option<&mut T> { Deque::back_mut(self) }
+    fn len(&self) -> usize { Deque::len(self) }
+}
+
+impl<T, A> QueueContainer<T> for Deque<T, A>
+where A: Allocator + Clone
+{
+    fn pop_front(&mut self) -> Option<T> { Deque::pop_front(self) }
+    fn front(&self) -> Option<&T> { Deque::front(self) }
+    fn front_mut(&mut self) -> Option<&mut T> { Deque::front_mut(self) }
+}
+
+/// std::stack, which is only the underlying container (a [`Deque`] by default)
+#[repr(C)]
+pub struct Stack<T, C = Deque<T, Global>>
+where C: StackContainer<T>
+{
+    c: C, // c
+    _data: PhantomData<T>
+}
+
+impl<T> Stack<T, Deque<T, Global>> {
+    pub fn new() -> Self { Self::from_container(Deque::new()) }
+}
+
+impl<T> Default for Stack<T, Deque<T, Global>> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T, C> Stack<T, C>
+where C: StackContainer<T>
+{
+    pub fn from_container(c: C) -> Self { Self { c, _data: PhantomData } }
+    pub fn container(&self) -> &C { &self.c }
+    pub fn container_mut(&mut self) -> &mut C { &mut self.c }
+    pub fn into_container(self) -> C { self.c }
+    pub fn len(&self) -> usize { self.c.len() }
+    pub fn is_empty(&self) -> bool { self.c.is_empty() }
+    pub fn push(&mut self, value: T) { self.c.push_back(value) }
+    pub fn pop(&mut self) -> Option<T> { self.c.pop_back() }
+    /// Returns the most recently pushed element
+    pub fn top(&self) -> Option<&T> { self.c.back() }
+    pub fn top_mut(&mut self) -> Option<&mut T> { self.c.back_mut() }
+    /// Checks if the container has no elements
+    pub fn empty(&self) -> bool { self.is_empty() }
+    /// Returns the number of elements in the container
+    pub fn size(&self) -> usize { self.len() }
+}
+
+/// std::queue, which is only the underlying container (a [`Deque`] by default)
+#[repr(C)]
+pub struct Queue<T, C = Deque<T, Global>>
+where C: QueueContainer<T>
+{
+    c: C, // c
+    _data: PhantomData<T>
+}
+
+impl<T> Queue<T, Deque<T, Global>> {
+    pub fn new() -> Self { Self::from_container(Deque::new()) }
+}
+
+impl<T> Default for Queue<T, Deque<T, Global>> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T, C> Queue<T, C>
+where C: QueueContainer<T>
+{
+    pub fn from_container(c: C) -> Self { Self { c, _data: PhantomData } }
+    pub fn container(&self) -> &C { &self.c }
+    pub fn container_mut(&mut self) -> &mut C { &mut self.c }
+    pub fn into_container(self) -> C { self.c }
+    pub fn len(&self) -> usize { self.c.len() }
+    pub fn is_empty(&self) -> bool { self.c.is_empty() }
+    /// Adds an element to the back of the queue
+    pub fn push(&mut self, value: T) { self.c.push_back(value) }
+    /// Removes the element at the front of the queue
+    pub fn pop(&mut self) -> Option<T> { self.c.pop_front() }
+    pub fn front(&self) -> Option<&T> { self.c.front() }
+    pub fn front_mut(&mut self) -> Option<&mut T> { self.c.front_mut() }
+    pub fn back(&self) -> Option<&T> { self.c.back() }
+    pub fn back_mut(&mut self) -> Option<&mut T> { self.c.back_mut() }
+    /// Checks if the container has no elements
+    pub fn empty(&self) -> bool { self.is_empty() }
+    /// Returns the number of elements in the container
+    pub fn size(&self) -> usize { self.len() }
+}
+
+/// std::priority_queue, which keeps its container (a [`Vector`] by default) arranged as a heap
+/// with MSVC's heap algorithms, so the order of the underlying array matches the C++ side after
+/// every push and pop
+#[repr(C)]
+pub struct PriorityQueue<T, C = Vector<T, Global>, P = CompareLess>
+where C: StackContainer<T> + AsRef<[T]> + AsMut<[T]>,
+      P: TreeCompare<T, T>,
+      T: PartialEq + PartialOrd
+{
+    c: C, // c
+    comp: CompareMember<P>, // comp
+    _data: PhantomData<T>
+}
+
+// comp is a plain member rather than a compressed pair, so an empty comparator still takes up a
+// byte after the container while a stateful one is stored as it is
+#[repr(C)]
+union CompareMember<P> {
+    comp: ManuallyDrop<P>,
+    _empty: u8
+}
+
+impl<P> Drop for CompareMember<P> {
+    fn drop(&mut self) { unsafe { ManuallyDrop::drop(&mut self.comp) } }
+}
+
+impl<T> PriorityQueue<T, Vector<T, Global>, CompareLess>
+where T: PartialEq + PartialOrd
+{
+    pub fn new() -> Self { Self::new_in(Global) }
+}
+
+impl<T> Default for PriorityQueue<T, Vector<T, Global>, CompareLess>
+where T: PartialEq + PartialOrd
+{
+    fn default() -> Self { Self::new() }
+}
+
+impl<T, P> PriorityQueue<T, Vector<T, Global>, P>
+where P: TreeCompare<T, T> + Default,
+      T: PartialEq + PartialOrd
+{
+    pub fn from_vec(vec: Vec<T>) -> Self { Self::from_container(Vector::from_vec(vec)) }
+}
+
+impl<T, P, A> PriorityQueue<T, Vector<T, A>, P>
+where P: TreeCompare<T, T>,
+      T: PartialEq + PartialOrd,
+      A: Allocator
+{
+    pub fn new_in(alloc: A) -> Self
+    where P: Default
+    {
+        Self::from_container(Vector::new_in(alloc))
+    }
+}
+
+impl<T, C, P> PriorityQueue<T, C, P>
+where C: StackContainer<T> + AsRef<[T]> + AsMut<[T]>,
+      P: TreeCompare<T, T>,
+      T: PartialEq + PartialOrd
+{
+    /// Makes a heap out of an existing container
+    pub fn from_container(c: C) -> Self
+    where P: Default
+    {
+        Self::with_compare(P::default(), c)
+    }
+    /// Makes a heap out of an existing container, ordered by the given comparison function object
+    pub fn with_compare(comp: P, mut c: C) -> Self {
+        make_heap_by(c.as_mut(), |a, b| comp.compare_aa(a, b));
+        Self { c, comp: CompareMember { comp: ManuallyDrop::new(comp) }, _data: PhantomData }
+    }
+    pub fn container(&self) -> &C { &self.c }
+    pub fn into_container(self) -> C {
+        let mut this = ManuallyDrop::new(self);
+        unsafe {
+            ManuallyDrop::drop(&mut this.comp.comp);
+            std::ptr::read(&this.c)
+        }
+    }
+    /// Returns the comparison function object
+    pub fn comp(&self) -> &P { unsafe { &self.comp.comp } }
+    /// The elements in the order they're stored in the heap
+    pub fn as_slice(&self) -> &[T] { self.c.as_ref() }
+    pub fn len(&self) -> usize { self.c.len() }
+    pub fn is_empty(&self) -> bool { self.c.is_empty() }
+    pub fn push(&mut self, value: T) {
+        self.c.push_back(value);
+        let comp = unsafe { &self.comp.comp };
+        push_heap_by(self.c.as_mut(), |a, b| comp.compare_aa(a, b));
+    }
+    /// Removes the greatest element according to the comparator
+    pub fn pop(&mut self) -> Option<T> {
+        let comp = unsafe { &self.comp.comp };
+        pop_heap_by(self.c.as_mut(), |a, b| comp.compare_aa(a, b));
+        self.c.pop_back()
+    }
+    /// Returns the greatest element according to the comparator
+    pub fn top(&self) -> Option<&T> { self.c.as_ref().first() }
+    /// Checks if the container has no elements
+    pub fn empty(&self) -> bool { self.is_empty() }
+    /// Returns the number of elements in the container
+    pub fn size(&self) -> usize { self.len() }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        generic::tree::{ CompareGreater, TreeCompare },
+        msvc::{ algorithm::is_heap, deque::Deque, vector::Vector }
+    };
+    use super::{ PriorityQueue, Queue, Stack };
+    use std::error::Error;
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    // Stateful comparator, ranking values by their remainder
+    pub struct ByRemainder(u32);
+    impl TreeCompare<u32, u32> for ByRemainder {
+        fn compare_aa(&self, d0: &u32, d1: &u32) -> bool { d0 % self.0 < d1 % self.0 }
+        fn compare_ab(&self, d0: &u32, d1: &u32) -> bool { d0 % self.0 < d1 % self.0 }
+        fn compare_ba(&self, d0: &u32, d1: &u32) -> bool { d0 % self.0 < d1 % self.0 }
+    }
+
+    #[test]
+    pub fn adapter_layout() -> TestReturn {
+        assert!(std::mem::size_of::<Stack<u32>>() == std::mem::size_of::<Deque<u32>>(), "Stack should only be its container");
+        assert!(std::mem::size_of::<Queue<u32>>() == 40, "Queue should only be its container");
+        assert!(std::mem::size_of::<PriorityQueue<u32>>() == 32, "Priority queue should be a vector then the comparator");
+        assert!(std::mem::offset_of!(PriorityQueue<u32>, comp) == 24, "Empty comparator should take a byte after the vector");
+        assert!(std::mem::size_of::<PriorityQueue<u32, Vector<u32>, ByRemainder>>() == 32, "Stateful comparator should fit after the vector");
+        let queue = PriorityQueue::with_compare(ByRemainder(7), Vector::<u32>::new());
+        let comp = unsafe { *((&queue as *const PriorityQueue<u32, Vector<u32>, ByRemainder> as *const u8).add(24) as *const u32) };
+        assert!(comp == 7, "Comparator state should be stored at offset 24, got {}", comp);
+        Ok(())
+    }
+
+    #[test]
+    pub fn stack_and_queue() -> TestReturn {
+        let mut stack = Stack::new();
+        for i in 0..5 { stack.push(i); }
+        *stack.top_mut().unwrap() = 40;
+        assert!(stack.pop() == Some(40) && stack.top() == Some(&3) && stack.size() == 4, "Stack should pop the last pushed value");
+        let mut vec_stack = Stack::from_container(Vector::from_vec(vec![1, 2]));
+        vec_stack.push(3);
+        assert!(vec_stack.pop() == Some(3) && vec_stack.container().as_slice() == [1, 2], "Stack should work over a vector");
+
+        let mut queue = Queue::new();
+        for i in 0..5 { queue.push(i); }
+        assert!(queue.front() == Some(&0) && queue.back() == Some(&4), "Queue should have both ends");
+        assert!(queue.pop() == Some(0) && queue.pop() == Some(1) && queue.len() == 3, "Queue should pop the first pushed value");
+        let rest: Vec<i32> = queue.container().iter().copied().collect();
+        assert!(rest == [2, 3, 4], "Queue should keep its order");
+        Ok(())
+    }
+
+    // Expected orders are from pushing and popping a std::priority_queue<int>, which uses the
+    // same heap algorithm in MSVC and libstdc++ (built here with g++)
+    #[test]
+    pub fn priority_queue_order() -> TestReturn {
+        let mut queue: PriorityQueue<i32> = PriorityQueue::new();
+        for v in [3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5] { queue.push(v); }
+        assert!(queue.as_slice() == [9, 6, 5, 5, 5, 3, 2, 1, 4, 1, 3], "Heap should match C++, got {:?}", queue.as_slice());
+        assert!(queue.pop() == Some(9) && queue.top() == Some(&6), "Greatest value should be popped");
+        assert!(queue.as_slice() == [6, 5, 5, 5, 3, 3, 2, 1, 4, 1], "Heap should match C++ after pop, got {:?}", queue.as_slice());
+
+        let mut tasks: PriorityQueue<u32, Vector<u32>, CompareGreater> = PriorityQueue::from_vec(vec![30, 10, 50, 20, 40]);
+        assert!(tasks.as_slice() == [10, 20, 50, 30, 40], "Min heap should match C++, got {:?}", tasks.as_slice());
+        let mut order = vec![];
+        while let Some(task) = tasks.pop() { order.push(task); }
+        assert!(order == [10, 20, 30, 40, 50] && tasks.empty(), "Min heap should pop in ascending order");
+
+        let mut buckets = PriorityQueue::with_compare(ByRemainder(10), Vector::from_vec(vec![13, 7, 21, 45, 38]));
+        assert!(buckets.comp().0 == 10 && buckets.top() == Some(&38), "Stateful comparator should order the heap");
+        buckets.push(59);
+        let mut order = vec![];
+        while let Some(v) = buckets.pop() { order.push(v); }
+        assert!(order == [59, 38, 7, 45, 13, 21], "Heap should pop by remainder, got {:?}", order);
+
+        let mut queue: PriorityQueue<u32> = PriorityQueue::new();
+        for i in 0..200 { queue.push((i * 97) % 61); }
+        assert!(is_heap(queue.as_slice()), "Heap should stay valid");
+        Ok(())
+    }
+}
@@ -0,0 +1,721 @@
+//! Ports of MSVC's <algorithm>, which reproduce the same comparisons and element order as the
+//! C++ versions. Predicates return if the first argument is ordered before the second, like
+//! C++'s operator<. Each function takes anything that can be viewed as a slice, such as a
+//! [`Vector`](crate::msvc::vector::Vector).
+
+// https://github.com/microsoft/STL/blob/main/stl/inc/algorithm
+// https://github.com/microsoft/STL/blob/main/stl/inc/xutility
+
+// MSVC moves the value being placed out of the range, leaving a hole that's filled as it moves
+// through the range. These carry the value in the hole by swapping instead, which makes the same
+// comparisons and leaves the same order without needing to read out of the slice.
+
+// _ISORT_MAX: ranges up to this size are insertion sorted
+const ISORT_MAX: usize = 32;
+
+// _Push_heap_by_index: moves the value at hole up towards top
+fn push_heap_by_index<T, F>(slice: &mut [T], mut hole: usize, top: usize, less: &mut F)
+where F: FnMut(&T, &T) -> bool
+{
+    while top < hole {
+        let index = (hole - 1) >> 1;
+        if !less(&slice[index], &slice[hole]) { break }
+        slice.swap(index, hole);
+        hole = index;
+    }
+}
+
+// _Pop_heap_hole_by_index: moves the larger child into the hole until the hole reaches the
+// bottom of the heap, then pushes the value back up from there
+fn pop_heap_hole_by_index<T, F>(slice: &mut [T], mut hole: usize, bottom: usize, less: &mut F)
+where F: FnMut(&T, &T) -> bool
+{
+    let top = hole;
+    let mut index = hole;
+    let max_sequence_non_leaf = bottom.saturating_sub(1) >> 1;
+    while index < max_sequence_non_leaf {
+        index = 2 * index + 2;
+        if less(&slice[index], &slice[index - 1]) { index -= 1; }
+        slice.swap(hole, index);
+        hole = index;
+    }
+    if index == max_sequence_non_leaf && bottom.is_multiple_of(2) {
+        slice.swap(hole, bottom - 1);
+        hole = bottom - 1;
+    }
+    push_heap_by_index(slice, hole, top, less);
+}
+
+// _Pop_heap_unchecked
+fn pop_heap_unchecked<T, F>(slice: &mut [T], less: &mut F)
+where F: FnMut(&T, &T) -> bool
+{
+    if slice.len() >= 2 {
+        let last = slice.len() - 1;
+        // _Pop_heap_hole_unchecked
+        slice.swap(0, last);
+        pop_heap_hole_by_index(slice, 0, last, less);
+    }
+}
+
+// _Make_heap_unchecked
+fn make_heap_unchecked<T, F>(slice: &mut [T], less: &mut F)
+where F: FnMut(&T, &T) -> bool
+{
+    let bottom = slice.len();
+    for hole in (0..bottom >> 1).rev() {
+        pop_heap_hole_by_index(slice, hole, bottom, less);
+    }
+}
+
+// _Sort_heap_unchecked
+fn sort_heap_unchecked<T, F>(slice: &mut [T], less: &mut F)
+where F: FnMut(&T, &T) -> bool
+{
+    for last in (2..=slice.len()).rev() {
+        pop_heap_unchecked(&mut slice[..last], less);
+    }
+}
+
+/// Adds the last element of the slice to the heap made by the rest of the slice
+pub fn push_heap<T, S>(values: &mut S)
+where T: PartialOrd,
+      S: AsMut<[T]> + ?Sized
+{
+    push_heap_by(values, |a, b| a < b)
+}
+/// Adds the last element of the slice to the heap made by the rest of the slice, using a
+/// predicate that returns if the first argument is ordered before the second
+pub fn push_heap_by<T, S, F>(values: &mut S, mut less: F)
+where S: AsMut<[T]> + ?Sized,
+      F: FnMut(&T, &T) -> bool
+{
+    let slice = values.as_mut();
+    if slice.len() >= 2 {
+        push_heap_by_index(slice, slice.len() - 1, 0, &mut less);
+    }
+}
+
+/// Moves the largest element of the heap to the end of the slice, and makes the rest of the
+/// slice into a heap
+pub fn pop_heap<T, S>(values: &mut S)
+where T: PartialOrd,
+      S: AsMut<[T]> + ?Sized
+{
+    pop_heap_by(values, |a, b| a < b)
+}
+/// Moves the largest element of the heap to the end of the slice, and makes the rest of the
+/// slice into a heap, using a predicate that returns if the first argument is ordered before the
+/// second
+pub fn pop_heap_by<T, S, F>(values: &mut S, mut less: F)
+where S: AsMut<[T]> + ?Sized,
+      F: FnMut(&T, &T) -> bool
+{
+    pop_heap_unchecked(values.as_mut(), &mut less)
+}
+
+/// Rearranges the slice into a max heap
+pub fn make_heap<T, S>(values: &mut S)
+where T: PartialOrd,
+      S: AsMut<[T]> + ?Sized
+{
+    make_heap_by(values, |a, b| a < b)
+}
+/// Rearranges the slice into a heap, using a predicate that returns if the first argument is
+/// ordered before the second
+pub fn make_heap_by<T, S, F>(values: &mut S, mut less: F)
+where S: AsMut<[T]> + ?Sized,
+      F: FnMut(&T, &T) -> bool
+{
+    make_heap_unchecked(values.as_mut(), &mut less)
+}
+
+/// Sorts a max heap into ascending order
+pub fn sort_heap<T, S>(values: &mut S)
+where T: PartialOrd,
+      S: AsMut<[T]> + ?Sized
+{
+    sort_heap_by(values, |a, b| a < b)
+}
+/// Sorts a heap, using a predicate that returns if the first argument is ordered before the
+/// second
+pub fn sort_heap_by<T, S, F>(values: &mut S, mut less: F)
+where S: AsMut<[T]> + ?Sized,
+      F: FnMut(&T, &T) -> bool
+{
+    sort_heap_unchecked(values.as_mut(), &mut less)
+}
+
+/// Checks if the slice is a max heap
+pub fn is_heap<T, S>(values: &S) -> bool
+where T: PartialOrd,
+      S: AsRef<[T]> + ?Sized
+{
+    is_heap_by(values, |a, b| a < b)
+}
+/// Checks if the slice is a heap, using a predicate that returns if the first argument is
+/// ordered before the second
+pub fn is_heap_by<T, S, F>(values: &S, mut less: F) -> bool
+where S: AsRef<[T]> + ?Sized,
+      F: FnMut(&T, &T) -> bool
+{
+    let slice = values.as_ref();
+    (1..slice.len()).all(|i| !less(&slice[(i - 1) >> 1], &slice[i]))
+}
+
+// _Insertion_sort_unchecked
+fn insertion_sort<T, F>(slice: &mut [T], less: &mut F)
+where F: FnMut(&T, &T) -> bool
+{
+    for mid in 1..slice.len() {
+        if less(&slice[mid], &slice[0]) {
+            // found new earliest element, move to front
+            slice[..=mid].rotate_right(1);
+        } else {
+            // the first element stops the search, so this can't go past it
+            let mut hole = mid;
+            while less(&slice[hole], &slice[hole - 1]) {
+                slice.swap(hole, hole - 1);
+                hole -= 1;
+            }
+        }
+    }
+}
+
+// _Med3_unchecked: sorts three elements
+fn med3<T, F>(slice: &mut [T], first: usize, mid: usize, last: usize, less: &mut F)
+where F: FnMut(&T, &T) -> bool
+{
+    if less(&slice[mid], &slice[first]) { slice.swap(mid, first); }
+    if less(&slice[last], &slice[mid]) {
+        slice.swap(last, mid);
+        if less(&slice[mid], &slice[first]) { slice.swap(mid, first); }
+    }
+}
+
+// _Guess_median_unchecked: moves the median of three (or Tukey's ninther for more than 40
+// elements) to mid. last is inclusive.
+fn guess_median<T, F>(slice: &mut [T], first: usize, mid: usize, last: usize, less: &mut F)
+where F: FnMut(&T, &T) -> bool
+{
+    let count = last - first;
+    if count > 40 {
+        let step = (count + 1) >> 3;
+        let two_step = step << 1;
+        med3(slice, first, first + step, first + two_step, less);
+        med3(slice, mid - step, mid, mid + step, less);
+        med3(slice, last - two_step, last - step, last, less);
+        med3(slice, first + step, mid, last - step, less);
+    } else {
+        med3(slice, first, mid, last, less);
+    }
+}
+
+// _Partition_by_median_guess_unchecked: partitions around a fat pivot, returning the range of
+// elements equivalent to the pivot
+fn partition_by_median_guess<T, F>(slice: &mut [T], less: &mut F) -> (usize, usize)
+where F: FnMut(&T, &T) -> bool
+{
+    let first = 0;
+    let last = slice.len();
+    let mid = first + ((last - first) >> 1);
+    guess_median(slice, first, mid, last - 1, less);
+    let mut pfirst = mid;
+    let mut plast = pfirst + 1;
+
+    while first < pfirst
+    && !less(&slice[pfirst - 1], &slice[pfirst])
+    && !less(&slice[pfirst], &slice[pfirst - 1]) {
+        pfirst -= 1;
+    }
+    while plast < last
+    && !less(&slice[plast], &slice[pfirst])
+    && !less(&slice[pfirst], &slice[plast]) {
+        plast += 1;
+    }
+
+    let mut gfirst = plast;
+    let mut glast = pfirst;
+    loop {
+        // scan up for an element that belongs below the pivot
+        while gfirst < last {
+            if less(&slice[pfirst], &slice[gfirst]) {
+            } else if less(&slice[gfirst], &slice[pfirst]) {
+                break
+            } else {
+                if plast != gfirst { slice.swap(plast, gfirst); }
+                plast += 1;
+            }
+            gfirst += 1;
+        }
+        // scan down for an element that belongs above the pivot
+        while first < glast {
+            if less(&slice[glast - 1], &slice[pfirst]) {
+            } else if less(&slice[pfirst], &slice[glast - 1]) {
+                break
+            } else {
+                pfirst -= 1;
+                if pfirst != glast - 1 { slice.swap(pfirst, glast - 1); }
+            }
+            glast -= 1;
+        }
+
+        if glast == first && gfirst == last {
+            return (pfirst, plast)
+        }
+        if glast == first {
+            // no room at bottom, rotate pivot upward
+            if plast != gfirst { slice.swap(pfirst, plast); }
+            plast += 1;
+            slice.swap(pfirst, gfirst);
+            pfirst += 1;
+            gfirst += 1;
+        } else if gfirst == last {
+            // no room at top, rotate pivot downward
+            glast -= 1;
+            pfirst -= 1;
+            if glast != pfirst { slice.swap(glast, pfirst); }
+            plast -= 1;
+            slice.swap(pfirst, plast);
+        } else {
+            glast -= 1;
+            slice.swap(gfirst, glast);
+            gfirst += 1;
+        }
+    }
+}
+
+// _Sort_unchecked: introsort, which falls back to heap sort once there have been more than
+// 1.5 log2(N) divisions
+fn sort_unchecked<T, F>(slice: &mut [T], mut first: usize, mut last: usize, mut ideal: usize, less: &mut F)
+where F: FnMut(&T, &T) -> bool
+{
+    loop {
+        if last - first <= ISORT_MAX {
+            insertion_sort(&mut slice[first..last], less);
+            return
+        }
+        if ideal == 0 {
+            make_heap_unchecked(&mut slice[first..last], less);
+            sort_heap_unchecked(&mut slice[first..last], less);
+            return
+        }
+        let (mid_first, mid_last) = partition_by_median_guess(&mut slice[first..last], less);
+        let (mid_first, mid_last) = (first + mid_first, first + mid_last);
+        ideal = (ideal >> 1) + (ideal >> 2);
+        // recurse on the smaller half and loop on the larger one
+        if mid_first - first < last - mid_last {
+            sort_unchecked(slice, first, mid_first, ideal, less);
+            first = mid_last;
+        } else {
+            sort_unchecked(slice, mid_last, last, ideal, less);
+            last = mid_first;
+        }
+    }
+}
+
+/// Sorts into ascending order. Like C++, this isn't stable, and equivalent elements end up in the
+/// same order as they would with MSVC's std::sort.
+pub fn sort<T, S>(values: &mut S)
+where T: PartialOrd,
+      S: AsMut<[T]> + ?Sized
+{
+    sort_by(values, |a, b| a < b)
+}
+/// Sorts using a predicate that returns if the first argument is ordered before the second.
+/// Equivalent elements end up in the same order as they would with MSVC's std::sort.
+pub fn sort_by<T, S, F>(values: &mut S, mut less: F)
+where S: AsMut<[T]> + ?Sized,
+      F: FnMut(&T, &T) -> bool
+{
+    let slice = values.as_mut();
+    let len = slice.len();
+    sort_unchecked(slice, 0, len, len, &mut less);
+}
+
+/// Sorts into ascending order, keeping equivalent elements in order
+pub fn stable_sort<T, S>(values: &mut S)
+where T: PartialOrd,
+      S: AsMut<[T]> + ?Sized
+{
+    stable_sort_by(values, |a, b| a < b)
+}
+/// Sorts using a predicate that returns if the first argument is ordered before the second,
+/// keeping equivalent elements in order. A stable sort only has one possible result, so this
+/// uses Rust's sort rather than MSVC's buffered merge sort.
+pub fn stable_sort_by<T, S, F>(values: &mut S, mut less: F)
+where S: AsMut<[T]> + ?Sized,
+      F: FnMut(&T, &T) -> bool
+{
+    values.as_mut().sort_by(|a, b| match (less(a, b), less(b, a)) {
+        (true, _) => std::cmp::Ordering::Less,
+        (_, true) => std::cmp::Ordering::Greater,
+        _ => std::cmp::Ordering::Equal
+    });
+}
+
+/// Sorts the smallest mid elements into the start of the slice, leaving the rest in an
+/// unspecified order
+pub fn partial_sort<T, S>(values: &mut S, mid: usize)
+where T: PartialOrd,
+      S: AsMut<[T]> + ?Sized
+{
+    partial_sort_by(values, mid, |a, b| a < b)
+}
+/// Sorts the first mid elements according to a predicate that returns if the first argument is
+/// ordered before the second into the start of the slice, leaving the rest in an unspecified
+/// order
+pub fn partial_sort_by<T, S, F>(values: &mut S, mid: usize, mut less: F)
+where S: AsMut<[T]> + ?Sized,
+      F: FnMut(&T, &T) -> bool
+{
+    let slice = values.as_mut();
+    assert!(mid <= slice.len(), "Tried to partially sort past the end of the slice");
+    if mid == 0 { return }
+    make_heap_unchecked(&mut slice[..mid], &mut less);
+    for next in mid..slice.len() {
+        if less(&slice[next], &slice[0]) {
+            // replace top with new largest (_Pop_heap_hole_unchecked)
+            slice.swap(0, next);
+            pop_heap_hole_by_index(&mut slice[..mid], 0, mid, &mut less);
+        }
+    }
+    sort_heap_unchecked(&mut slice[..mid], &mut less);
+}
+
+/// Moves the element that would be at nth in sorted order to nth, with smaller elements before
+/// it and larger elements after it
+pub fn nth_element<T, S>(values: &mut S, nth: usize)
+where T: PartialOrd,
+      S: AsMut<[T]> + ?Sized
+{
+    nth_element_by(values, nth, |a, b| a < b)
+}
+/// Moves the element that would be at nth when sorted by a predicate that returns if the first
+/// argument is ordered before the second to nth, with the elements before it ordered before it
+pub fn nth_element_by<T, S, F>(values: &mut S, nth: usize, mut less: F)
+where S: AsMut<[T]> + ?Sized,
+      F: FnMut(&T, &T) -> bool
+{
+    let slice = values.as_mut();
+    if nth >= slice.len() { return }
+    let mut first = 0;
+    let mut last = slice.len();
+    while last - first > ISORT_MAX {
+        let (mid_first, mid_last) = partition_by_median_guess(&mut slice[first..last], &mut less);
+        let (mid_first, mid_last) = (first + mid_first, first + mid_last);
+        if mid_last <= nth {
+            first = mid_last;
+        } else if mid_first <= nth {
+            // nth is inside the fat pivot
+            return
+        } else {
+            last = mid_first;
+        }
+    }
+    insertion_sort(&mut slice[first..last], &mut less);
+}
+
+/// A random number engine, like C++'s UniformRandomBitGenerator named requirement
+pub trait UniformRandomBitGenerator {
+    /// Size of the engine's result_type in bits
+    const RESULT_BITS: u32;
+    /// Smallest value returned by generate()
+    const MIN: u64;
+    /// Largest value returned by generate()
+    const MAX: u64;
+    /// Returns the next value from the engine, like operator()
+    fn generate(&mut self) -> u64;
+}
+
+/// Port of MSVC's legacy _Rng_from_urng, which adapts an engine to pick values in [0, index). It
+/// uses as few engine calls as possible, then rejects values that would bias the result.
+pub(crate) struct RngFromUrng<'a, G>
+where G: UniformRandomBitGenerator
+{
+    rng: &'a mut G, // _Ref
+    bits: u32, // _Bits
+    bit_mask: u64, // _Bmask
+    // _Udiff is the larger of the engine's result and the unsigned type of the output
+    udiff_bits: u32,
+    udiff_mask: u64
+}
+
+impl<'a, G> RngFromUrng<'a, G>
+where G: UniformRandomBitGenerator
+{
+    pub(crate) fn new(rng: &'a mut G, output_bits: u32) -> Self {
+        let udiff_bits = output_bits.max(G::RESULT_BITS);
+        let udiff_mask = u64::MAX >> (u64::BITS - udiff_bits);
+        let mut bits = udiff_bits;
+        let mut bit_mask = udiff_mask;
+        while G::MAX - G::MIN < bit_mask {
+            bit_mask >>= 1;
+            bits -= 1;
+        }
+        Self { rng, bits, bit_mask, udiff_bits, udiff_mask }
+    }
+    // operator(): returns a value in [0, index)
+    pub(crate) fn next(&mut self, index: u64) -> u64 {
+        loop {
+            let mut ret = 0;
+            let mut mask = 0;
+            while mask < index - 1 {
+                ret = ((ret << (self.bits - 1)) << 1) & self.udiff_mask;
+                ret |= self.get_bits();
+                mask = ((mask << (self.bits - 1)) << 1) & self.udiff_mask;
+                mask |= self.bit_mask;
+            }
+            // ret is in [0, mask] and index - 1 <= mask, so return if unbiased
+            if ret / index < mask / index || mask % index == index - 1 {
+                return ret % index
+            }
+        }
+    }
+    // _Get_all_bits: returns a value using every bit of _Udiff
+    pub(crate) fn get_all_bits(&mut self) -> u64 {
+        let mut ret = 0;
+        let mut num = 0;
+        while num < self.udiff_bits {
+            ret = ((ret << (self.bits - 1)) << 1) & self.udiff_mask;
+            ret |= self.get_bits();
+            num += self.bits;
+        }
+        ret
+    }
+    // _Get_bits: returns a value in [0, _Bmask]
+    fn get_bits(&mut self) -> u64 {
+        loop {
+            let value = self.rng.generate().wrapping_sub(G::MIN) & self.udiff_mask;
+            if value <= self.bit_mask { return value }
+        }
+    }
+}
+
+/// Port of MSVC's _Rng_from_urng_v2, which replaced [`RngFromUrng`] in shuffle and
+/// uniform_int_distribution. Indices that fit in one engine call use Daniel Lemire's
+/// multiply-shift method ("Fast Random Integer Generation in an Interval", 2019), and larger ones
+/// fall back to the legacy loop.
+pub(crate) struct RngFromUrngV2<'a, G>
+where G: UniformRandomBitGenerator
+{
+    legacy: RngFromUrng<'a, G>
+}
+
+impl<'a, G> RngFromUrngV2<'a, G>
+where G: UniformRandomBitGenerator
+{
+    pub(crate) fn new(rng: &'a mut G, output_bits: u32) -> Self {
+        Self { legacy: RngFromUrng::new(rng, output_bits) }
+    }
+    // operator(): returns a value in [0, index)
+    pub(crate) fn next(&mut self, index: u64) -> u64 {
+        let mask = self.legacy.bit_mask;
+        if index - 1 > mask { return self.legacy.next(index) }
+        // the high bits of the product are the result, and the low bits tell if it's biased
+        let mut product = self.legacy.get_bits() as u128 * index as u128;
+        let mut rem = product as u64 & mask;
+        if rem < index {
+            let threshold = (mask - index + 1) % index;
+            while rem < threshold {
+                product = self.legacy.get_bits() as u128 * index as u128;
+                rem = product as u64 & mask;
+            }
+        }
+        (product >> self.legacy.bits) as u64
+    }
+    // _Get_all_bits: returns a value using every bit of _Udiff
+    pub(crate) fn get_all_bits(&mut self) -> u64 { self.legacy.get_all_bits() }
+}
+
+/// Shuffles the elements, making the same engine calls and swaps as MSVC's std::shuffle, which
+/// picks indices with _Rng_from_urng_v2. Use [`shuffle_legacy`] for binaries built against an
+/// older STL that still used _Rng_from_urng.
+pub fn shuffle<T, S, G>(values: &mut S, rng: &mut G)
+where S: AsMut<[T]> + ?Sized,
+      G: UniformRandomBitGenerator
+{
+    // _Rng_from_urng_v2<ptrdiff_t, G>
+    let mut rng = RngFromUrngV2::new(rng, isize::BITS);
+    random_shuffle(values.as_mut(), |index| rng.next(index));
+}
+
+/// Shuffles the elements like std::shuffle from STL versions that predate _Rng_from_urng_v2
+pub fn shuffle_legacy<T, S, G>(values: &mut S, rng: &mut G)
+where S: AsMut<[T]> + ?Sized,
+      G: UniformRandomBitGenerator
+{
+    // _Rng_from_urng<ptrdiff_t, G>
+    let mut rng = RngFromUrng::new(rng, isize::BITS);
+    random_shuffle(values.as_mut(), |index| rng.next(index));
+}
+
+// _Random_shuffle1: randomly place an element from [first, target] at target
+fn random_shuffle<T, F>(slice: &mut [T], mut next: F)
+where F: FnMut(u64) -> u64
+{
+    for target in 1..slice.len() {
+        let offset = next(target as u64 + 1) as usize;
+        if offset != target { slice.swap(target, offset); }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::msvc::vector::Vector;
+    use super::{
+        is_heap, is_heap_by, make_heap, make_heap_by, nth_element, partial_sort, pop_heap, pop_heap_by,
+        push_heap, shuffle, shuffle_legacy, sort, sort_by, stable_sort_by, UniformRandomBitGenerator
+    };
+    use std::error::Error;
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    // libstdc++'s heap functions are the same algorithm as MSVC's, so the expected orders were
+    // taken from std::make_heap, std::push_heap and std::pop_heap built with g++
+    #[test]
+    pub fn heap_golden() -> TestReturn {
+        let mut values = vec![3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5];
+        make_heap(&mut values);
+        assert!(values == [9, 6, 4, 5, 5, 3, 2, 1, 1, 3, 5], "make_heap should match MSVC, got {:?}", values);
+        values.push(8);
+        push_heap(&mut values);
+        assert!(values == [9, 6, 8, 5, 5, 4, 2, 1, 1, 3, 5, 3], "push_heap should match MSVC, got {:?}", values);
+        pop_heap(&mut values);
+        assert!(values == [8, 6, 4, 5, 5, 3, 2, 1, 1, 3, 5, 9], "pop_heap should match MSVC, got {:?}", values);
+        assert!(is_heap(&values[..11]) && !is_heap(&values), "Remaining values should be a heap");
+        Ok(())
+    }
+
+    #[test]
+    pub fn heap_with_predicate() -> TestReturn {
+        let mut values: Vec<u32> = (0..100).map(|i| (i * 37 + 11) % 64).collect();
+        make_heap_by(&mut values, |a, b| a > b);
+        assert!(is_heap_by(&values, |a, b| a > b) && values[0] == 0, "Min heap should have the smallest value on top");
+        let mut sorted = vec![];
+        for end in (1..=values.len()).rev() {
+            pop_heap_by(&mut values[..end], |a, b| a > b);
+            sorted.push(values[end - 1]);
+        }
+        assert!(sorted.windows(2).all(|w| w[0] <= w[1]), "Popping a min heap should give ascending values");
+        Ok(())
+    }
+
+    // (key, id) pairs where only the key is compared, so the ids show where equivalent elements
+    // ended up
+    fn keyed(count: u32, modulo: u32) -> Vec<(u32, u32)> {
+        (0..count).map(|i| ((i * 7919 + 13) % modulo, i)).collect()
+    }
+    fn ids(values: &[(u32, u32)]) -> Vec<u32> { values.iter().map(|v| v.1).collect() }
+
+    #[test]
+    pub fn sort_matches_msvc() -> TestReturn {
+        // insertion sort is stable
+        let mut small = keyed(32, 5);
+        sort_by(&mut small, |a, b| a.0 < b.0);
+        let mut expected = keyed(32, 5);
+        expected.sort_by_key(|v| v.0);
+        assert!(small == expected, "Insertion sort should keep equivalent elements in order");
+
+        // partitioning with a fat pivot
+        let mut values = keyed(50, 4);
+        sort_by(&mut values, |a, b| a.0 < b.0);
+        assert!(values.windows(2).all(|w| w[0].0 <= w[1].0), "Values should be sorted");
+        assert!(ids(&values) == GOLDEN_SORT_50, "Equivalent elements should be in MSVC's order, got {:?}", ids(&values));
+
+        // Tukey's ninther
+        let mut values = keyed(100, 10);
+        sort_by(&mut values, |a, b| a.0 < b.0);
+        assert!(values.windows(2).all(|w| w[0].0 <= w[1].0), "Values should be sorted");
+        assert!(ids(&values) == GOLDEN_SORT_100, "Equivalent elements should be in MSVC's order, got {:?}", ids(&values));
+
+        let mut vector = Vector::from_vec((0..1000u32).map(|i| (i * 7919) % 1009).collect());
+        sort(&mut vector);
+        assert!(vector.as_slice().windows(2).all(|w| w[0] <= w[1]), "Vector should be sorted");
+        Ok(())
+    }
+
+    #[test]
+    pub fn sort_shapes() -> TestReturn {
+        for count in [33, 64, 200, 1000] {
+            let mut values: Vec<u32> = (0..count).collect();
+            values.reverse();
+            sort(&mut values);
+            assert!(values.iter().copied().eq(0..count), "Reversed values should be sorted");
+            let mut values: Vec<u32> = (0..count).map(|i| if i % 2 == 0 { i } else { count - i }).collect();
+            sort(&mut values);
+            assert!(values.windows(2).all(|w| w[0] <= w[1]), "Alternating values should be sorted");
+            // with no divisions left, the range is heap sorted
+            let mut values: Vec<u32> = (0..count).map(|i| (i * 7919) % 101).collect();
+            let len = values.len();
+            super::sort_unchecked(&mut values, 0, len, 0, &mut |a, b| a < b);
+            assert!(values.windows(2).all(|w| w[0] <= w[1]), "Heap sort fallback should sort");
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn partial_and_nth() -> TestReturn {
+        let input: Vec<u32> = (0..200).map(|i| (i * 7919 + 13) % 211).collect();
+        let mut sorted = input.clone();
+        sorted.sort();
+
+        let mut values = input.clone();
+        partial_sort(&mut values, 10);
+        assert!(values[..10] == sorted[..10], "First 10 values should be the smallest, sorted");
+        let mut rest = values[10..].to_vec();
+        rest.sort();
+        assert!(rest == sorted[10..], "Remaining values should be kept");
+
+        for nth in [0, 1, 50, 100, 199] {
+            let mut values = input.clone();
+            nth_element(&mut values, nth);
+            assert!(values[nth] == sorted[nth], "nth_element should place the {}th value", nth);
+            assert!(values[..nth].iter().all(|v| *v <= values[nth]) && values[nth..].iter().all(|v| *v >= values[nth]), "Values should be partitioned around nth");
+        }
+
+        let mut values = keyed(100, 10);
+        stable_sort_by(&mut values, |a, b| a.0 < b.0);
+        let mut expected = keyed(100, 10);
+        expected.sort_by_key(|v| v.0);
+        assert!(values == expected, "Stable sort should keep equivalent elements in order");
+        Ok(())
+    }
+
+    // std::minstd_rand0 style engine, with a range that doesn't cover all 32 bits
+    struct Lcg(u32);
+    impl UniformRandomBitGenerator for Lcg {
+        const RESULT_BITS: u32 = 32;
+        const MIN: u64 = 1;
+        const MAX: u64 = 2147483646;
+        fn generate(&mut self) -> u64 {
+            self.0 = ((self.0 as u64 * 16807) % 2147483647) as u32;
+            self.0 as u64
+        }
+    }
+
+    #[test]
+    pub fn shuffle_matches_msvc() -> TestReturn {
+        let mut values: Vec<u32> = (0..20).collect();
+        let mut rng = Lcg(1);
+        shuffle(&mut values, &mut rng);
+        assert!(values == GOLDEN_SHUFFLE_20, "Shuffle should match MSVC, got {:?}", values);
+        let mut sorted = values.clone();
+        sorted.sort();
+        assert!(sorted.iter().copied().eq(0..20), "Shuffle should keep every value");
+
+        let mut values: Vec<u32> = (0..20).collect();
+        let mut rng = Lcg(1);
+        shuffle_legacy(&mut values, &mut rng);
+        assert!(values == GOLDEN_SHUFFLE_LEGACY_20, "Legacy shuffle should match MSVC, got {:?}", values);
+        Ok(())
+    }
+
+    // Generated from MSVC's _Sort_unchecked and _Random_shuffle1 (with _Rng_from_urng_v2 and
+    // _Rng_from_urng), transcribed into C++ and built with g++. These only show that the port
+    // agrees with the transcription, and haven't been checked against a build made with MSVC.
+    const GOLDEN_SORT_50: [u32; 50] = [13, 1, 45, 41, 33, 5, 49, 17, 37, 9, 25, 29, 21, 0, 28, 32, 4, 36, 8, 40, 44, 48, 16, 20, 24, 12, 3, 7, 11, 27, 35, 39, 23, 15, 19, 47, 31, 43, 2, 10, 6, 22, 34, 26, 18, 38, 42, 30, 14, 46];
+    const GOLDEN_SORT_100: [u32; 100] = [3, 33, 83, 73, 13, 43, 23, 63, 53, 93, 12, 2, 52, 32, 82, 62, 72, 42, 22, 92, 1, 31, 81, 11, 51, 41, 71, 21, 61, 91, 30, 40, 50, 60, 20, 70, 10, 0, 80, 90, 49, 39, 19, 99, 89, 9, 79, 59, 69, 29, 98, 38, 48, 8, 78, 28, 88, 18, 68, 58, 7, 77, 37, 47, 57, 87, 67, 17, 27, 97, 66, 6, 76, 16, 46, 36, 86, 56, 96, 26, 25, 55, 65, 85, 35, 95, 15, 45, 5, 75, 54, 14, 34, 84, 94, 44, 64, 4, 74, 24];
+    const GOLDEN_SHUFFLE_20: [u32; 20] = [9, 16, 13, 3, 1, 6, 2, 5, 15, 19, 12, 17, 14, 18, 7, 10, 11, 0, 4, 8];
+    const GOLDEN_SHUFFLE_LEGACY_20: [u32; 20] = [2, 7, 1, 16, 12, 6, 19, 15, 17, 18, 14, 0, 13, 3, 9, 4, 11, 10, 8, 5];
+}
@@ -0,0 +1,461 @@
+#![allow(dead_code)]
+use allocator_api2::{
+    alloc::{ Allocator, Global },
+    boxed::Box as ABox
+};
+use std::{
+    alloc::Layout,
+    fmt::Display,
+    marker::PhantomData,
+    ops::{ Index, IndexMut },
+    ptr::NonNull
+};
+
+// https://github.com/microsoft/STL/blob/main/stl/inc/deque
+
+// _Minimum_map_size
+const MINIMUM_MAP_SIZE: usize = 8;
+
+// _Container_proxy. Unlike the other containers, deque derives from _Container_base12 even in
+// release builds, so a proxy is always allocated. Deque iterators find the map and offset through
+// _Mycont in every build, so it has to point at the deque before C++ touches it. Since the deque
+// can be moved on the Rust side, that's done by setup_pointers() rather than on construction.
+#[repr(C)]
+struct ContainerProxy {
+    container: *const u8, // _Mycont
+    first_iter: *mut u8 // _Myfirstiter
+}
+
+/// A double ended queue made of fixed size blocks, which are found through a circular map of
+/// block pointers. Elements are addressed by an offset into the map, so blocks are reused as the
+/// queue moves around the map and are only freed when the deque is cleared. Call
+/// [`Deque::setup_pointers`] once the deque is at its final address before handing it to C++.
+#[repr(C)]
+pub struct Deque<T, A = Global> // std::deque
+where A: Allocator + Clone
+{
+    proxy: *mut ContainerProxy, // _Myproxy
+    map: *mut *mut T, // _Map
+    map_size: usize, // _Mapsize, zero or a power of two
+    offset: usize, // _Myoff
+    len: usize, // _Mysize
+    _allocator: A
+}
+
+impl<T> Deque<T, Global> {
+    pub fn new() -> Self { Self::new_in(Global) }
+    pub fn from_vec(vec: Vec<T>) -> Self { Self::from_vec_in(vec, Global) }
+    pub fn new_standalone() -> ABox<Self, Global> { Self::new_standalone_in(Global) }
+    pub fn from_vec_standalone(vec: Vec<T>) -> ABox<Self, Global> { Self::from_vec_standalone_in(vec, Global) }
+}
+
+impl<T> Default for Deque<T, Global> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T, A> Deque<T, A>
+where A: Allocator + Clone
+{
+    /// Number of elements per block (_Block_size), which fits 16 bytes of elements with
+    /// at least one element per block
+    pub const BLOCK_SIZE: usize = match std::mem::size_of::<T>() {
+        0..=1 => 16,
+        2 => 8,
+        3..=4 => 4,
+        5..=8 => 2,
+        _ => 1
+    };
+
+    pub fn new_in(alloc: A) -> Self {
+        assert!(std::mem::size_of::<A>() == 0, "Allocator must be zero-sized!");
+        let proxy = alloc.allocate(Layout::new::<ContainerProxy>()).unwrap().as_ptr() as *mut ContainerProxy;
+        unsafe { std::ptr::write(proxy, ContainerProxy { container: std::ptr::null(), first_iter: std::ptr::null_mut() }) };
+        Self {
+            proxy,
+            map: std::ptr::null_mut(),
+            map_size: 0,
+            offset: 0,
+            len: 0,
+            _allocator: alloc
+        }
+    }
+    pub fn from_vec_in(vec: Vec<T>, alloc: A) -> Self {
+        let mut new = Self::new_in(alloc);
+        for v in vec { new.push_back(v); }
+        new
+    }
+    /// Creates a deque on the heap with its proxy already pointing back to it
+    pub fn new_standalone_in(alloc: A) -> ABox<Self, A> {
+        let mut new = ABox::new_in(Self::new_in(alloc.clone()), alloc);
+        new.setup_pointers();
+        new
+    }
+    pub fn from_vec_standalone_in(vec: Vec<T>, alloc: A) -> ABox<Self, A> {
+        let mut new = ABox::new_in(Self::from_vec_in(vec, alloc.clone()), alloc);
+        new.setup_pointers();
+        new
+    }
+    /// Point the container proxy (_Mycont) to the deque's current address. C++ iterators go
+    /// through it to reach the map, so this must be called after the deque is moved and before
+    /// it's handed to C++.
+    pub fn setup_pointers(&mut self) {
+        unsafe { (*self.proxy).container = &raw const *self as *const u8 };
+    }
+    pub fn len(&self) -> usize { self.len }
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+    /// Returns the number of block pointers in the map
+    pub fn map_size(&self) -> usize { self.map_size }
+
+    fn block_layout() -> Layout { Layout::array::<T>(Self::BLOCK_SIZE).unwrap() }
+    fn map_layout(size: usize) -> Layout { Layout::array::<*mut T>(size).unwrap() }
+
+    // _Getblock
+    fn get_block(&self, offset: usize) -> usize {
+        (offset / Self::BLOCK_SIZE) & (self.map_size - 1)
+    }
+    // Pointer to the element at an offset into the map, allocating the block if needed
+    unsafe fn slot(&mut self, offset: usize) -> *mut T {
+        let block = self.map.add(self.get_block(offset));
+        if (*block).is_null() {
+            *block = self._allocator.allocate(Self::block_layout()).unwrap().as_ptr() as *mut T;
+        }
+        (*block).add(offset % Self::BLOCK_SIZE)
+    }
+    fn element(&self, index: usize) -> *mut T {
+        let offset = self.offset + index;
+        unsafe { (*self.map.add(self.get_block(offset))).add(offset % Self::BLOCK_SIZE) }
+    }
+
+    // _Growmap: grows the map by at least count blocks, doubling the size each time
+    fn grow_map(&mut self, count: usize) {
+        let mut new_size = if self.map_size > 0 { self.map_size } else { 1 };
+        while new_size - self.map_size < count || new_size < MINIMUM_MAP_SIZE {
+            new_size *= 2;
+        }
+        let count = new_size - self.map_size;
+        let block_offset = self.offset / Self::BLOCK_SIZE;
+        unsafe {
+            let new_map = self._allocator.allocate(Self::map_layout(new_size)).unwrap().as_ptr() as *mut *mut T;
+            // copy blocks from the initial block to the end of the old map
+            std::ptr::copy_nonoverlapping(self.map.add(block_offset), new_map.add(block_offset), self.map_size - block_offset);
+            let mut next = new_map.add(self.map_size);
+            if block_offset <= count {
+                // the blocks before the initial block follow the end of the old map
+                std::ptr::copy_nonoverlapping(self.map, next, block_offset);
+                next = next.add(block_offset);
+                std::ptr::write_bytes(next, 0, count - block_offset);
+                std::ptr::write_bytes(new_map, 0, block_offset);
+            } else {
+                std::ptr::copy_nonoverlapping(self.map, next, count);
+                std::ptr::copy_nonoverlapping(self.map.add(count), new_map, block_offset - count);
+                std::ptr::write_bytes(new_map.add(block_offset - count), 0, count);
+            }
+            if !self.map.is_null() {
+                self._allocator.deallocate(NonNull::new_unchecked(self.map as *mut u8), Self::map_layout(self.map_size));
+            }
+            self.map = new_map;
+        }
+        self.map_size = new_size;
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        if (self.offset + self.len).is_multiple_of(Self::BLOCK_SIZE)
+        && self.map_size <= (self.len + Self::BLOCK_SIZE) / Self::BLOCK_SIZE {
+            self.grow_map(1);
+        }
+        self.offset &= self.map_size * Self::BLOCK_SIZE - 1;
+        let new_offset = self.offset + self.len;
+        unsafe { std::ptr::write(self.slot(new_offset), value) };
+        self.len += 1;
+    }
+    pub fn push_front(&mut self, value: T) {
+        if self.offset.is_multiple_of(Self::BLOCK_SIZE)
+        && self.map_size <= (self.len + Self::BLOCK_SIZE) / Self::BLOCK_SIZE {
+            self.grow_map(1);
+        }
+        self.offset &= self.map_size * Self::BLOCK_SIZE - 1;
+        let new_offset = if self.offset != 0 { self.offset } else { self.map_size * Self::BLOCK_SIZE } - 1;
+        unsafe { std::ptr::write(self.slot(new_offset), value) };
+        self.offset = new_offset;
+        self.len += 1;
+    }
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() { return None }
+        let value = unsafe { std::ptr::read(self.element(self.len - 1)) };
+        self.len -= 1;
+        if self.len == 0 { self.offset = 0; }
+        Some(value)
+    }
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() { return None }
+        let value = unsafe { std::ptr::read(self.element(0)) };
+        self.len -= 1;
+        if self.len == 0 { self.offset = 0; } else { self.offset += 1; }
+        Some(value)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len { return None }
+        Some(unsafe { &*self.element(index) })
+    }
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len { return None }
+        Some(unsafe { &mut *self.element(index) })
+    }
+
+    pub fn iter(&self) -> DequeIterator<'_, T, A> { self.into_iter() }
+    pub fn iter_mut(&mut self) -> DequeIteratorMut<'_, T, A> { self.into_iter() }
+
+    // _Tidy: drops every element, then frees the blocks and the map
+    fn tidy(&mut self) {
+        while self.pop_back().is_some() {}
+        unsafe {
+            for block in (0..self.map_size).rev() {
+                let block = *self.map.add(block);
+                if !block.is_null() {
+                    self._allocator.deallocate(NonNull::new_unchecked(block as *mut u8), Self::block_layout());
+                }
+            }
+            if !self.map.is_null() {
+                self._allocator.deallocate(NonNull::new_unchecked(self.map as *mut u8), Self::map_layout(self.map_size));
+            }
+        }
+        self.map_size = 0;
+        self.map = std::ptr::null_mut();
+    }
+}
+
+// C++ API
+impl<T, A> Deque<T, A>
+where A: Allocator + Clone
+{
+    /// Checks if the container has no elements
+    pub fn empty(&self) -> bool { self.is_empty() }
+    /// Returns the number of elements in the container
+    pub fn size(&self) -> usize { self.len }
+    /// Erases all elements from the container, freeing every block and the map
+    pub fn clear(&mut self) { self.tidy() }
+    /// Returns a reference to the element at specified location index, with bounds checking.
+    pub fn at(&self, index: usize) -> Option<&T> { self.get(index) }
+    /// Returns a mutable reference to the element at specified location index, with bounds checking.
+    pub fn at_mut(&mut self, index: usize) -> Option<&mut T> { self.get_mut(index) }
+    /// Returns a reference to the first element, or None if the container is empty
+    pub fn front(&self) -> Option<&T> { self.get(0) }
+    /// Returns a mutable reference to the first element, or None if the container is empty
+    pub fn front_mut(&mut self) -> Option<&mut T> { self.get_mut(0) }
+    /// Returns a reference to the last element, or None if the container is empty
+    pub fn back(&self) -> Option<&T> { self.get(self.len.wrapping_sub(1)) }
+    /// Returns a mutable reference to the last element, or None if the container is empty
+    pub fn back_mut(&mut self) -> Option<&mut T> { self.get_mut(self.len.wrapping_sub(1)) }
+}
+
+impl<T, A> Drop for Deque<T, A>
+where A: Allocator + Clone
+{
+    fn drop(&mut self) {
+        self.tidy();
+        unsafe { self._allocator.deallocate(NonNull::new_unchecked(self.proxy as *mut u8), Layout::new::<ContainerProxy>()) };
+    }
+}
+
+impl<T, A> Index<usize> for Deque<T, A>
+where A: Allocator + Clone
+{
+    type Output = T;
+    fn index(&self, index: usize) -> &Self::Output {
+        assert!(index < self.len, "Tried to index a deque out of bounds");
+        unsafe { &*self.element(index) }
+    }
+}
+
+impl<T, A> IndexMut<usize> for Deque<T, A>
+where A: Allocator + Clone
+{
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        assert!(index < self.len, "Tried to index a deque out of bounds");
+        unsafe { &mut *self.element(index) }
+    }
+}
+
+impl<T, A> Display for Deque<T, A>
+where T: Display,
+      A: Allocator + Clone
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values: Vec<std::string::String> = self.iter().map(|v| format!("{}", v)).collect();
+        write!(f, "Deque [ {} ]", values.join(", "))
+    }
+}
+
+pub struct DequeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    deque: &'a Deque<T, A>,
+    front: usize,
+    back: usize
+}
+
+impl<'a, T, A> Iterator for DequeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back { return None }
+        self.front += 1;
+        Some(unsafe { &*self.deque.element(self.front - 1) })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.back - self.front, Some(self.back - self.front)) }
+}
+
+impl<T, A> DoubleEndedIterator for DequeIterator<'_, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back { return None }
+        self.back -= 1;
+        Some(unsafe { &*self.deque.element(self.back) })
+    }
+}
+
+impl<T, A> ExactSizeIterator for DequeIterator<'_, T, A>
+where A: Allocator + Clone {}
+
+pub struct DequeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    deque: *mut Deque<T, A>,
+    front: usize,
+    back: usize,
+    _marker: PhantomData<&'a mut T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for DequeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back { return None }
+        self.front += 1;
+        Some(unsafe { &mut *(*self.deque).element(self.front - 1) })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.back - self.front, Some(self.back - self.front)) }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for DequeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back { return None }
+        self.back -= 1;
+        Some(unsafe { &mut *(*self.deque).element(self.back) })
+    }
+}
+
+impl<'a, T: 'a, A: 'a> ExactSizeIterator for DequeIteratorMut<'a, T, A>
+where A: Allocator + Clone {}
+
+impl<'a, T, A> IntoIterator for &'a Deque<T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    type IntoIter = DequeIterator<'a, T, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        DequeIterator { deque: self, front: 0, back: self.len }
+    }
+}
+
+impl<'a, T, A> IntoIterator for &'a mut Deque<T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    type IntoIter = DequeIteratorMut<'a, T, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        DequeIteratorMut { front: 0, back: self.len, deque: self, _marker: PhantomData }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use allocator_api2::{ alloc::Global, boxed::Box as ABox };
+    use crate::{
+        generic::deque::check_against_vec_deque,
+        msvc::string::String as CppString
+    };
+    use super::Deque;
+    use std::error::Error;
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    #[test]
+    pub fn deque_layout() -> TestReturn {
+        assert!(std::mem::size_of::<Deque<u32>>() == 40, "Deque should be the proxy, map, map size, offset and size");
+        let sizes = [Deque::<u8>::BLOCK_SIZE, Deque::<u16>::BLOCK_SIZE, Deque::<u32>::BLOCK_SIZE, Deque::<u64>::BLOCK_SIZE];
+        assert!(sizes == [16, 8, 4, 2], "Blocks should hold 16 bytes, got {:?}", sizes);
+        let sizes = [Deque::<[u8; 12]>::BLOCK_SIZE, Deque::<CppString>::BLOCK_SIZE];
+        assert!(sizes == [1, 1], "Large elements should be one per block, got {:?}", sizes);
+        let deque: Deque<u32> = Deque::new();
+        assert!(!deque.proxy.is_null() && deque.map.is_null() && deque.map_size == 0, "Empty deque should only have a proxy");
+        Ok(())
+    }
+
+    #[test]
+    pub fn deque_proxy_container() -> TestReturn {
+        let deque = Deque::from_vec_standalone(vec![1u32, 2, 3]);
+        let address = &raw const *deque as *const u8;
+        assert!(unsafe { (*deque.proxy).container } == address, "Proxy should point to a standalone deque");
+        let mut moved = ABox::into_inner(deque);
+        moved.setup_pointers();
+        assert!(unsafe { (*moved.proxy).container } == &raw const moved as *const u8, "Proxy should follow the deque after setup_pointers");
+        assert!(unsafe { (*moved.proxy).first_iter }.is_null(), "Proxy shouldn't have any iterators");
+        Ok(())
+    }
+
+    #[test]
+    pub fn deque_map_growth() -> TestReturn {
+        let mut deque = Deque::new();
+        deque.push_back(0u32);
+        assert!(deque.map_size == 8 && deque.offset == 0, "First push should make the minimum map");
+        for i in 1..28 { deque.push_back(i); }
+        assert!(deque.map_size == 8, "Map should hold 28 elements without growing");
+        deque.push_back(28);
+        assert!(deque.map_size == 16, "Map should double once a block is left spare");
+
+        let mut deque = Deque::new();
+        deque.push_front(0u32);
+        assert!(deque.offset == 31 && unsafe { !(*deque.map.add(7)).is_null() }, "Front push should wrap to the end of the map");
+        for i in 1..4 { deque.push_front(i); }
+        assert!(deque.offset == 28 && deque.map_size == 8, "Front block should fill backwards");
+        for i in 4..8 { deque.push_back(i); }
+        let old_map = deque.map;
+        let blocks: Vec<_> = (0..8).map(|i| unsafe { *deque.map.add(i) }).collect();
+        for i in 8..40 { deque.push_front(i); }
+        assert!(deque.map_size == 16 && deque.map != old_map, "Map should grow from the front");
+        unsafe {
+            assert!(*deque.map.add(7) == blocks[7], "Initial block should stay at its offset");
+            assert!(*deque.map.add(8) == blocks[0], "Blocks before the initial block should move after the old map");
+        }
+        let expected: Vec<u32> = (0..40).rev().filter(|v| *v < 4 || *v >= 8).chain(4..8).collect();
+        assert!(deque.iter().copied().collect::<Vec<_>>() == expected, "Values should survive map growth");
+        Ok(())
+    }
+
+    #[test]
+    pub fn deque_matches_vec_deque() -> TestReturn {
+        let mut deque = check_against_vec_deque!(Deque::new(), 2000, |v| v, |v: &mut u32| *v += 1, |_| ());
+        deque.clear();
+        assert!(deque.is_empty() && deque.map.is_null(), "Cleared deque should free its map");
+        Ok(())
+    }
+
+    #[test]
+    pub fn deque_command_queue() -> TestReturn {
+        let mut commands: Deque<CppString> = Deque::new();
+        for name in ["move", "attack", "jump"] { commands.push_back(CppString::from_str_in(name, Global)); }
+        commands.push_front(CppString::from_str_in("pause", Global));
+        let first = commands.pop_front().unwrap();
+        assert!(<&str>::from(&first) == "pause", "Front command should be taken first");
+        let names: Vec<&str> = commands.iter().map(<&str>::from).collect();
+        assert!(names == ["move", "attack", "jump"], "Commands should stay in order");
+        Ok(())
+    }
+}
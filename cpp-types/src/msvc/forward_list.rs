@@ -0,0 +1,459 @@
+#![allow(dead_code)]
+use allocator_api2::alloc::{ Allocator, Global };
+use std::{
+    alloc::Layout,
+    fmt::Display,
+    marker::PhantomData,
+    ptr::NonNull
+};
+
+// https://github.com/microsoft/STL/blob/main/stl/inc/forward_list
+
+// _Flist_val is only a pointer to the first node, which is null for an empty list. Unlike
+// std::list there's no sentinel node and no size, so finding the length walks the list.
+#[repr(C)]
+pub struct ForwardList<T, A = Global> // std::forward_list
+where A: Allocator + Clone
+{
+    head: *mut ForwardListNode<T, A>, // _Myhead
+    _allocator: A,
+    _data: PhantomData<T>
+}
+
+#[repr(C)]
+pub struct ForwardListNode<T, A = Global>
+where A: Allocator + Clone
+{
+    next: *mut ForwardListNode<T, A>, // _Next
+    value: T, // _Myval
+    _allocator: A
+}
+
+// Points to the next pointer of a node, or to _Myhead for before_begin(). MSVC treats
+// &_Myhead as a node since _Next is its first field, but taking the address of the link
+// avoids making a node out of the list itself.
+type Link<T, A> = *mut *mut ForwardListNode<T, A>;
+
+impl<T, A> ForwardListNode<T, A>
+where A: Allocator + Clone
+{
+    // _Buynode
+    fn new(value: T, alloc: A, next: *mut Self) -> *mut Self {
+        let new = alloc.allocate(Layout::new::<Self>()).unwrap().as_ptr() as *mut Self;
+        unsafe {
+            (*new).next = next;
+            std::ptr::write(&raw mut (*new).value, value);
+            std::ptr::write(&raw mut (*new)._allocator, alloc);
+        }
+        new
+    }
+    // _Freenode: moves the value out of an unlinked node, then frees it
+    unsafe fn free(node: *mut Self, alloc: A) -> T {
+        let value = std::ptr::read(&raw const (*node).value);
+        alloc.deallocate(NonNull::new_unchecked(node as *mut u8), Layout::new::<Self>());
+        value
+    }
+    fn link(node: *mut Self) -> Link<T, A> { unsafe { &raw mut (*node).next } }
+    pub fn value(&self) -> &T { &self.value }
+    pub fn value_mut(&mut self) -> &mut T { &mut self.value }
+}
+
+impl<T> ForwardList<T, Global> {
+    pub fn new() -> Self { Self::new_in(Global) }
+    pub fn from_vec(vec: Vec<T>) -> Self { Self::from_vec_in(vec, Global) }
+}
+
+impl<T> Default for ForwardList<T, Global> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T, A> ForwardList<T, A>
+where A: Allocator + Clone
+{
+    pub fn new_in(alloc: A) -> Self {
+        assert!(std::mem::size_of::<A>() == 0, "Allocator must be zero-sized!");
+        Self { head: std::ptr::null_mut(), _allocator: alloc, _data: PhantomData }
+    }
+    pub fn from_vec_in(vec: Vec<T>, alloc: A) -> Self {
+        let mut list = Self::new_in(alloc);
+        for v in vec.into_iter().rev() { list.push_front(v); }
+        list
+    }
+    pub fn is_empty(&self) -> bool { self.head.is_null() }
+    /// Counts the elements, which walks the whole list
+    pub fn len(&self) -> usize { self.iter().count() }
+
+    pub fn front(&self) -> Option<&T> {
+        if self.is_empty() { return None }
+        Some(unsafe { &(*self.head).value })
+    }
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        if self.is_empty() { return None }
+        Some(unsafe { &mut (*self.head).value })
+    }
+    pub fn push_front(&mut self, value: T) {
+        self.head = ForwardListNode::new(value, self._allocator.clone(), self.head);
+    }
+    pub fn pop_front(&mut self) -> Option<T> {
+        unsafe { Self::erase_after_link(&raw mut self.head, self._allocator.clone()) }
+    }
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    /// Returns a cursor at the position before the first element, which elements can be
+    /// inserted after
+    pub fn before_begin(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut { current: std::ptr::null_mut(), list: self }
+    }
+
+    // _Erase_after
+    unsafe fn erase_after_link(link: Link<T, A>, alloc: A) -> Option<T> {
+        let erased = *link;
+        if erased.is_null() { return None }
+        *link = (*erased).next;
+        Some(ForwardListNode::free(erased, alloc))
+    }
+
+    /// Reverses the order of the elements
+    pub fn reverse(&mut self) {
+        let mut current = self.head;
+        if current.is_null() { return }
+        let mut next = unsafe { (*current).next };
+        if next.is_null() { return }
+        unsafe {
+            (*current).next = std::ptr::null_mut();
+            while !next.is_null() {
+                let next_next = (*next).next;
+                (*next).next = current;
+                current = next;
+                next = next_next;
+            }
+        }
+        self.head = current;
+    }
+
+    /// Sorts the list, keeping equivalent elements in order. This is the same merge sort as
+    /// MSVC's forward_list::sort, so the comparisons are made in the same order.
+    pub fn sort(&mut self)
+    where T: PartialOrd
+    {
+        self.sort_by(|a, b| a < b)
+    }
+    /// Sorts the list with a predicate that returns if the first argument is ordered before the
+    /// second, like C++'s operator<
+    pub fn sort_by<F>(&mut self, mut less: F)
+    where F: FnMut(&T, &T) -> bool
+    {
+        unsafe {
+            let bfirst = &raw mut self.head;
+            let mut bmid = Self::sort2(bfirst, &mut less);
+            let mut bound = 2;
+            while !(*bmid).is_null() {
+                let blast = Self::sort_bounded(bmid, bound, &mut less);
+                bmid = Self::inplace_merge(bfirst, bmid, blast, &mut less);
+                bound <<= 1;
+            }
+        }
+    }
+
+    // _Inplace_merge: merges the sorted ranges (bfirst1, bmid] and (bmid, blast], returning the
+    // link one before the new end of the range
+    unsafe fn inplace_merge<F>(mut bfirst1: Link<T, A>, bmid: Link<T, A>, blast: Link<T, A>, less: &mut F) -> Link<T, A>
+    where F: FnMut(&T, &T) -> bool
+    {
+        let mut first2 = *bmid;
+        loop {
+            // advance over elements already in position
+            let mut first1;
+            loop {
+                if bfirst1 == bmid { return blast }
+                first1 = *bfirst1;
+                if less(&(*first2).value, &(*first1).value) { break }
+                bfirst1 = ForwardListNode::link(first1);
+            }
+            // find the end of the run of elements ordered before first1 in the second range
+            let mut brun_end = ForwardListNode::link(first2);
+            let mut run_end;
+            loop {
+                run_end = *brun_end;
+                if brun_end == blast || !less(&(*run_end).value, &(*first1).value) { break }
+                brun_end = ForwardListNode::link(run_end);
+            }
+            // (bmid, brun_end] moves to after bfirst1
+            *bmid = run_end;
+            *bfirst1 = first2;
+            *brun_end = first1;
+            if brun_end == blast { return bmid }
+            bfirst1 = brun_end;
+            first2 = run_end;
+        }
+    }
+
+    // _Sort2: sorts the two elements after bfirst, returning the link one before the end of the
+    // sorted region
+    unsafe fn sort2<F>(bfirst: Link<T, A>, less: &mut F) -> Link<T, A>
+    where F: FnMut(&T, &T) -> bool
+    {
+        let first1 = *bfirst;
+        if first1.is_null() { return bfirst }
+        let first2 = (*first1).next;
+        if first2.is_null() { return ForwardListNode::link(first1) }
+        if less(&(*first2).value, &(*first1).value) {
+            *bfirst = first2;
+            (*first1).next = (*first2).next;
+            (*first2).next = first1;
+            return ForwardListNode::link(first1)
+        }
+        ForwardListNode::link(first2)
+    }
+
+    // _Sort: sorts up to bound elements after bfirst, returning the link one before the end of
+    // the sorted region
+    unsafe fn sort_bounded<F>(bfirst: Link<T, A>, bound: usize, less: &mut F) -> Link<T, A>
+    where F: FnMut(&T, &T) -> bool
+    {
+        if bound <= 2 { return Self::sort2(bfirst, less) }
+        let half_bound = bound / 2;
+        let bmid = Self::sort_bounded(bfirst, half_bound, less);
+        if (*bmid).is_null() { return bmid }
+        let blast = Self::sort_bounded(bmid, half_bound, less);
+        Self::inplace_merge(bfirst, bmid, blast, less)
+    }
+
+    pub fn iter(&self) -> ForwardListIterator<'_, T, A> { self.into_iter() }
+    pub fn iter_mut(&mut self) -> ForwardListIteratorMut<'_, T, A> { self.into_iter() }
+}
+
+/// A cursor over a [`ForwardList`], which is either at an element or before the first element.
+/// Like forward_list's iterators, it can only move forward and edits the list after itself.
+pub struct CursorMut<'a, T, A = Global>
+where A: Allocator + Clone
+{
+    list: &'a mut ForwardList<T, A>,
+    // null at before_begin()
+    current: *mut ForwardListNode<T, A>
+}
+
+impl<T, A> CursorMut<'_, T, A>
+where A: Allocator + Clone
+{
+    fn link(&mut self) -> Link<T, A> {
+        match self.current.is_null() {
+            true => &raw mut self.list.head,
+            false => ForwardListNode::link(self.current)
+        }
+    }
+    /// Returns the current element, or None before the first element
+    pub fn current(&mut self) -> Option<&mut T> {
+        if self.current.is_null() { return None }
+        Some(unsafe { &mut (*self.current).value })
+    }
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let next = unsafe { *self.link() };
+        if next.is_null() { return None }
+        Some(unsafe { &mut (*next).value })
+    }
+    /// Moves to the next element, returning false without moving if there isn't one
+    pub fn move_next(&mut self) -> bool {
+        let next = unsafe { *self.link() };
+        if next.is_null() { return false }
+        self.current = next;
+        true
+    }
+    /// Inserts a value after the cursor
+    pub fn insert_after(&mut self, value: T) {
+        let link = self.link();
+        unsafe { *link = ForwardListNode::new(value, self.list._allocator.clone(), *link) };
+    }
+    /// Removes the element after the cursor
+    pub fn erase_after(&mut self) -> Option<T> {
+        let link = self.link();
+        unsafe { ForwardList::erase_after_link(link, self.list._allocator.clone()) }
+    }
+    /// Moves every element of other after the cursor, leaving other empty
+    pub fn splice_after(&mut self, other: &mut ForwardList<T, A>) {
+        if other.is_empty() { return }
+        let link = self.link();
+        unsafe {
+            let mut last = other.head;
+            while !(*last).next.is_null() { last = (*last).next; }
+            (*last).next = *link;
+            *link = other.head;
+        }
+        other.head = std::ptr::null_mut();
+    }
+}
+
+impl<T, A> Drop for ForwardList<T, A>
+where A: Allocator + Clone
+{
+    fn drop(&mut self) { self.clear() }
+}
+
+impl<T, A> Display for ForwardList<T, A>
+where T: Display,
+      A: Allocator + Clone
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values: Vec<std::string::String> = self.iter().map(|v| format!("{}", v)).collect();
+        write!(f, "ForwardList [ {} ]", values.join(", "))
+    }
+}
+
+pub struct ForwardListIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    node: *mut ForwardListNode<T, A>,
+    _marker: PhantomData<&'a T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for ForwardListIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.node.is_null() { return None }
+        let out = self.node;
+        self.node = unsafe { (*out).next };
+        Some(unsafe { &(*out).value })
+    }
+}
+
+pub struct ForwardListIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    node: *mut ForwardListNode<T, A>,
+    _marker: PhantomData<&'a mut T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for ForwardListIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.node.is_null() { return None }
+        let out = self.node;
+        self.node = unsafe { (*out).next };
+        Some(unsafe { &mut (*out).value })
+    }
+}
+
+impl<'a, T, A> IntoIterator for &'a ForwardList<T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    type IntoIter = ForwardListIterator<'a, T, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        ForwardListIterator { node: self.head, _marker: PhantomData }
+    }
+}
+
+impl<'a, T, A> IntoIterator for &'a mut ForwardList<T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    type IntoIter = ForwardListIteratorMut<'a, T, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        ForwardListIteratorMut { node: self.head, _marker: PhantomData }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use allocator_api2::alloc::Global;
+    use crate::msvc::string::String as CppString;
+    use super::{ ForwardList, ForwardListNode };
+    use std::error::Error;
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    fn values<T: Copy>(list: &ForwardList<T>) -> Vec<T> { list.iter().copied().collect() }
+
+    #[test]
+    pub fn forward_list_layout() -> TestReturn {
+        assert!(std::mem::size_of::<ForwardList<u64>>() == 8, "Forward list should only be the head pointer");
+        assert!(std::mem::size_of::<ForwardListNode<u32>>() == 16, "Node should be the next pointer then the value");
+        let mut list = ForwardList::new();
+        assert!(list.is_empty() && list.head.is_null(), "Empty list should have a null head");
+        list.push_front(5u32);
+        let node = list.head;
+        unsafe {
+            assert!((*node).next.is_null(), "Last node should have a null next pointer");
+            assert!(*(node as *const u8).add(8).cast::<u32>() == 5, "Value should be after the next pointer");
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn forward_list_push_pop() -> TestReturn {
+        let mut list = ForwardList::new();
+        for i in 0..5 { list.push_front(i); }
+        assert!(values(&list) == [4, 3, 2, 1, 0] && list.len() == 5, "Values should be pushed to the front");
+        assert!(list.front() == Some(&4), "Front should be the last pushed value");
+        *list.front_mut().unwrap() = 40;
+        assert!(list.pop_front() == Some(40) && list.pop_front() == Some(3), "Values should be popped from the front");
+        for v in list.iter_mut() { *v *= 10; }
+        assert!(values(&list) == [20, 10, 0], "Values should be edited in place");
+        list.clear();
+        assert!(list.is_empty() && list.pop_front().is_none(), "Cleared list should be empty");
+
+        let mut names: ForwardList<CppString> = ForwardList::new();
+        for name in ["Door", "Enemy", "Player"] { names.push_front(CppString::from_str_in(name, Global)); }
+        let names: Vec<&str> = names.iter().map(<&str>::from).collect();
+        assert!(names == ["Player", "Enemy", "Door"], "Strings should be stored in order");
+        Ok(())
+    }
+
+    #[test]
+    pub fn forward_list_cursor() -> TestReturn {
+        let mut list = ForwardList::from_vec(vec![1, 2, 4]);
+        let mut cursor = list.before_begin();
+        assert!(cursor.current().is_none() && cursor.peek_next() == Some(&mut 1), "Cursor should start before the first element");
+        cursor.insert_after(0);
+        assert!(cursor.move_next() && cursor.move_next() && cursor.move_next(), "Cursor should move to the third element");
+        assert!(cursor.current() == Some(&mut 2), "Cursor should be at 2");
+        cursor.insert_after(3);
+        assert!(cursor.move_next() && cursor.move_next() && !cursor.move_next(), "Cursor should stop at the last element");
+        cursor.insert_after(5);
+        assert!(values(&list) == [0, 1, 2, 3, 4, 5], "Values should be inserted after the cursor");
+
+        let mut cursor = list.before_begin();
+        assert!(cursor.erase_after() == Some(0), "First element should be erased");
+        cursor.move_next();
+        assert!(cursor.erase_after() == Some(2) && cursor.current() == Some(&mut 1), "Element after 1 should be erased");
+        let mut other = ForwardList::from_vec(vec![10, 20]);
+        cursor.splice_after(&mut other);
+        assert!(other.is_empty(), "Spliced list should be empty");
+        while cursor.move_next() {}
+        assert!(cursor.erase_after().is_none(), "Nothing should come after the last element");
+        let mut other = ForwardList::from_vec(vec![30]);
+        cursor.splice_after(&mut other);
+        list.before_begin().splice_after(&mut ForwardList::from_vec(vec![-1]));
+        assert!(values(&list) == [-1, 1, 10, 20, 3, 4, 5, 30], "Lists should be spliced after the cursor");
+        Ok(())
+    }
+
+    #[test]
+    pub fn forward_list_reverse_sort() -> TestReturn {
+        let mut list = ForwardList::from_vec((0..6).collect());
+        list.reverse();
+        assert!(values(&list) == [5, 4, 3, 2, 1, 0], "List should be reversed");
+        let mut single = ForwardList::from_vec(vec![1]);
+        single.reverse();
+        assert!(values(&single) == [1], "Single element should be unchanged");
+
+        for count in 0..70u32 {
+            let input: Vec<(u32, u32)> = (0..count).map(|i| ((i * 7 + 3) % 5, i)).collect();
+            let mut list = ForwardList::from_vec(input.clone());
+            list.sort_by(|a, b| a.0 < b.0);
+            let mut expected = input;
+            expected.sort_by_key(|v| v.0);
+            assert!(values(&list) == expected, "List of {} should be stably sorted", count);
+        }
+        let mut list = ForwardList::from_vec(vec![3, 1, 2]);
+        list.sort();
+        assert!(values(&list) == [1, 2, 3], "List should be sorted with operator<");
+        Ok(())
+    }
+}
@@ -122,6 +122,7 @@ impl<R> With0Params<R>
 where
     R: Sized,
 {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self {
             _return_value: PhantomData::<R>,
@@ -439,6 +440,7 @@ impl<P0, P1, P2, P3, P4, P5, P6, P7, R> With8Params<P0, P1, P2, P3, P4, P5, P6,
 where
     R: Sized,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         _param0: P0,
         _param1: P1,
@@ -501,6 +503,7 @@ impl<P0, P1, P2, P3, P4, P5, P6, P7, P8, R> With9Params<P0, P1, P2, P3, P4, P5,
 where
     R: Sized,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         _param0: P0,
         _param1: P1,
@@ -568,6 +571,7 @@ With10Params<P0, P1, P2, P3, P4, P5, P6, P7, P8, P9, R>
 where
     R: Sized,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         _param0: P0,
         _param1: P1,
@@ -641,6 +645,7 @@ With11Params<P0, P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, R>
 where
     R: Sized,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         _param0: P0,
         _param1: P1,
@@ -732,6 +737,7 @@ With12Params<P0, P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11, R>
 where
     R: Sized,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         _param0: P0,
         _param1: P1,
@@ -828,6 +834,7 @@ With13Params<P0, P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11, P12, R>
 where
     R: Sized,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         _param0: P0,
         _param1: P1,
@@ -929,6 +936,7 @@ With14Params<P0, P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11, P12, P13, R>
 where
     R: Sized,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         _param0: P0,
         _param1: P1,
@@ -1035,6 +1043,7 @@ With15Params<P0, P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11, P12, P13, P14, R>
 where
     R: Sized,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         _param0: P0,
         _param1: P1,
@@ -1146,6 +1155,7 @@ With16Params<P0, P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11, P12, P13, P14, P1
 where
     R: Sized,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         _param0: P0,
         _param1: P1,
@@ -1280,6 +1290,7 @@ With17Params<P0, P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11, P12, P13, P14, P1
 where
     R: Sized,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         _param0: P0,
         _param1: P1,
@@ -1441,6 +1452,7 @@ With18Params<P0, P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11, P12, P13, P14, P1
 where
     R: Sized,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         _param0: P0,
         _param1: P1,
@@ -1630,6 +1642,7 @@ With19Params<
 where
     R: Sized,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         _param0: P0,
         _param1: P1,
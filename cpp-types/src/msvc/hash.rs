@@ -1,15 +1,215 @@
-use std::hash::{ Hash, Hasher };
+pub use crate::generic::hash::{ EqualTo, HasherInit, KeyEqual };
+use std::{
+    fmt::{ self, Debug, Formatter },
+    hash::{ Hash, Hasher },
+    marker::PhantomData
+};
 
-const FNV_OFFSET_BASIS: u64 = 0xCBF29CE484222325;
-const FNV_PRIME: u64 = 0x100000001b3;
+pub const FNV_OFFSET_BASIS: u64 = 0xCBF29CE484222325;
+pub const FNV_PRIME: u64 = 0x100000001b3;
 
-pub struct FNV1A(u64);
+/// _Fnv1a_append_bytes: folds bytes into an existing FNV-1a hash value
+pub fn fnv1a_append_bytes(val: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(val, |v, b| (v ^ *b as u64).wrapping_mul(FNV_PRIME))
+}
+
+/// Boost's hash_combine, which many game codebases use to build std::hash specializations
+/// out of the hashes of each field
+pub fn hash_combine(seed: u64, hash: u64) -> u64 {
+    seed ^ hash.wrapping_add(0x9e3779b9).wrapping_add(seed << 6).wrapping_add(seed >> 2)
+}
+
+/// The value MSVC's std::hash<T> produces for a type. Unlike [`Hash`], this hashes exactly
+/// the bytes the C++ side hashes (no length prefixes or terminators), so implementations must
+/// not go through a [`Hasher`]. Can be derived for key structs with `#[derive(CppHash)]`.
+pub trait CppHash {
+    fn cpp_hash(&self) -> u64;
+}
+
+pub use cpp_types_macro::CppHash;
+
+/// Types that can be folded into an FNV-1a hash by their object representation, as
+/// _Fnv1a_append_value does. Derived alongside [`CppHash`] for structs using the default
+/// `fnv` strategy, where the fields are appended in declaration order (padding is skipped).
+pub trait FnvAppend {
+    fn fnv1a_append(&self, val: u64) -> u64;
+}
+
+// std::hash for integral types, pointers and enums: _Hash_representation
+macro_rules! impl_cpp_hash_representation {
+    ($($ty:ty),*) => {
+        $(
+            impl FnvAppend for $ty {
+                fn fnv1a_append(&self, val: u64) -> u64 { fnv1a_append_bytes(val, &self.to_ne_bytes()) }
+            }
+            impl CppHash for $ty {
+                fn cpp_hash(&self) -> u64 { self.fnv1a_append(FNV_OFFSET_BASIS) }
+            }
+        )*
+    };
+}
+
+impl_cpp_hash_representation!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl FnvAppend for bool {
+    fn fnv1a_append(&self, val: u64) -> u64 { fnv1a_append_bytes(val, &[*self as u8]) }
+}
+
+impl CppHash for bool {
+    fn cpp_hash(&self) -> u64 { self.fnv1a_append(FNV_OFFSET_BASIS) }
+}
+
+impl FnvAppend for char {
+    // char32_t
+    fn fnv1a_append(&self, val: u64) -> u64 { (*self as u32).fnv1a_append(val) }
+}
+
+impl CppHash for char {
+    fn cpp_hash(&self) -> u64 { self.fnv1a_append(FNV_OFFSET_BASIS) }
+}
+
+impl FnvAppend for f32 {
+    fn fnv1a_append(&self, val: u64) -> u64 { fnv1a_append_bytes(val, &self.to_ne_bytes()) }
+}
+
+impl FnvAppend for f64 {
+    fn fnv1a_append(&self, val: u64) -> u64 { fnv1a_append_bytes(val, &self.to_ne_bytes()) }
+}
+
+// std::hash for floating point types: -0.0 is normalized to 0.0 before hashing the representation
+impl CppHash for f32 {
+    fn cpp_hash(&self) -> u64 {
+        let value = if *self == 0.0 { 0.0f32 } else { *self };
+        value.fnv1a_append(FNV_OFFSET_BASIS)
+    }
+}
+
+impl CppHash for f64 {
+    fn cpp_hash(&self) -> u64 {
+        let value = if *self == 0.0 { 0.0f64 } else { *self };
+        value.fnv1a_append(FNV_OFFSET_BASIS)
+    }
+}
+
+impl<T> FnvAppend for *const T {
+    fn fnv1a_append(&self, val: u64) -> u64 { (*self as usize).fnv1a_append(val) }
+}
+
+impl<T> CppHash for *const T {
+    fn cpp_hash(&self) -> u64 { self.fnv1a_append(FNV_OFFSET_BASIS) }
+}
+
+impl<T> FnvAppend for *mut T {
+    fn fnv1a_append(&self, val: u64) -> u64 { (*self as usize).fnv1a_append(val) }
+}
+
+impl<T> CppHash for *mut T {
+    fn cpp_hash(&self) -> u64 { self.fnv1a_append(FNV_OFFSET_BASIS) }
+}
+
+impl<T, const N: usize> FnvAppend for [T; N]
+where T: FnvAppend
+{
+    fn fnv1a_append(&self, val: u64) -> u64 { self.iter().fold(val, |v, e| e.fnv1a_append(v)) }
+}
+
+// Borrowed forms of std::string and std::wstring, which hash the same as the owned strings
+impl CppHash for str {
+    fn cpp_hash(&self) -> u64 { fnv1a_append_bytes(FNV_OFFSET_BASIS, self.as_bytes()) }
+}
+
+impl CppHash for [u16] {
+    fn cpp_hash(&self) -> u64 { self.iter().fold(FNV_OFFSET_BASIS, |v, c| c.fnv1a_append(v)) }
+}
+
+impl<T> CppHash for &T
+where T: CppHash + ?Sized
+{
+    fn cpp_hash(&self) -> u64 { (**self).cpp_hash() }
+}
+
+/// Hash function object used by the hash tables to turn a value into a hash code. Hash tables
+/// store an instance of this (as _Uhash_compare does), so it can carry state such as a seed.
+pub trait HashFunction<T: ?Sized> {
+    fn hash_value(&self, value: &T) -> u64;
+}
+
+/// A hash table's hash type parameter, which is either a Rust hasher ([`Hasher`] +
+/// [`HasherInit`]) or a function object. Hashers keep their running state in themselves, so the
+/// table stores an empty [`HasherFunction`] for them instead. Function objects are stored as they
+/// are, with `type Function = Self`.
+pub trait HashParam {
+    type Function;
+}
+
+impl<H> HashParam for H
+where H: Hasher + HasherInit
+{
+    type Function = HasherFunction<H>;
+}
+
+/// Empty function object that hashes values with a new `H` each time
+pub struct HasherFunction<H>(PhantomData<fn() -> H>);
+
+impl<H> Default for HasherFunction<H> {
+    fn default() -> Self { Self(PhantomData) }
+}
+
+impl<H> Clone for HasherFunction<H> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<H> Copy for HasherFunction<H> {}
+
+impl<H> Debug for HasherFunction<H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str("HasherFunction") }
+}
+
+impl<H, T> HashFunction<T> for HasherFunction<H>
+where H: Hasher + HasherInit,
+      T: Hash
+{
+    fn hash_value(&self, value: &T) -> u64 { H::get_hash(value) }
+}
+
+// Hash writes a 0xff terminator after str and a length prefix before slices, so borrowed
+// strings are written the same way as String's Hash impl instead
+impl<H> HashFunction<str> for HasherFunction<H>
+where H: Hasher + HasherInit
+{
+    fn hash_value(&self, value: &str) -> u64 {
+        let mut hasher = H::new();
+        hasher.write(value.as_bytes());
+        hasher.finish()
+    }
+}
+
+impl<H> HashFunction<[u16]> for HasherFunction<H>
+where H: Hasher + HasherInit
+{
+    fn hash_value(&self, value: &[u16]) -> u64 {
+        let mut hasher = H::new();
+        value.iter().for_each(|c| hasher.write(&c.to_ne_bytes()));
+        hasher.finish()
+    }
+}
 
-pub trait HasherInit {
-    fn new() -> Self where Self: Sized;
-    fn get_hash<H>(value: &H) -> u64 where H: Hash;
+/// std::hash<T>, which hashes values through [`CppHash`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdHash;
+
+impl HashParam for StdHash {
+    type Function = Self;
+}
+
+impl<T> HashFunction<T> for StdHash
+where T: CppHash + ?Sized
+{
+    fn hash_value(&self, value: &T) -> u64 { value.cpp_hash() }
 }
 
+pub struct FNV1A(u64);
+
 impl HasherInit for FNV1A {
     fn new() -> Self { Self(FNV_OFFSET_BASIS) }
     fn get_hash<H>(value: &H) -> u64 where H: Hash {
@@ -82,8 +282,10 @@ impl Hasher for NoHashU64 {
 
 #[cfg(test)]
 pub mod tests {
-    use crate::msvc::string::String;
-    use super::{ HasherInit, FNV1A };
+    use crate::msvc::string::{ String, StringView };
+    use crate::msvc::unordered::Map;
+    use allocator_api2::alloc::Global;
+    use super::{ CppHash, HashFunction, HasherInit, StdHash, FNV1A };
     use std::{
         error::Error,
         hash::{ Hash, Hasher }
@@ -112,4 +314,105 @@ pub mod tests {
         check_hash("Door2", 0x7A3F740D0F6C7C81); // 0x3f == 1
         Ok(())
     }
+
+    #[test]
+    pub fn cpp_hash_primitives() -> TestReturn {
+        assert!(5u32.cpp_hash() == 0x2d401a55eec16520, "std::hash<unsigned int> should hash the 4 byte representation");
+        assert!(5u64.cpp_hash() == 0x0de21504f16dc720, "std::hash<unsigned long long> should hash the 8 byte representation");
+        assert!((-1i32).cpp_hash() == 0x994f76653e2a3951, "std::hash<int> should hash the 4 byte representation");
+        assert!(0x41u8.cpp_hash() == 0xaf63fc4c860222ec, "std::hash<unsigned char> should hash a single byte");
+        assert!(true.cpp_hash() == 0xaf63bc4c8601b62c, "std::hash<bool> should hash a single byte");
+        assert!('A'.cpp_hash() == 0x41u32.cpp_hash(), "std::hash<char32_t> should hash the 4 byte representation");
+        assert!((0x1000 as *const u8).cpp_hash() == 0x53a03f8d0add0c15, "std::hash<T*> should hash the pointer representation");
+        assert!(<&u32 as CppHash>::cpp_hash(&&5) == 5u32.cpp_hash(), "References should hash the referenced value");
+        assert!(StdHash.hash_value(&5u32) == 0x2d401a55eec16520, "StdHash should use CppHash");
+        Ok(())
+    }
+
+    #[test]
+    pub fn cpp_hash_floats() -> TestReturn {
+        assert!(1.5f32.cpp_hash() == 0x4a98c77f9ba36558, "std::hash<float> should hash the 4 byte representation");
+        assert!(0.0f32.cpp_hash() == 0x4d25767f9dce13f5, "std::hash<float> of 0.0 should hash zeroed bytes");
+        assert!((-0.0f32).cpp_hash() == 0.0f32.cpp_hash(), "std::hash<float> should treat -0.0 as 0.0");
+        assert!((-0.0f64).cpp_hash() == 0xa8c7f832281a39c5, "std::hash<double> should treat -0.0 as 0.0");
+        assert!((-2.5f64).cpp_hash() == 0xa8b9a032280d66e1, "std::hash<double> should hash the 8 byte representation");
+        Ok(())
+    }
+
+    #[test]
+    pub fn cpp_hash_strings() -> TestReturn {
+        let string = String::from_str("Player");
+        assert!(string.cpp_hash() == 0x333DC56DDFFD8EA0, "std::hash<std::string> should hash the characters");
+        let view = StringView::from_str("Player");
+        assert!(view.cpp_hash() == 0x333DC56DDFFD8EA0, "std::hash<std::string_view> should match std::hash<std::string>");
+        assert!(FNV1A::get_hash(&string) == string.cpp_hash(), "FNV1A should match CppHash for strings");
+        Ok(())
+    }
+
+    #[derive(CppHash, PartialEq)]
+    struct Key {
+        id: u32,
+        kind: u16
+    }
+
+    #[derive(CppHash)]
+    #[cpp_hash(fnv)]
+    struct Outer {
+        key: Key,
+        flag: bool
+    }
+
+    #[derive(CppHash)]
+    struct Pair(u8, u8);
+
+    #[derive(CppHash)]
+    #[cpp_hash(hash_combine)]
+    struct Combined {
+        a: u32,
+        b: f32
+    }
+
+    #[derive(CppHash)]
+    #[cpp_hash(with = "custom_hash")]
+    struct Custom(u32);
+
+    fn custom_hash(value: &Custom) -> u64 { value.0 as u64 * 31 }
+
+    #[derive(CppHash)]
+    #[repr(i32)]
+    #[allow(dead_code)]
+    enum Kind { A = 1, B = 7 }
+
+    #[test]
+    pub fn derive_fnv_append() -> TestReturn {
+        let key = Key { id: 5, kind: 3 };
+        assert!(key.cpp_hash() == 0x88ff39fb83ed3b9b, "Fields should be appended in declaration order");
+        let outer = Outer { key, flag: true };
+        assert!(outer.cpp_hash() == 0xb6eb20612c1c46ae, "Nested structs should append their fields");
+        assert!(Pair(1, 2).cpp_hash() == 0x082f2407b4e8902a, "Tuple struct fields should be appended in order");
+        assert!(Kind::B.cpp_hash() == 7i32.cpp_hash(), "Enums should hash their discriminant representation");
+        Ok(())
+    }
+
+    #[test]
+    pub fn derive_hash_combine_and_custom() -> TestReturn {
+        let combined = Combined { a: 5, b: -0.0 };
+        assert!(combined.cpp_hash() == 0x853c08ef9183a57d, "hash_combine should combine std::hash of each field");
+        assert!(Custom(2).cpp_hash() == 62, "Custom hash function should be called");
+        Ok(())
+    }
+
+    #[test]
+    pub fn derived_key_in_map() -> TestReturn {
+        let mut map: Map<StdHash, Key, u32, Global> = Map::new_in(Global);
+        for i in 0..20 {
+            assert!(map.insert(Key { id: i, kind: (i % 3) as u16 }, i * 2), "Key {} should be inserted", i);
+        }
+        for i in 0..20 {
+            let key = Key { id: i, kind: (i % 3) as u16 };
+            assert!(map.find(&key).unwrap().get_value() == &(i * 2), "Value for key {} should be {}", i, i * 2);
+        }
+        assert!(!map.contains(&Key { id: 1, kind: 2 }), "Key with a different kind should not be found");
+        Ok(())
+    }
 }
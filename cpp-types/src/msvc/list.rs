@@ -12,8 +12,6 @@ use std::{
 // See https://devblogs.microsoft.com/oldnewthing/20230804-00/?p=108547
 // https://github.com/microsoft/STL/blob/main/stl/inc/list
 
-type ForwardList<T, A> = List<ListForwardNode<T, A>, T, A>; // std::forward_list
-
 #[repr(C)]
 pub struct List<N, T, A = Global> // std::list
 where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
@@ -27,6 +25,7 @@ where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
 }
 
 impl<T> List<ListNode<T, Global>, T, Global> {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self { Self::new_inner(Global) }
     pub fn from_vec(vec: Vec<T>) -> Self { Self::from_vec_in(vec, Global) }
 }
@@ -223,6 +222,7 @@ where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
         None
     }
 
+    #[allow(clippy::needless_borrow, clippy::unnecessary_mut_passed)]
     pub fn clear(&mut self) {
         let head = self.get_nil();
         let mut node = self.first_ptr();
@@ -238,6 +238,298 @@ where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
     }
 }
 
+impl<T, A> List<ListNode<T, A>, T, A>
+where A: Allocator + Clone
+{
+    // Links new before `before`, which can be the head node to append it. The caller updates len.
+    pub(crate) unsafe fn link_node_before(&mut self, before: *mut ListNode<T, A>, new: *mut ListNode<T, A>) {
+        let after = (*before).prev;
+        (*new).next = before;
+        (*new).prev = after;
+        (*after).next = new;
+        (*before).prev = new;
+    }
+
+    // Unlinks the node without dropping or freeing it
+    pub(crate) unsafe fn unlink_node(&mut self, node: *mut ListNode<T, A>) {
+        (*(*node).prev).next = (*node).next;
+        (*(*node).next).prev = (*node).prev;
+        self.len -= 1;
+    }
+
+    // Links the chain of nodes from first to last (inclusive) before `before`
+    unsafe fn link_chain_before(&mut self, before: *mut ListNode<T, A>, first: *mut ListNode<T, A>, last: *mut ListNode<T, A>, count: usize) {
+        let after = (*before).prev;
+        (*after).next = first;
+        (*first).prev = after;
+        (*last).next = before;
+        (*before).prev = last;
+        self.len += count;
+    }
+
+    // Unlinks the chain of nodes from first to last (inclusive) in other, then links it before
+    // `before` in this list
+    unsafe fn transfer_before(&mut self, before: *mut ListNode<T, A>, other: &mut Self, first: *mut ListNode<T, A>, last: *mut ListNode<T, A>, count: usize) {
+        if count == 0 { return }
+        (*(*first).prev).next = (*last).next;
+        (*(*last).next).prev = (*first).prev;
+        other.len -= count;
+        self.link_chain_before(before, first, last, count);
+    }
+
+    // Unlinks the chain of nodes from first to last (inclusive), moving them into a new list
+    unsafe fn split_chain(&mut self, first: *mut ListNode<T, A>, last: *mut ListNode<T, A>, count: usize) -> Self {
+        let mut out = Self::new_in(self._allocator.clone());
+        let end = out.head;
+        out.transfer_before(end, self, first, last, count);
+        out
+    }
+
+    // Moves every node from other before `before`, leaving other empty
+    unsafe fn splice_list_before(&mut self, before: *mut ListNode<T, A>, mut other: Self) {
+        let (first, last, count) = ((*other.head).next, (*other.head).prev, other.len);
+        self.transfer_before(before, &mut other, first, last, count);
+    }
+
+    /// Returns a cursor at the first element, or the "ghost" position if the list is empty
+    pub fn cursor_front(&self) -> Cursor<'_, T, A> {
+        Cursor { current: unsafe { (*self.head).next }, index: 0, list: self }
+    }
+    /// Returns a cursor at the last element, or the "ghost" position if the list is empty
+    pub fn cursor_back(&self) -> Cursor<'_, T, A> {
+        Cursor { current: unsafe { (*self.head).prev }, index: self.len.saturating_sub(1), list: self }
+    }
+    /// Returns a cursor at the first element that can edit the list
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut { current: unsafe { (*self.head).next }, index: 0, list: self }
+    }
+    /// Returns a cursor at the last element that can edit the list
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut { current: unsafe { (*self.head).prev }, index: self.len.saturating_sub(1), list: self }
+    }
+
+    // _Unchecked_splice for a single node already in this list
+    pub(crate) unsafe fn splice_node_before(&mut self, before: *mut ListNode<T, A>, node: *mut ListNode<T, A>) {
+        (*(*node).prev).next = (*node).next;
+        (*(*node).next).prev = (*node).prev;
+        self.link_node_before(before, node);
+    }
+}
+
+// std::list operations which relink nodes, so elements are never moved or reallocated. Positions
+// are indices like insert and remove, where an index equal to len() is the end of the list.
+// Predicates return if the first argument is ordered before the second, like C++'s operator<.
+impl<T, A> List<ListNode<T, A>, T, A>
+where A: Allocator + Clone
+{
+    // Node at the index, or the head node if the index is len()
+    fn node_at(&self, index: usize) -> *mut ListNode<T, A> {
+        assert!(index <= self.len, "Tried to access position {} out of bounds", index);
+        if index == self.len { return self.head }
+        &raw const *self.get_unchecked(index) as *mut ListNode<T, A>
+    }
+
+    // _Unchecked_splice: moves [first, last) before `before` within this list, returning last
+    unsafe fn unchecked_splice(before: *mut ListNode<T, A>, first: *mut ListNode<T, A>, last: *mut ListNode<T, A>) -> *mut ListNode<T, A> {
+        let first_prev = (*first).prev;
+        (*first_prev).next = last;
+        let last_prev = (*last).prev;
+        (*last_prev).next = before;
+        let before_prev = (*before).prev;
+        (*before_prev).next = first;
+        (*before).prev = last_prev;
+        (*last).prev = first_prev;
+        (*first).prev = before_prev;
+        last
+    }
+
+    // _Merge_same: merges the sorted ranges [first, mid) and [mid, last), returning the new
+    // beginning of the range
+    unsafe fn merge_same<F>(mut first: *mut ListNode<T, A>, mut mid: *mut ListNode<T, A>, last: *mut ListNode<T, A>, less: &mut F) -> *mut ListNode<T, A>
+    where F: FnMut(&T, &T) -> bool
+    {
+        let new_first;
+        if less((*mid).value(), (*first).value()) {
+            // mid will be spliced to the front of the range
+            new_first = mid;
+        } else {
+            // skip over elements from the first range that are already in position
+            new_first = first;
+            loop {
+                first = (*first).next;
+                if first == mid { return new_first }
+                if less((*mid).value(), (*first).value()) { break }
+            }
+        }
+        loop {
+            // find the run of elements from the second range which go before first
+            let run_start = mid;
+            loop {
+                mid = (*mid).next;
+                if mid == last || !less((*mid).value(), (*first).value()) { break }
+            }
+            Self::unchecked_splice(first, run_start, mid);
+            if mid == last { return new_first }
+            loop {
+                first = (*first).next;
+                if first == mid { return new_first }
+                if less((*mid).value(), (*first).value()) { break }
+            }
+        }
+    }
+
+    // _Sort: sorts the size nodes from first, updating first to the new beginning of the range,
+    // and returns the node after the range
+    unsafe fn sort_range<F>(first: &mut *mut ListNode<T, A>, size: usize, less: &mut F) -> *mut ListNode<T, A>
+    where F: FnMut(&T, &T) -> bool
+    {
+        match size {
+            0 => return *first,
+            1 => return (**first).next,
+            _ => ()
+        };
+        let mut mid = Self::sort_range(first, size / 2, less);
+        let last = Self::sort_range(&mut mid, size - size / 2, less);
+        *first = Self::merge_same(*first, mid, last, less);
+        last
+    }
+
+    /// Moves every element of other before the element at index
+    pub fn splice(&mut self, index: usize, other: &mut Self) {
+        let before = self.node_at(index);
+        let (first, last, count) = unsafe { ((*other.head).next, (*other.head).prev, other.len) };
+        unsafe { self.transfer_before(before, other, first, last, count) };
+    }
+    /// Moves the element at other_index in other before the element at index
+    pub fn splice_one(&mut self, index: usize, other: &mut Self, other_index: usize) {
+        assert!(other_index < other.len, "Tried to splice value out of bounds");
+        let before = self.node_at(index);
+        let node = other.node_at(other_index);
+        unsafe { self.transfer_before(before, other, node, node, 1) };
+    }
+    /// Moves the elements in range from other before the element at index
+    pub fn splice_range(&mut self, index: usize, other: &mut Self, range: std::ops::Range<usize>) {
+        assert!(range.start <= range.end && range.end <= other.len, "Tried to splice range out of bounds");
+        if range.is_empty() { return }
+        let before = self.node_at(index);
+        let first = other.node_at(range.start);
+        let last = other.node_at(range.end - 1);
+        unsafe { self.transfer_before(before, other, first, last, range.len()) };
+    }
+
+    /// Merges the elements of other into this list. Both lists must already be sorted, and
+    /// equivalent elements from this list go first.
+    pub fn merge(&mut self, other: &mut Self)
+    where T: PartialOrd
+    {
+        self.merge_by(other, |a, b| a < b)
+    }
+    pub fn merge_by<F>(&mut self, other: &mut Self, mut less: F)
+    where F: FnMut(&T, &T) -> bool
+    {
+        if other.is_empty() { return }
+        let was_empty = self.is_empty();
+        unsafe {
+            let first = (*self.head).next;
+            let mid = (*other.head).next;
+            let end = self.head;
+            self.transfer_before(end, other, mid, (*other.head).prev, other.len);
+            if !was_empty { Self::merge_same(first, mid, end, &mut less); }
+        }
+    }
+
+    /// Sorts the list, keeping equivalent elements in order. This is the same merge sort as
+    /// MSVC's list::sort, so the comparisons are made in the same order.
+    pub fn sort(&mut self)
+    where T: PartialOrd
+    {
+        self.sort_by(|a, b| a < b)
+    }
+    pub fn sort_by<F>(&mut self, mut less: F)
+    where F: FnMut(&T, &T) -> bool
+    {
+        let mut first = unsafe { (*self.head).next };
+        unsafe { Self::sort_range(&mut first, self.len, &mut less) };
+    }
+
+    /// Reverses the order of the elements
+    pub fn reverse(&mut self) {
+        let head = self.head;
+        let mut node = head;
+        loop {
+            unsafe {
+                let next = (*node).next;
+                std::mem::swap(&mut (*node).next, &mut (*node).prev);
+                if next == head { break }
+                node = next;
+            }
+        }
+    }
+
+    // Unlinks and frees a node, dropping its value
+    unsafe fn erase_node(&mut self, node: *mut ListNode<T, A>) {
+        self.unlink_node(node);
+        drop(ListNode::free(node, self._allocator.clone()));
+    }
+
+    /// Removes consecutive duplicate elements, returning how many were removed
+    pub fn unique(&mut self) -> usize
+    where T: PartialEq
+    {
+        self.unique_by(|a, b| a == b)
+    }
+    /// Removes each element that's equal to the element kept before it according to eq,
+    /// returning how many were removed
+    pub fn unique_by<F>(&mut self, mut eq: F) -> usize
+    where F: FnMut(&T, &T) -> bool
+    {
+        let head = self.head;
+        let mut removed = 0;
+        unsafe {
+            let mut first = (*head).next;
+            if first == head { return 0 }
+            let mut after = (*first).next;
+            while after != head {
+                let next = (*after).next;
+                if eq((*first).value(), (*after).value()) {
+                    self.erase_node(after);
+                    removed += 1;
+                } else {
+                    first = after;
+                }
+                after = next;
+            }
+        }
+        removed
+    }
+
+    /// Removes every element that matches the predicate, returning how many were removed
+    pub fn remove_if<F>(&mut self, mut pred: F) -> usize
+    where F: FnMut(&T) -> bool
+    {
+        let head = self.head;
+        let mut removed = 0;
+        unsafe {
+            let mut node = (*head).next;
+            while node != head {
+                let next = (*node).next;
+                if pred((*node).value()) {
+                    self.erase_node(node);
+                    removed += 1;
+                }
+                node = next;
+            }
+        }
+        removed
+    }
+    /// Keeps only the elements that match the predicate
+    pub fn retain<F>(&mut self, mut pred: F)
+    where F: FnMut(&T) -> bool
+    {
+        self.remove_if(|v| !pred(v));
+    }
+}
+
 impl<N, T, A> List<N, T, A>
 where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
       A: Allocator + Clone
@@ -261,6 +553,7 @@ where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
         after.set_next(new, head);
     }
 
+    #[allow(clippy::len_zero)]
     pub(crate) fn link_first(&mut self, first: &mut N) {
         if self.len() > 0 { return; }
         let head = self.head;
@@ -423,10 +716,20 @@ impl<'a, N, T: 'a, A> ListIterator<'a, N, T, A>
 where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
       A: Allocator + Clone
 {
-    fn collided(&self) -> bool {
-        let fwd_ptr = match &self.curr { Some(v) => &raw const **v, None => self.nil as *const N };
-        let bck_ptr = match &self.curr_rev { Some(v) => &raw const **v, None => self.nil as *const N };
-        std::ptr::eq(fwd_ptr, bck_ptr)
+    // Iterates from first to last (inclusive), or nothing if first is the head node
+    pub(crate) unsafe fn from_range(nil: *mut N, first: *mut N, last: *mut N) -> Self {
+        let empty = std::ptr::eq(first, nil);
+        Self {
+            nil,
+            curr: if empty { None } else { Some(&*first) },
+            curr_rev: if empty { None } else { Some(&*last) },
+            _type_marker: PhantomData,
+            _alloc_marker: PhantomData
+        }
+    }
+    // The forward and back iterators have met once one of them yields the other's node
+    fn collided(v: &N, other: Option<&N>) -> bool {
+        other.is_some_and(|o| std::ptr::eq(v, o))
     }
 }
 
@@ -437,9 +740,9 @@ where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
         self.curr.take().map(|v| {
-            self.curr = match self.collided() {
-                false => v.next(self.nil),
-                true => None
+            match Self::collided(v, self.curr_rev) {
+                false => self.curr = v.next(self.nil),
+                true => self.curr_rev = None
             };
             v.value()
         })
@@ -452,9 +755,9 @@ where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
 {
     fn next_back(&mut self) -> Option<Self::Item> {
         self.curr_rev.take().map(|v| {
-            self.curr_rev = match self.collided() {
-                false => v.prev(self.nil),
-                true => None
+            match Self::collided(v, self.curr) {
+                false => self.curr_rev = v.prev(self.nil),
+                true => self.curr = None
             };
             v.value()
         })
@@ -514,6 +817,170 @@ where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
     }
 }
 
+// Cursors point at an element, or at the "ghost" position between the last and first elements,
+// which is the list's head node. They're modeled after the cursors for Rust's LinkedList.
+
+/// A cursor over a [`List`] which can move forward and backward
+pub struct Cursor<'a, T, A = Global>
+where A: Allocator + Clone
+{
+    list: &'a List<ListNode<T, A>, T, A>,
+    current: *mut ListNode<T, A>,
+    // index of the current node, or the list's length at the ghost position
+    index: usize
+}
+
+/// A cursor over a [`List`] which can also insert, remove, split and splice elements in O(1)
+pub struct CursorMut<'a, T, A = Global>
+where A: Allocator + Clone
+{
+    list: &'a mut List<ListNode<T, A>, T, A>,
+    current: *mut ListNode<T, A>,
+    index: usize
+}
+
+impl<T, A> Clone for Cursor<'_, T, A>
+where A: Allocator + Clone
+{
+    fn clone(&self) -> Self { Self { list: self.list, current: self.current, index: self.index } }
+}
+
+impl<'a, T, A> Cursor<'a, T, A>
+where A: Allocator + Clone
+{
+    fn is_ghost(&self) -> bool { self.current == self.list.head }
+    /// Returns the index of the current element, or None at the ghost position
+    pub fn index(&self) -> Option<usize> { if self.is_ghost() { None } else { Some(self.index) } }
+    /// Moves to the next element. Moving past the last element goes to the ghost position, and
+    /// moving from the ghost position goes to the first element.
+    pub fn move_next(&mut self) {
+        self.index = if self.is_ghost() { 0 } else { self.index + 1 };
+        self.current = unsafe { (*self.current).next };
+    }
+    /// Moves to the previous element. Moving past the first element goes to the ghost position,
+    /// and moving from the ghost position goes to the last element.
+    pub fn move_prev(&mut self) {
+        self.index = if self.index == 0 { self.list.len } else { self.index - 1 };
+        self.current = unsafe { (*self.current).prev };
+    }
+    pub fn current(&self) -> Option<&'a T> {
+        if self.is_ghost() { return None }
+        Some(unsafe { (*self.current).value() })
+    }
+    pub fn peek_next(&self) -> Option<&'a T> {
+        let next = unsafe { (*self.current).next };
+        if next == self.list.head { return None }
+        Some(unsafe { (*next).value() })
+    }
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        let prev = unsafe { (*self.current).prev };
+        if prev == self.list.head { return None }
+        Some(unsafe { (*prev).value() })
+    }
+    pub fn front(&self) -> Option<&'a T> { self.list.first().map(|n| n.value()) }
+    pub fn back(&self) -> Option<&'a T> { self.list.last().map(|n| n.value()) }
+}
+
+impl<'a, T, A> CursorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    fn is_ghost(&self) -> bool { self.current == self.list.head }
+    /// Returns the index of the current element, or None at the ghost position
+    pub fn index(&self) -> Option<usize> { if self.is_ghost() { None } else { Some(self.index) } }
+    /// Moves to the next element, wrapping through the ghost position
+    pub fn move_next(&mut self) {
+        self.index = if self.is_ghost() { 0 } else { self.index + 1 };
+        self.current = unsafe { (*self.current).next };
+    }
+    /// Moves to the previous element, wrapping through the ghost position
+    pub fn move_prev(&mut self) {
+        self.index = if self.index == 0 { self.list.len } else { self.index - 1 };
+        self.current = unsafe { (*self.current).prev };
+    }
+    pub fn current(&mut self) -> Option<&mut T> {
+        if self.is_ghost() { return None }
+        Some(unsafe { (*self.current).value_mut() })
+    }
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let next = unsafe { (*self.current).next };
+        if next == self.list.head { return None }
+        Some(unsafe { (*next).value_mut() })
+    }
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        let prev = unsafe { (*self.current).prev };
+        if prev == self.list.head { return None }
+        Some(unsafe { (*prev).value_mut() })
+    }
+    /// Returns a read-only cursor at the same position
+    pub fn as_cursor(&self) -> Cursor<'_, T, A> {
+        Cursor { list: self.list, current: self.current, index: self.index }
+    }
+
+    /// Inserts a value after the current element, or at the front at the ghost position
+    pub fn insert_after(&mut self, value: T) {
+        let node = ListNode::new(value, self.list._allocator.clone(), self.list.head);
+        unsafe {
+            let before = (*self.current).next;
+            self.list.link_chain_before(before, node, node, 1);
+        }
+        // the ghost's index is the list's length
+        if self.is_ghost() { self.index += 1; }
+    }
+    /// Inserts a value before the current element, or at the back at the ghost position
+    pub fn insert_before(&mut self, value: T) {
+        let node = ListNode::new(value, self.list._allocator.clone(), self.list.head);
+        unsafe { self.list.link_chain_before(self.current, node, node, 1) };
+        self.index += 1;
+    }
+    /// Removes the current element and moves to the next one. Does nothing at the ghost
+    /// position.
+    pub fn remove_current(&mut self) -> Option<T> {
+        if self.is_ghost() { return None }
+        let node = self.current;
+        unsafe {
+            self.current = (*node).next;
+            self.list.unlink_node(node);
+            Some(ListNode::free(node, self.list._allocator.clone()))
+        }
+    }
+    /// Moves every element after the current one into a new list. At the ghost position, this
+    /// moves the whole list.
+    pub fn split_after(&mut self) -> List<ListNode<T, A>, T, A> {
+        let count = match self.is_ghost() {
+            true => self.list.len,
+            false => self.list.len - self.index - 1
+        };
+        let out = unsafe { self.list.split_chain((*self.current).next, (*self.list.head).prev, count) };
+        if self.is_ghost() { self.index = 0; }
+        out
+    }
+    /// Moves every element before the current one into a new list. At the ghost position, this
+    /// moves the whole list.
+    pub fn split_before(&mut self) -> List<ListNode<T, A>, T, A> {
+        let count = match self.is_ghost() {
+            true => self.list.len,
+            false => self.index
+        };
+        let out = unsafe { self.list.split_chain((*self.list.head).next, (*self.current).prev, count) };
+        self.index = 0;
+        out
+    }
+    /// Moves every element of other after the current one, or to the front at the ghost position
+    pub fn splice_after(&mut self, other: List<ListNode<T, A>, T, A>) {
+        let count = other.len;
+        unsafe {
+            let before = (*self.current).next;
+            self.list.splice_list_before(before, other);
+        }
+        if self.is_ghost() { self.index += count; }
+    }
+    /// Moves every element of other before the current one, or to the back at the ghost position
+    pub fn splice_before(&mut self, other: List<ListNode<T, A>, T, A>) {
+        self.index += other.len;
+        unsafe { self.list.splice_list_before(self.current, other) };
+    }
+}
+
 impl<N, T, A> Drop for List<N, T, A>
 where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
       A: Allocator + Clone
@@ -594,6 +1061,20 @@ where A: Allocator
     fn set_prev(&mut self, prev: *mut Self, nil: *mut Self) where Self: Sized;
 }
 
+impl<T, A> ListNode<T, A>
+where A: Allocator + Clone
+{
+    // Raw links, which point to the head node at either end of the list
+    pub(crate) fn next_raw(&self) -> *mut Self { self.next }
+    pub(crate) fn prev_raw(&self) -> *mut Self { self.prev }
+    // _Freenode: moves the value out of an unlinked node, then frees it
+    pub(crate) unsafe fn free(node: *mut Self, alloc: A) -> T {
+        let value = ManuallyDrop::take(&mut (*node).val);
+        alloc.deallocate(NonNull::new_unchecked(node as *mut u8), Layout::new::<Self>());
+        value
+    }
+}
+
 impl<T, A> ListSingleNode<T, A> for ListNode<T, A>
 where A: Allocator + Clone
 {
@@ -633,6 +1114,7 @@ where A: Allocator + Clone
             false => Some(unsafe{NonNull::new_unchecked(self.next)})
         }
     }
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
     fn set_next(&mut self, next: *mut Self, nil: *mut Self) where Self: Sized {
         if next != nil {
             unsafe { (&mut *next).prev = self }
@@ -664,6 +1146,7 @@ where A: Allocator + Clone
             false => Some(unsafe{NonNull::new_unchecked(self.prev)})
         }
     }
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
     fn set_prev(&mut self, prev: *mut Self, nil: *mut Self) where Self: Sized {
         if prev != nil {
             unsafe { (&mut *prev).next = self }
@@ -692,6 +1175,7 @@ impl<N, T, A> From<List<N, T, A>> for Vec<T>
 where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
       A: Allocator + Clone,
 {
+    #[allow(clippy::uninit_vec)]
     fn from(mut value: List<N, T, A>) -> Self {
         let old_len = value.len();
         let mut vec: Vec<T> = Vec::with_capacity(old_len);
@@ -776,6 +1260,7 @@ where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
     }
 }
 
+    #[allow(clippy::len_zero)]
     #[test]
     pub fn create_blank_list() -> TestReturn {
         let list: List<ListNode<u32, Global>, u32, Global> = List::new();
@@ -784,6 +1269,7 @@ where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
         Ok(())
     }
 
+    #[allow(clippy::partialeq_to_none)]
     #[test]
     pub fn list_push_pop() -> TestReturn {
         let mut list = List::new();
@@ -831,6 +1317,7 @@ where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
         Ok(())
     }
 
+    #[allow(clippy::partialeq_to_none)]
     #[test]
     pub fn list_find() -> TestReturn { 
         let list = List::from_vec(vec![20, 30, 15, 5, 40, 25]);
@@ -967,4 +1454,167 @@ where N: ListSingleNode<T, A> + ListDoubleNode<T, A>,
         list.check_list_iterator_delegate(|v, e| { let s: &str = v.into(); s == *e }, &expected_strs);
         Ok(())
     }
+
+    // Walks the list in both directions, checking the links and length
+    fn list_values<T: Copy, A: Allocator + Clone>(list: &List<ListNode<T, A>, T, A>) -> Vec<T> {
+        let forward: Vec<T> = list.iter().copied().collect();
+        let mut backward: Vec<T> = list.iter().rev().copied().collect();
+        backward.reverse();
+        assert!(forward.len() == list.len(), "List should have {} elements instead of {}", list.len(), forward.len());
+        assert!(forward.len() == backward.len(), "Forward and backward links should visit the same nodes");
+        forward
+    }
+
+    #[test]
+    pub fn list_cursor() -> TestReturn {
+        let list = List::from_vec(vec![1, 2, 3]);
+        let mut cursor = list.cursor_front();
+        assert!(cursor.current() == Some(&1) && cursor.index() == Some(0), "Cursor should start at the front");
+        assert!(cursor.peek_prev().is_none() && cursor.peek_next() == Some(&2), "Peek should see the neighbors");
+        cursor.move_prev();
+        assert!(cursor.current().is_none() && cursor.index().is_none(), "Moving before the front should reach the ghost");
+        assert!(cursor.peek_next() == Some(&1) && cursor.peek_prev() == Some(&3), "Ghost should sit between the back and front");
+        cursor.move_prev();
+        assert!(cursor.current() == Some(&3) && cursor.index() == Some(2), "Moving before the ghost should reach the back");
+        cursor.move_next();
+        cursor.move_next();
+        assert!(cursor.current() == Some(&1) && cursor.index() == Some(0), "Moving after the ghost should reach the front");
+        let back = list.cursor_back();
+        assert!(back.current() == Some(&3) && back.front() == Some(&1) && back.back() == Some(&3), "Cursor should start at the back");
+
+        let mut empty: List<ListNode<u32, Global>, u32, Global> = List::new();
+        let mut cursor = empty.cursor_front_mut();
+        assert!(cursor.current().is_none() && cursor.remove_current().is_none(), "Empty list should only have the ghost");
+        cursor.insert_after(2);
+        cursor.insert_before(3);
+        cursor.insert_after(1);
+        assert!(cursor.index().is_none(), "Cursor should still be at the ghost");
+        assert!(list_values(&empty) == [1, 2, 3], "Inserting at the ghost should go to the front and back");
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_cursor_edits() -> TestReturn {
+        // remove even values, and insert a copy after each odd value in one pass
+        let mut list = List::from_vec((0..10).collect());
+        let mut cursor = list.cursor_front_mut();
+        while let Some(v) = cursor.current() {
+            let v = *v;
+            if v % 2 == 0 {
+                assert!(cursor.remove_current() == Some(v), "{} should be removed", v);
+            } else {
+                cursor.insert_after(v * 10);
+                cursor.move_next();
+                cursor.move_next();
+            }
+        }
+        assert!(cursor.index().is_none(), "Cursor should end at the ghost");
+        assert!(list_values(&list) == [1, 10, 3, 30, 5, 50, 7, 70, 9, 90], "Edits should be applied in order");
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.insert_before(2);
+        assert!(cursor.index() == Some(3) && cursor.current() == Some(&mut 3), "Inserting before should shift the index");
+        *cursor.peek_prev().unwrap() = 20;
+        let tail = cursor.split_after();
+        assert!(cursor.index() == Some(3), "Splitting after should keep the index");
+        let head = cursor.split_before();
+        assert!(cursor.index() == Some(0) && cursor.current() == Some(&mut 3), "Splitting before should move the index to 0");
+        assert!(list_values(&head) == [1, 10, 20] && list_values(&tail) == [30, 5, 50, 7, 70, 9, 90], "Splits should take the nodes");
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.splice_before(head);
+        assert!(cursor.index() == Some(3) && cursor.current() == Some(&mut 3), "Splicing before should shift the index");
+        cursor.splice_after(tail);
+        assert!(cursor.index() == Some(3), "Splicing after should keep the index");
+        assert!(list_values(&list) == [1, 10, 20, 3, 30, 5, 50, 7, 70, 9, 90], "Splices should put the list back together");
+
+        // splits and splices at the ghost take or add the whole list
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_next();
+        let all = cursor.split_after();
+        assert!(list.is_empty() && all.len() == 11, "Splitting at the ghost should take everything");
+        let mut cursor = list.cursor_front_mut();
+        cursor.splice_before(all);
+        cursor.splice_after(List::from_vec(vec![0]));
+        cursor.splice_before(List::from_vec(vec![100]));
+        assert!(cursor.index().is_none() && cursor.as_cursor().peek_prev() == Some(&100), "Cursor should still be at the ghost");
+        assert!(list_values(&list) == [0, 1, 10, 20, 3, 30, 5, 50, 7, 70, 9, 90, 100], "Splices at the ghost should go to the ends");
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_splice() -> TestReturn {
+        let mut list = List::from_vec(vec![1, 2, 3]);
+        let mut other = List::from_vec(vec![10, 20, 30, 40, 50]);
+        let address = &raw const *other.get_unchecked(1).value();
+        list.splice_one(1, &mut other, 1);
+        assert!(list_values(&list) == [1, 20, 2, 3] && list_values(&other) == [10, 30, 40, 50], "One node should be moved");
+        assert!(std::ptr::eq(list.get_unchecked(1).value(), address), "Node should be relinked instead of copied");
+        list.splice_range(4, &mut other, 1..3);
+        assert!(list_values(&list) == [1, 20, 2, 3, 30, 40] && list_values(&other) == [10, 50], "Range should be moved to the end");
+        list.splice_range(0, &mut other, 1..1);
+        assert!(other.len() == 2, "Empty range should move nothing");
+        list.splice(0, &mut other);
+        assert!(list_values(&list) == [10, 50, 1, 20, 2, 3, 30, 40] && other.is_empty(), "Whole list should be moved to the front");
+        other.splice(0, &mut list);
+        assert!(list.is_empty() && list_values(&other) == [10, 50, 1, 20, 2, 3, 30, 40], "Whole list should be moved into an empty list");
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_sort_and_merge() -> TestReturn {
+        // sort by priority only, so equal priorities show if the sort is stable
+        let mut queue = List::from_vec((0..200u32).map(|i| ((i * 37) % 11, i)).collect());
+        let mut comparisons = 0;
+        queue.sort_by(|a, b| { comparisons += 1; a.0 < b.0 });
+        let values = list_values(&queue);
+        let mut expected = values.clone();
+        expected.sort_by_key(|v| v.0);
+        assert!(values.windows(2).all(|w| w[0].0 < w[1].0 || (w[0].0 == w[1].0 && w[0].1 < w[1].1)), "Sort should be stable");
+        assert!(values == expected, "Sort should match a stable sort");
+        assert!(comparisons < 200 * 8, "Merge sort should make O(n log n) comparisons instead of {}", comparisons);
+
+        for count in 0..40u32 {
+            let mut list = List::from_vec((0..count).map(|i| (i * 7 + 3) % 13).collect());
+            list.sort();
+            let mut expected: Vec<u32> = (0..count).map(|i| (i * 7 + 3) % 13).collect();
+            expected.sort();
+            assert!(list_values(&list) == expected, "List of {} should be sorted", count);
+        }
+
+        let mut a = List::from_vec(vec![(1, 'a'), (3, 'a'), (5, 'a'), (7, 'a')]);
+        let mut b = List::from_vec(vec![(0, 'b'), (3, 'b'), (4, 'b'), (9, 'b')]);
+        a.merge_by(&mut b, |x, y| x.0 < y.0);
+        let merged = list_values(&a);
+        assert!(b.is_empty(), "Merged list should be empty");
+        assert!(merged == [(0, 'b'), (1, 'a'), (3, 'a'), (3, 'b'), (4, 'b'), (5, 'a'), (7, 'a'), (9, 'b')], "Merge should keep this list's elements first, got {:?}", merged);
+        let mut empty = List::new();
+        empty.merge(&mut List::from_vec(vec![1, 2]));
+        assert!(list_values(&empty) == [1, 2], "Merging into an empty list should take every element");
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_reverse_unique_remove() -> TestReturn {
+        let mut list = List::from_vec(vec![1, 1, 2, 3, 3, 3, 1, 4, 4]);
+        assert!(list.unique() == 4 && list_values(&list) == [1, 2, 3, 1, 4], "Consecutive duplicates should be removed");
+        // unique compares against the element that was kept
+        let mut list = List::from_vec(vec![1, 2, 3, 4, 10, 11, 12]);
+        assert!(list.unique_by(|a, b| b - a < 3) == 4, "4 elements should be within 3 of a kept element");
+        assert!(list_values(&list) == [1, 4, 10], "Elements should be compared to the last kept element");
+
+        let mut list = List::from_vec((0..10).collect());
+        list.reverse();
+        assert!(list_values(&list) == [9, 8, 7, 6, 5, 4, 3, 2, 1, 0], "List should be reversed");
+        assert!(list.remove_if(|v| v % 3 == 0) == 4, "Multiples of 3 should be removed");
+        assert!(list_values(&list) == [8, 7, 5, 4, 2, 1], "Other elements should be untouched");
+        list.retain(|v| *v > 4);
+        assert!(list_values(&list) == [8, 7, 5], "Only elements over 4 should be kept");
+        let mut empty: List<ListNode<u32, Global>, u32, Global> = List::new();
+        empty.reverse();
+        assert!(empty.unique() == 0 && empty.is_empty(), "Empty list should be unchanged");
+        Ok(())
+    }
 }
@@ -0,0 +1,701 @@
+//! Ports of MSVC's <random> engines and distributions. The engines have the same layout as the
+//! C++ objects, so a game's engine state can be read and written in place, and the distributions
+//! make the same engine calls and return the same values as MSVC's, except where
+//! [`NormalDistribution`] relies on the logarithm.
+use crate::msvc::algorithm::{ RngFromUrng, RngFromUrngV2, UniformRandomBitGenerator };
+use std::ops::{ Add, Div, Mul, Sub };
+
+// https://github.com/microsoft/STL/blob/main/stl/inc/random
+
+// std::mersenne_twister_engine, which inherits _Circ_buf (an unsigned int index followed by twice
+// the state size, so the next state can be generated without overwriting the current one) and the
+// TR1 mersenne_twister (which stores the tempering mask d)
+macro_rules! mersenne_twister_engine {
+    ($(#[$attr:meta])* $name:ident, $ty:ty, w: $w:expr, n: $n:expr, m: $m:expr, r: $r:expr, a: $a:expr,
+     u: $u:expr, d: $d:expr, s: $s:expr, b: $b:expr, t: $t:expr, c: $c:expr, l: $l:expr, f: $f:expr) => {
+        $(#[$attr])*
+        #[repr(C)]
+        #[derive(Clone)]
+        pub struct $name {
+            idx: u32, // _Idx
+            ax: [$ty; 2 * $n], // _Ax
+            dxval: $ty // _Dxval
+        }
+
+        impl $name {
+            pub const DEFAULT_SEED: $ty = 5489;
+            const N: usize = $n;
+            const M: usize = $m;
+            const A: $ty = $a;
+            const F: $ty = $f;
+            const WMSK: $ty = <$ty>::MAX >> (<$ty>::BITS - $w);
+            const HMSK: $ty = (Self::WMSK << $r) & Self::WMSK;
+            const LMSK: $ty = !Self::HMSK & Self::WMSK;
+
+            pub fn new() -> Self { Self::with_seed(Self::DEFAULT_SEED) }
+            pub fn with_seed(seed: $ty) -> Self {
+                let mut engine = Self { idx: 0, ax: [0; 2 * $n], dxval: $d };
+                engine.seed(seed);
+                engine
+            }
+            pub fn seed(&mut self, seed: $ty) {
+                let mut prev = seed & Self::WMSK;
+                self.ax[0] = prev;
+                for i in 1..Self::N {
+                    prev = (i as $ty).wrapping_add(Self::F.wrapping_mul(prev ^ (prev >> ($w - 2)))) & Self::WMSK;
+                    self.ax[i] = prev;
+                }
+                self.idx = Self::N as u32;
+            }
+            /// Returns the next value, like operator()
+            pub fn generate(&mut self) -> $ty {
+                if self.idx as usize == Self::N {
+                    self.refill_upper();
+                } else if 2 * Self::N <= self.idx as usize {
+                    self.refill_lower();
+                }
+                let mut res = self.ax[self.idx as usize] & Self::WMSK;
+                self.idx += 1;
+                res ^= (res >> $u) & self.dxval;
+                res ^= (res << $s) & $b;
+                res ^= (res << $t) & $c;
+                res ^= (res & Self::WMSK) >> $l;
+                res
+            }
+            /// Advances the engine as if generate() was called count times
+            pub fn discard(&mut self, count: u64) {
+                for _ in 0..count { self.generate(); }
+            }
+            fn twist(&self, upper: usize, lower: usize, other: usize) -> $ty {
+                let tmp = (self.ax[upper] & Self::HMSK) | (self.ax[lower] & Self::LMSK);
+                (tmp >> 1) ^ (if tmp & 1 != 0 { Self::A } else { 0 }) ^ self.ax[other]
+            }
+            // _Refill_lower: compute values for the lower half of the history array
+            fn refill_lower(&mut self) {
+                let (n, m) = (Self::N, Self::M);
+                let mut i = 0;
+                while i < n - m {
+                    self.ax[i] = self.twist(i + n, i + n + 1, i + n + m);
+                    i += 1;
+                }
+                while i < n - 1 {
+                    self.ax[i] = self.twist(i + n, i + n + 1, i + m - n);
+                    i += 1;
+                }
+                self.ax[i] = self.twist(i + n, 0, m - 1);
+                self.idx = 0;
+            }
+            // _Refill_upper: compute values for the upper half of the history array
+            fn refill_upper(&mut self) {
+                let (n, m) = (Self::N, Self::M);
+                for i in n..2 * n {
+                    self.ax[i] = self.twist(i - n, i - n + 1, i - n + m);
+                }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self { Self::new() }
+        }
+
+        impl UniformRandomBitGenerator for $name {
+            const RESULT_BITS: u32 = <$ty>::BITS;
+            const MIN: u64 = 0;
+            const MAX: u64 = <$name>::WMSK as u64;
+            fn generate(&mut self) -> u64 { <$name>::generate(self) as u64 }
+        }
+    };
+}
+
+mersenne_twister_engine!(
+    /// std::mt19937
+    Mt19937, u32, w: 32, n: 624, m: 397, r: 31, a: 0x9908b0df, u: 11, d: 0xffffffff,
+    s: 7, b: 0x9d2c5680, t: 15, c: 0xefc60000, l: 18, f: 1812433253
+);
+
+mersenne_twister_engine!(
+    /// std::mt19937_64
+    #[allow(non_camel_case_types)]
+    Mt19937_64, u64, w: 64, n: 312, m: 156, r: 31, a: 0xb5026f5aa96619e9, u: 29, d: 0x5555555555555555,
+    s: 17, b: 0x71d67fffeda60000, t: 37, c: 0xfff7eee000000000, l: 43, f: 6364136223846793005
+);
+
+/// std::linear_congruential_engine<unsigned int, A, C, M>, where an M of 0 means 2^32
+#[repr(C)]
+#[derive(Clone)]
+pub struct LinearCongruentialEngine<const A: u32, const C: u32, const M: u32> {
+    prev: u32 // _Prev
+}
+
+/// std::minstd_rand0
+pub type MinstdRand0 = LinearCongruentialEngine<16807, 0, 2147483647>;
+/// std::minstd_rand
+pub type MinstdRand = LinearCongruentialEngine<48271, 0, 2147483647>;
+
+impl<const A: u32, const C: u32, const M: u32> LinearCongruentialEngine<A, C, M> {
+    pub const DEFAULT_SEED: u32 = 1;
+
+    pub fn new() -> Self { Self::with_seed(Self::DEFAULT_SEED) }
+    pub fn with_seed(seed: u32) -> Self {
+        let mut engine = Self { prev: 0 };
+        engine.seed(seed);
+        engine
+    }
+    fn modulo(value: u64) -> u64 {
+        if M == 0 { value & u32::MAX as u64 } else { value % M as u64 }
+    }
+    pub fn seed(&mut self, seed: u32) {
+        let value = Self::modulo(seed as u64) as u32;
+        self.prev = if Self::modulo(C as u64) == 0 && value == 0 { 1 } else { value };
+    }
+    /// Returns the next value, like operator()
+    pub fn generate(&mut self) -> u32 {
+        self.prev = Self::modulo(A as u64 * self.prev as u64 + C as u64) as u32;
+        self.prev
+    }
+    /// Advances the engine as if generate() was called count times
+    pub fn discard(&mut self, count: u64) {
+        for _ in 0..count { self.generate(); }
+    }
+}
+
+impl<const A: u32, const C: u32, const M: u32> Default for LinearCongruentialEngine<A, C, M> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<const A: u32, const C: u32, const M: u32> UniformRandomBitGenerator for LinearCongruentialEngine<A, C, M> {
+    const RESULT_BITS: u32 = u32::BITS;
+    const MIN: u64 = if C == 0 { 1 } else { 0 };
+    const MAX: u64 = if M == 0 { u32::MAX as u64 } else { M as u64 - 1 };
+    fn generate(&mut self) -> u64 { LinearCongruentialEngine::generate(self) as u64 }
+}
+
+/// std::ranlux24_base, a subtract_with_carry_engine<unsigned int, 24, 10, 24>. Like the mersenne
+/// twister, _Swc_base keeps the history in a _Circ_buf that's twice the long lag.
+#[repr(C)]
+#[derive(Clone)]
+pub struct Ranlux24Base {
+    idx: u32, // _Idx
+    ax: [u32; 48], // _Ax
+    carry: u32 // _Carry
+}
+
+impl Ranlux24Base {
+    pub const DEFAULT_SEED: u32 = 19780503;
+    const W: u32 = 24;
+    const S: usize = 10;
+    const R: usize = 24;
+
+    pub fn new() -> Self { Self::with_seed(Self::DEFAULT_SEED) }
+    pub fn with_seed(seed: u32) -> Self {
+        let mut engine = Self { idx: 0, ax: [0; 48], carry: 0 };
+        engine.seed(seed);
+        engine
+    }
+    pub fn seed(&mut self, seed: u32) {
+        let mut lcg = LinearCongruentialEngine::<40014, 0, 2147483563>::with_seed(
+            if seed == 0 { Self::DEFAULT_SEED } else { seed });
+        for i in 0..Self::R {
+            self.ax[i] = lcg.generate() & ((1 << Self::W) - 1);
+        }
+        self.carry = (self.ax[Self::R - 1] == 0) as u32;
+        self.idx = Self::R as u32;
+    }
+    /// Returns the next value, like operator()
+    pub fn generate(&mut self) -> u32 {
+        let (s, r) = (Self::S, Self::R);
+        let i = if 2 * r <= self.idx as usize { 0 } else { self.idx as usize };
+        if i < s {
+            self.setx(i, self.ax[i + 2 * r - s], self.ax[i + r]);
+        } else if i < r {
+            self.setx(i, self.ax[i - s], self.ax[i + r]);
+        } else {
+            self.setx(i, self.ax[i - s], self.ax[i - r]);
+        }
+        self.idx = (i + 1) as u32;
+        self.ax[i]
+    }
+    /// Advances the engine as if generate() was called count times
+    pub fn discard(&mut self, count: u64) {
+        for _ in 0..count { self.generate(); }
+    }
+    // _Setx: x(i) = x(i - s) - x(i - r) - carry, borrowing from the modulus on underflow
+    fn setx(&mut self, i: usize, xis: u32, xir: u32) {
+        let (value, underflow_r) = xis.overflowing_sub(xir);
+        let (value, underflow_c) = value.overflowing_sub(self.carry);
+        if underflow_r || underflow_c {
+            self.ax[i] = value.wrapping_add(1 << Self::W);
+            self.carry = 1;
+        } else {
+            self.ax[i] = value;
+            self.carry = 0;
+        }
+    }
+}
+
+impl Default for Ranlux24Base {
+    fn default() -> Self { Self::new() }
+}
+
+impl UniformRandomBitGenerator for Ranlux24Base {
+    const RESULT_BITS: u32 = u32::BITS;
+    const MIN: u64 = 0;
+    const MAX: u64 = (1 << Self::W) - 1;
+    fn generate(&mut self) -> u64 { Ranlux24Base::generate(self) as u64 }
+}
+
+/// std::ranlux24, a discard_block_engine<ranlux24_base, 223, 23> that uses the first 23 values of
+/// every block of 223
+#[repr(C)]
+#[derive(Clone)]
+pub struct Ranlux24 {
+    eng: Ranlux24Base, // _Eng
+    n: i32 // _Nx
+}
+
+impl Ranlux24 {
+    const P: i32 = 223;
+    const R: i32 = 23;
+
+    pub fn new() -> Self { Self::with_seed(Ranlux24Base::DEFAULT_SEED) }
+    pub fn with_seed(seed: u32) -> Self { Self { eng: Ranlux24Base::with_seed(seed), n: 0 } }
+    pub fn seed(&mut self, seed: u32) {
+        self.eng.seed(seed);
+        self.n = 0;
+    }
+    /// The underlying engine
+    pub fn base(&self) -> &Ranlux24Base { &self.eng }
+    /// Returns the next value, like operator()
+    pub fn generate(&mut self) -> u32 {
+        if Self::R <= self.n {
+            // discard the rest of the block
+            for _ in self.n..Self::P { self.eng.generate(); }
+            self.n = 0;
+        }
+        self.n += 1;
+        self.eng.generate()
+    }
+    /// Advances the engine as if generate() was called count times
+    pub fn discard(&mut self, count: u64) {
+        for _ in 0..count { self.generate(); }
+    }
+}
+
+impl Default for Ranlux24 {
+    fn default() -> Self { Self::new() }
+}
+
+impl UniformRandomBitGenerator for Ranlux24 {
+    const RESULT_BITS: u32 = u32::BITS;
+    const MIN: u64 = Ranlux24Base::MIN;
+    const MAX: u64 = Ranlux24Base::MAX;
+    fn generate(&mut self) -> u64 { Ranlux24::generate(self) as u64 }
+}
+
+/// Floating point types that can be produced by [`generate_canonical`]
+pub trait RealType: Copy + PartialOrd + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> {
+    /// numeric_limits::digits
+    const DIGITS: u32;
+    fn from_u64(value: u64) -> Self;
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn ln(self) -> Self;
+    fn sqrt(self) -> Self;
+}
+
+impl RealType for f32 {
+    const DIGITS: u32 = f32::MANTISSA_DIGITS;
+    fn from_u64(value: u64) -> Self { value as f32 }
+    fn from_f64(value: f64) -> Self { value as f32 }
+    fn to_f64(self) -> f64 { self as f64 }
+    fn ln(self) -> Self { f32::ln(self) }
+    fn sqrt(self) -> Self { f32::sqrt(self) }
+}
+
+impl RealType for f64 {
+    const DIGITS: u32 = f64::MANTISSA_DIGITS;
+    fn from_u64(value: u64) -> Self { value as f64 }
+    fn from_f64(value: f64) -> Self { value }
+    fn to_f64(self) -> f64 { self }
+    fn ln(self) -> Self { f64::ln(self) }
+    fn sqrt(self) -> Self { f64::sqrt(self) }
+}
+
+// _Generate_canonical_iterations: the number of engine calls needed for at least bits bits of
+// entropy, max(1, ceil(bits / log2(range)))
+fn generate_canonical_iterations(bits: u32, min: u64, max: u64) -> u32 {
+    if bits == 0 || (min == 0 && max == u64::MAX) { return 1 }
+    let range = (max - min) + 1;
+    let target = u64::MAX >> (u64::BITS - bits);
+    let mut prod: u64 = 1;
+    let mut ceil = 0;
+    while prod <= target {
+        ceil += 1;
+        if prod > u64::MAX / range { break }
+        prod *= range;
+    }
+    ceil
+}
+
+/// std::generate_canonical, which returns a value in [0, 1] made from at least BITS random bits.
+/// Unlike libstdc++, MSVC doesn't clamp the result below 1, so rounding can produce exactly 1.
+pub fn generate_canonical<R, const BITS: usize, G>(rng: &mut G) -> R
+where R: RealType,
+      G: UniformRandomBitGenerator
+{
+    let min_bits = BITS.min(R::DIGITS as usize) as u32;
+    let gen_min = R::from_u64(G::MIN);
+    let gen_max = R::from_u64(G::MAX);
+    let range = (gen_max - gen_min) + R::from_u64(1);
+    let count = generate_canonical_iterations(min_bits, G::MIN, G::MAX);
+    let mut ans = R::from_u64(0);
+    let mut factor = R::from_u64(1);
+    for _ in 0..count {
+        ans = ans + (R::from_u64(rng.generate()) - gen_min) * factor;
+        factor = factor * range;
+    }
+    ans / factor
+}
+
+// _NRAND: a canonical value using as many bits as the result type holds
+fn nrand<R, G>(rng: &mut G) -> R
+where R: RealType,
+      G: UniformRandomBitGenerator
+{
+    generate_canonical::<R, { usize::MAX }, G>(rng)
+}
+
+/// Integer types that can be produced by [`UniformIntDistribution`]
+pub trait UniformInt: Copy + PartialOrd {
+    const BITS: u32;
+    /// The value converted to its unsigned type, then widened
+    fn to_unsigned(self) -> u64;
+    fn from_unsigned(value: u64) -> Self;
+}
+
+macro_rules! impl_uniform_int {
+    ($(($ty:ty, $uty:ty)),*) => {
+        $(
+            impl UniformInt for $ty {
+                const BITS: u32 = <$ty>::BITS;
+                fn to_unsigned(self) -> u64 { self as $uty as u64 }
+                fn from_unsigned(value: u64) -> Self { value as $uty as $ty }
+            }
+        )*
+    };
+}
+
+impl_uniform_int!((i16, u16), (u16, u16), (i32, u32), (u32, u32), (i64, u64), (u64, u64), (isize, usize), (usize, usize));
+
+/// std::uniform_int_distribution, which returns values in [a, b]
+#[repr(C)]
+#[derive(Clone)]
+pub struct UniformIntDistribution<T = i32>
+where T: UniformInt
+{
+    min: T, // _Par._Min
+    max: T // _Par._Max
+}
+
+impl<T> UniformIntDistribution<T>
+where T: UniformInt
+{
+    pub fn new(min: T, max: T) -> Self {
+        assert!(min <= max, "invalid min and max arguments for uniform_int");
+        Self { min, max }
+    }
+    pub fn a(&self) -> T { self.min }
+    pub fn b(&self) -> T { self.max }
+    pub fn min(&self) -> T { self.min }
+    pub fn max(&self) -> T { self.max }
+    /// Returns the next value, like operator(). This works in the unsigned type, using
+    /// _Rng_from_urng_v2 to pick an offset from the minimum.
+    pub fn generate<G>(&self, rng: &mut G) -> T
+    where G: UniformRandomBitGenerator
+    {
+        let mut generator = RngFromUrngV2::new(rng, T::BITS);
+        let offset = match self.index() {
+            Some(index) => generator.next(index),
+            None => generator.get_all_bits()
+        };
+        self.offset_value(offset)
+    }
+    /// Returns the next value like uniform_int_distribution from STL versions that predate
+    /// _Rng_from_urng_v2
+    pub fn generate_legacy<G>(&self, rng: &mut G) -> T
+    where G: UniformRandomBitGenerator
+    {
+        let mut generator = RngFromUrng::new(rng, T::BITS);
+        let offset = match self.index() {
+            Some(index) => generator.next(index),
+            None => generator.get_all_bits()
+        };
+        self.offset_value(offset)
+    }
+    // The number of values in the range, or None if it covers the whole unsigned type
+    fn index(&self) -> Option<u64> {
+        let mask = u64::MAX >> (u64::BITS - T::BITS);
+        let diff = self.max.to_unsigned().wrapping_sub(self.min.to_unsigned()) & mask;
+        if diff == mask { None } else { Some(diff + 1) }
+    }
+    fn offset_value(&self, offset: u64) -> T {
+        let mask = u64::MAX >> (u64::BITS - T::BITS);
+        T::from_unsigned(offset.wrapping_add(self.min.to_unsigned()) & mask)
+    }
+}
+
+/// std::uniform_real_distribution, which returns values in [a, b)
+#[repr(C)]
+#[derive(Clone)]
+pub struct UniformRealDistribution<T = f64>
+where T: RealType
+{
+    min: T, // _Par._Min
+    max: T // _Par._Max
+}
+
+impl<T> UniformRealDistribution<T>
+where T: RealType
+{
+    pub fn new(min: T, max: T) -> Self {
+        assert!(min <= max, "invalid min and max arguments for uniform_real");
+        Self { min, max }
+    }
+    pub fn a(&self) -> T { self.min }
+    pub fn b(&self) -> T { self.max }
+    pub fn min(&self) -> T { self.min }
+    pub fn max(&self) -> T { self.max }
+    /// Returns the next value, like operator()
+    pub fn generate<G>(&self, rng: &mut G) -> T
+    where G: UniformRandomBitGenerator
+    {
+        nrand::<T, G>(rng) * (self.max - self.min) + self.min
+    }
+}
+
+/// std::bernoulli_distribution, which returns true with probability p
+#[repr(C)]
+#[derive(Clone)]
+pub struct BernoulliDistribution {
+    p: f64 // _Par._Px
+}
+
+impl BernoulliDistribution {
+    pub fn new(p: f64) -> Self {
+        assert!((0.0..=1.0).contains(&p), "invalid probability argument for bernoulli_distribution");
+        Self { p }
+    }
+    pub fn p(&self) -> f64 { self.p }
+    /// Returns the next value, like operator()
+    pub fn generate<G>(&self, rng: &mut G) -> bool
+    where G: UniformRandomBitGenerator
+    {
+        nrand::<f64, G>(rng) < self.p
+    }
+}
+
+/// std::normal_distribution. MSVC uses the polar method, which makes two values at a time and
+/// keeps the second for the next call.
+///
+/// The engine calls and arithmetic match MSVC, but the logarithm and square root come from the
+/// Rust standard library. The square root is correctly rounded everywhere, while the logarithm
+/// may differ from the MSVC CRT's log and logf in the last bit for some inputs, so results aren't
+/// guaranteed to be bit-exact.
+#[repr(C)]
+#[derive(Clone)]
+pub struct NormalDistribution<T = f64>
+where T: RealType
+{
+    mean: T, // _Par._Mean
+    sigma: T, // _Par._Sigma
+    valid: bool, // _Valid
+    x2: T // _X2
+}
+
+impl<T> NormalDistribution<T>
+where T: RealType
+{
+    pub fn new(mean: T, sigma: T) -> Self {
+        assert!(T::from_u64(0) < sigma, "invalid sigma argument for normal_distribution");
+        Self { mean, sigma, valid: false, x2: T::from_u64(0) }
+    }
+    pub fn mean(&self) -> T { self.mean }
+    pub fn stddev(&self) -> T { self.sigma }
+    /// Forgets the saved value, so the next call uses the engine
+    pub fn reset(&mut self) { self.valid = false; }
+    /// Returns the next value, like operator()
+    pub fn generate<G>(&mut self, rng: &mut G) -> T
+    where G: UniformRandomBitGenerator
+    {
+        let res = if self.valid {
+            self.valid = false;
+            self.x2
+        } else {
+            let (zero, one, two) = (T::from_u64(0), T::from_u64(1), T::from_u64(2));
+            // reject points outside the unit circle, working in T like MSVC
+            let (v1, v2, sx) = loop {
+                let v1 = two * nrand::<T, G>(rng) - one;
+                let v2 = two * nrand::<T, G>(rng) - one;
+                let sx = v1 * v1 + v2 * v2;
+                if sx < one && v1 != zero && v2 != zero { break (v1, v2, sx) }
+            };
+            let fx = (T::from_f64(-2.0) * sx.ln() / sx).sqrt();
+            self.x2 = fx * v2;
+            self.valid = true;
+            fx * v1
+        };
+        res * self.sigma + self.mean
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::msvc::algorithm::UniformRandomBitGenerator;
+    use super::{
+        generate_canonical, BernoulliDistribution, MinstdRand, Mt19937, Mt19937_64, NormalDistribution,
+        Ranlux24, Ranlux24Base, UniformIntDistribution, UniformRealDistribution
+    };
+    use std::error::Error;
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    #[test]
+    pub fn random_layout() -> TestReturn {
+        assert!(std::mem::size_of::<Mt19937>() == 5000, "mt19937 should be the index, 1248 values and the mask");
+        assert!(std::mem::offset_of!(Mt19937, ax) == 4, "mt19937 values should follow the 4 byte index");
+        assert!(std::mem::size_of::<Mt19937_64>() == 5008, "mt19937_64 should be the index, 624 values and the mask");
+        assert!(std::mem::offset_of!(Mt19937_64, ax) == 8, "mt19937_64 values should be aligned after the index");
+        assert!(std::mem::size_of::<MinstdRand>() == 4, "minstd_rand should only be the previous value");
+        assert!(std::mem::size_of::<Ranlux24Base>() == 200, "ranlux24_base should be the index, 48 values and the carry");
+        assert!(std::mem::size_of::<Ranlux24>() == 204, "ranlux24 should be the base engine and a count");
+        assert!(std::mem::size_of::<UniformIntDistribution<i32>>() == 8, "uniform_int_distribution should be its bounds");
+        assert!(std::mem::size_of::<NormalDistribution<f64>>() == 32, "normal_distribution should have the saved value");
+        Ok(())
+    }
+
+    // Engine outputs are fixed by the standard, and were checked against g++
+    #[test]
+    pub fn engine_sequences() -> TestReturn {
+        let mut mt = Mt19937::new();
+        assert!(mt.idx == 624, "Seeding should leave the index at the end of the lower half");
+        let first = [mt.generate(), mt.generate(), mt.generate()];
+        assert!(first == [3499211612, 581869302, 3890346734] && mt.idx == 627, "mt19937 should match C++, got {:?}", first);
+        mt.discard(9996);
+        assert!(mt.generate() == 4123659995, "10000th mt19937 value should match C++");
+        assert!(Mt19937::with_seed(1234).generate() == 822569775, "Seeded mt19937 should match C++");
+
+        let mut mt64 = Mt19937_64::new();
+        assert!(mt64.generate() == 14514284786278117030, "mt19937_64 should match C++");
+        mt64.discard(9998);
+        assert!(mt64.generate() == 9981545732273789042, "10000th mt19937_64 value should match C++");
+
+        let mut minstd = MinstdRand::new();
+        assert!(minstd.generate() == 48271, "minstd_rand should match C++");
+        minstd.discard(9998);
+        assert!(minstd.generate() == 399268537, "10000th minstd_rand value should match C++");
+
+        let mut base = Ranlux24Base::new();
+        assert!(base.generate() == 15039276, "ranlux24_base should match C++");
+        base.discard(9998);
+        assert!(base.generate() == 7937952, "10000th ranlux24_base value should match C++");
+
+        let mut ranlux = Ranlux24::new();
+        assert!(ranlux.generate() == 15039276, "ranlux24 should match C++");
+        ranlux.discard(9998);
+        assert!(ranlux.generate() == 9901578, "10000th ranlux24 value should match C++");
+        Ok(())
+    }
+
+    #[test]
+    pub fn engine_state_copy() -> TestReturn {
+        let mut mt = Mt19937::with_seed(99);
+        mt.discard(1000);
+        // continuing from a copy of the raw state gives the same values
+        let mut copy: Mt19937 = unsafe { std::ptr::read(&mt) };
+        let original: Vec<u32> = (0..1500).map(|_| mt.generate()).collect();
+        let copied: Vec<u32> = (0..1500).map(|_| copy.generate()).collect();
+        assert!(original == copied, "Copied engine should continue the sequence");
+        assert!(<Mt19937 as UniformRandomBitGenerator>::MAX == u32::MAX as u64, "mt19937 should cover 32 bits");
+        Ok(())
+    }
+
+    // Expected values are from MSVC's distributions transcribed into C++, using the standard
+    // engines from g++. They only show that the port agrees with the transcription, and haven't
+    // been checked against a build made with MSVC.
+    #[test]
+    pub fn uniform_int_matches_msvc() -> TestReturn {
+        let mut mt = Mt19937::with_seed(42);
+        let dist = UniformIntDistribution::new(-5, 5);
+        let values: Vec<i32> = (0..12).map(|_| dist.generate(&mut mt)).collect();
+        assert!(values == [-1, 3, 5, -3, 3, 3, 1, 1, -4, -1, -4, -4], "uniform_int should match MSVC, got {:?}", values);
+        let mut mt = Mt19937::with_seed(42);
+        let values: Vec<i32> = (0..12).map(|_| dist.generate_legacy(&mut mt)).collect();
+        assert!(values == [1, 2, 4, -2, -3, 5, -2, -5, 3, -3, -4, 5], "Legacy uniform_int should match MSVC, got {:?}", values);
+
+        // minstd_rand only has 30 full bits per call
+        let mut minstd = MinstdRand::with_seed(7);
+        let dice = UniformIntDistribution::new(1, 6);
+        let values: Vec<i32> = (0..12).map(|_| dice.generate(&mut minstd)).collect();
+        assert!(values == [1, 3, 3, 4, 3, 1, 2, 2, 2, 1, 4, 4], "Dice rolls should match MSVC, got {:?}", values);
+        let mut minstd = MinstdRand::with_seed(7);
+        let values: Vec<i32> = (0..12).map(|_| dice.generate_legacy(&mut minstd)).collect();
+        assert!(values == [1, 2, 1, 4, 6, 5, 5, 5, 6, 4, 2, 3], "Legacy dice rolls should match MSVC, got {:?}", values);
+
+        let mut mt = Mt19937::with_seed(42);
+        let full = UniformIntDistribution::new(0, u64::MAX);
+        let values: Vec<u64> = (0..3).map(|_| full.generate(&mut mt)).collect();
+        assert!(values == [6909045637428952499, 17537583593393853710, 13502904847239337031], "Full range should match MSVC, got {:?}", values);
+
+        // ranges wider than one engine call fall back to the legacy loop
+        let mut ranlux = Ranlux24::new();
+        let wide = UniformIntDistribution::new(-1000000000000i64, 1000000000000);
+        let values: Vec<i64> = (0..4).map(|_| wide.generate(&mut ranlux)).collect();
+        assert!(values == [-682801740585, 637137004949, 142352444362, -489254467852], "Wide range should match MSVC, got {:?}", values);
+        Ok(())
+    }
+
+    #[test]
+    pub fn canonical_matches_msvc() -> TestReturn {
+        let mut minstd = MinstdRand::new();
+        let values: Vec<u64> = (0..3).map(|_| generate_canonical::<f64, 53, _>(&mut minstd).to_bits()).collect();
+        assert!(values == [0x3fb5c4afc25715b1, 0x3fec88145f989ce2, 0x3fc847c12358eaf6], "Canonical double should match MSVC, got {:x?}", values);
+
+        let mut ranlux = Ranlux24::new();
+        let values: Vec<u32> = (0..3).map(|_| generate_canonical::<f32, 24, _>(&mut ranlux).to_bits()).collect();
+        assert!(values == [0x3f657b2c, 0x3f791555, 0x3f59f2de], "Canonical float should match MSVC, got {:x?}", values);
+        assert!(ranlux.generate() == 7150092, "Canonical float should make one engine call each");
+
+        let mut mt64 = Mt19937_64::new();
+        let values: Vec<u64> = (0..3).map(|_| generate_canonical::<f64, 64, _>(&mut mt64).to_bits()).collect();
+        assert!(values == [0x3fe92da3239eded6, 0x3fd007deb1e2f204, 0x3fe6bdd196d57c8b], "Canonical from 64 bits should match MSVC, got {:x?}", values);
+        Ok(())
+    }
+
+    #[test]
+    pub fn real_distributions_match_msvc() -> TestReturn {
+        let mut mt = Mt19937::with_seed(42);
+        let dist = UniformRealDistribution::new(0.0f64, 10.0);
+        let values: Vec<u64> = (0..5).map(|_| dist.generate(&mut mt).to_bits()).collect();
+        assert!(values == [0x401fdc99a406fb4b, 0x3ffd597d29581d42, 0x401f30092b35479f, 0x4017dfbee337f244, 0x4011d553d2ee3da2],
+            "uniform_real should match MSVC, got {:x?}", values);
+
+        let mut mt = Mt19937::with_seed(42);
+        let coin = BernoulliDistribution::new(0.3);
+        let values: Vec<u8> = (0..20).map(|_| coin.generate(&mut mt) as u8).collect();
+        assert!(values == [0, 1, 0, 0, 0, 1, 0, 0, 1, 0, 1, 0, 0, 1, 0, 0, 0, 1, 1, 0], "bernoulli should match MSVC, got {:?}", values);
+
+        // the logarithm isn't the MSVC CRT's, so these only hold where both round the same way
+        let mut mt = Mt19937::with_seed(42);
+        let mut normal = NormalDistribution::new(0.0f64, 1.0);
+        let values: Vec<u64> = (0..5).map(|_| normal.generate(&mut mt).to_bits()).collect();
+        assert!(values == [0x3fe07e6d7e33381a, 0xbfe19b855ed55900, 0x3ff5e52bf4920101, 0x3fde53bc7b7c395c, 0xbfbfc81bf85cd950],
+            "normal should match MSVC, got {:x?}", values);
+        assert!(normal.valid && mt.generate() == 249467210, "normal should keep the second value of a pair");
+
+        let mut mt = Mt19937::with_seed(42);
+        let mut normal = NormalDistribution::new(10.0f32, 2.0);
+        let values: Vec<u32> = (0..4).map(|_| normal.generate(&mut mt).to_bits()).collect();
+        assert!(values == [0x410f7508, 0x414719fb, 0x41371528, 0x413bd40f], "Float normal should match MSVC, got {:x?}", values);
+        Ok(())
+    }
+}
@@ -48,10 +48,12 @@ where A: Allocator + Clone
         unsafe { Layout::from_size_align_unchecked(size, align) }
     }
 
+    #[allow(clippy::missing_safety_doc)]
     pub unsafe fn get_data_ptr(&self) -> *const T {
         (&raw const *self).add(1) as *const T
     }
 
+    #[allow(clippy::missing_safety_doc)]
     pub unsafe fn get_data_ptr_mut(&mut self) -> *mut T {
         (&raw mut *self).add(1) as *mut T
     }
@@ -403,6 +405,7 @@ pub mod tests {
         Ok(())
     }
 
+    #[allow(clippy::partialeq_to_none)]
     #[test]
     fn weak_pointers() -> TestReturn {
         let weak_ptr;
@@ -2,7 +2,13 @@
 
 #![allow(dead_code, unused_imports)]
 use allocator_api2::alloc::{ Allocator, Global };
-use crate::generic::string::CharBehavior;
+use crate::{
+    generic::{
+        lookup::{ KeyBorrow, ToKey },
+        string::CharBehavior
+    },
+    msvc::hash::{ fnv1a_append_bytes, CppHash, FNV_OFFSET_BASIS }
+};
 use std::{
     alloc::Layout,
     cmp::Ordering,
@@ -51,7 +57,9 @@ where T: CharBehavior  + PartialEq,
 }
 
 impl String<u8, Global> {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self { Self::new_using(Global) }
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(text: &str) -> Self { Self::from_str_in(text, Global) }
 }
 
@@ -157,6 +165,11 @@ where T: CharBehavior  + PartialEq,
         }
     }
 
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.get_ptr(), self.size) }
+    }
+
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize { self.size }
 
     pub fn capacity(&self) -> usize { self.capacity }
@@ -217,6 +230,18 @@ where A: Allocator + Clone
         new
     }
 
+    pub fn from_slice_in_wide(text: &[u16], alloc: A) -> Self {
+        let mut new = Self::new_in(alloc);
+        // +1 to account for null terminator
+        let full_len = text.len() + 1;
+        let c0 = (MAX_STORAGE_SIZE / size_of::<u16>()) - 1;
+        let new_cap = ((full_len + c0) & !c0) - 1;
+        new.resize(new_cap);
+        unsafe { std::ptr::copy_nonoverlapping(text.as_ptr(), new.get_ptr_mut(), text.len()); }
+        new.size = text.len();
+        new
+    }
+
     pub fn push_str(&mut self, str: &str) {
         // +1 to account for null terminator
         let new_len = self.len() + str.len() + 1;
@@ -256,14 +281,35 @@ where T: CharBehavior  + PartialEq,
 }
 
 impl<T, A> PartialOrd for String<T, A>
-where T: CharBehavior  + PartialEq,
+where T: CharBehavior  + PartialOrd,
       A: Allocator + Clone
 {
+    // char_traits::compare compares characters rather than bytes (wmemcmp for wide strings)
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.as_bytes().partial_cmp(other.as_bytes())
+        self.as_slice().partial_cmp(other.as_slice())
     }
 }
 
+impl<A> KeyBorrow<str> for String<u8, A>
+where A: Allocator + Clone
+{
+    fn key_borrow(&self) -> &str { self.into() }
+}
+
+impl<A> KeyBorrow<[u16]> for String<u16, A>
+where A: Allocator + Clone
+{
+    fn key_borrow(&self) -> &[u16] { self.as_slice() }
+}
+
+impl ToKey<String<u8, Global>> for str {
+    fn to_key(&self) -> String<u8, Global> { String::from_str(self) }
+}
+
+impl ToKey<String<u16, Global>> for [u16] {
+    fn to_key(&self) -> String<u16, Global> { String::from_slice_in_wide(self, Global) }
+}
+
 impl<A> From<&String<u8, A>> for &str
 where A: Allocator + Clone
 {
@@ -343,6 +389,14 @@ where T: CharBehavior  + PartialEq,
     }
 }
 
+impl<T, A> CppHash for String<T, A>
+where T: CharBehavior  + PartialEq,
+      A: Allocator + Clone
+{
+    // _Hash_array_representation
+    fn cpp_hash(&self) -> u64 { fnv1a_append_bytes(FNV_OFFSET_BASIS, self.as_bytes()) }
+}
+
 impl<T, A> Clone for String<T, A>
 where T: CharBehavior  + PartialEq,
       A: Allocator + Clone
@@ -372,6 +426,7 @@ where T: CharBehavior  + PartialEq,
     }
 }
 
+#[allow(clippy::items_after_test_module)]
 #[cfg(test)]
 pub mod tests {
     use allocator_api2::alloc::Global;
@@ -494,7 +549,9 @@ where T: CharBehavior  + PartialEq,
 }
 
 impl StringView<u8, Global> {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self { Self::new_using(Global) }
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(text: &str) -> Self { Self::from_str_in(text, Global) }
 }
 
@@ -615,6 +672,14 @@ where T: CharBehavior + PartialEq,
     }
 }
 
+impl<T, A> CppHash for StringView<T, A>
+where T: CharBehavior + PartialEq,
+      A: Allocator + Clone
+{
+    // _Hash_array_representation
+    fn cpp_hash(&self) -> u64 { fnv1a_append_bytes(FNV_OFFSET_BASIS, self.as_bytes()) }
+}
+
 impl<A> From<&StringView<u8, A>> for &str
 where A: Allocator + Clone
 {
@@ -1,9 +1,14 @@
 #![allow(dead_code, unused_imports)]
 use allocator_api2::alloc::{ Allocator, Global };
+pub use crate::generic::{
+    lookup::{ KeyBorrow, KeyOf, ToKey },
+    tree::{ CompareGreater, CompareLess, MapPair, TreeCompare }
+};
 use std::{
     alloc::Layout,
     fmt::{ Display, Debug },
     hash::{ Hash, Hasher },
+    iter::Peekable,
     mem::{ align_of, size_of },
     marker::{ PhantomData, PhantomPinned }
 };
@@ -13,19 +18,40 @@ use std::{
 
 #[repr(C)]
 pub struct Tree<C, T0, T1, A = Global>
-where C: TreeCompare<T0, T1>,
-      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
-      // T1: PartialEq + PartialEq<T0> + PartialOrd + PartialOrd<T0>,
+where C: TreeCompare<T1, T1>,
+      T0: KeyOf<T1>,
       T1: PartialEq + PartialOrd,
       A: Allocator + Clone
 {
-    head: *mut TreeNode<T0, A>,
-    size: usize,
+    // _Compressed_pair<key_compare, _Compressed_pair<_Alnode, _Scary_val>>: the comparator comes
+    // first, and takes no space when it's empty
+    compare: C, // key_compare
+    head: *mut TreeNode<T0, A>, // _Myhead
+    size: usize, // _Mysize
     _allocator: A,
-    _comparison: PhantomData<C>,
     _key_ty: PhantomData<T1>
 }
 
+/// Why sorted input couldn't be built into a tree, with the index of the offending element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FromSortedError {
+    /// The element is ordered before the one preceding it
+    Unsorted(usize),
+    /// The element is equivalent to the one preceding it
+    Duplicate(usize)
+}
+
+impl Display for FromSortedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unsorted(i) => write!(f, "element {} is ordered before the previous element", i),
+            Self::Duplicate(i) => write!(f, "element {} is equivalent to the previous element", i)
+        }
+    }
+}
+
+impl std::error::Error for FromSortedError {}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum NodeColor {
@@ -33,6 +59,7 @@ pub enum NodeColor {
     Black
 }
 
+// _Tree_child
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum NodeDirection {
@@ -42,97 +69,370 @@ pub enum NodeDirection {
 
 #[repr(C)]
 pub struct TreeNode<T, A = Global>
-where T: PartialEq + PartialOrd,
-      A: Allocator + Clone
+where A: Allocator + Clone
 {
-    left: *mut TreeNode<T, A>,
-    parent: *mut TreeNode<T, A>,
-    right: *mut TreeNode<T, A>,
-    color: NodeColor,
-    nil: bool,
-    data: T,
+    left: *mut TreeNode<T, A>, // _Left
+    parent: *mut TreeNode<T, A>, // _Parent
+    right: *mut TreeNode<T, A>, // _Right
+    color: NodeColor, // _Color
+    nil: bool, // _Isnil
+    data: T, // _Myval
     _allocator: A
 }
 
-pub trait TreeCompare<A, B>
-where A: PartialEq + PartialEq<B> + PartialOrd + PartialOrd<B>,
-      // B: PartialEq<A> + PartialOrd<A>
-{
-    fn compare_aa(d0: &A, d1: &A) -> bool;
-    fn compare_ab(d0: &A, d1: &B) -> bool;
-    // fn compare_ba(d0: &B, d1: &A) -> bool;
-}
-
-pub struct CompareLess; // std::less
-impl<A, B> TreeCompare<A, B> for CompareLess
-where A: PartialEq + PartialEq<B> + PartialOrd + PartialOrd<B>,
-      // B: PartialEq<A> + PartialOrd<A>
-{
-    fn compare_aa(d0: &A, d1: &A) -> bool { d0 < d1 }
-    fn compare_ab(d0: &A, d1: &B) -> bool { d0 < d1 }
-    // fn compare_ba(d0: &B, d1: &A) -> bool { d0 < d1 }
-}
-
-pub struct CompareGreater; // std::greater
-impl<A, B> TreeCompare<A, B> for CompareGreater
-where A: PartialEq + PartialEq<B> + PartialOrd + PartialOrd<B>,
-      // B: PartialEq<A> + PartialOrd<A>
-{
-    fn compare_aa(d0: &A, d1: &A) -> bool { d0 > d1 }
-    fn compare_ab(d0: &A, d1: &B) -> bool { d0 > d1 }
-    // fn compare_ba(d0: &B, d1: &A) -> bool { d0 > d1 }
-}
-
 impl<C, T0, T1, A> Tree<C, T0, T1, A>
-where C: TreeCompare<T0, T1>,
-      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
-      // T1: PartialEq + PartialEq<T0> + PartialOrd + PartialOrd<T0>, 
+where C: TreeCompare<T1, T1>,
+      T0: KeyOf<T1>,
       T1: PartialEq + PartialOrd,
       A: Allocator + Clone
 {
-    pub fn new_inner(alloc: A) -> Self {
+    pub fn new_inner(alloc: A) -> Self
+    where C: Default
+    {
+        Self::new_inner_with_compare(C::default(), alloc)
+    }
+    pub fn new_inner_with_compare(compare: C, alloc: A) -> Self {
+        assert!(std::mem::size_of::<A>() == 0, "Allocator must be zero-sized!");
         let head = unsafe { TreeNode::new_head(alloc.clone()) };
-        Self { head, size: 0, _allocator: alloc, _comparison: PhantomData, _key_ty: PhantomData }
+        Self { compare, head, size: 0, _allocator: alloc, _key_ty: PhantomData }
     }
+    /// Builds a tree in linear time from values in strictly increasing order. Fails without
+    /// allocating any nodes if the values are out of order or contain duplicates.
+    pub fn from_sorted_vec_with_compare(values: Vec<T0>, compare: C, alloc: A) -> Result<Self, FromSortedError> {
+        for (i, w) in values.windows(2).enumerate() {
+            if !compare.compare_aa(w[0].key_of(), w[1].key_of()) {
+                return Err(match compare.compare_aa(w[1].key_of(), w[0].key_of()) {
+                    true => FromSortedError::Unsorted(i + 1),
+                    false => FromSortedError::Duplicate(i + 1)
+                })
+            }
+        }
+        let mut tree = Self::new_inner_with_compare(compare, alloc);
+        let nodes: Vec<*mut TreeNode<T0, A>> = values.into_iter().map(|v| unsafe { tree.new_node(v) }).collect();
+        if nodes.is_empty() { return Ok(tree) }
+        let full_levels = (nodes.len() + 1).ilog2() as usize;
+        unsafe {
+            let root = tree.build_sorted(&nodes, 0, full_levels);
+            (*root).parent = tree.head;
+            (*tree.head).parent = root;
+            (*tree.head).left = nodes[0];
+            (*tree.head).right = nodes[nodes.len() - 1];
+        }
+        tree.size = nodes.len();
+        Ok(tree)
+    }
+
+    // Links the nodes into a subtree where the sizes of each node's children differ by at most
+    // one, so every leaf is on the last full level or the one below it. Nodes on that partial
+    // level are red and the rest are black, which keeps the black height equal on every path.
+    unsafe fn build_sorted(&self, nodes: &[*mut TreeNode<T0, A>], depth: usize, full_levels: usize) -> *mut TreeNode<T0, A> {
+        if nodes.is_empty() { return self.head }
+        let mid = nodes.len() / 2;
+        let node = nodes[mid];
+        let left = self.build_sorted(&nodes[..mid], depth + 1, full_levels);
+        let right = self.build_sorted(&nodes[mid + 1..], depth + 1, full_levels);
+        (*node).left = left;
+        (*node).right = right;
+        if !(*left).nil { (*left).parent = node; }
+        if !(*right).nil { (*right).parent = node; }
+        (*node).color = if depth < full_levels { NodeColor::Black } else { NodeColor::Red };
+        node
+    }
+
+    /// Returns the comparison function object, like key_comp()
+    pub fn key_comp(&self) -> &C { &self.compare }
     pub fn len(&self) -> usize { self.size }
     pub fn is_empty(&self) -> bool { self.size == 0 }
 
     unsafe fn new_node(&self, value: T0) -> *mut TreeNode<T0, A> {
-        let node = TreeNode::new_node(self._allocator.clone(), self.head); 
-        std::ptr::write((&raw mut (&mut *node).data), value);
-        node
+        TreeNode::new_node(value, self._allocator.clone(), self.head)
     }
-    // SAFETY: self.head always points to the leaf/sentinel node
+    // SAFETY: self.head always points to the leaf/sentinel node. Its value is uninitialized, so
+    // only the link fields can be read through this.
     fn get_head(&self) -> &TreeNode<T0, A> { unsafe { &*self.head } }
-    fn get_head_mut(&mut self) -> &mut TreeNode<T0, A> { unsafe { &mut *self.head } }
-
-    fn get_root(&self) -> Option<&TreeNode<T0, A>> { self.get_head().get_parent() }
-    fn get_root_mut(&mut self) -> Option<&mut TreeNode<T0, A>> { self.get_head_mut().get_parent_mut() }
-    // SAFETY: At least one element must be added to the tree or these methods will panic
-    unsafe fn get_root_unchecked(&self) -> &TreeNode<T0, A> { self.get_head().get_parent().unwrap() }
-    unsafe fn get_root_unchecked_mut(&mut self) -> &mut TreeNode<T0, A> { self.get_head_mut().get_parent_mut().unwrap() }
-    unsafe fn get_root_ptr(&self) -> *const TreeNode<T0, A> { self.get_head().parent }
-    unsafe fn get_root_ptr_mut(&self) -> *mut TreeNode<T0, A> { self.get_head().parent }
-
-    unsafe fn make_initial_insertion(&self, node: &mut TreeNode<T0, A>) -> Option<(&mut TreeNode<T0, A>, NodeDirection)> {
-        let mut curr_node: *mut TreeNode<T0, A> = (&mut *self.head).parent;
-        loop {
+
+    unsafe fn key<'b>(node: *mut TreeNode<T0, A>) -> &'b T1 where T0: 'b { (*node).data.key_of() }
+
+    pub fn first(&self) -> Option<&T0> {
+        if self.is_empty() { return None }
+        Some(unsafe { &(*(*self.head).left).data })
+    }
+    pub fn last(&self) -> Option<&T0> {
+        if self.is_empty() { return None }
+        Some(unsafe { &(*(*self.head).right).data })
+    }
+
+    // _Find_lower_bound: returns where a node for key would be inserted, along with the first
+    // node which isn't ordered before key (the head node if there isn't one)
+    unsafe fn find_lower_bound<Q>(&self, key: &Q) -> (*mut TreeNode<T0, A>, NodeDirection, *mut TreeNode<T0, A>)
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        let mut parent = (*self.head).parent;
+        let mut child = NodeDirection::Right;
+        let mut bound = self.head;
+        let mut try_node = parent;
+        while !(*try_node).nil {
+            parent = try_node;
+            if self.compare.compare_aa(Self::key(try_node).key_borrow(), key) {
+                child = NodeDirection::Right;
+                try_node = (*try_node).right;
+            } else {
+                child = NodeDirection::Left;
+                bound = try_node;
+                try_node = (*try_node).left;
+            }
+        }
+        (parent, child, bound)
+    }
+
+    // _Find_upper_bound: returns where a node for key would be inserted after any equivalent
+    // nodes, along with the first node which is ordered after key
+    unsafe fn find_upper_bound<Q>(&self, key: &Q) -> (*mut TreeNode<T0, A>, NodeDirection, *mut TreeNode<T0, A>)
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        let mut parent = (*self.head).parent;
+        let mut child = NodeDirection::Right;
+        let mut bound = self.head;
+        let mut try_node = parent;
+        while !(*try_node).nil {
+            parent = try_node;
+            if self.compare.compare_aa(key, Self::key(try_node).key_borrow()) {
+                child = NodeDirection::Left;
+                bound = try_node;
+                try_node = (*try_node).left;
+            } else {
+                child = NodeDirection::Right;
+                try_node = (*try_node).right;
+            }
+        }
+        (parent, child, bound)
+    }
+
+    // _Lower_bound_duplicate
+    unsafe fn lower_bound_duplicate<Q>(&self, bound: *mut TreeNode<T0, A>, key: &Q) -> bool
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        !(*bound).nil && !self.compare.compare_aa(key, Self::key(bound).key_borrow())
+    }
+
+    fn find_node<Q>(&self, key: &Q) -> Option<*mut TreeNode<T0, A>>
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        unsafe {
+            let (_, _, bound) = self.find_lower_bound(key);
+            match self.lower_bound_duplicate(bound, key) {
+                true => Some(bound),
+                false => None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, value: T0) -> bool {
+        unsafe {
+            let (parent, child, bound) = self.find_lower_bound::<T1>(value.key_of());
             // Duplicate entries are not allowed
-            let node_ref = &mut *curr_node;
-            if node.data == node_ref.data { return None; } 
-            let dir = if C::compare_aa(&node.data, &node_ref.data) { NodeDirection::Left } else { NodeDirection::Right };
-            let next = match dir {
-                NodeDirection::Left => node_ref.get_left_mut(),
-                NodeDirection::Right => node_ref.get_right_mut()
-            };
-            curr_node = match next {
-                Some(v) => v,
-                None => {
-                    node.parent = curr_node;
-                    return Some((node_ref, dir))
+            if self.lower_bound_duplicate::<T1>(bound, value.key_of()) { return false }
+            let node = self.new_node(value);
+            self.insert_node(parent, child, node);
+        }
+        true
+    }
+
+    /// Inserts a value even if there are elements with an equivalent key, placing it after them
+    pub fn insert_multi(&mut self, value: T0) -> &mut T0 {
+        unsafe {
+            let (parent, child, _) = self.find_upper_bound::<T1>(value.key_of());
+            let node = self.new_node(value);
+            &mut (*self.insert_node(parent, child, node)).data
+        }
+    }
+
+    // _Eqrange: the first node equivalent to key, and the first node ordered after it
+    unsafe fn equal_range_nodes<Q>(&self, key: &Q) -> (*mut TreeNode<T0, A>, *mut TreeNode<T0, A>)
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        let (_, _, lower) = self.find_lower_bound(key);
+        let (_, _, upper) = self.find_upper_bound(key);
+        (lower, upper)
+    }
+
+    /// Iterates over every element with a key equivalent to key
+    pub fn equal_range<Q>(&self, key: &Q) -> TreeIterator<'_, T0, A>
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        let (first, end) = unsafe { self.equal_range_nodes(key) };
+        TreeIterator {
+            front: first,
+            back: unsafe { TreeNode::decrement(end) },
+            done: std::ptr::eq(first, end),
+            _marker: PhantomData
+        }
+    }
+
+    /// Returns the number of elements with a key equivalent to key
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self.equal_range(key).count()
+    }
+
+    /// Removes every element with a key equivalent to key, returning how many were removed
+    pub fn remove_all<Q>(&mut self, key: &Q) -> usize
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        let (mut curr, end) = unsafe { self.equal_range_nodes(key) };
+        let mut count = 0;
+        while !std::ptr::eq(curr, end) {
+            let next = unsafe { TreeNode::increment(curr) };
+            drop(unsafe { self.erase_node(curr) });
+            curr = next;
+            count += 1;
+        }
+        count
+    }
+
+    // Unlinks the node, leaving it owned by a node handle
+    unsafe fn extract_handle(&mut self, node: *mut TreeNode<T0, A>) -> NodeHandle<T0, A> {
+        self.extract(node);
+        NodeHandle { node, _allocator: self._allocator.clone() }
+    }
+
+    /// Unlinks the element with the given key, returning a handle that owns its node
+    pub fn extract_key<Q>(&mut self, key: &Q) -> Option<NodeHandle<T0, A>>
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self.find_node(key).map(|n| unsafe { self.extract_handle(n) })
+    }
+
+    /// Links the handle's node into the tree without reallocating it. If there's already an
+    /// element with an equivalent key, the handle is given back.
+    pub fn insert_handle(&mut self, handle: NodeHandle<T0, A>) -> Result<&mut T0, NodeHandle<T0, A>> {
+        unsafe {
+            let key = (*handle.node).data.key_of();
+            let (parent, child, bound) = self.find_lower_bound::<T1>(key);
+            if self.lower_bound_duplicate::<T1>(bound, key) { return Err(handle) }
+            Ok(&mut (*self.link_handle(parent, child, handle)).data)
+        }
+    }
+
+    /// Links the handle's node into the tree after any elements with an equivalent key
+    pub fn insert_handle_multi(&mut self, handle: NodeHandle<T0, A>) -> &mut T0 {
+        unsafe {
+            let (parent, child, _) = self.find_upper_bound::<T1>((*handle.node).data.key_of());
+            &mut (*self.link_handle(parent, child, handle)).data
+        }
+    }
+
+    unsafe fn link_handle(&mut self, parent: *mut TreeNode<T0, A>, child: NodeDirection, handle: NodeHandle<T0, A>) -> *mut TreeNode<T0, A> {
+        let node = handle.into_raw();
+        TreeNode::reset_links(node, self.head);
+        self.insert_node(parent, child, node)
+    }
+
+    /// Moves every node from other whose key isn't already in this tree, without reallocating.
+    /// Nodes with duplicate keys are left in other.
+    pub fn merge<C2>(&mut self, other: &mut Tree<C2, T0, T1, A>)
+    where C2: TreeCompare<T1, T1>
+    {
+        unsafe {
+            let mut curr = (*other.head).left;
+            while !(*curr).nil {
+                let node = curr;
+                curr = TreeNode::increment(curr);
+                let (parent, child, bound) = self.find_lower_bound::<T1>((*node).data.key_of());
+                if self.lower_bound_duplicate::<T1>(bound, (*node).data.key_of()) { continue }
+                let handle = other.extract_handle(node);
+                self.link_handle(parent, child, handle);
+            }
+        }
+    }
+
+    // _Insert_node: links new_node as a child of parent, then rebalances the tree
+    unsafe fn insert_node(&mut self, parent: *mut TreeNode<T0, A>, child: NodeDirection, new_node: *mut TreeNode<T0, A>) -> *mut TreeNode<T0, A> {
+        self.size += 1;
+        let head = self.head;
+        (*new_node).parent = parent;
+        if parent == head {
+            // first node in tree, just set head values
+            (*head).left = new_node;
+            (*head).parent = new_node;
+            (*head).right = new_node;
+            (*new_node).color = NodeColor::Black;
+            return new_node;
+        }
+        match child {
+            NodeDirection::Right => {
+                (*parent).right = new_node;
+                if parent == (*head).right { (*head).right = new_node; }
+            },
+            NodeDirection::Left => {
+                (*parent).left = new_node;
+                if parent == (*head).left { (*head).left = new_node; }
+            }
+        };
+        let mut node = new_node;
+        while (*(*node).parent).color == NodeColor::Red {
+            let parent = (*node).parent;
+            let grandparent = (*parent).parent;
+            if parent == (*grandparent).left {
+                // fixup red-red in left subtree
+                let sibling = (*grandparent).right;
+                if (*sibling).color == NodeColor::Red {
+                    // parent's sibling has two red children, blacken both
+                    (*parent).color = NodeColor::Black;
+                    (*sibling).color = NodeColor::Black;
+                    (*grandparent).color = NodeColor::Red;
+                    node = grandparent;
+                } else {
+                    // parent's sibling has red and black children
+                    if node == (*parent).right {
+                        node = parent;
+                        self.rotate_left(node);
+                    }
+                    (*(*node).parent).color = NodeColor::Black;
+                    (*(*(*node).parent).parent).color = NodeColor::Red;
+                    self.rotate_right((*(*node).parent).parent);
                 }
-            };
+            } else {
+                // fixup red-red in right subtree
+                let sibling = (*grandparent).left;
+                if (*sibling).color == NodeColor::Red {
+                    (*parent).color = NodeColor::Black;
+                    (*sibling).color = NodeColor::Black;
+                    (*grandparent).color = NodeColor::Red;
+                    node = grandparent;
+                } else {
+                    if node == (*parent).left {
+                        node = parent;
+                        self.rotate_right(node);
+                    }
+                    (*(*node).parent).color = NodeColor::Black;
+                    (*(*(*node).parent).parent).color = NodeColor::Red;
+                    self.rotate_left((*(*node).parent).parent);
+                }
+            }
         }
+        // root is always black
+        (*(*head).parent).color = NodeColor::Black;
+        new_node
     }
 
     //
@@ -144,25 +444,21 @@ where C: TreeCompare<T0, T1>,
     //     / \     / \
     //    o  y    x   o
     //
-    // NOTE: Assume that r and p are valid.
+    // _Lrotate. NOTE: Assume that r is valid.
     unsafe fn rotate_left(&mut self, n: *mut TreeNode<T0, A>) {
-        let n = &mut *n;
-        let p = n.parent;
-        let r = &mut *n.right;
-        let o = r.left;
-        n.right = o;
-        if !(&*o).nil { (&mut*o).parent = n }
-        r.left = n;
-        n.parent = r;
-        r.parent = p;
-        if std::ptr::eq(p, self.head) { 
-            (&mut *self.head).parent = r 
+        let r = (*n).right;
+        (*n).right = (*r).left;
+        if !(*(*r).left).nil { (*(*r).left).parent = n; }
+        (*r).parent = (*n).parent;
+        if n == (*self.head).parent {
+            (*self.head).parent = r;
+        } else if n == (*(*n).parent).left {
+            (*(*n).parent).left = r;
         } else {
-            match (&*p).right == n {
-                true => (&mut*p).right = r,
-                false => (&mut*p).left = r
-            }
+            (*(*n).parent).right = r;
         }
+        (*r).left = n;
+        (*n).parent = r;
     }
 
     //
@@ -174,542 +470,1322 @@ where C: TreeCompare<T0, T1>,
     //   / \             / \
     //  x  o            o  y
     //
-    // NOTE: Assume that r and p are valid.
+    // _Rrotate. NOTE: Assume that r is valid.
     unsafe fn rotate_right(&mut self, n: *mut TreeNode<T0, A>) {
-        let n = &mut *n;
-        let p = n.parent;
-        let r = &mut *n.left;
-        let o = r.right;
-        n.left = o;
-        if !(&*o).nil { (&mut*o).parent = n }
-        r.right = n;
-        n.parent = r;
-        r.parent = p;
-        if std::ptr::eq(p, self.head) { 
-            (&mut *self.head).parent = r 
+        let r = (*n).left;
+        (*n).left = (*r).right;
+        if !(*(*r).right).nil { (*(*r).right).parent = n; }
+        (*r).parent = (*n).parent;
+        if n == (*self.head).parent {
+            (*self.head).parent = r;
+        } else if n == (*(*n).parent).right {
+            (*(*n).parent).right = r;
         } else {
-            match (&*p).right == n {
-                true => (&mut*p).right = r,
-                false => (&mut*p).left = r
-            }
-        }
-    }
-    // NOTE: Assume that n->parent is valid
-    unsafe fn get_sibling(&self, n: *mut TreeNode<T0, A>) -> *mut TreeNode<T0, A> {
-        let node = &*n;
-        match node.get_parent().unwrap().left == n {
-            true => (&mut *node.parent).right,
-            false => (&mut *node.parent).left
+            (*(*n).parent).left = r;
         }
+        (*r).right = n;
+        (*n).parent = r;
     }
 
-    unsafe fn get_direction(&self, n: *mut TreeNode<T0, A>) -> NodeDirection {
-        let node = &*n;
-        match node.get_parent().unwrap().right == n {
-            true => NodeDirection::Right,
-            false => NodeDirection::Left
+    // _Extract: unlinks a node from the tree and rebalances it. The node isn't freed.
+    unsafe fn extract(&mut self, erased: *mut TreeNode<T0, A>) -> *mut TreeNode<T0, A> {
+        let head = self.head;
+        let successor = TreeNode::increment(erased);
+        let mut fix_node; // the node to recolor as needed
+        let mut fix_parent; // parent of fix_node (which may be nil)
+        let mut node = erased;
+        if (*(*node).left).nil {
+            fix_node = (*node).right; // stitch up right subtree
+        } else if (*(*node).right).nil {
+            fix_node = (*node).left; // stitch up left subtree
+        } else {
+            // two subtrees, must lift successor node to replace erased
+            node = successor;
+            fix_node = (*node).right;
         }
-    }
-    // NOTE: Assume that grandparent (node->parent->parent) is valid when starting (only call this
-    // if tree's height >= 2)
-    unsafe fn post_insert_maintain_rbt(&mut self, n: *mut TreeNode<T0, A>) {
-        let mut node = n;
-        loop {
-            // retuns if a parent exists and is red, since that violates rb-tree rules
-            let mut parent = match (&mut *node).get_parent_mut() {
-                Some(v) => {
-                    if v.color == NodeColor::Black { return }
-                    v
-                },
-                None => return
-            };
-            let grandparent = match parent.parent.as_mut() {
-                Some(v) => v,
-                None => return
-            };
-            let uncle = self.get_sibling(&raw mut *parent);
-            let unc_dir = self.get_direction(&raw mut *parent);
-            if !(&*uncle).nil && (&*uncle).color == NodeColor::Red {
-                (&mut *parent).color = NodeColor::Black;
-                assert!((&*node).color == NodeColor::Red, "Node should be red");
-                (&mut *uncle).color = NodeColor::Black;
-                (&mut *grandparent).color = NodeColor::Red;
-                // travel up 2 tree levels
-                node = grandparent;
+        if node == erased {
+            // at most one subtree, relink it
+            fix_parent = (*erased).parent;
+            if !(*fix_node).nil { (*fix_node).parent = fix_parent; }
+            if (*head).parent == erased {
+                (*head).parent = fix_node;
+            } else if (*fix_parent).left == erased {
+                (*fix_parent).left = fix_node;
             } else {
-                let inner_grandchild = match unc_dir {
-                    NodeDirection::Left => parent.right,
-                    NodeDirection::Right => parent.left
+                (*fix_parent).right = fix_node;
+            }
+            if (*head).left == erased {
+                (*head).left = match (*fix_node).nil {
+                    true => fix_parent, // smallest is parent of erased node
+                    false => TreeNode::min(fix_node) // smallest in relinked subtree
                 };
-                if std::ptr::eq(inner_grandchild, node) {
-                    parent = match unc_dir {
-                        NodeDirection::Left => {
-                            self.rotate_left(&raw mut *parent);
-                            &mut *((&mut *grandparent).left)
-                        },
-                        NodeDirection::Right => {
-                            self.rotate_right(&raw mut *parent);
-                            &mut *((&mut *grandparent).right)
-                        },
-                    } 
-                }
-                match unc_dir {
-                    NodeDirection::Left => self.rotate_right(&raw mut *grandparent),
-                    NodeDirection::Right => self.rotate_left(&raw mut *grandparent),
+            }
+            if (*head).right == erased {
+                (*head).right = match (*fix_node).nil {
+                    true => fix_parent, // largest is parent of erased node
+                    false => TreeNode::max(fix_node) // largest in relinked subtree
+                };
+            }
+        } else {
+            // erased has two subtrees, node is successor to erased
+            (*(*erased).left).parent = node;
+            (*node).left = (*erased).left;
+            if node == (*erased).right {
+                fix_parent = node; // successor is next to erased
+            } else {
+                // successor further down, link in place of erased
+                fix_parent = (*node).parent;
+                if !(*fix_node).nil { (*fix_node).parent = fix_parent; }
+                (*fix_parent).left = fix_node;
+                (*node).right = (*erased).right;
+                (*(*erased).right).parent = node;
+            }
+            if (*head).parent == erased {
+                (*head).parent = node;
+            } else if (*(*erased).parent).left == erased {
+                (*(*erased).parent).left = node;
+            } else {
+                (*(*erased).parent).right = node;
+            }
+            (*node).parent = (*erased).parent;
+            std::mem::swap(&mut (*node).color, &mut (*erased).color);
+        }
+        if (*erased).color == NodeColor::Black {
+            // erasing black link, must recolor/rebalance tree
+            while fix_node != (*head).parent && (*fix_node).color == NodeColor::Black {
+                if fix_node == (*fix_parent).left {
+                    // fixup left subtree
+                    node = (*fix_parent).right;
+                    if (*node).color == NodeColor::Red {
+                        // rotate red up from right subtree
+                        (*node).color = NodeColor::Black;
+                        (*fix_parent).color = NodeColor::Red;
+                        self.rotate_left(fix_parent);
+                        node = (*fix_parent).right;
+                    }
+                    if (*node).nil {
+                        fix_node = fix_parent; // shouldn't happen
+                    } else if (*(*node).left).color == NodeColor::Black && (*(*node).right).color == NodeColor::Black {
+                        // redden right subtree with black children
+                        (*node).color = NodeColor::Red;
+                        fix_node = fix_parent;
+                    } else {
+                        // must rearrange right subtree
+                        if (*(*node).right).color == NodeColor::Black {
+                            // rotate red up from left sub-subtree
+                            (*(*node).left).color = NodeColor::Black;
+                            (*node).color = NodeColor::Red;
+                            self.rotate_right(node);
+                            node = (*fix_parent).right;
+                        }
+                        (*node).color = (*fix_parent).color;
+                        (*fix_parent).color = NodeColor::Black;
+                        (*(*node).right).color = NodeColor::Black;
+                        self.rotate_left(fix_parent);
+                        break; // tree now recolored/rebalanced
+                    }
+                } else {
+                    // fixup right subtree
+                    node = (*fix_parent).left;
+                    if (*node).color == NodeColor::Red {
+                        // rotate red up from left subtree
+                        (*node).color = NodeColor::Black;
+                        (*fix_parent).color = NodeColor::Red;
+                        self.rotate_right(fix_parent);
+                        node = (*fix_parent).left;
+                    }
+                    if (*node).nil {
+                        fix_node = fix_parent; // shouldn't happen
+                    } else if (*(*node).right).color == NodeColor::Black && (*(*node).left).color == NodeColor::Black {
+                        // redden left subtree with black children
+                        (*node).color = NodeColor::Red;
+                        fix_node = fix_parent;
+                    } else {
+                        // must rearrange left subtree
+                        if (*(*node).left).color == NodeColor::Black {
+                            // rotate red up from right sub-subtree
+                            (*(*node).right).color = NodeColor::Black;
+                            (*node).color = NodeColor::Red;
+                            self.rotate_left(node);
+                            node = (*fix_parent).left;
+                        }
+                        (*node).color = (*fix_parent).color;
+                        (*fix_parent).color = NodeColor::Black;
+                        (*(*node).left).color = NodeColor::Black;
+                        self.rotate_right(fix_parent);
+                        break; // tree now recolored/rebalanced
+                    }
                 }
-                parent.color = NodeColor::Black;
-                grandparent.color = NodeColor::Red;
-                return;
+                fix_parent = (*fix_node).parent;
             }
+            (*fix_node).color = NodeColor::Black; // stopping node is black
         }
+        if self.size > 0 { self.size -= 1; }
+        erased
     }
 
-    pub fn insert(&mut self, value: T0) -> bool {
-        let count = self.len();
-        self.size += 1;
-        let node = unsafe { self.new_node(value) };
-        let mut head = self.get_head_mut();
-        if count == 0 {
-            // We are the only node!
-            head.left = node;
-            head.parent = node;
-            head.right = node;
-            unsafe { (&mut *node).color = NodeColor::Black };
-            return true;
-        }
-        // traverse BST, starting from root (head->parent), then add as leaf
-        match unsafe { self.make_initial_insertion(&mut *node) } {
-            Some((n, d)) => {
-                match d {
-                    NodeDirection::Left => n.left = node,
-                    NodeDirection::Right => n.right = node
-                };
-            }
-            // this fails if attempting to add a duplicate key
-            None => return false
-        };
-        // msvc tree specific:
-        // set head node left/right if smallest or largest value
-        head = self.get_head_mut();
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self.find_node(key).is_some()
+    }
+
+    pub fn find<Q>(&self, key: &Q) -> Option<&T0>
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self.find_node(key).map(|n| unsafe { &(*n).data })
+    }
+
+    pub fn find_mut<Q>(&mut self, key: &Q) -> Option<&mut T0>
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self.find_node(key).map(|n| unsafe { &mut (*n).data })
+    }
+
+    /// Iterates from the first element that isn't ordered before key
+    pub fn lower_bound<Q>(&self, key: &Q) -> TreeIterator<'_, T0, A>
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        let (_, _, bound) = unsafe { self.find_lower_bound(key) };
+        self.iter_from(bound)
+    }
+
+    /// Iterates from the first element that is ordered after key
+    pub fn upper_bound<Q>(&self, key: &Q) -> TreeIterator<'_, T0, A>
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        let (_, _, bound) = unsafe { self.find_upper_bound(key) };
+        self.iter_from(bound)
+    }
+
+    fn iter_from(&self, start: *mut TreeNode<T0, A>) -> TreeIterator<'_, T0, A> {
+        TreeIterator {
+            front: start,
+            back: unsafe { (*self.head).right },
+            done: unsafe { (*start).nil },
+            _marker: PhantomData
+        }
+    }
+
+    pub fn iter(&self) -> TreeIterator<'_, T0, A> { self.into_iter() }
+    pub fn iter_mut(&mut self) -> TreeIteratorMut<'_, T0, A> { self.into_iter() }
+
+    // Unlinks the node, then moves its value out and frees it
+    unsafe fn erase_node(&mut self, node: *mut TreeNode<T0, A>) -> T0 {
+        self.extract(node);
+        TreeNode::delete(node, self._allocator.clone())
+    }
+
+    pub fn take<Q>(&mut self, key: &Q) -> Option<T0>
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self.find_node(key).map(|n| unsafe { self.erase_node(n) })
+    }
+
+    // remove type can be different from storage type (e.g for maps, store as MapPair(MapKey,
+    // MapValue), but find based on MapKey or a borrowed form of it)
+    pub fn remove<Q>(&mut self, key: &Q) -> bool
+    where T1: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self.take(key).is_some()
+    }
+
+    pub fn clear(&mut self) {
         unsafe {
-            if C::compare_aa(&(&mut *node).data, &head.get_left().unwrap().data) {
-                head.left = node;
-            }
-            if C::compare_aa(&head.get_left().unwrap().data, &(&mut *node).data) {
-                head.right = node;
-            }
+            self.erase_tree((*self.head).parent);
+            (*self.head).left = self.head;
+            (*self.head).parent = self.head;
+            (*self.head).right = self.head;
         }
-        // maintain red-black tree property
-        let parent = unsafe { (&mut *node).get_parent_mut().unwrap() };
-        match parent.get_parent_mut() { // has grandparent (height >= 2)
-            Some(_) => unsafe {
-                // do some cleaning up
-                self.post_insert_maintain_rbt(node);
-                self.get_root_unchecked_mut().color = NodeColor::Black;
-            }, // height = 1, do nothing. rb-tree properties are maintained
-            None => (),
-        };
-        true
+        self.size = 0;
     }
 
-    pub fn contains(&self, value: T1) -> bool {
-        let mut current = self.get_root();
-        while let Some(n) = current {
-            if n.data == value { return true; }
-            if C::compare_ab(&n.data, &value) {
-                current = n.get_right();
-            } else {
-                current = n.get_left();
-            }
+    // _Erase_tree: free a subtree without rebalancing
+    unsafe fn erase_tree(&mut self, mut root: *mut TreeNode<T0, A>) {
+        while !(*root).nil {
+            self.erase_tree((*root).right);
+            let next = (*root).left;
+            drop(TreeNode::delete(root, self._allocator.clone()));
+            root = next;
         }
-        false
     }
 
-    pub fn find(&self, value: T1) -> Option<&T0> {
-        let mut current = self.get_root();
-        while let Some(n) = current {
-            if n.data == value { return Some(&n.data); }
-            if C::compare_ab(&n.data, &value) {
-                current = n.get_right();
-            } else {
-                current = n.get_left();
-            }
+    fn traverse(&self) -> Vec<&TreeNode<T0, A>> {
+        let mut entries = vec![];
+        let mut current = unsafe { (*self.head).left };
+        while !unsafe { (*current).nil } {
+            entries.push(unsafe { &*current });
+            current = unsafe { TreeNode::increment(current) };
         }
-        None
+        entries
     }
+}
 
-    pub fn find_mut(&mut self, value: T1) -> Option<&mut T0> {
-        let mut current = self.get_root_mut();
-        while let Some(n) = current {
-            if n.data == value { return Some(&mut n.data); }
-            if C::compare_ab(&n.data, &value) {
-                current = n.get_right_mut();
-            } else {
-                current = n.get_left_mut();
-            }
+impl<C, T0, T1, A> Tree<C, T0, T1, A>
+where C: TreeCompare<T1, T1>,
+      T0: KeyOf<T1> + Debug,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    pub(super) fn traverse_test(&self) {
+        let head = self.get_head();
+        println!("Nil @ 0x{:x}: <l:0x{:x} p:0x{:x} r:0x{:x}>", 
+        &raw const *head as usize, head.left as usize, head.parent as usize, head.right as usize);
+        if !self.is_empty() {
+            unsafe { (*self.get_head().parent).traverse_printf(); }
         }
-        None
     }
-    // remove type can be different from storage type (e.g for maps, store as MapNode(MapKey,
-    // MapValue), but find based on MapKey which is PartialEq<MapNode>
-    pub fn remove(&mut self, value: T1) -> bool
-    {
-        let _target = match self.find_mut(value) {
-            Some(v) => v,
-            None => return false
+
+    pub(super) fn traverse_debug(&self) -> Vec<&TreeNode<T0, A>> {
+        self.traverse()
+    }
+
+    pub fn insert_print(&mut self, value: T0) -> bool {
+        println!("insert {:?}", value);
+        self.insert(value)
+    }
+}
+
+impl<T, A> TreeNode<T, A>
+where A: Allocator + Clone
+{
+    // _Tree_node::_Buyheadnode, for root node. The value is left uninitialized.
+    unsafe fn new_head(alloc: A) -> *mut Self {
+        let new = alloc.allocate(Layout::new::<Self>()).unwrap().as_ptr() as *mut Self;
+        (*new).left = new;
+        (*new).parent = new;
+        (*new).right = new;
+        (*new).color = NodeColor::Black;
+        (*new).nil = true;
+        std::ptr::write(&raw mut (*new)._allocator, alloc);
+        new
+    }
+    // _Tree_node::_Buynode, for child nodes
+    unsafe fn new_node(value: T, alloc: A, head: *mut Self) -> *mut Self {
+        let new = alloc.allocate(Layout::new::<Self>()).unwrap().as_ptr() as *mut Self;
+        (*new).left = head;
+        (*new).parent = head;
+        (*new).right = head;
+        (*new).color = NodeColor::Red;
+        (*new).nil = false;
+        std::ptr::write(&raw mut (*new).data, value);
+        std::ptr::write(&raw mut (*new)._allocator, alloc);
+        new
+    }
+    // Resets an extracted node's links, as if it was just created by _Buynode
+    unsafe fn reset_links(node: *mut Self, head: *mut Self) {
+        (*node).left = head;
+        (*node).parent = head;
+        (*node).right = head;
+        (*node).color = NodeColor::Red;
+    }
+    // _Freenode: moves the value out of a child node, then frees it
+    unsafe fn delete(node: *mut Self, alloc: A) -> T {
+        let value = std::ptr::read(&raw const (*node).data);
+        alloc.deallocate(std::ptr::NonNull::new_unchecked(node as *mut u8), Layout::new::<Self>());
+        value
+    }
+
+    pub fn value(&self) -> &T { &self.data }
+    pub fn value_mut(&mut self) -> &mut T { &mut self.data }
+
+    // _Min
+    unsafe fn min(mut node: *mut Self) -> *mut Self {
+        while !(*(*node).left).nil { node = (*node).left; }
+        node
+    }
+    // _Max
+    unsafe fn max(mut node: *mut Self) -> *mut Self {
+        while !(*(*node).right).nil { node = (*node).right; }
+        node
+    }
+    // _Tree_unchecked_const_iterator::operator++
+    unsafe fn increment(mut node: *mut Self) -> *mut Self {
+        if (*node).nil {
+            // end() shouldn't be incremented, don't move
+            return node;
+        }
+        if !(*(*node).right).nil {
+            return Self::min((*node).right);
+        }
+        let mut parent = (*node).parent;
+        while !(*parent).nil && node == (*parent).right {
+            node = parent;
+            parent = (*node).parent;
+        }
+        parent
+    }
+    // _Tree_unchecked_const_iterator::operator--
+    unsafe fn decrement(mut node: *mut Self) -> *mut Self {
+        if (*node).nil {
+            // end() ==> rightmost
+            return (*node).right;
+        }
+        if !(*(*node).left).nil {
+            return Self::max((*node).left);
+        }
+        let mut parent = (*node).parent;
+        while !(*parent).nil && node == (*parent).left {
+            node = parent;
+            parent = (*node).parent;
+        }
+        if (*node).nil { node } else { parent }
+    }
+}
+
+impl<C, T0, T1, A> Drop for Tree<C, T0, T1, A>
+where C: TreeCompare<T1, T1>,
+      T0: KeyOf<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    fn drop(&mut self) {
+        self.clear();
+        unsafe { self._allocator.deallocate(std::ptr::NonNull::new_unchecked(self.head as *mut u8), Layout::new::<TreeNode<T0, A>>()); }
+    }
+}
+
+impl<T, A> TreeNode<T, A>
+where T: Debug,
+      A: Allocator + Clone
+{
+    unsafe fn traverse_printf(&self) {
+        if !(*self.left).nil { (*self.left).traverse_printf() }
+        let ptr = &raw const *self as usize;
+        println!("Node @ 0x{:x}: <l:0x{:x} p:0x{:x} r:0x{:x}> [{:?}, {:?}]", 
+        ptr, self.left as usize, self.parent as usize, self.right as usize, self.data, self.color);
+        if !(*self.right).nil { (*self.right).traverse_printf() }
+    }
+}
+
+impl<'a, C, T0, T1, A> IntoIterator for &'a Tree<C, T0, T1, A>
+where C: TreeCompare<T1, T1>,
+      T0: KeyOf<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    type Item = &'a T0;
+    type IntoIter = TreeIterator<'a, T0, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_from(unsafe { (*self.head).left })
+    }
+}
+
+impl<'a, C, T0, T1, A> IntoIterator for &'a mut Tree<C, T0, T1, A>
+where C: TreeCompare<T1, T1>,
+      T0: KeyOf<T1>,
+      T1: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    type Item = &'a mut T0;
+    type IntoIter = TreeIteratorMut<'a, T0, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        TreeIteratorMut {
+            front: unsafe { (*self.head).left },
+            back: unsafe { (*self.head).right },
+            done: self.is_empty(),
+            _marker: PhantomData
+        }
+    }
+}
+
+// Inorder traversal using the tree iterator's increment/decrement, so no stack is needed
+pub struct TreeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    front: *mut TreeNode<T, A>,
+    back: *mut TreeNode<T, A>,
+    done: bool,
+    _marker: PhantomData<&'a T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for TreeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None }
+        let out = self.front;
+        match std::ptr::eq(self.front, self.back) {
+            true => self.done = true,
+            false => self.front = unsafe { TreeNode::increment(self.front) }
         };
-        self.size -= 1;
-        true
+        Some(unsafe { &(*out).data })
     }
+}
 
-    fn traverse(&self) -> Vec<&TreeNode<T0, A>> {
-        let mut entries = vec![];
-        let mut stack: Vec<&TreeNode<T0, A>> = vec![];
-        let mut current = match self.get_root() {
-            Some(v) => v,
-            None => return entries
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for TreeIterator<'a, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.done { return None }
+        let out = self.back;
+        match std::ptr::eq(self.front, self.back) {
+            true => self.done = true,
+            false => self.back = unsafe { TreeNode::decrement(self.back) }
+        };
+        Some(unsafe { &(*out).data })
+    }
+}
+
+pub struct TreeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    front: *mut TreeNode<T, A>,
+    back: *mut TreeNode<T, A>,
+    done: bool,
+    _marker: PhantomData<&'a mut T>
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for TreeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None }
+        let out = self.front;
+        match std::ptr::eq(self.front, self.back) {
+            true => self.done = true,
+            false => self.front = unsafe { TreeNode::increment(self.front) }
         };
+        Some(unsafe { &mut (*out).data })
+    }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for TreeIteratorMut<'a, T, A>
+where A: Allocator + Clone
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.done { return None }
+        let out = self.back;
+        match std::ptr::eq(self.front, self.back) {
+            true => self.done = true,
+            false => self.back = unsafe { TreeNode::decrement(self.back) }
+        };
+        Some(unsafe { &mut (*out).data })
+    }
+}
+
+#[repr(C)]
+pub struct Set<C, T, A = Global>
+where C: TreeCompare<T, T>,
+      T: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    _impl: Tree<C, T, T, A>
+}
+
+impl<C, T> Set<C, T, Global>
+where C: TreeCompare<T, T> + Default,
+      T: PartialEq + PartialOrd
+{
+    pub fn from_sorted_vec(values: Vec<T>) -> Result<Self, FromSortedError> { Self::from_sorted_vec_in(values, Global) }
+    pub fn from_sorted_iter<I>(iter: I) -> Result<Self, FromSortedError>
+    where I: IntoIterator<Item = T>
+    {
+        Self::from_sorted_iter_in(iter, Global)
+    }
+}
+
+impl<C, T, A> Set<C, T, A>
+where C: TreeCompare<T, T>,
+      T: PartialEq + PartialOrd,
+      A: Allocator + Clone
+{
+    pub fn new_inner(alloc: A) -> Self
+    where C: Default
+    {
+        Self { _impl: Tree::new_inner(alloc) }
+    }
+    pub fn new_inner_with_compare(compare: C, alloc: A) -> Self {
+        Self { _impl: Tree::new_inner_with_compare(compare, alloc) }
+    }
+    /// Builds the set in linear time from values in strictly increasing order
+    pub fn from_sorted_vec_in(values: Vec<T>, alloc: A) -> Result<Self, FromSortedError>
+    where C: Default
+    {
+        Self::from_sorted_vec_with_compare(values, C::default(), alloc)
+    }
+    pub fn from_sorted_vec_with_compare(values: Vec<T>, compare: C, alloc: A) -> Result<Self, FromSortedError> {
+        Ok(Self { _impl: Tree::from_sorted_vec_with_compare(values, compare, alloc)? })
+    }
+    /// Builds the set in linear time from an iterator of values in strictly increasing order
+    pub fn from_sorted_iter_in<I>(iter: I, alloc: A) -> Result<Self, FromSortedError>
+    where C: Default,
+          I: IntoIterator<Item = T>
+    {
+        Self::from_sorted_vec_in(iter.into_iter().collect(), alloc)
+    }
+    pub fn key_comp(&self) -> &C { self._impl.key_comp() }
+    pub fn insert(&mut self, value: T) -> bool { self._impl.insert(value) }
+    pub fn len(&self) -> usize { self._impl.len() }
+    pub fn is_empty(&self) -> bool { self._impl.is_empty() }
+    pub fn clear(&mut self) { self._impl.clear() }
+    pub fn first(&self) -> Option<&T> { self._impl.first() }
+    pub fn last(&self) -> Option<&T> { self._impl.last() }
+    pub fn iter(&self) -> TreeIterator<'_, T, A> { self._impl.iter() }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.contains(value)
+    }
+    pub fn find<Q>(&self, value: &Q) -> Option<&T>
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.find(value)
+    }
+    pub fn find_mut<Q>(&mut self, value: &Q) -> Option<&mut T>
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.find_mut(value)
+    }
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.remove(value)
+    }
+    /// Unlinks the element equivalent to value, returning a handle that owns its node
+    pub fn extract<Q>(&mut self, value: &Q) -> Option<NodeHandle<T, A>>
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.extract_key(value)
+    }
+    /// Links the handle's node into the set, or gives the handle back if the value is already
+    /// in the set
+    pub fn insert_node(&mut self, handle: NodeHandle<T, A>) -> Result<&mut T, NodeHandle<T, A>> {
+        self._impl.insert_handle(handle)
+    }
+    /// Moves the nodes for values that aren't in this set from other
+    pub fn merge<C2>(&mut self, other: &mut Set<C2, T, A>)
+    where C2: TreeCompare<T, T>
+    {
+        self._impl.merge(&mut other._impl)
+    }
+    pub fn lower_bound<Q>(&self, value: &Q) -> TreeIterator<'_, T, A>
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.lower_bound(value)
+    }
+    pub fn upper_bound<Q>(&self, value: &Q) -> TreeIterator<'_, T, A>
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.upper_bound(value)
+    }
+
+    // Set operations walk both sets in order like the algorithms in <algorithm>, so both sets
+    // must be ordered by equivalent comparators. This set's comparator is the one that's used.
+
+    /// Iterates over values in either set, like std::set_union. Values in both sets are taken
+    /// from this set.
+    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, C, T, A> {
+        Union { merge: MergeIter::new(self, other) }
+    }
+    /// Iterates over values in both sets, like std::set_intersection
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, C, T, A> {
+        Intersection { merge: MergeIter::new(self, other) }
+    }
+    /// Iterates over values in this set that aren't in other, like std::set_difference
+    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, C, T, A> {
+        Difference { merge: MergeIter::new(self, other) }
+    }
+    /// Iterates over values in exactly one of the sets, like std::set_symmetric_difference
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, C, T, A> {
+        SymmetricDifference { merge: MergeIter::new(self, other) }
+    }
+    /// Checks if every value in this set is in other, like std::includes(other, self)
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.len() <= other.len() && self.difference(other).next().is_none()
+    }
+    /// Checks if every value in other is in this set, like std::includes(self, other)
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.len() <= self.len() && other.difference(self).next().is_none()
+    }
+    /// Checks if the sets have no values in common
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).next().is_none()
+    }
+}
+
+// Walks two sets in order, yielding the next value from one or both of them
+struct MergeIter<'a, C, T, A>
+where T: 'a,
+      A: Allocator + Clone + 'a
+{
+    a: Peekable<TreeIterator<'a, T, A>>,
+    b: Peekable<TreeIterator<'a, T, A>>,
+    compare: &'a C
+}
+
+enum MergeStep<'a, T> {
+    A(&'a T),
+    B(&'a T),
+    Both(&'a T, &'a T)
+}
+
+impl<'a, C, T, A> MergeIter<'a, C, T, A>
+where C: TreeCompare<T, T>,
+      T: PartialEq + PartialOrd + 'a,
+      A: Allocator + Clone + 'a
+{
+    fn new(a: &'a Set<C, T, A>, b: &'a Set<C, T, A>) -> Self {
+        Self { a: a.iter().peekable(), b: b.iter().peekable(), compare: a.key_comp() }
+    }
+    fn next_step(&mut self) -> Option<MergeStep<'a, T>> {
+        let step = match (self.a.peek(), self.b.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => MergeStep::A(self.a.next()?),
+            (None, Some(_)) => MergeStep::B(self.b.next()?),
+            (Some(x), Some(y)) => {
+                if self.compare.compare_aa(x, y) { MergeStep::A(self.a.next()?) }
+                else if self.compare.compare_aa(y, x) { MergeStep::B(self.b.next()?) }
+                else { MergeStep::Both(self.a.next()?, self.b.next()?) }
+            }
+        };
+        Some(step)
+    }
+}
+
+/// Lazy iterator for [`Set::union`]
+pub struct Union<'a, C, T, A = Global>
+where T: 'a,
+      A: Allocator + Clone + 'a
+{
+    merge: MergeIter<'a, C, T, A>
+}
+
+impl<'a, C, T, A> Iterator for Union<'a, C, T, A>
+where C: TreeCompare<T, T>,
+      T: PartialEq + PartialOrd + 'a,
+      A: Allocator + Clone + 'a
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.merge.next_step()? {
+            MergeStep::A(v) | MergeStep::B(v) | MergeStep::Both(v, _) => Some(v)
+        }
+    }
+}
+
+/// Lazy iterator for [`Set::intersection`]
+pub struct Intersection<'a, C, T, A = Global>
+where T: 'a,
+      A: Allocator + Clone + 'a
+{
+    merge: MergeIter<'a, C, T, A>
+}
+
+impl<'a, C, T, A> Iterator for Intersection<'a, C, T, A>
+where C: TreeCompare<T, T>,
+      T: PartialEq + PartialOrd + 'a,
+      A: Allocator + Clone + 'a
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let MergeStep::Both(v, _) = self.merge.next_step()? { return Some(v) }
+        }
+    }
+}
+
+/// Lazy iterator for [`Set::difference`]
+pub struct Difference<'a, C, T, A = Global>
+where T: 'a,
+      A: Allocator + Clone + 'a
+{
+    merge: MergeIter<'a, C, T, A>
+}
+
+impl<'a, C, T, A> Iterator for Difference<'a, C, T, A>
+where C: TreeCompare<T, T>,
+      T: PartialEq + PartialOrd + 'a,
+      A: Allocator + Clone + 'a
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // nothing left in this set means there's nothing left to yield
+            self.merge.a.peek()?;
+            if let MergeStep::A(v) = self.merge.next_step()? { return Some(v) }
+        }
+    }
+}
+
+/// Lazy iterator for [`Set::symmetric_difference`]
+pub struct SymmetricDifference<'a, C, T, A = Global>
+where T: 'a,
+      A: Allocator + Clone + 'a
+{
+    merge: MergeIter<'a, C, T, A>
+}
+
+impl<'a, C, T, A> Iterator for SymmetricDifference<'a, C, T, A>
+where C: TreeCompare<T, T>,
+      T: PartialEq + PartialOrd + 'a,
+      A: Allocator + Clone + 'a
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
         loop {
-            while !current.nil {
-                stack.push(current);
-                current = unsafe { &*current.left };
+            match self.merge.next_step()? {
+                MergeStep::A(v) | MergeStep::B(v) => return Some(v),
+                MergeStep::Both(..) => ()
             }
-            let out = match stack.pop() {
-                Some(v) => {
-                    entries.push(v);
-                    v
-                },
-                None => break
-            };
-            current = unsafe { &*out.right };
         }
-        entries
     }
 }
 
-impl<C, T0, T1, A> Tree<C, T0, T1, A>
-where C: TreeCompare<T0, T1>,
-      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1> + Debug,
-      // T1: PartialEq + PartialEq<T0> + PartialOrd + PartialOrd<T0>,
-T1: PartialEq + PartialOrd,
+#[repr(C)]
+pub struct Map<C, K, V, A = Global>
+where C: TreeCompare<K, K>,
+      K: PartialEq + PartialOrd,
       A: Allocator + Clone
 {
-    pub(super) fn traverse_test(&self) {
-        let head = self.get_head();
-        println!("Nil @ 0x{:x}: <l:0x{:x} p:0x{:x} r:0x{:x}>", 
-        &raw const *head as usize, head.left as usize, head.parent as usize, head.right as usize);
-        if !self.is_empty() {
-            self.get_head().get_parent().unwrap().traverse_printf();
-        }
-    }
-
-    pub(super) fn traverse_debug(&self) -> Vec<&TreeNode<T0, A>> {
-        self.traverse()
-    }
-
-    pub fn insert_print(&mut self, value: T0) -> bool {
-        println!("insert {:?}", value);
-        self.insert(value)
+    _impl: Tree<C, MapPair<K, V>, K, A>
+}
+impl<C, K, V> Map<C, K, V, Global>
+where C: TreeCompare<K, K> + Default,
+      K: PartialEq + PartialOrd
+{
+    pub fn from_sorted_vec(pairs: Vec<(K, V)>) -> Result<Self, FromSortedError> { Self::from_sorted_vec_in(pairs, Global) }
+    pub fn from_sorted_iter<I>(iter: I) -> Result<Self, FromSortedError>
+    where I: IntoIterator<Item = (K, V)>
+    {
+        Self::from_sorted_iter_in(iter, Global)
     }
 }
 
-impl<T, A> TreeNode<T, A>
-where T: PartialEq + PartialOrd,
+impl<C, K, V, A> Map<C, K, V, A>
+where C: TreeCompare<K, K>,
+      K: PartialEq + PartialOrd,
       A: Allocator + Clone
 {
-    // _Tree_node::_Buyheadnode, for root node
-    unsafe fn new_head(alloc: A) -> *mut Self {
-        let new = &mut *(alloc.allocate(Layout::new::<Self>()).unwrap().as_ptr() as *mut Self);
-        let p_new = &raw mut *new;
-        new.left = p_new;
-        new.parent = p_new;
-        new.right = p_new;
-        new.color = NodeColor::Black;
-        new.nil = true;
-        new._allocator = alloc.clone();
-        p_new
+    pub fn new_inner(alloc: A) -> Self
+    where C: Default
+    {
+        Self { _impl: Tree::new_inner(alloc) }
     }
-    // _Tree_node::_Buynode, for child nodes
-    unsafe fn new_node(alloc: A, head: *mut Self) -> *mut Self {
-        let new = &mut *(alloc.allocate(Layout::new::<Self>()).unwrap().as_ptr() as *mut Self);
-        let p_new = &raw mut *new;
-        new.left = head;
-        new.parent = head;
-        new.right = head;
-        new.color = NodeColor::Red;
-        new.nil = false;
-        p_new
+    pub fn new_inner_with_compare(compare: C, alloc: A) -> Self {
+        Self { _impl: Tree::new_inner_with_compare(compare, alloc) }
     }
-
-    fn get_parent(&self) -> Option<&Self> { 
-        let parent = unsafe { &*self.parent };
-        match parent.nil {
-            true => None,
-            false => Some(parent)
-        }
+    /// Builds the map in linear time from pairs with keys in strictly increasing order
+    pub fn from_sorted_vec_in(pairs: Vec<(K, V)>, alloc: A) -> Result<Self, FromSortedError>
+    where C: Default
+    {
+        Self::from_sorted_vec_with_compare(pairs, C::default(), alloc)
     }
-    fn get_left(&self) -> Option<&Self> {
-        let child = unsafe { &*self.left };
-        match child.nil {
-            true => None,
-            false => Some(child)
-        }
+    pub fn from_sorted_vec_with_compare(pairs: Vec<(K, V)>, compare: C, alloc: A) -> Result<Self, FromSortedError> {
+        let pairs = pairs.into_iter().map(|(k, v)| MapPair::new(k, v)).collect();
+        Ok(Self { _impl: Tree::from_sorted_vec_with_compare(pairs, compare, alloc)? })
     }
-    fn get_right(&self) -> Option<&Self> {
-        let child = unsafe { &*self.right };
-        match child.nil {
-            true => None,
-            false => Some(child)
-        }
+    /// Builds the map in linear time from an iterator of pairs with keys in strictly
+    /// increasing order
+    pub fn from_sorted_iter_in<I>(iter: I, alloc: A) -> Result<Self, FromSortedError>
+    where C: Default,
+          I: IntoIterator<Item = (K, V)>
+    {
+        let pairs = iter.into_iter().map(|(k, v)| MapPair::new(k, v)).collect();
+        Ok(Self { _impl: Tree::from_sorted_vec_with_compare(pairs, C::default(), alloc)? })
     }
-
-    fn get_parent_mut(&mut self) -> Option<&mut Self> { 
-        let parent = unsafe { &mut *self.parent };
-        match parent.nil {
-            true => None,
-            false => Some(parent)
-        }
+    pub fn key_comp(&self) -> &C { self._impl.key_comp() }
+    pub fn insert(&mut self, key: K, value: V) -> bool { 
+        let pair = MapPair::new(key, value);
+        self._impl.insert(pair)
     }
-    fn get_left_mut(&mut self) -> Option<&mut Self> {
-        let child = unsafe { &mut *self.left };
-        match child.nil {
-            true => None,
-            false => Some(child)
-        }
+    pub fn len(&self) -> usize { self._impl.len() }
+    pub fn is_empty(&self) -> bool { self._impl.is_empty() }
+    pub fn clear(&mut self) { self._impl.clear() }
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.contains(key)
+    }
+    pub fn find<Q>(&self, key: &Q) -> Option<&V>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.find(key).map(|v| v.get_value())
+    }
+    pub fn find_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.find_mut(key).map(|v| v.get_value_mut())
+    }
+    /// Removes the element with the given key, returning its value
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.take(key).map(|p| p.value)
+    }
+    /// Unlinks the element with the given key, returning a handle that owns its node
+    pub fn extract<Q>(&mut self, key: &Q) -> Option<NodeHandle<MapPair<K, V>, A>>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.extract_key(key)
+    }
+    /// Links the handle's node into the map, or gives the handle back if the key is already in
+    /// the map
+    pub fn insert_node(&mut self, handle: NodeHandle<MapPair<K, V>, A>) -> Result<&mut V, NodeHandle<MapPair<K, V>, A>> {
+        self._impl.insert_handle(handle).map(|p| p.get_value_mut())
     }
-    fn get_right_mut(&mut self) -> Option<&mut Self> {
-        let child = unsafe { &mut *self.right };
-        match child.nil {
-            true => None,
-            false => Some(child)
+    /// Moves the nodes for keys that aren't in this map from other
+    pub fn merge<C2>(&mut self, other: &mut Map<C2, K, V, A>)
+    where C2: TreeCompare<K, K>
+    {
+        self._impl.merge(&mut other._impl)
+    }
+    /// Gets the entry for a key for in-place manipulation. If the key is vacant, it's only
+    /// converted into an owned key (which may allocate) once.
+    pub fn entry<Q>(&mut self, key: &Q) -> Entry<'_, C, K, V, A>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ToKey<K> + ?Sized
+    {
+        let (parent, child, bound) = unsafe { self._impl.find_lower_bound(key) };
+        match unsafe { self._impl.lower_bound_duplicate(bound, key) } {
+            true => Entry::Occupied(OccupiedEntry { map: self, node: bound }),
+            false => Entry::Vacant(VacantEntry { key: key.to_key(), map: self, parent, child })
         }
     }
+    pub fn lower_bound<Q>(&self, key: &Q) -> TreeIterator<'_, MapPair<K, V>, A>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.lower_bound(key)
+    }
+    pub fn upper_bound<Q>(&self, key: &Q) -> TreeIterator<'_, MapPair<K, V>, A>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.upper_bound(key)
+    }
+
+    pub fn iter(&self) -> TreeIterator<'_, MapPair<K, V>, A> {
+        self._impl.into_iter()
+    }
+    pub fn iter_mut(&mut self) -> TreeIteratorMut<'_, MapPair<K, V>, A> {
+        self._impl.iter_mut()
+    }
 }
 
-impl<C, T0, T1, A> Drop for Tree<C, T0, T1, A>
-where C: TreeCompare<T0, T1>,
-      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
-      //T1: PartialEq + PartialEq<T0> + PartialOrd + PartialOrd<T0>,
-      T1: PartialEq + PartialOrd,
+/// A view into a single entry of a [`Map`], which is either vacant or occupied
+pub enum Entry<'a, C, K, V, A = Global>
+where C: TreeCompare<K, K>,
+      K: PartialEq + PartialOrd,
       A: Allocator + Clone
 {
-    fn drop(&mut self) {
-        let nodes: Vec<&TreeNode<T0, A>> = self.traverse();
-        for n in nodes {
-            // SAFETY: This is the last time that any tree node can be accessed
-            unsafe { std::ptr::drop_in_place(&raw const *n as *mut TreeNode<T0, A>); }
-        }
-    }
+    Occupied(OccupiedEntry<'a, C, K, V, A>),
+    Vacant(VacantEntry<'a, C, K, V, A>)
 }
 
-impl<T, A> TreeNode<T, A>
-where T: PartialEq + PartialOrd + Debug,
+pub struct OccupiedEntry<'a, C, K, V, A = Global>
+where C: TreeCompare<K, K>,
+      K: PartialEq + PartialOrd,
       A: Allocator + Clone
 {
-    fn traverse_printf(&self) {
-        if let Some(l) = self.get_left() { l.traverse_printf() }
-        let ptr = &raw const *self as usize;
-        println!("Node @ 0x{:x}: <l:0x{:x} p:0x{:x} r:0x{:x}> [{:?}, {:?}]", 
-        ptr, self.left as usize, self.parent as usize, self.right as usize, self.data, self.color);
-        if let Some(r) = self.get_right() { r.traverse_printf() }
-    }
+    map: &'a mut Map<C, K, V, A>,
+    node: *mut TreeNode<MapPair<K, V>, A>
 }
 
-impl<'a, C, T0, T1, A> IntoIterator for &'a Tree<C, T0, T1, A>
-where C: TreeCompare<T0, T1>,
-      T0: PartialEq + PartialEq<T1> + PartialOrd + PartialOrd<T1>,
-      // T1: PartialEq + PartialEq<T0> + PartialOrd + PartialOrd<T0>,
-    T1: PartialEq + PartialOrd,
+pub struct VacantEntry<'a, C, K, V, A = Global>
+where C: TreeCompare<K, K>,
+      K: PartialEq + PartialOrd,
       A: Allocator + Clone
 {
-    type Item = &'a T0;
-    type IntoIter = TreeIterator<'a, T0, A>;
-    fn into_iter(self) -> Self::IntoIter {
-        // inorder traversal, so get leftmost node
-        unsafe {
-            let mut stack = vec![];
-            let current = match (&*self.head).get_parent() {
-                Some(v) => {
-                    let mut c = v;
-                    while !c.nil {
-                        stack.push(c);
-                        c = &*c.left;
-                    }
-                    Some(c)
-                },
-                None => None
-            };
-            Self::IntoIter {
-                current,
-                stack
-            }
-        }
-    }
+    map: &'a mut Map<C, K, V, A>,
+    key: K,
+    // insertion point found by _Find_lower_bound
+    parent: *mut TreeNode<MapPair<K, V>, A>,
+    child: NodeDirection
 }
 
-pub struct TreeIterator<'a, T, A>
-where T: PartialEq + PartialOrd,
+impl<'a, C, K, V, A> Entry<'a, C, K, V, A>
+where C: TreeCompare<K, K>,
+      K: PartialEq + PartialOrd,
       A: Allocator + Clone
 {
-    current: Option<&'a TreeNode<T, A>>,
-    stack: Vec<&'a TreeNode<T, A>>
+    pub fn key(&self) -> &K {
+        match self {
+            Self::Occupied(e) => e.key(),
+            Self::Vacant(e) => e.key()
+        }
+    }
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Self::Occupied(e) => e.into_mut(),
+            Self::Vacant(e) => e.insert(default)
+        }
+    }
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where F: FnOnce() -> V
+    {
+        match self {
+            Self::Occupied(e) => e.into_mut(),
+            Self::Vacant(e) => e.insert(default())
+        }
+    }
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where F: FnOnce(&mut V)
+    {
+        if let Self::Occupied(e) = &mut self { f(e.get_mut()) }
+        self
+    }
 }
 
-impl<'a, T, A> Iterator for TreeIterator<'a, T, A>
-where T: PartialEq + PartialOrd,
+impl<'a, C, K, V, A> Entry<'a, C, K, V, A>
+where C: TreeCompare<K, K>,
+      K: PartialEq + PartialOrd,
+      V: Default,
       A: Allocator + Clone
 {
-    type Item = &'a T;
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.current {
-            Some(_) => {
-                unsafe {
-                    while !self.current.unwrap().nil {
-                        self.stack.push(self.current.unwrap());
-                        self.current = Some(&*self.current.unwrap().left);
-                    }
-                }
-            },
-            None => return None
-        };
-        let out = match self.stack.pop() {
-            Some(v) => v,
-            None => return None
-        };
-        self.current = Some(unsafe { &*out.right });
-        Some(&out.data)
-    }
+    pub fn or_default(self) -> &'a mut V { self.or_insert_with(V::default) }
 }
 
-#[repr(C)]
-pub struct Set<C, T, A = Global>
-where C: TreeCompare<T, T>,
-      T: PartialEq + PartialOrd,
+impl<'a, C, K, V, A> OccupiedEntry<'a, C, K, V, A>
+where C: TreeCompare<K, K>,
+      K: PartialEq + PartialOrd,
       A: Allocator + Clone
 {
-    _impl: Tree<C, T, T, A>
+    pub fn key(&self) -> &K { unsafe { &(*self.node).data.key } }
+    pub fn get(&self) -> &V { unsafe { &(*self.node).data.value } }
+    pub fn get_mut(&mut self) -> &mut V { unsafe { &mut (*self.node).data.value } }
+    pub fn into_mut(self) -> &'a mut V { unsafe { &mut (*self.node).data.value } }
+    pub fn insert(&mut self, value: V) -> V { std::mem::replace(self.get_mut(), value) }
+    pub fn remove(self) -> V { unsafe { self.map._impl.erase_node(self.node).value } }
 }
 
-impl<C, T, A> Set<C, T, A>
-where C: TreeCompare<T, T>,
-      T: PartialEq + PartialOrd,
+impl<'a, C, K, V, A> VacantEntry<'a, C, K, V, A>
+where C: TreeCompare<K, K>,
+      K: PartialEq + PartialOrd,
       A: Allocator + Clone
 {
-    pub fn new_inner(alloc: A) -> Self { Self { _impl: Tree::new_inner(alloc) }}
-    pub fn insert(&mut self, value: T) -> bool { self._impl.insert(value) }
-    pub fn len(&self) -> usize { self._impl.len() }
-    pub fn is_empty(&self) -> bool { self._impl.is_empty() }
-    pub fn contains(&self, value: T) -> bool { self._impl.contains(value) }
-    pub fn find(&self, value: T) -> Option<&T> { self._impl.find(value) }
-    pub fn find_mut(&mut self, value: T) -> Option<&mut T> { self._impl.find_mut(value) }
+    pub fn key(&self) -> &K { &self.key }
+    pub fn into_key(self) -> K { self.key }
+    pub fn insert(self, value: V) -> &'a mut V {
+        unsafe {
+            let node = self.map._impl.new_node(MapPair::new(self.key, value));
+            self.map._impl.insert_node(self.parent, self.child, node);
+            &mut (*node).data.value
+        }
+    }
 }
 
-#[repr(C)]
-pub struct MapPair<K, V>
-where K: PartialEq + PartialOrd
+/// Owns a node that has been extracted from a tree (C++17 node_type). The node can be linked
+/// into another tree with the same node type without being reallocated.
+pub struct NodeHandle<T, A = Global>
+where A: Allocator + Clone
 {
-    key: K,
-    value: V
+    node: *mut TreeNode<T, A>,
+    _allocator: A
 }
 
-impl<K, V> MapPair<K, V>
-where K: PartialEq + PartialOrd
+impl<T, A> NodeHandle<T, A>
+where A: Allocator + Clone
 {
-    pub fn new(key: K, value: V) -> Self {
-        Self { key, value }
+    pub fn value(&self) -> &T { unsafe { &(*self.node).data } }
+    pub fn value_mut(&mut self) -> &mut T { unsafe { &mut (*self.node).data } }
+    /// Frees the node, returning its value
+    pub fn into_value(self) -> T {
+        let this = std::mem::ManuallyDrop::new(self);
+        unsafe { TreeNode::delete(this.node, this._allocator.clone()) }
+    }
+    fn into_raw(self) -> *mut TreeNode<T, A> {
+        std::mem::ManuallyDrop::new(self).node
     }
-    pub fn get_key(&self) -> &K { &self.key }
-    pub fn get_value(&self) -> &V { &self.value }
-    pub fn get_key_mut(&mut self) -> &mut K { &mut self.key }
-    pub fn get_value_mut(&mut self) -> &mut V { &mut self.value }
 }
 
-impl<K, V> MapPair<K, V>
-where K: PartialEq + PartialOrd + Copy + Hash
+impl<K, V, A> NodeHandle<MapPair<K, V>, A>
+where K: PartialEq + PartialOrd,
+      A: Allocator + Clone
 {
-    pub fn get_key_copy(&self) -> K { self.key }
+    pub fn key(&self) -> &K { self.value().get_key() }
+    /// The key can be changed, since the node isn't in a tree
+    pub fn key_mut(&mut self) -> &mut K { self.value_mut().get_key_mut() }
+    pub fn mapped(&self) -> &V { self.value().get_value() }
+    pub fn mapped_mut(&mut self) -> &mut V { self.value_mut().get_value_mut() }
 }
 
-impl<K, V> PartialEq for MapPair<K, V>
-where K: PartialEq + PartialOrd + Hash
+impl<T, A> Drop for NodeHandle<T, A>
+where A: Allocator + Clone
 {
-    fn eq(&self, other: &Self) -> bool {
-        self.key == other.key
+    fn drop(&mut self) {
+        drop(unsafe { TreeNode::delete(self.node, self._allocator.clone()) });
     }
 }
 
-impl<K, V> PartialEq<K> for MapPair<K, V>
-where K: PartialEq + PartialOrd + Hash
+// std::multiset, which keeps equivalent elements in insertion order
+#[repr(C)]
+pub struct MultiSet<C, T, A = Global>
+where C: TreeCompare<T, T>,
+      T: PartialEq + PartialOrd,
+      A: Allocator + Clone
 {
-    fn eq(&self, other: &K) -> bool {
-        self.key == *other
-    }
+    _impl: Tree<C, T, T, A>
 }
 
-impl<K, V> PartialOrd for MapPair<K, V>
-where K: PartialEq + PartialOrd + Hash
+impl<C, T, A> MultiSet<C, T, A>
+where C: TreeCompare<T, T>,
+      T: PartialEq + PartialOrd,
+      A: Allocator + Clone
 {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.key.partial_cmp(&other.key)
+    pub fn new_inner(alloc: A) -> Self
+    where C: Default
+    {
+        Self { _impl: Tree::new_inner(alloc) }
     }
-}
+    pub fn new_inner_with_compare(compare: C, alloc: A) -> Self {
+        Self { _impl: Tree::new_inner_with_compare(compare, alloc) }
+    }
+    pub fn key_comp(&self) -> &C { self._impl.key_comp() }
+    /// Inserts the value after any equivalent elements
+    pub fn insert(&mut self, value: T) -> &mut T { self._impl.insert_multi(value) }
+    pub fn len(&self) -> usize { self._impl.len() }
+    pub fn is_empty(&self) -> bool { self._impl.is_empty() }
+    pub fn clear(&mut self) { self._impl.clear() }
+    pub fn first(&self) -> Option<&T> { self._impl.first() }
+    pub fn last(&self) -> Option<&T> { self._impl.last() }
+    pub fn iter(&self) -> TreeIterator<'_, T, A> { self._impl.iter() }
 
-impl<K, V> PartialOrd<K> for MapPair<K, V>
-where K: PartialEq + PartialOrd + Hash
-{
-    fn partial_cmp(&self, other: &K) -> Option<std::cmp::Ordering> {
-        self.key.partial_cmp(other)
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.contains(value)
+    }
+    /// Finds the first element equivalent to value
+    pub fn find<Q>(&self, value: &Q) -> Option<&T>
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.find(value)
+    }
+    pub fn count<Q>(&self, value: &Q) -> usize
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.count(value)
+    }
+    pub fn equal_range<Q>(&self, value: &Q) -> TreeIterator<'_, T, A>
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.equal_range(value)
+    }
+    /// Removes every element equivalent to value, returning how many were removed
+    pub fn erase<Q>(&mut self, value: &Q) -> usize
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.remove_all(value)
+    }
+    /// Unlinks the first element equivalent to value, returning a handle that owns its node
+    pub fn extract<Q>(&mut self, value: &Q) -> Option<NodeHandle<T, A>>
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.extract_key(value)
+    }
+    /// Links the handle's node into the set after any equivalent elements
+    pub fn insert_node(&mut self, handle: NodeHandle<T, A>) -> &mut T {
+        self._impl.insert_handle_multi(handle)
+    }
+    pub fn lower_bound<Q>(&self, value: &Q) -> TreeIterator<'_, T, A>
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.lower_bound(value)
+    }
+    pub fn upper_bound<Q>(&self, value: &Q) -> TreeIterator<'_, T, A>
+    where T: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.upper_bound(value)
     }
 }
 
+// std::multimap, which keeps values for equivalent keys in insertion order
 #[repr(C)]
-pub struct Map<C, K, V, A>
-where C: TreeCompare<MapPair<K, V>, K>,
-      K: PartialEq + PartialOrd + Hash,
+pub struct MultiMap<C, K, V, A = Global>
+where C: TreeCompare<K, K>,
+      K: PartialEq + PartialOrd,
       A: Allocator + Clone
 {
     _impl: Tree<C, MapPair<K, V>, K, A>
 }
-impl<C, K, V, A> Map<C, K, V, A>
-where C: TreeCompare<MapPair<K, V>, K>,
-      K: PartialEq + PartialOrd + Hash,
+
+impl<C, K, V, A> MultiMap<C, K, V, A>
+where C: TreeCompare<K, K>,
+      K: PartialEq + PartialOrd,
       A: Allocator + Clone
 {
-    pub fn new_inner(alloc: A) -> Self { Self { _impl: Tree::new_inner(alloc) }}
-    pub fn insert(&mut self, key: K, value: V) -> bool { 
-        let pair = MapPair::new(key, value);
-        self._impl.insert(pair)
+    pub fn new_inner(alloc: A) -> Self
+    where C: Default
+    {
+        Self { _impl: Tree::new_inner(alloc) }
+    }
+    pub fn new_inner_with_compare(compare: C, alloc: A) -> Self {
+        Self { _impl: Tree::new_inner_with_compare(compare, alloc) }
+    }
+    pub fn key_comp(&self) -> &C { self._impl.key_comp() }
+    /// Inserts the pair after any elements with an equivalent key, returning the inserted value
+    pub fn insert(&mut self, key: K, value: V) -> &mut V {
+        self._impl.insert_multi(MapPair::new(key, value)).get_value_mut()
     }
     pub fn len(&self) -> usize { self._impl.len() }
     pub fn is_empty(&self) -> bool { self._impl.is_empty() }
-    pub fn find(&self, value: K) -> Option<&V> { 
-        self._impl.find(value).map(|v| v.get_value())
+    pub fn clear(&mut self) { self._impl.clear() }
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.contains(key)
+    }
+    /// Finds the value of the first element with the given key
+    pub fn find<Q>(&self, key: &Q) -> Option<&V>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.find(key).map(|v| v.get_value())
+    }
+    pub fn find_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.find_mut(key).map(|v| v.get_value_mut())
+    }
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.count(key)
+    }
+    pub fn equal_range<Q>(&self, key: &Q) -> TreeIterator<'_, MapPair<K, V>, A>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.equal_range(key)
+    }
+    /// Removes every element with the given key, returning how many were removed
+    pub fn erase<Q>(&mut self, key: &Q) -> usize
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.remove_all(key)
+    }
+    /// Unlinks the first element with the given key, returning a handle that owns its node
+    pub fn extract<Q>(&mut self, key: &Q) -> Option<NodeHandle<MapPair<K, V>, A>>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.extract_key(key)
+    }
+    /// Links the handle's node into the map after any elements with an equivalent key
+    pub fn insert_node(&mut self, handle: NodeHandle<MapPair<K, V>, A>) -> &mut V {
+        self._impl.insert_handle_multi(handle).get_value_mut()
+    }
+    pub fn lower_bound<Q>(&self, key: &Q) -> TreeIterator<'_, MapPair<K, V>, A>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.lower_bound(key)
     }
-    pub fn find_mut(&mut self, value: K) -> Option<&mut V> { 
-        self._impl.find_mut(value).map(|v| v.get_value_mut())
+    pub fn upper_bound<Q>(&self, key: &Q) -> TreeIterator<'_, MapPair<K, V>, A>
+    where K: KeyBorrow<Q>,
+          C: TreeCompare<Q, Q>,
+          Q: PartialEq + PartialOrd + ?Sized
+    {
+        self._impl.upper_bound(key)
     }
 
     pub fn iter(&self) -> TreeIterator<'_, MapPair<K, V>, A> {
-        self._impl.into_iter()
+        self._impl.iter()
+    }
+    pub fn iter_mut(&mut self) -> TreeIteratorMut<'_, MapPair<K, V>, A> {
+        self._impl.iter_mut()
     }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use crate::msvc::string::String;
     use super::{
+        CompareGreater,
         CompareLess, 
+        Entry,
+        FromSortedError,
+        Map,
+        MapPair,
+        MultiMap,
+        MultiSet,
         NodeColor,
+        Set,
         TreeCompare,
         Tree, 
         TreeNode
     };
 
-    use allocator_api2::alloc::Global;
-    use std::error::Error;
+    use allocator_api2::alloc::{ AllocError, Allocator, Global };
+    use std::{ alloc::Layout, cell::Cell, error::Error, ptr::NonNull };
 
     type TestReturn = Result<(), Box<dyn Error>>;
     type Node1 = TreeNode<u32, Global>;
 
+    #[allow(clippy::len_zero)]
     #[test]
     pub fn create_blank_tree() -> TestReturn {
         let tree: Tree<CompareLess, u32, u32, Global> = Tree::new_inner(Global);
@@ -743,6 +1819,7 @@ pub mod tests {
         }
     }
 
+    #[allow(clippy::assertions_on_constants)]
     #[test]
     pub fn tree_insert_entries() -> TestReturn {
         let mut tree: Tree<CompareLess, u32, u32, Global> = Tree::new_inner(Global);
@@ -981,23 +2058,394 @@ pub mod tests {
         Ok(())
     }
 
+    // Checks the red-black properties, parent links, the head's min/max pointers and that keys
+    // are unique
+    fn check_tree<C, T0, T1, A>(tree: &Tree<C, T0, T1, A>)
+    where C: TreeCompare<T1, T1>,
+          T0: super::KeyOf<T1>,
+          T1: PartialEq + PartialOrd,
+          A: Allocator + Clone
+    {
+        check_tree_links(tree);
+        for w in tree.traverse().windows(2) {
+            assert!(tree.compare.compare_aa(w[0].data.key_of(), w[1].data.key_of()), "Nodes should be in order");
+        }
+    }
+
+    // Same as check_tree, but allows equivalent keys
+    fn check_tree_links<C, T0, T1, A>(tree: &Tree<C, T0, T1, A>)
+    where C: TreeCompare<T1, T1>,
+          T0: super::KeyOf<T1>,
+          T1: PartialEq + PartialOrd,
+          A: Allocator + Clone
+    {
+        fn black_height<T, A: Allocator + Clone>(n: *const TreeNode<T, A>) -> usize {
+            unsafe {
+                if (*n).nil { return 1 }
+                if (*n).color == NodeColor::Red {
+                    assert!((*(*n).left).color == NodeColor::Black && (*(*n).right).color == NodeColor::Black, "Red node should have black children");
+                }
+                if !(*(*n).left).nil { assert!(std::ptr::eq((*(*n).left).parent, n), "Left child should point to parent"); }
+                if !(*(*n).right).nil { assert!(std::ptr::eq((*(*n).right).parent, n), "Right child should point to parent"); }
+                let left = black_height((*n).left);
+                assert!(left == black_height((*n).right), "Black height should be equal for both subtrees");
+                left + if (*n).color == NodeColor::Black { 1 } else { 0 }
+            }
+        }
+        let head = tree.get_head();
+        let nodes = tree.traverse();
+        assert!(nodes.len() == tree.len(), "Traversal should visit {} nodes instead of {}", tree.len(), nodes.len());
+        for w in nodes.windows(2) {
+            assert!(!tree.compare.compare_aa(w[1].data.key_of(), w[0].data.key_of()), "Nodes should be in order");
+        }
+        if tree.is_empty() {
+            assert!(std::ptr::eq(head.parent, head), "Root of empty tree should be the head");
+            return;
+        }
+        unsafe { assert!((*head.parent).color == NodeColor::Black, "Root should be black"); }
+        assert!(std::ptr::eq(head.left, nodes[0]), "Head's left should point to the smallest node");
+        assert!(std::ptr::eq(head.right, *nodes.last().unwrap()), "Head's right should point to the largest node");
+        black_height(head.parent);
+    }
+
+    // Deterministic shuffle of 1..=count
+    fn shuffled(count: u32) -> Vec<u32> {
+        (0..count).map(|i| (i * 37) % count + 1).collect()
+    }
+
     #[test]
     pub fn tree_find_entries() -> TestReturn {
+        let mut tree: Tree<CompareLess, u32, u32, Global> = Tree::new_inner(Global);
+        for i in shuffled(101) { tree.insert(i * 2); }
+        check_tree(&tree);
+        assert!(!tree.insert(100), "Duplicate entry should not be inserted");
+        assert!(tree.len() == 101, "Tree should have 101 entries instead of {}", tree.len());
+        for i in 1..=101 {
+            assert!(tree.find(&(i * 2)) == Some(&(i * 2)), "Tree should contain {}", i * 2);
+            assert!(!tree.contains(&(i * 2 + 1)), "Tree shouldn't contain {}", i * 2 + 1);
+        }
+        assert!(tree.first() == Some(&2) && tree.last() == Some(&202), "First and last should be 2 and 202");
+        assert!(tree.lower_bound(&50).next() == Some(&50), "lower_bound(50) should start at 50");
+        assert!(tree.lower_bound(&51).next() == Some(&52), "lower_bound(51) should start at 52");
+        assert!(tree.upper_bound(&50).next() == Some(&52), "upper_bound(50) should start at 52");
+        assert!(tree.upper_bound(&202).next().is_none(), "upper_bound(202) should be empty");
+        assert!(tree.iter().rev().copied().eq((1..=101).rev().map(|i| i * 2)), "Reverse iteration should be in descending order");
+        for v in tree.iter_mut() { *v += 1; }
+        assert!(tree.iter().copied().eq((1..=101).map(|i| i * 2 + 1)), "iter_mut should modify values in place");
         Ok(())
     }
 
     #[test]
     pub fn tree_remove_entries() -> TestReturn {
+        let mut tree: Tree<CompareLess, u32, u32, Global> = Tree::new_inner(Global);
+        for i in shuffled(200) { tree.insert(i); }
+        check_tree(&tree);
+        for (n, i) in shuffled(200).into_iter().filter(|i| i % 3 != 0).enumerate() {
+            assert!(tree.remove(&i), "{} should be removed", i);
+            assert!(!tree.remove(&i), "{} should already be removed", i);
+            if n % 16 == 0 { check_tree(&tree); }
+        }
+        check_tree(&tree);
+        assert!(tree.iter().copied().eq((1..=200).filter(|i| i % 3 == 0)), "Only multiples of 3 should remain");
+        assert!(tree.take(&3) == Some(3), "take should return the removed value");
+        assert!(tree.first() == Some(&6), "First entry should now be 6");
+        tree.clear();
+        check_tree(&tree);
+        assert!(tree.is_empty() && tree.first().is_none(), "Tree should be empty after clear");
+        tree.insert(5);
+        check_tree(&tree);
+        assert!(tree.remove(&5) && tree.is_empty(), "Removing only node should empty the tree");
+        check_tree(&tree);
         Ok(())
     }
 
     #[test]
     pub fn tree_as_set() -> TestReturn {
+        let mut set: Set<CompareLess, u32> = Set::new_inner(Global);
+        for i in [5, 3, 8, 1, 4, 7, 9, 2, 6] { assert!(set.insert(i), "{} should be inserted", i); }
+        assert!(!set.insert(4), "Duplicate value shouldn't be inserted");
+        assert!(set.iter().copied().eq(1..=9), "Set should iterate in ascending order");
+        assert!(set.remove(&4) && !set.contains(&4), "4 should be removed");
+        assert!(set.len() == 8, "Set should have 8 entries");
+        let mut greater: Set<CompareGreater, u32> = Set::new_inner(Global);
+        for i in 1..=9 { greater.insert(i); }
+        assert!(greater.iter().copied().eq((1..=9).rev()), "std::greater set should iterate in descending order");
+        assert!(greater.lower_bound(&5).copied().eq((1..=5).rev()), "lower_bound should follow comparator order");
         Ok(())
     }
 
     #[test]
     pub fn tree_as_map() -> TestReturn {
+        let mut map: Map<CompareLess, String, u32> = Map::new_inner(Global);
+        for (i, name) in ["Player", "Enemy0", "Enemy1", "Door", "Chest"].into_iter().enumerate() {
+            assert!(map.insert(String::from_str(name), i as u32), "{} should be inserted", name);
+        }
+        assert!(!map.insert(String::from_str("Door"), 10), "Duplicate key shouldn't be inserted");
+        assert!(map.find("Player") == Some(&0), "Player should be found with a &str");
+        assert!(map.contains("Enemy1") && !map.contains("Enemy2"), "Enemy1 should be in map but not Enemy2");
+        assert!(map.iter().map(|p| <&str>::from(p.get_key())).eq(["Chest", "Door", "Enemy0", "Enemy1", "Player"]),
+            "Map keys should iterate in byte order");
+        assert!(map.remove("Enemy0") == Some(1), "Removing Enemy0 should return its value");
+        assert!(map.remove("Enemy0").is_none(), "Enemy0 should already be removed");
+        *map.find_mut("Door").unwrap() += 10;
+        assert!(map.find("Door") == Some(&13), "Door should be modified in place");
+
+        *map.entry("Player").and_modify(|v| *v += 1).or_insert(100) += 1;
+        assert!(map.find("Player") == Some(&2), "Existing entry should be modified");
+        *map.entry("Key").or_insert(50) += 1;
+        assert!(map.find("Key") == Some(&51), "Vacant entry should be inserted");
+        assert!(*map.entry("Chest").or_default() == 4, "Occupied entry should keep its value");
+        match map.entry("Door") {
+            Entry::Occupied(e) => assert!(e.remove() == 13, "Removing Door entry should return its value"),
+            Entry::Vacant(_) => panic!("Door should be occupied")
+        };
+        assert!(map.len() == 4 && !map.contains("Door"), "Door should be removed through its entry");
+        check_tree(&map._impl);
+
+        let mut wide: Map<CompareLess, String<u16>, u32> = Map::new_inner(Global);
+        wide.insert(String::from_str_wide("Player"), 1);
+        wide.insert(String::from_str_wide("Door"), 2);
+        let key: Vec<u16> = "Door".encode_utf16().collect();
+        assert!(wide.find(key.as_slice()) == Some(&2), "Wide string key should be found with a &[u16]");
+        *wide.entry(&*"Chest".encode_utf16().collect::<Vec<_>>()).or_insert(0) += 3;
+        assert!(wide.find(String::from_str_wide("Chest").as_slice()) == Some(&3), "Vacant wide entry should be inserted");
+        Ok(())
+    }
+
+    // Orders by the remainder first, like a comparator holding a runtime setting
+    struct CompareModulo { modulus: u64 }
+    impl TreeCompare<u32, u32> for CompareModulo {
+        fn compare_aa(&self, d0: &u32, d1: &u32) -> bool {
+            (*d0 as u64 % self.modulus, d0) < (*d1 as u64 % self.modulus, d1)
+        }
+        fn compare_ab(&self, d0: &u32, d1: &u32) -> bool { self.compare_aa(d0, d1) }
+        fn compare_ba(&self, d0: &u32, d1: &u32) -> bool { self.compare_aa(d0, d1) }
+    }
+
+    // Case insensitive comparison through a case folding table, like a comparator using a locale
+    struct CompareFolded { table: *const [u8; 256] }
+    impl CompareFolded {
+        fn less(&self, d0: &[u8], d1: &[u8]) -> bool {
+            let table = unsafe { &*self.table };
+            d0.iter().map(|c| table[*c as usize]).lt(d1.iter().map(|c| table[*c as usize]))
+        }
+    }
+    impl TreeCompare<String, String> for CompareFolded {
+        fn compare_aa(&self, d0: &String, d1: &String) -> bool { self.less(d0.as_slice(), d1.as_slice()) }
+        fn compare_ab(&self, d0: &String, d1: &String) -> bool { self.compare_aa(d0, d1) }
+        fn compare_ba(&self, d0: &String, d1: &String) -> bool { self.compare_aa(d0, d1) }
+    }
+    impl TreeCompare<str, str> for CompareFolded {
+        fn compare_aa(&self, d0: &str, d1: &str) -> bool { self.less(d0.as_bytes(), d1.as_bytes()) }
+        fn compare_ab(&self, d0: &str, d1: &str) -> bool { self.compare_aa(d0, d1) }
+        fn compare_ba(&self, d0: &str, d1: &str) -> bool { self.compare_aa(d0, d1) }
+    }
+
+    #[test]
+    pub fn tree_stateful_compare() -> TestReturn {
+        assert!(std::mem::size_of::<Map<CompareLess, u32, u32>>() == 16, "Empty comparator should take no space");
+        let mut map = Map::new_inner_with_compare(CompareModulo { modulus: 3 }, Global);
+        assert!(std::mem::size_of_val(&map) == 24, "Stateful comparator should be stored in the tree");
+        let head_offset = &raw const map._impl.head as usize - &raw const map as usize;
+        assert!(head_offset == 8, "Head should be stored after the comparator instead of at {}", head_offset);
+        for i in 0..10 { map.insert(i, i * 10); }
+        check_tree(&map._impl);
+        let keys: Vec<u32> = map.iter().map(|p| *p.get_key()).collect();
+        assert!(keys == [0, 3, 6, 9, 1, 4, 7, 2, 5, 8], "Keys should be ordered by remainder, got {:?}", keys);
+        assert!(map.find(&7) == Some(&70) && map.remove(&4) == Some(40), "Lookups should use the stored comparator");
+        assert!(map.key_comp().modulus == 3, "key_comp should return the stored comparator");
+
+        let mut table = [0u8; 256];
+        for (i, c) in table.iter_mut().enumerate() { *c = (i as u8).to_ascii_lowercase(); }
+        let mut set = Set::new_inner_with_compare(CompareFolded { table: &raw const table }, Global);
+        for name in ["player", "Door", "CHEST", "Player"] { set.insert(String::from_str(name)); }
+        assert!(set.len() == 3, "Player should be a duplicate of player");
+        assert!(set.iter().map(<&str>::from).eq(["CHEST", "Door", "player"]), "Set should be ordered case insensitively");
+        assert!(set.contains("PLAYER") && set.contains("chest"), "Lookups should be case insensitive");
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_as_multimap() -> TestReturn {
+        // Drop table keyed by rarity, where each rarity has several items
+        let mut drops: MultiMap<CompareLess, u32, u32> = MultiMap::new_inner(Global);
+        for (rarity, item) in [(2, 200), (1, 100), (3, 300), (1, 101), (2, 201), (1, 102), (3, 301), (2, 202)] {
+            assert!(*drops.insert(rarity, item) == item, "Insert should return the new item");
+        }
+        check_tree_links(&drops._impl);
+        assert!(drops.len() == 8, "Drop table should have 8 items");
+        let items: Vec<u32> = drops.iter().map(|p| *p.get_value()).collect();
+        assert!(items == [100, 101, 102, 200, 201, 202, 300, 301], "Equal keys should keep insertion order, got {:?}", items);
+        assert!(drops.count(&1) == 3 && drops.count(&3) == 2 && drops.count(&4) == 0, "Counts should match");
+        let items: Vec<u32> = drops.equal_range(&2).map(|p| *p.get_value()).collect();
+        assert!(items == [200, 201, 202], "Equal range for 2 should be in insertion order, got {:?}", items);
+        let items: Vec<u32> = drops.equal_range(&2).rev().map(|p| *p.get_value()).collect();
+        assert!(items == [202, 201, 200], "Equal range should be double ended, got {:?}", items);
+        assert!(drops.equal_range(&0).next().is_none() && drops.equal_range(&4).next().is_none(), "Missing keys should have empty ranges");
+        assert!(drops.find(&3) == Some(&300), "Find should return the first equivalent element");
+
+        assert!(drops.erase(&2) == 3 && drops.erase(&2) == 0, "Erase should remove all items of rarity 2 once");
+        check_tree_links(&drops._impl);
+        let items: Vec<u32> = drops.iter().map(|p| *p.get_value()).collect();
+        assert!(items == [100, 101, 102, 300, 301], "Other rarities should be untouched, got {:?}", items);
+
+        // Priority schedule, with the highest priority first
+        let mut schedule: MultiMap<CompareGreater, u32, &str> = MultiMap::new_inner(Global);
+        for (priority, task) in [(1, "idle"), (5, "render"), (5, "audio"), (9, "input"), (1, "gc")] {
+            schedule.insert(priority, task);
+        }
+        let tasks: Vec<&str> = schedule.iter().map(|p| *p.get_value()).collect();
+        assert!(tasks == ["input", "render", "audio", "idle", "gc"], "Schedule should be by priority then insertion, got {:?}", tasks);
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_as_multiset() -> TestReturn {
+        let mut set: MultiSet<CompareLess, u32> = MultiSet::new_inner(Global);
+        for i in shuffled(300) { set.insert(i % 17); }
+        check_tree_links(&set._impl);
+        assert!(set.len() == 300, "Set should have 300 elements");
+        for k in 0..17 {
+            let expected = (1..=300).filter(|i| i % 17 == k).count();
+            assert!(set.count(&k) == expected, "Count for {} should be {} instead of {}", k, expected, set.count(&k));
+            assert!(set.equal_range(&k).all(|v| *v == k), "Equal range for {} should only have {}", k, k);
+        }
+        assert!(set.lower_bound(&5).next() == Some(&5) && set.upper_bound(&5).next() == Some(&6), "Bounds should skip over duplicates");
+        for k in (0..17).step_by(2) {
+            assert!(set.erase(&k) > 0, "{} should be erased", k);
+            check_tree_links(&set._impl);
+        }
+        assert!(set.iter().all(|v| v % 2 == 1), "Only odd values should remain");
+        assert!(set.first() == Some(&1) && set.last() == Some(&15), "First and last should be updated");
+        set.clear();
+        assert!(set.is_empty() && set.count(&1) == 0, "Cleared set should be empty");
+        Ok(())
+    }
+
+    thread_local! { static ALLOCATIONS: Cell<usize> = const { Cell::new(0) }; }
+
+    // Stands in for a game's allocator, counting how many allocations were made on this thread
+    #[derive(Clone, Copy)]
+    struct CountingAllocator;
+    unsafe impl Allocator for CountingAllocator {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            ALLOCATIONS.with(|c| c.set(c.get() + 1));
+            Global.allocate(layout)
+        }
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) { Global.deallocate(ptr, layout) }
+    }
+
+    #[test]
+    pub fn tree_node_handles() -> TestReturn {
+        let mut from: Map<CompareLess, u32, u32, CountingAllocator> = Map::new_inner(CountingAllocator);
+        let mut to: Map<CompareLess, u32, u32, CountingAllocator> = Map::new_inner(CountingAllocator);
+        for i in shuffled(40) { from.insert(i, i * 10); }
+        for i in [5, 10, 15] { to.insert(i, 0); }
+        let allocations = ALLOCATIONS.with(|c| c.get());
+
+        let address = &raw const *from.find(&7).unwrap();
+        let mut handle = from.extract(&7).unwrap();
+        assert!(handle.key() == &7 && handle.mapped() == &70, "Handle should own the extracted pair");
+        assert!(!from.contains(&7) && from.len() == 39, "Extracted key should be removed");
+        check_tree(&from._impl);
+        *handle.key_mut() = 100;
+        *handle.mapped_mut() = 1000;
+        let value = to.insert_node(handle).ok().unwrap();
+        assert!(std::ptr::eq(value, address), "Node should be linked without being reallocated");
+        assert!(to.find(&100) == Some(&1000) && to.len() == 4, "Handle should be inserted with its new key");
+        check_tree(&to._impl);
+
+        // a duplicate key gives the handle back
+        let handle = from.extract(&5).unwrap();
+        let handle = to.insert_node(handle).err().unwrap();
+        assert!(handle.key() == &5 && handle.mapped() == &50, "Duplicate key should return the handle");
+        assert!(from.insert_node(handle).is_ok() && from.find(&5) == Some(&50), "Handle should go back where it came from");
+
+        to.merge(&mut from);
+        assert!(to.len() == 40 && from.len() == 3, "Merge should move all non duplicate keys");
+        assert!(from.iter().map(|p| *p.get_key()).eq([5, 10, 15]), "Duplicate keys should be left in the source");
+        assert!(to.find(&10) == Some(&0) && to.find(&11) == Some(&110), "Merge should keep existing values");
+        check_tree(&to._impl);
+        check_tree(&from._impl);
+        assert!(allocations == ALLOCATIONS.with(|c| c.get()), "Moving nodes should not allocate");
+
+        let mut set: Set<CompareGreater, u32, CountingAllocator> = Set::new_inner(CountingAllocator);
+        let mut other: Set<CompareLess, u32, CountingAllocator> = Set::new_inner(CountingAllocator);
+        for i in 0..10 { other.insert(i); }
+        set.merge(&mut other);
+        assert!(set.iter().copied().eq((0..10).rev()) && other.is_empty(), "Merge should reorder by the destination's comparator");
+        let mut handle = set.extract(&3).unwrap();
+        *handle.value_mut() = 30;
+        assert!(handle.into_value() == 30 && set.len() == 9, "Handle should give up its value");
+
+        let mut multi: MultiMap<CompareLess, u32, u32> = MultiMap::new_inner(Global);
+        let mut single: Map<CompareLess, u32, u32> = Map::new_inner(Global);
+        single.insert(1, 10);
+        multi.insert(1, 11);
+        let handle: super::NodeHandle<MapPair<u32, u32>> = single.extract(&1).unwrap();
+        multi.insert_node(handle);
+        assert!(multi.equal_range(&1).map(|p| *p.get_value()).eq([11, 10]), "Handle should go after equivalent keys");
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_from_sorted() -> TestReturn {
+        for count in 0..=300u32 {
+            let mut set: Set<CompareLess, u32> = Set::from_sorted_iter(0..count)?;
+            check_tree(&set._impl);
+            assert!(set.len() == count as usize && set.iter().copied().eq(0..count), "Set of {} should be built in order", count);
+            assert!((0..count).all(|i| set.contains(&i)) && !set.contains(&count), "Set of {} should find every value", count);
+            // the tree should still rebalance correctly afterwards
+            set.insert(count);
+            set.remove(&(count / 2));
+            check_tree(&set._impl);
+        }
+
+        // item IDs to names, sorted by descending ID
+        let items: Vec<(u32, String)> = (0..1000).rev().map(|i| (i * 3, String::from_str(&format!("Item{}", i)))).collect();
+        let map: Map<CompareGreater, u32, String> = Map::from_sorted_vec(items)?;
+        check_tree(&map._impl);
+        assert!(map.len() == 1000 && map.iter().next().unwrap().get_key() == &2997, "Map should be ordered by descending ID");
+        assert!(map.find(&300).map(<&str>::from) == Some("Item100"), "Item 300 should be found");
+
+        let err = Set::<CompareLess, u32>::from_sorted_vec(vec![1, 2, 5, 4, 6]).err();
+        assert!(err == Some(FromSortedError::Unsorted(3)), "Out of order element should be reported, got {:?}", err);
+        let err = Map::<CompareLess, u32, u32>::from_sorted_iter([(1, 0), (2, 0), (2, 1)]).err();
+        assert!(err == Some(FromSortedError::Duplicate(2)), "Duplicate key should be reported, got {:?}", err);
+        let err = Set::<CompareGreater, u32>::from_sorted_vec(vec![1, 2]).err();
+        assert!(err == Some(FromSortedError::Unsorted(1)), "Order should follow the comparator, got {:?}", err);
+        Ok(())
+    }
+
+    #[test]
+    pub fn tree_set_algebra() -> TestReturn {
+        let unlocked: Set<CompareLess, u32> = Set::from_sorted_iter([1, 2, 4, 8, 16, 32])?;
+        let expected: Set<CompareLess, u32> = Set::from_sorted_iter([2, 3, 4, 5, 32, 64])?;
+        let empty: Set<CompareLess, u32> = Set::new_inner(Global);
+        let union: Vec<u32> = unlocked.union(&expected).copied().collect();
+        assert!(union == [1, 2, 3, 4, 5, 8, 16, 32, 64], "Union should merge both sets, got {:?}", union);
+        let both: Vec<u32> = unlocked.intersection(&expected).copied().collect();
+        assert!(both == [2, 4, 32], "Intersection should have shared values, got {:?}", both);
+        let missing: Vec<u32> = expected.difference(&unlocked).copied().collect();
+        assert!(missing == [3, 5, 64], "Difference should have expected values that aren't unlocked, got {:?}", missing);
+        let extra: Vec<u32> = unlocked.difference(&expected).copied().collect();
+        assert!(extra == [1, 8, 16], "Difference should have unlocked values that aren't expected, got {:?}", extra);
+        let changed: Vec<u32> = unlocked.symmetric_difference(&expected).copied().collect();
+        assert!(changed == [1, 3, 5, 8, 16, 64], "Symmetric difference should have values in one set, got {:?}", changed);
+        assert!(unlocked.union(&empty).eq(unlocked.iter()) && empty.intersection(&unlocked).next().is_none(), "Empty set should be an identity");
+
+        let subset: Set<CompareLess, u32> = Set::from_sorted_iter([2, 4])?;
+        assert!(subset.is_subset(&unlocked) && subset.is_subset(&expected), "{{2, 4}} should be a subset of both");
+        assert!(unlocked.is_superset(&subset) && !subset.is_superset(&unlocked), "Superset should be the reverse of subset");
+        assert!(!unlocked.is_subset(&expected) && unlocked.is_subset(&unlocked), "Subset should include equal sets");
+        assert!(empty.is_subset(&unlocked) && empty.is_disjoint(&unlocked), "Empty set should be a disjoint subset");
+        assert!(!unlocked.is_disjoint(&expected) && subset.is_disjoint(&Set::from_sorted_iter([1, 3])?), "Disjoint should check for shared values");
+
+        // the set's own ordering is used for the merge walk
+        let a: Set<CompareGreater, u32> = Set::from_sorted_iter([9, 5, 3])?;
+        let b: Set<CompareGreater, u32> = Set::from_sorted_iter([7, 5, 1])?;
+        let union: Vec<u32> = a.union(&b).copied().collect();
+        assert!(union == [9, 7, 5, 3, 1], "Union should follow the comparator, got {:?}", union);
         Ok(())
     }
 }
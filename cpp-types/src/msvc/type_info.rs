@@ -1,3 +1,4 @@
+use crate::msvc::hash::{ fnv1a_append_bytes, CppHash, FNV_OFFSET_BASIS };
 use std::{
     ffi::{ c_char, CStr },
     fmt::Debug,
@@ -44,6 +45,14 @@ impl Hash for TypeInfo {
     }
 }
 
+impl CppHash for TypeInfo {
+    // type_info::hash_code (__std_type_info_hash)
+    fn cpp_hash(&self) -> u64 {
+        let val = fnv1a_append_bytes(FNV_OFFSET_BASIS, self.get_decorated_name().as_bytes());
+        val ^ val >> 0x20
+    }
+}
+
 #[repr(C)]
 pub struct ObjectLocator {
     signature: u32,
@@ -58,4 +67,31 @@ impl ObjectLocator {
     pub fn get_struct_offset(&self) -> u32 {
         self.offset
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+pub mod tests {
+    use crate::msvc::hash::CppHash;
+    use super::TypeInfo;
+    use std::error::Error;
+
+    type TestReturn = Result<(), Box<dyn Error>>;
+
+    // type_info followed by its decorated name, as laid out in the RTTI data
+    #[repr(C)]
+    struct TypeDescriptor {
+        info: TypeInfo,
+        name: [u8; 13]
+    }
+
+    #[test]
+    pub fn type_info_hash_code() -> TestReturn {
+        let player = TypeDescriptor {
+            info: TypeInfo { cpp_vtable: std::ptr::null(), undecorated_name: std::ptr::null() },
+            name: *b".?AVPlayer@@\0"
+        };
+        assert!(player.info.get_decorated_name() == "?AVPlayer@@", "Decorated name should skip the leading '.'");
+        // __std_type_info_hash: FNV-1a over the decorated name, then value ^= value >> 32
+        assert!(player.info.cpp_hash() == 0xa69819963bd44a8a, "hash_code for class Player should be 0xa69819963bd44a8a");
+        Ok(())
+    }
+}
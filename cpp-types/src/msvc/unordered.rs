@@ -1,7 +1,9 @@
 #![allow(dead_code, unused_imports)]
 use allocator_api2::alloc::{ Allocator, Global };
+use crate::generic::lookup::{ KeyBorrow, KeyOf, ToKey };
+pub use crate::generic::tree::MapPair;
 use crate::msvc::{
-    hash::{ HasherInit, FNV1A },
+    hash::{ CppHash, EqualTo, HashFunction, HashParam, KeyEqual, FNV1A },
     list::{ 
         List, ListDoubleNode, ListSingleNode, 
         ListNode, ListIterator, ListIteratorMut 
@@ -20,114 +22,178 @@ use std::{
 
 const MIN_BUCKET_COUNT: usize = 8;
 
+// Iterators over the key-value pairs of a map
+type PairIterator<'a, K, V, A> = ListIterator<'a, ListNode<MapPair<K, V>, A>, MapPair<K, V>, A>;
+type PairIteratorMut<'a, K, V, A> = ListIteratorMut<'a, ListNode<MapPair<K, V>, A>, MapPair<K, V>, A>;
+
+// The first and last (inclusive) nodes of a run of equivalent elements
+type NodeRange<T, A> = (*mut ListNode<T, A>, *mut ListNode<T, A>);
+
+// _Uhash_compare<Kty, Hasher, Keyeq>, which is _Compressed_pair<Hasher, _Compressed_pair<Keyeq,
+// float>>. Empty function objects take no space, leaving just the max load factor.
 #[repr(C)]
 #[derive(Debug)]
-pub struct KeyEqual(f32); // load factor
+pub struct HashCompare<H, E> {
+    hasher: H,
+    key_equal: E,
+    max_load_factor: f32 // _Max_bucket_size
+}
 
 #[repr(C)]
-pub struct HashTable<H, T0, T1, A = Global>
-where H: Hasher + HasherInit,
-      T0: Hash + PartialEq<T1> + PartialEq, 
-      T1: Hash,
+pub struct HashTable<H, T0, T1, A = Global, E = EqualTo>
+where H: HashParam<Function: HashFunction<T1>>,
+      E: KeyEqual<T1>,
+      T0: KeyOf<T1>,
       A: Allocator + Clone
 {
-    _traits_obj: KeyEqual, // (key_eq)
-    list: List<ListNode<T0, A>, T0, A>, // list of elements
-    buckets: Vector<*mut ListNode<T0, A>, A>,
-    mask: usize,
-    max_index: usize,
+    _traits_obj: HashCompare<H::Function, E>, // _Traitsobj
+    list: List<ListNode<T0, A>, T0, A>, // _List
+    buckets: Vector<*mut ListNode<T0, A>, A>, // _Vec
+    mask: usize, // _Mask
+    max_index: usize, // _Maxidx
     _allocator: A,
-    _hash: PhantomData<H>,
     _key_ty: PhantomData<T1>
 }
 
-#[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum AttachType { Before, After, Empty }
-
-impl<H, T0, T1, A> HashTable<H, T0, T1, A>
-where H: Hasher + HasherInit,
-      T0: Hash + PartialEq<T1> + PartialEq, 
-      T1: Hash,
+impl<H, T0, T1, A, E> HashTable<H, T0, T1, A, E>
+where H: HashParam<Function: HashFunction<T1>>,
+      E: KeyEqual<T1>,
+      T0: KeyOf<T1>,
       A: Allocator + Clone
 {
     pub fn len(&self) -> usize { self.list.len() }
     pub fn is_empty(&self) -> bool { self.list.is_empty() }
 
-    pub fn new_inner(alloc: A) -> Self {
+    pub fn new_inner(alloc: A) -> Self
+    where H::Function: Default,
+          E: Default
+    {
+        Self::new_inner_with(H::Function::default(), E::default(), alloc)
+    }
+
+    pub fn new_inner_with(hasher: H::Function, key_equal: E, alloc: A) -> Self {
         assert!(std::mem::size_of::<A>() == 0, "Allocator must be zero-sized!");
         let list = List::new_in(alloc.clone());
         let mut buckets = Vector::new_in(alloc.clone());
         buckets.resize(MIN_BUCKET_COUNT << 1);
         for _ in 0..buckets.cap() { buckets.push(list.get_nil()) }
         Self {
-            _traits_obj: KeyEqual(1.0),
+            _traits_obj: HashCompare { hasher, key_equal, max_load_factor: 1.0 },
             list,
             buckets,
             mask: MIN_BUCKET_COUNT - 1,
             max_index: MIN_BUCKET_COUNT,
             _allocator: alloc,
-            _hash: PhantomData,
             _key_ty: PhantomData
         }
     }
 
-    fn get_max_load(&self) -> f32 { self._traits_obj.0 }
+    fn hash_key<Q>(&self, key: &Q) -> u64
+    where H: HashParam<Function: HashFunction<Q>>,
+          Q: ?Sized
+    {
+        self._traits_obj.hasher.hash_value(key)
+    }
+    fn hash_value(&self, value: &T0) -> u64 { self.hash_key::<T1>(value.key_of()) }
+
+    pub fn hash_function(&self) -> &H::Function { &self._traits_obj.hasher }
+    pub fn key_eq(&self) -> &E { &self._traits_obj.key_equal }
+    fn get_max_load(&self) -> f32 { self._traits_obj.max_load_factor }
     fn get_load_factor(&self) -> f32 { self.list.len() as f32 / self.max_index as f32 }
 
     pub fn bucket_count(&self) -> usize { self.max_index }
     fn max_bucket_count(&self) -> usize { (isize::MAX >> 1) as usize }
 
-    fn find_node_by_key(&self, key: &T1) -> Option<*mut ListNode<T0, A>> {
-        let bucket = (H::get_hash(key) as usize) & self.mask;
-        let mut curr = self.buckets[bucket << 1];
-        loop {
-            if unsafe { (&*curr).value() == key } { return Some(curr) }
-            curr = match unsafe { (&*curr).next(self.list.get_nil()) } {
-                Some(v) => &raw const *v as *mut ListNode<T0, A>,
-                None => return None
-            };
-        }
+    // _Find: like MSVC, this finds the last of any equivalent elements
+    fn find_node<Q>(&self, key: &Q) -> Option<*mut ListNode<T0, A>>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T1: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        unsafe { self.find_last(key, self.hash_key(key)).1 }
     }
     fn find_node_by_value(&self, value: &T0) -> Option<*mut ListNode<T0, A>> {
-        let bucket = (H::get_hash(value) as usize) & self.mask;
-        let mut curr = self.buckets[bucket << 1];
-        loop {
-            if unsafe { (&*curr).value() == value } { return Some(curr) }
-            curr = match unsafe { (&*curr).next(self.list.get_nil()) } {
-                Some(v) => &raw const *v as *mut ListNode<T0, A>,
-                None => return None
-            };
-        }
+        self.find_node::<T1>(value.key_of())
     }
 
-    fn find(&self, key: &T1) -> Option<&T0> {
-        self.find_node_by_key(key).map(|n| unsafe { (&*n).value() })
+    pub fn find<Q>(&self, key: &Q) -> Option<&T0>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T1: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self.find_node(key).map(|n| unsafe { (&*n).value() })
+    }
+    pub fn find_mut<Q>(&mut self, key: &Q) -> Option<&mut T0>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T1: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self.find_node(key).map(|n| unsafe { (&mut *n).value_mut() })
     }
-    fn find_mut(&mut self, key: &T1) -> Option<&mut T0> {
-        self.find_node_by_key(key).map(|n| unsafe { (&mut *n).value_mut() })
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T1: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self.find_node(key).is_some()
     }
-    fn contains(&self, key: &T1) -> bool { self.find_node_by_key(key).is_some() }
 
     fn iter(&self) -> ListIterator<'_, ListNode<T0, A>, T0, A> { self.into_iter() }
     fn iter_mut(&mut self) -> ListIteratorMut<'_, ListNode<T0, A>, T0, A> { self.into_iter() }
 
-    fn erase(&mut self, key: &T1) {
-        let target = self.find_node_by_key(key);
-        if target.is_none() { return; }
-        let bucket = (H::get_hash(key) as usize) & self.mask;
-        let target = unsafe { &*target.unwrap() };
+    /// Removes the element with the given key, returning it
+    pub fn take<Q>(&mut self, key: &Q) -> Option<T0>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T1: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        let target = self.find_node(key)?;
+        let bucket = (self.hash_key(key) as usize) & self.mask;
+        Some(unsafe { self.erase_node(target, bucket) })
+    }
+
+    /// Removes the element with the given key, returning if an element was removed
+    pub fn erase<Q>(&mut self, key: &Q) -> bool
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T1: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self.take(key).is_some()
+    }
+
+    // _Unchecked_erase: fixes up the bucket's range, then unlinks the node from the list
+    unsafe fn erase_node(&mut self, target: *mut ListNode<T0, A>, bucket: usize) -> T0 {
+        self.unlink_from_bucket(target, bucket);
+        self.list.remove_unchecked(target)
+    }
+
+    // Unlinks the node, leaving it owned by a node handle
+    unsafe fn extract_node(&mut self, target: *mut ListNode<T0, A>, bucket: usize) -> NodeHandle<T0, A> {
+        self.unlink_from_bucket(target, bucket);
+        self.list.unlink_node(target);
+        NodeHandle { node: target, _allocator: self._allocator.clone() }
+    }
+
+    // Shrinks the range of the node's bucket so it no longer covers the node
+    unsafe fn unlink_from_bucket(&mut self, target: *mut ListNode<T0, A>, bucket: usize) {
+        let target = &*target;
         let first = self.buckets[bucket << 1];
-        let last = self.buckets[bucket << 1 + 1];
+        let last = self.buckets[(bucket << 1) + 1];
         if std::ptr::addr_eq(last, target) {
             if std::ptr::addr_eq(first, target) {
                 // make bucket empty
                 self.buckets[bucket << 1] = self.list.get_nil();
-                self.buckets[bucket << 1 + 1] = self.list.get_nil();
+                self.buckets[(bucket << 1) + 1] = self.list.get_nil();
             } else {
                 // move end back one element
                 let prev = target.prev_ptr(self.list.get_nil());
-                self.buckets[bucket << 1 + 1] = match prev.map(|v| v.as_ptr()) {
+                self.buckets[(bucket << 1) + 1] = match prev.map(|v| v.as_ptr()) {
                     Some(v) => v, None => std::ptr::null_mut()
                 };
             }
@@ -139,91 +205,270 @@ where H: Hasher + HasherInit,
             };
             self.buckets[bucket << 1] = next;
         }
-        unsafe { self.list.remove_unchecked(&raw const *target as *mut ListNode<T0, A>) };
     }
 
 }
-impl<H, T0, T1, A> HashTable<H, T0, T1, A>
-where H: Hasher + HasherInit,
-      T0: Hash + PartialEq<T1> + PartialEq, 
-      T1: Hash,
+impl<H, T0, T1, A, E> HashTable<H, T0, T1, A, E>
+where H: HashParam<Function: HashFunction<T1>>,
+      E: KeyEqual<T1>,
+      T0: KeyOf<T1>,
       A: Allocator + Clone
 {
-    fn get_first_node_for_bucket(&self, value: &T0, bucket: usize) -> Result<Option<*mut ListNode<T0, A>>, ()> {
+    // _Find_last: searches the bucket backwards for key. Returns the node to insert a new element
+    // before, along with the last element equivalent to key if there is one. New elements go
+    // after their last equivalent, or at the start of the bucket (or end of the list if the
+    // bucket is empty).
+    unsafe fn find_last<Q>(&self, key: &Q, hash: u64) -> (*mut ListNode<T0, A>, Option<*mut ListNode<T0, A>>)
+    where E: KeyEqual<Q>,
+          T1: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        let bucket = hash as usize & self.mask;
+        let end = self.list.get_nil();
         let mut curr = self.buckets[(bucket << 1) + 1];
+        if curr == end { return (end, None) }
         let first = self.buckets[bucket << 1];
-        // bucket has no entries, so this is allowed, but no node exists
-        if curr == self.list.get_nil() { return Ok(None) }
         loop {
-            // don't allow this, duplicate entries aren't allowed
-            if unsafe { (&*curr).value() == value } { return Err(()) }
-            if std::ptr::eq(curr, first) { return Ok(Some(&raw const *curr as *mut ListNode<T0, A>))}
-            curr = match unsafe { (&*curr).prev(self.list.get_nil()) } {
-                // bucket has an entry and a node to attach onto
-                Some(v) => &raw const *v as *mut ListNode<T0, A>,
-                // we reached the beginning of the linked list, stop here
-                None => return Ok(Some(curr))
-            };
+            if self._traits_obj.key_equal.key_eq((*curr).value().key_of().key_borrow(), key) {
+                return ((*curr).next_raw(), Some(curr))
+            }
+            if curr == first { return (curr, None) }
+            curr = (*curr).prev_raw();
         }
     }
 
-    pub fn insert(&mut self, value: T0) -> bool {
-        // if (self.list.len() as isize) < 0 {
-        //     panic!("unordered_map/set too long");
-        // }
-        // _Hashval = _Traitsobj(_Keyval)
+    // _Insert_new_node_before: links the node into the list, then widens its bucket to cover it
+    unsafe fn insert_new_node_before(&mut self, hash: u64, before: *mut ListNode<T0, A>, node: *mut ListNode<T0, A>) {
+        let after = (*before).prev_raw();
+        self.list.link_node_before(before, node);
+        self.list.set_len(self.list.len() + 1);
+        let end = self.list.get_nil();
+        let bucket = hash as usize & self.mask;
+        if self.buckets[bucket << 1] == end {
+            // bucket is empty, set both
+            self.buckets[bucket << 1] = node;
+            self.buckets[(bucket << 1) + 1] = node;
+        } else if self.buckets[bucket << 1] == before {
+            // new node is the lowest element in the bucket
+            self.buckets[bucket << 1] = node;
+        } else if self.buckets[(bucket << 1) + 1] == after {
+            // new node is the highest element in the bucket
+            self.buckets[(bucket << 1) + 1] = node;
+        }
+    }
+
+    // _Emplace: returns the inserted node, or the existing node if multi is false and there's
+    // already an element with an equivalent key
+    fn emplace(&mut self, value: T0, multi: bool) -> (*mut ListNode<T0, A>, bool) {
+        let hash = self.hash_value(&value);
+        let (mut before, duplicate) = unsafe { self.find_last::<T1>(value.key_of(), hash) };
+        if let (Some(node), false) = (duplicate, multi) { return (node, false) }
+        if self.rehash_for_1() {
+            before = unsafe { self.find_last::<T1>(value.key_of(), hash).0 };
+        }
+        let node = ListNode::new(value, self._allocator.clone(), self.list.get_nil());
+        unsafe { self.insert_new_node_before(hash, before, node) };
+        (node, true)
+    }
+
+    // _Check_rehash_required_1 and _Rehash_for_1: grows the table if another element would go
+    // over the max load factor. Returns if the table was rehashed.
+    fn rehash_for_1(&mut self) -> bool {
         let required_buckets = (self.max_index as f32 * self.get_max_load()) as usize;
-        if self.list.len() >= required_buckets {
-            let new_bucket_count = if self.max_index < 512 { self.max_index * 8 } else { self.max_index * 2 };
-            self.resize(new_bucket_count);
+        if self.list.len() < required_buckets { return false }
+        let new_bucket_count = if self.max_index < 512 { self.max_index * 8 } else { self.max_index * 2 };
+        self.resize(new_bucket_count);
+        true
+    }
+
+    /// Unlinks the element with the given key, returning a handle that owns its node
+    pub fn extract_key<Q>(&mut self, key: &Q) -> Option<NodeHandle<T0, A>>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T1: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        let target = self.find_node(key)?;
+        let bucket = (self.hash_key(key) as usize) & self.mask;
+        Some(unsafe { self.extract_node(target, bucket) })
+    }
+
+    // _Insert_node_handle: links the handle's node without reallocating it, or gives the handle
+    // back if multi is false and there's already an element with an equivalent key
+    fn insert_handle_inner(&mut self, handle: NodeHandle<T0, A>, multi: bool) -> Result<*mut ListNode<T0, A>, NodeHandle<T0, A>> {
+        let node = handle.node;
+        let hash = self.hash_value(handle.value());
+        let (mut before, duplicate) = unsafe { self.find_last::<T1>((*node).value().key_of(), hash) };
+        if duplicate.is_some() && !multi { return Err(handle) }
+        if self.rehash_for_1() {
+            before = unsafe { self.find_last::<T1>((*node).value().key_of(), hash).0 };
+        }
+        unsafe { self.insert_new_node_before(hash, before, handle.into_raw()) };
+        Ok(node)
+    }
+
+    /// Links the handle's node into the table. If there's already an element with an
+    /// equivalent key, the handle is given back.
+    pub fn insert_handle(&mut self, handle: NodeHandle<T0, A>) -> Result<&mut T0, NodeHandle<T0, A>> {
+        self.insert_handle_inner(handle, false).map(|n| unsafe { (&mut *n).value_mut() })
+    }
+
+    /// Links the handle's node into the table after any elements with an equivalent key
+    pub fn insert_handle_multi(&mut self, handle: NodeHandle<T0, A>) -> &mut T0 {
+        match self.insert_handle_inner(handle, true) {
+            Ok(n) => unsafe { (&mut *n).value_mut() },
+            Err(_) => unreachable!("Multi insertion always succeeds")
         }
-        let bucket = H::get_hash(&value) as usize & self.mask;
-        let newnode = unsafe { &mut *ListNode::new(value, self._allocator.clone(), self.list.get_nil()) };
-        self.add_to_bucket_list(newnode, bucket, true)
-    }
-    fn add_to_bucket_list(&mut self, node: &mut ListNode<T0, A>, bucket: usize, not_in_list: bool) -> bool {
-        // attach the new node into the linked list
-        let (mode, attach_node) = match self.get_first_node_for_bucket(node.value(), bucket) {
-            Ok(v) => match v {
-                Some(v) => (AttachType::Before, Some(v)),
-                // if there's no bucket, attach to the end of the linked list
-                None => match self.list.last_mut() {
-                    Some(v) => (AttachType::After, Some(&raw mut *v)),
-                    None => (AttachType::Empty, None)
+    }
+
+    /// Moves every node from other whose key isn't already in this table, without
+    /// reallocating. Nodes with duplicate keys are left in other.
+    pub fn merge<H2, E2>(&mut self, other: &mut HashTable<H2, T0, T1, A, E2>)
+    where H2: HashParam<Function: HashFunction<T1>>,
+          E2: KeyEqual<T1>
+    {
+        let end = other.list.get_nil();
+        let mut curr = unsafe { (*end).next_raw() };
+        while curr != end {
+            let node = curr;
+            curr = unsafe { (*curr).next_raw() };
+            let key = unsafe { (*node).value().key_of() };
+            if unsafe { self.find_last::<T1>(key, self.hash_key(key)).1.is_some() } { continue }
+            let bucket = (other.hash_key(key) as usize) & other.mask;
+            let handle = unsafe { other.extract_node(node, bucket) };
+            drop(self.insert_handle_inner(handle, false));
+        }
+    }
+
+    pub fn insert(&mut self, value: T0) -> bool { self.emplace(value, false).1 }
+
+    /// Inserts a value even if there are elements with an equivalent key, placing it after them
+    pub fn insert_multi(&mut self, value: T0) -> &mut T0 {
+        let (node, _) = self.emplace(value, true);
+        unsafe { (&mut *node).value_mut() }
+    }
+
+    // _Equal_range: the first and last (inclusive) elements equivalent to key, which are adjacent
+    fn equal_range_nodes<Q>(&self, key: &Q) -> Option<NodeRange<T0, A>>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T1: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        let bucket = (self.hash_key(key) as usize) & self.mask;
+        let mut curr = self.buckets[bucket << 1];
+        let last = self.buckets[(bucket << 1) + 1];
+        if curr == self.list.get_nil() { return None }
+        let matches = |n: *mut ListNode<T0, A>| unsafe {
+            self._traits_obj.key_equal.key_eq((*n).value().key_of().key_borrow(), key)
+        };
+        loop {
+            if matches(curr) {
+                let first = curr;
+                while curr != last && matches(unsafe { (*curr).next_raw() }) {
+                    curr = unsafe { (*curr).next_raw() };
                 }
+                return Some((first, curr))
             }
-            Err(_) => return false
-        };
-        if not_in_list {
-            // manually set pointers for bucket list
-            match mode {
-                AttachType::Before => unsafe { self.list.link_before(&mut *attach_node.unwrap(), node) },
-                AttachType::After => unsafe { self.list.link_after(&mut *attach_node.unwrap(), node) },
-                AttachType::Empty => self.list.link_first(node),
-            };
-            unsafe { self.list.set_len(self.list.len() + 1); }
+            if curr == last { return None }
+            curr = unsafe { (*curr).next_raw() };
         }
-        if mode == AttachType::After || mode == AttachType::Empty {
-            self.buckets[(bucket << 1) + 1] = node;
-        }
-        self.buckets[bucket << 1] = node;
-        true
+    }
+
+    /// Iterates over every element with a key equivalent to key
+    pub fn equal_range<Q>(&self, key: &Q) -> ListIterator<'_, ListNode<T0, A>, T0, A>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T1: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        let nil = self.list.get_nil();
+        let (first, last) = self.equal_range_nodes(key).unwrap_or((nil, nil));
+        unsafe { ListIterator::from_range(nil, first, last) }
+    }
 
+    /// Returns the number of elements with a key equivalent to key
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T1: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self.equal_range(key).count()
     }
+
+    /// Removes every element with a key equivalent to key, returning how many were removed
+    pub fn erase_all<Q>(&mut self, key: &Q) -> usize
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T1: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        let (mut curr, last) = match self.equal_range_nodes(key) {
+            Some(v) => v,
+            None => return 0
+        };
+        let bucket = (self.hash_key(key) as usize) & self.mask;
+        let mut count = 1;
+        while curr != last {
+            let next = unsafe { (*curr).next_raw() };
+            unsafe { self.erase_node(curr, bucket) };
+            curr = next;
+            count += 1;
+        }
+        unsafe { self.erase_node(last, bucket) };
+        count
+    }
+
+    // _Forced_rehash: redistributes the elements into newsize buckets. Elements are spliced so
+    // each bucket stays contiguous in the list, with equivalent elements kept together in order.
     pub fn resize(&mut self, newsize: usize) {
         self.max_index = newsize;
         self.mask = newsize - 1;
         self.buckets.resize(newsize << 1);
+        let end = self.list.get_nil();
         for i in 0..(newsize << 1) {
-            unsafe { std::ptr::write(self.buckets.as_mut_ptr().add(i), self.list.get_nil()); }
+            unsafe { std::ptr::write(self.buckets.as_mut_ptr().add(i), end); }
         }
         unsafe { self.buckets.set_len(newsize << 1); }
-        // force borrow to split
-        let mut curr = self.list.first_mut().map(|f| unsafe { &mut *(&raw mut *f) });
-        while let Some(n) = curr {
-            let bucket = H::get_hash(n.value()) as usize & self.mask;
-            self.add_to_bucket_list(n, bucket, false);
-            curr = n.next_mut(self.list.get_nil());
+        let mut inserted = unsafe { (*end).next_raw() };
+        // remember the next node, since splicing moves the inserted node
+        while inserted != end {
+            let next_inserted = unsafe { (*inserted).next_raw() };
+            let bucket = self.hash_value(unsafe { (*inserted).value() }) as usize & self.mask;
+            let lo = self.buckets[bucket << 1];
+            let hi = self.buckets[(bucket << 1) + 1];
+            let equal = |n: *mut ListNode<T0, A>| unsafe {
+                self._traits_obj.key_equal.key_eq((*inserted).value().key_of(), (*n).value().key_of())
+            };
+            if lo == end {
+                // the bucket was empty, set it to the inserted element
+                self.buckets[bucket << 1] = inserted;
+                self.buckets[(bucket << 1) + 1] = inserted;
+            } else if equal(hi) {
+                // equivalent to the end of the bucket, insert after it
+                let before = unsafe { (*hi).next_raw() };
+                if before != inserted { unsafe { self.list.splice_node_before(before, inserted) } }
+                self.buckets[(bucket << 1) + 1] = inserted;
+            } else {
+                // search backwards to keep equivalent elements in order
+                let mut before = hi;
+                loop {
+                    if before == lo {
+                        // no equivalent keys in the bucket, insert it at the beginning
+                        unsafe { self.list.splice_node_before(before, inserted) };
+                        self.buckets[bucket << 1] = inserted;
+                        break;
+                    }
+                    before = unsafe { (*before).prev_raw() };
+                    if equal(before) {
+                        // found an equivalent element, insert after it
+                        unsafe { self.list.splice_node_before((*before).next_raw(), inserted) };
+                        break;
+                    }
+                }
+            }
+            inserted = next_inserted;
         }
     }
 
@@ -236,28 +481,32 @@ where H: Hasher + HasherInit,
 }
 
 // For debugging
-impl<H, T0, T1, A> HashTable<H, T0, T1, A>
-where H: Hasher + HasherInit,
-      T0: Hash + PartialEq<T1> + PartialEq, 
-      T1: Hash,
+impl<H, T0, T1, A, E> HashTable<H, T0, T1, A, E>
+where H: HashParam<Function: HashFunction<T1>>,
+      E: KeyEqual<T1>,
+      T0: KeyOf<T1>,
       A: Allocator + Clone
 {
     pub(super) fn get_bucket_mask(&self) -> usize { self.mask }
+    #[allow(clippy::unnecessary_mut_passed)]
     pub(super) unsafe fn get_bucket_first(&self, bucket: usize) -> Option<&T0> {
         let bp = self.buckets[bucket << 1];
         if bp == self.list.get_nil() { return None }
         Some((&mut *bp).value())
     }
+    #[allow(clippy::unnecessary_mut_passed)]
     pub(super) unsafe fn get_bucket_node_first(&self, bucket: usize) -> Option<&ListNode<T0, A>> {
         let bp = self.buckets[bucket << 1];
         if bp == self.list.get_nil() { return None }
         Some(&mut *bp)
     }
+    #[allow(clippy::unnecessary_mut_passed)]
     pub(super) unsafe fn get_bucket_last(&self, bucket: usize) -> Option<&T0> {
         let bp = self.buckets[(bucket << 1) + 1];
         if bp == self.list.get_nil() { return None }
         Some((&mut *bp).value())
     }
+    #[allow(clippy::unnecessary_mut_passed)]
     pub(super) unsafe fn get_bucket_node_last(&self, bucket: usize) -> Option<&ListNode<T0, A>> {
         let bp = self.buckets[(bucket << 1) + 1];
         if bp == self.list.get_nil() { return None }
@@ -273,13 +522,12 @@ where H: Hasher + HasherInit,
         }
         count
     }
-    pub(super) fn get_hasher(&self) -> H { H::new() }
 }
 
-impl<'a, H, T0, T1, A> IntoIterator for &'a HashTable<H, T0, T1, A>
-where H: Hasher + HasherInit,
-      T0: Hash + PartialEq<T1> + PartialEq, 
-      T1: Hash,
+impl<'a, H, T0, T1, A, E> IntoIterator for &'a HashTable<H, T0, T1, A, E>
+where H: HashParam<Function: HashFunction<T1>>,
+      E: KeyEqual<T1>,
+      T0: KeyOf<T1>,
       A: Allocator + Clone
 {
     type Item = &'a T0;
@@ -287,10 +535,10 @@ where H: Hasher + HasherInit,
     fn into_iter(self) -> Self::IntoIter { self.list.iter() }
 }
 
-impl<'a, H, T0, T1, A> IntoIterator for &'a mut HashTable<H, T0, T1, A>
-where H: Hasher + HasherInit,
-      T0: Hash + PartialEq<T1> + PartialEq, 
-      T1: Hash,
+impl<'a, H, T0, T1, A, E> IntoIterator for &'a mut HashTable<H, T0, T1, A, E>
+where H: HashParam<Function: HashFunction<T1>>,
+      E: KeyEqual<T1>,
+      T0: KeyOf<T1>,
       A: Allocator + Clone
 {
     type Item = &'a mut T0;
@@ -298,20 +546,20 @@ where H: Hasher + HasherInit,
     fn into_iter(self) -> Self::IntoIter { self.list.iter_mut() }
 }
 
-impl<'a, H, T0: 'a, T1, A> Index<&T1> for HashTable<H, T0, T1, A>
-where H: Hasher + HasherInit,
-      T0: Hash + PartialEq<T1> + PartialEq, 
-      T1: Hash,
+impl<H, T0, T1, A, E> Index<&T1> for HashTable<H, T0, T1, A, E>
+where H: HashParam<Function: HashFunction<T1>>,
+      E: KeyEqual<T1>,
+      T0: KeyOf<T1>,
       A: Allocator + Clone
 {
     type Output = T0;
     fn index(&self, index: &T1) -> &Self::Output { self.find(index).unwrap() }
 }
 
-impl<'a, H, T0: 'a, T1, A> IndexMut<&T1> for HashTable<H, T0, T1, A>
-where H: Hasher + HasherInit,
-      T0: Hash + PartialEq<T1> + PartialEq, 
-      T1: Hash,
+impl<H, T0, T1, A, E> IndexMut<&T1> for HashTable<H, T0, T1, A, E>
+where H: HashParam<Function: HashFunction<T1>>,
+      E: KeyEqual<T1>,
+      T0: KeyOf<T1>,
       A: Allocator + Clone
 {
     fn index_mut(&mut self, index: &T1) -> &mut Self::Output { self.find_mut(index).unwrap() }
@@ -321,28 +569,42 @@ where H: Hasher + HasherInit,
 
 // https://en.cppreference.com/w/cpp/container/unordered_set
 #[repr(C)]
-pub struct Set<H, T, A = Global>
-where H: Hasher + HasherInit,
-      T: Hash + PartialEq, 
+pub struct Set<H, T, A = Global, E = EqualTo>
+where H: HashParam<Function: HashFunction<T>>,
+      E: KeyEqual<T>,
       A: Allocator + Clone
-{ _impl: HashTable<H, T, T, A> }
+{ _impl: HashTable<H, T, T, A, E> }
 
 // C++ API
-impl<T> Set<FNV1A, T, Global>
+impl<T> Set<FNV1A, T, Global, EqualTo>
 where T: Hash + PartialEq
 {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self { Self::new_in(Global) }
 }
 
-impl<H, T, A> Set<H, T, A>
-where H: Hasher + HasherInit,
-      T: Hash + PartialEq, 
+impl<H, T, A, E> Set<H, T, A, E>
+where H: HashParam<Function: HashFunction<T>>,
+      E: KeyEqual<T>,
       A: Allocator + Clone
 {
     /// Constructs the unordered_set
-    pub fn new_in(alloc: A) -> Self {
+    pub fn new_in(alloc: A) -> Self
+    where H::Function: Default,
+          E: Default
+    {
         Self { _impl: HashTable::new_inner(alloc) }
     }
+    /// Constructs the unordered_set using the given hash and key equality function objects
+    pub fn with_hasher_in(hasher: H::Function, key_equal: E, alloc: A) -> Self {
+        Self { _impl: HashTable::new_inner_with(hasher, key_equal, alloc) }
+    }
+    /// Returns the function that hashes the keys
+    pub fn hash_function(&self) -> &H::Function { self._impl.hash_function() }
+    /// Returns the function that compares keys for equality
+    pub fn key_eq(&self) -> &E { self._impl.key_eq() }
+    /// Returns the maximum average number of elements per bucket
+    pub fn max_load_factor(&self) -> f32 { self._impl.get_max_load() }
     /// Checks if the container has no elements
     pub fn empty(&self) -> bool { self._impl.is_empty() }
     /// Returns the number of elements in the container,
@@ -350,17 +612,66 @@ where H: Hasher + HasherInit,
     /// Returns the maximum number of elements the container is able to hold due to system 
     /// or library implementation limitations
     pub fn max_size(&self) -> usize { self._impl.buckets.max_size() }
+    /// Returns the number of buckets in the container
+    pub fn bucket_count(&self) -> usize { self._impl.bucket_count() }
     /// Erases all elements from the container. After this call, size() returns zero.
     pub fn clear(&mut self) { self._impl.clear() }
     /// Inserts an element into the container, if the container doesn't already contain an 
     /// element with an equivalent key. This returns a bool that notes if the insertion took place
     pub fn insert(&mut self, value: T) -> bool { self._impl.insert(value) }
-    pub fn erase(&mut self, val: &T) { self._impl.erase(val) }
+    /// Removes the element equivalent to value, returning if an element was removed
+    pub fn erase<Q>(&mut self, value: &Q) -> bool
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.erase(value)
+    }
+    /// Unlinks the element equivalent to value, returning a handle that owns its node
+    pub fn extract<Q>(&mut self, value: &Q) -> Option<NodeHandle<T, A>>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.extract_key(value)
+    }
+    /// Links the handle's node into the set, or gives the handle back if the value is already
+    /// in the set
+    pub fn insert_node(&mut self, handle: NodeHandle<T, A>) -> Result<&mut T, NodeHandle<T, A>> {
+        self._impl.insert_handle(handle)
+    }
+    /// Moves the nodes for values that aren't in this set from other
+    pub fn merge<H2, E2>(&mut self, other: &mut Set<H2, T, A, E2>)
+    where H2: HashParam<Function: HashFunction<T>>,
+          E2: KeyEqual<T>
+    {
+        self._impl.merge(&mut other._impl)
+    }
+    /// Finds an element equivalent to value
+    pub fn find<Q>(&self, value: &Q) -> Option<&T>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.find(value)
+    }
+    /// Checks if there is an element equivalent to value in the container
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.contains(value)
+    }
 }
 
-impl<'a, H, T, A> IntoIterator for &'a Set<H, T, A>
-where H: Hasher + HasherInit,
-      T: Hash + PartialEq, 
+impl<'a, H, T, A, E> IntoIterator for &'a Set<H, T, A, E>
+where H: HashParam<Function: HashFunction<T>>,
+      E: KeyEqual<T>,
       A: Allocator + Clone
 {
     type Item = &'a T;
@@ -368,9 +679,9 @@ where H: Hasher + HasherInit,
     fn into_iter(self) -> Self::IntoIter { self._impl.iter() }
 }
 
-impl<'a, H, T, A> IntoIterator for &'a mut Set<H, T, A>
-where H: Hasher + HasherInit,
-      T: Hash + PartialEq, 
+impl<'a, H, T, A, E> IntoIterator for &'a mut Set<H, T, A, E>
+where H: HashParam<Function: HashFunction<T>>,
+      E: KeyEqual<T>,
       A: Allocator + Clone
 {
     type Item = &'a mut T;
@@ -378,18 +689,18 @@ where H: Hasher + HasherInit,
     fn into_iter(self) -> Self::IntoIter { self._impl.iter_mut() }
 }
 
-impl<'a, H, T: 'a, A> Index<&T> for Set<H, T, A>
-where H: Hasher + HasherInit,
-      T: Hash + PartialEq, 
+impl<H, T, A, E> Index<&T> for Set<H, T, A, E>
+where H: HashParam<Function: HashFunction<T>>,
+      E: KeyEqual<T>,
       A: Allocator + Clone
 {
     type Output = T;
     fn index(&self, index: &T) -> &Self::Output { &self._impl[index] }
 }
 
-impl<'a, H, T: 'a, A> IndexMut<&T> for Set<H, T, A>
-where H: Hasher + HasherInit,
-      T: Hash + PartialEq, 
+impl<H, T, A, E> IndexMut<&T> for Set<H, T, A, E>
+where H: HashParam<Function: HashFunction<T>>,
+      E: KeyEqual<T>,
       A: Allocator + Clone
 {
     fn index_mut(&mut self, index: &T) -> &mut Self::Output { &mut self._impl[index] }
@@ -397,78 +708,48 @@ where H: Hasher + HasherInit,
 
 // https://en.cppreference.com/w/cpp/container/unordered_map
 
-#[repr(C)]
-pub struct MapPair<K, V>
-where K: PartialEq + Hash
-{
-    key: K,
-    value: V
-}
-
-impl<K, V> MapPair<K, V>
-where K: PartialEq + Hash
-{
-    pub fn new(key: K, value: V) -> Self {
-        Self { key, value }
-    }
-    pub fn get_key(&self) -> &K { &self.key }
-    pub fn get_value(&self) -> &V { &self.value }
-    pub fn get_key_mut(&mut self) -> &mut K { &mut self.key }
-    pub fn get_value_mut(&mut self) -> &mut V { &mut self.value }
-}
-
-impl<K, V> MapPair<K, V>
-where K: PartialEq + Copy + Hash
+impl<K, V> CppHash for MapPair<K, V>
+where K: CppHash
 {
-    pub fn get_key_copy(&self) -> K { self.key }
-}
-
-impl<K, V> Hash for MapPair<K, V>
-where K: PartialEq + Hash
-{
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.key.hash(state)
-    }
-}
-
-impl<K, V> PartialEq for MapPair<K, V>
-where K: PartialEq + Hash
-{
-    fn eq(&self, other: &Self) -> bool {
-        self.key == other.key
-    }
-}
-
-impl<K, V> PartialEq<K> for MapPair<K, V>
-where K: PartialEq + Hash
-{
-    fn eq(&self, other: &K) -> bool {
-        self.key == *other
-    }
+    fn cpp_hash(&self) -> u64 { self.key.cpp_hash() }
 }
 
 #[repr(C)]
-pub struct Map<H, K, V, A = Global>
-where H: Hasher + HasherInit,
-      K: PartialEq + Hash,
+pub struct Map<H, K, V, A = Global, E = EqualTo>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
       A: Allocator + Clone
-{ _impl: HashTable<H, MapPair<K, V>, K, A> }
+{ _impl: HashTable<H, MapPair<K, V>, K, A, E> }
 // C++ API
-impl<K, V> Map<FNV1A, K, V, Global>
+impl<K, V> Map<FNV1A, K, V, Global, EqualTo>
 where K: PartialEq + Hash
 {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self { Self::new_in(Global) }
 }
 
-impl<H, K, V, A> Map<H, K, V, A>
-where H: Hasher + HasherInit,
-      K: PartialEq + Hash,
+impl<H, K, V, A, E> Map<H, K, V, A, E>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
       A: Allocator + Clone
 {
     /// Constructs the unordered_map
-    pub fn new_in(alloc: A) -> Self {
+    pub fn new_in(alloc: A) -> Self
+    where H::Function: Default,
+          E: Default
+    {
         Self { _impl: HashTable::new_inner(alloc) }
     }
+    /// Constructs the unordered_map using the given hash and key equality function objects
+    pub fn with_hasher_in(hasher: H::Function, key_equal: E, alloc: A) -> Self {
+        Self { _impl: HashTable::new_inner_with(hasher, key_equal, alloc) }
+    }
+    /// Returns the function that hashes the keys
+    pub fn hash_function(&self) -> &H::Function { self._impl.hash_function() }
+    /// Returns the function that compares keys for equality
+    pub fn key_eq(&self) -> &E { self._impl.key_eq() }
+    /// Returns the maximum average number of elements per bucket
+    pub fn max_load_factor(&self) -> f32 { self._impl.get_max_load() }
     /// Checks if the container has no elements
     pub fn empty(&self) -> bool { self._impl.is_empty() }
     /// Returns the number of elements in the container,
@@ -476,6 +757,8 @@ where H: Hasher + HasherInit,
     /// Returns the maximum number of elements the container is able to hold due to system 
     /// or library implementation limitations
     pub fn max_size(&self) -> usize { self._impl.buckets.max_size() }
+    /// Returns the number of buckets in the container
+    pub fn bucket_count(&self) -> usize { self._impl.bucket_count() }
     /// Erases all elements from the container. After this call, size() returns zero.
     pub fn clear(&mut self) { self._impl.clear() }
     /// Inserts an element into the container, if the container doesn't already contain an 
@@ -484,22 +767,193 @@ where H: Hasher + HasherInit,
         let pair = MapPair::new(key, value);
         self._impl.insert(pair) 
     }
+    #[allow(clippy::type_complexity)]
     pub fn iter(&self) -> ListIterator<'_, ListNode<MapPair<K, V>, A>, MapPair<K, V>, A> { self.into_iter() }
+    #[allow(clippy::type_complexity)]
     pub fn iter_mut(&mut self) -> ListIteratorMut<'_, ListNode<MapPair<K, V>, A>, MapPair<K, V>, A> { self.into_iter() }
 
-    pub fn find(&self, key: &K) -> Option<&MapPair<K, V>> {
+    /// Finds an element with key equivalent to key
+    pub fn find<Q>(&self, key: &Q) -> Option<&MapPair<K, V>>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ?Sized
+    {
         self._impl.find(key)
     }
-    pub fn find_mut(&mut self, key: &K) -> Option<&mut MapPair<K, V>> {
+    pub fn find_mut<Q>(&mut self, key: &Q) -> Option<&mut MapPair<K, V>>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ?Sized
+    {
         self._impl.find_mut(key)
     }
-    pub fn contains(&self, key: &K) -> bool { self._impl.contains(key) }
-    pub fn erase(&mut self, val: &K) { self._impl.erase(val) }
+    /// Checks if there is an element with key equivalent to key in the container
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.contains(key)
+    }
+    /// Removes the element with key equivalent to key, returning if an element was removed
+    pub fn erase<Q>(&mut self, key: &Q) -> bool
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.erase(key)
+    }
+    /// Removes the element with key equivalent to key, returning its value
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.take(key).map(|p| p.value)
+    }
+    /// Unlinks the element with key equivalent to key, returning a handle that owns its node
+    pub fn extract<Q>(&mut self, key: &Q) -> Option<NodeHandle<MapPair<K, V>, A>>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.extract_key(key)
+    }
+    /// Links the handle's node into the map, or gives the handle back if the key is already in
+    /// the map
+    pub fn insert_node(&mut self, handle: NodeHandle<MapPair<K, V>, A>) -> Result<&mut V, NodeHandle<MapPair<K, V>, A>> {
+        self._impl.insert_handle(handle).map(|p| p.get_value_mut())
+    }
+    /// Moves the nodes for keys that aren't in this map from other
+    pub fn merge<H2, E2>(&mut self, other: &mut Map<H2, K, V, A, E2>)
+    where H2: HashParam<Function: HashFunction<K>>,
+          E2: KeyEqual<K>
+    {
+        self._impl.merge(&mut other._impl)
+    }
+    /// Gets the entry for a key for in-place manipulation. If the key is vacant, it's only
+    /// converted into an owned key (which may allocate) once.
+    pub fn entry<Q>(&mut self, key: &Q) -> Entry<'_, H, K, V, A, E>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ToKey<K> + ?Sized
+    {
+        match self._impl.find_node(key) {
+            Some(node) => Entry::Occupied(OccupiedEntry { map: self, node }),
+            None => Entry::Vacant(VacantEntry { key: key.to_key(), map: self })
+        }
+    }
 }
 
-impl<'a, H, K, V, A> IntoIterator for &'a Map<H, K, V, A>
-where H: Hasher + HasherInit,
-      K: PartialEq + Hash,
+/// A view into a single entry of a [`Map`], which is either vacant or occupied
+pub enum Entry<'a, H, K, V, A = Global, E = EqualTo>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
+      A: Allocator + Clone
+{
+    Occupied(OccupiedEntry<'a, H, K, V, A, E>),
+    Vacant(VacantEntry<'a, H, K, V, A, E>)
+}
+
+pub struct OccupiedEntry<'a, H, K, V, A = Global, E = EqualTo>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
+      A: Allocator + Clone
+{
+    map: &'a mut Map<H, K, V, A, E>,
+    node: *mut ListNode<MapPair<K, V>, A>
+}
+
+pub struct VacantEntry<'a, H, K, V, A = Global, E = EqualTo>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
+      A: Allocator + Clone
+{
+    map: &'a mut Map<H, K, V, A, E>,
+    key: K
+}
+
+impl<'a, H, K, V, A, E> Entry<'a, H, K, V, A, E>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
+      A: Allocator + Clone
+{
+    pub fn key(&self) -> &K {
+        match self {
+            Self::Occupied(e) => e.key(),
+            Self::Vacant(e) => e.key()
+        }
+    }
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Self::Occupied(e) => e.into_mut(),
+            Self::Vacant(e) => e.insert(default)
+        }
+    }
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where F: FnOnce() -> V
+    {
+        match self {
+            Self::Occupied(e) => e.into_mut(),
+            Self::Vacant(e) => e.insert(default())
+        }
+    }
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where F: FnOnce(&mut V)
+    {
+        if let Self::Occupied(e) = &mut self { f(e.get_mut()) }
+        self
+    }
+}
+
+impl<'a, H, K, V, A, E> Entry<'a, H, K, V, A, E>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
+      V: Default,
+      A: Allocator + Clone
+{
+    pub fn or_default(self) -> &'a mut V { self.or_insert_with(V::default) }
+}
+
+impl<'a, H, K, V, A, E> OccupiedEntry<'a, H, K, V, A, E>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
+      A: Allocator + Clone
+{
+    pub fn key(&self) -> &K { unsafe { (&*self.node).value().get_key() } }
+    pub fn get(&self) -> &V { unsafe { (&*self.node).value().get_value() } }
+    pub fn get_mut(&mut self) -> &mut V { unsafe { (&mut *self.node).value_mut().get_value_mut() } }
+    pub fn into_mut(self) -> &'a mut V { unsafe { (&mut *self.node).value_mut().get_value_mut() } }
+    pub fn insert(&mut self, value: V) -> V { std::mem::replace(self.get_mut(), value) }
+    pub fn remove(self) -> V {
+        let bucket = self.map._impl.hash_value(unsafe { (&*self.node).value() }) as usize & self.map._impl.mask;
+        unsafe { self.map._impl.erase_node(self.node, bucket).value }
+    }
+}
+
+impl<'a, H, K, V, A, E> VacantEntry<'a, H, K, V, A, E>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
+      A: Allocator + Clone
+{
+    pub fn key(&self) -> &K { &self.key }
+    pub fn into_key(self) -> K { self.key }
+    pub fn insert(self, value: V) -> &'a mut V {
+        let (node, _) = self.map._impl.emplace(MapPair::new(self.key, value), false);
+        unsafe { (&mut *node).value_mut().get_value_mut() }
+    }
+}
+
+impl<'a, H, K, V, A, E> IntoIterator for &'a Map<H, K, V, A, E>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
       A: Allocator + Clone
 {
     type Item = &'a MapPair<K, V>;
@@ -507,9 +961,9 @@ where H: Hasher + HasherInit,
     fn into_iter(self) -> Self::IntoIter { self._impl.iter() }
 }
 
-impl<'a, H, K, V, A> IntoIterator for &'a mut Map<H, K, V, A>
-where H: Hasher + HasherInit,
-      K: PartialEq + Hash,
+impl<'a, H, K, V, A, E> IntoIterator for &'a mut Map<H, K, V, A, E>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
       A: Allocator + Clone
 {
     type Item = &'a mut MapPair<K, V>;
@@ -517,23 +971,307 @@ where H: Hasher + HasherInit,
     fn into_iter(self) -> Self::IntoIter { self._impl.iter_mut() }
 }
 
-impl<'a, H, K, V, A> Index<&K> for Map<H, K, V, A>
-where H: Hasher + HasherInit,
-      K: PartialEq + Hash,
+impl<H, K, V, A, E> Index<&K> for Map<H, K, V, A, E>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
       A: Allocator + Clone
 {
     type Output = MapPair<K, V>;
     fn index(&self, index: &K) -> &Self::Output { &self._impl[index] }
 }
 
-impl<'a, H, K, V, A> IndexMut<&K> for Map<H, K, V, A>
-where H: Hasher + HasherInit,
-      K: PartialEq + Hash,
+impl<H, K, V, A, E> IndexMut<&K> for Map<H, K, V, A, E>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
       A: Allocator + Clone
 {
     fn index_mut(&mut self, index: &K) -> &mut Self::Output { &mut self._impl[index] }
 }
 
+/// Owns a node that has been extracted from a hash table (C++17 node_type). The node can be
+/// linked into another table with the same node type without being reallocated.
+pub struct NodeHandle<T, A = Global>
+where A: Allocator + Clone
+{
+    node: *mut ListNode<T, A>,
+    _allocator: A
+}
+
+impl<T, A> NodeHandle<T, A>
+where A: Allocator + Clone
+{
+    pub fn value(&self) -> &T { unsafe { (*self.node).value() } }
+    pub fn value_mut(&mut self) -> &mut T { unsafe { (*self.node).value_mut() } }
+    /// Frees the node, returning its value
+    pub fn into_value(self) -> T {
+        let this = std::mem::ManuallyDrop::new(self);
+        unsafe { ListNode::free(this.node, this._allocator.clone()) }
+    }
+    fn into_raw(self) -> *mut ListNode<T, A> {
+        std::mem::ManuallyDrop::new(self).node
+    }
+}
+
+impl<K, V, A> NodeHandle<MapPair<K, V>, A>
+where A: Allocator + Clone
+{
+    pub fn key(&self) -> &K { self.value().get_key() }
+    /// The key can be changed, since the node isn't in a table
+    pub fn key_mut(&mut self) -> &mut K { self.value_mut().get_key_mut() }
+    pub fn mapped(&self) -> &V { self.value().get_value() }
+    pub fn mapped_mut(&mut self) -> &mut V { self.value_mut().get_value_mut() }
+}
+
+impl<T, A> Drop for NodeHandle<T, A>
+where A: Allocator + Clone
+{
+    fn drop(&mut self) {
+        drop(unsafe { ListNode::free(self.node, self._allocator.clone()) });
+    }
+}
+
+// https://en.cppreference.com/w/cpp/container/unordered_multiset
+#[repr(C)]
+pub struct MultiSet<H, T, A = Global, E = EqualTo>
+where H: HashParam<Function: HashFunction<T>>,
+      E: KeyEqual<T>,
+      A: Allocator + Clone
+{ _impl: HashTable<H, T, T, A, E> }
+
+// C++ API
+impl<T> MultiSet<FNV1A, T, Global, EqualTo>
+where T: Hash + PartialEq
+{
+    pub fn new() -> Self { Self::new_in(Global) }
+}
+
+impl<T> Default for MultiSet<FNV1A, T, Global, EqualTo>
+where T: Hash + PartialEq
+{
+    fn default() -> Self { Self::new() }
+}
+
+impl<H, T, A, E> MultiSet<H, T, A, E>
+where H: HashParam<Function: HashFunction<T>>,
+      E: KeyEqual<T>,
+      A: Allocator + Clone
+{
+    /// Constructs the unordered_multiset
+    pub fn new_in(alloc: A) -> Self
+    where H::Function: Default,
+          E: Default
+    {
+        Self { _impl: HashTable::new_inner(alloc) }
+    }
+    /// Constructs the unordered_multiset using the given hash and key equality function objects
+    pub fn with_hasher_in(hasher: H::Function, key_equal: E, alloc: A) -> Self {
+        Self { _impl: HashTable::new_inner_with(hasher, key_equal, alloc) }
+    }
+    /// Checks if the container has no elements
+    pub fn empty(&self) -> bool { self._impl.is_empty() }
+    /// Returns the number of elements in the container
+    pub fn size(&self) -> usize { self._impl.len() }
+    /// Returns the number of buckets in the container
+    pub fn bucket_count(&self) -> usize { self._impl.bucket_count() }
+    /// Erases all elements from the container. After this call, size() returns zero.
+    pub fn clear(&mut self) { self._impl.clear() }
+    /// Inserts an element into the container. Elements with an equivalent key are kept together,
+    /// with the new element placed after them.
+    pub fn insert(&mut self, value: T) -> &mut T { self._impl.insert_multi(value) }
+    /// Removes all elements equivalent to value, returning how many were removed
+    pub fn erase<Q>(&mut self, value: &Q) -> usize
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.erase_all(value)
+    }
+    /// Unlinks the last element equivalent to value, returning a handle that owns its node
+    pub fn extract<Q>(&mut self, value: &Q) -> Option<NodeHandle<T, A>>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.extract_key(value)
+    }
+    /// Links the handle's node into the set after any equivalent elements
+    pub fn insert_node(&mut self, handle: NodeHandle<T, A>) -> &mut T {
+        self._impl.insert_handle_multi(handle)
+    }
+    /// Finds an element equivalent to value
+    pub fn find<Q>(&self, value: &Q) -> Option<&T>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.find(value)
+    }
+    /// Checks if there is an element equivalent to value in the container
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.contains(value)
+    }
+    /// Returns the number of elements equivalent to value
+    pub fn count<Q>(&self, value: &Q) -> usize
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.count(value)
+    }
+    /// Iterates over all elements equivalent to value, in insertion order
+    pub fn equal_range<Q>(&self, value: &Q) -> ListIterator<'_, ListNode<T, A>, T, A>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          T: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.equal_range(value)
+    }
+    pub fn iter(&self) -> ListIterator<'_, ListNode<T, A>, T, A> { self._impl.iter() }
+}
+
+impl<'a, H, T, A, E> IntoIterator for &'a MultiSet<H, T, A, E>
+where H: HashParam<Function: HashFunction<T>>,
+      E: KeyEqual<T>,
+      A: Allocator + Clone
+{
+    type Item = &'a T;
+    type IntoIter = ListIterator<'a, ListNode<T, A>, T, A>;
+    fn into_iter(self) -> Self::IntoIter { self._impl.iter() }
+}
+
+// https://en.cppreference.com/w/cpp/container/unordered_multimap
+#[repr(C)]
+pub struct MultiMap<H, K, V, A = Global, E = EqualTo>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
+      A: Allocator + Clone
+{ _impl: HashTable<H, MapPair<K, V>, K, A, E> }
+
+// C++ API
+impl<K, V> MultiMap<FNV1A, K, V, Global, EqualTo>
+where K: PartialEq + Hash
+{
+    pub fn new() -> Self { Self::new_in(Global) }
+}
+
+impl<K, V> Default for MultiMap<FNV1A, K, V, Global, EqualTo>
+where K: PartialEq + Hash
+{
+    fn default() -> Self { Self::new() }
+}
+
+impl<H, K, V, A, E> MultiMap<H, K, V, A, E>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
+      A: Allocator + Clone
+{
+    /// Constructs the unordered_multimap
+    pub fn new_in(alloc: A) -> Self
+    where H::Function: Default,
+          E: Default
+    {
+        Self { _impl: HashTable::new_inner(alloc) }
+    }
+    /// Constructs the unordered_multimap using the given hash and key equality function objects
+    pub fn with_hasher_in(hasher: H::Function, key_equal: E, alloc: A) -> Self {
+        Self { _impl: HashTable::new_inner_with(hasher, key_equal, alloc) }
+    }
+    /// Checks if the container has no elements
+    pub fn empty(&self) -> bool { self._impl.is_empty() }
+    /// Returns the number of elements in the container
+    pub fn size(&self) -> usize { self._impl.len() }
+    /// Returns the number of buckets in the container
+    pub fn bucket_count(&self) -> usize { self._impl.bucket_count() }
+    /// Erases all elements from the container. After this call, size() returns zero.
+    pub fn clear(&mut self) { self._impl.clear() }
+    /// Inserts an element into the container. Elements with an equivalent key are kept together,
+    /// with the new element placed after them. Returns the inserted value.
+    pub fn insert(&mut self, key: K, value: V) -> &mut V {
+        self._impl.insert_multi(MapPair::new(key, value)).get_value_mut()
+    }
+    /// Removes all elements with a key equivalent to key, returning how many were removed
+    pub fn erase<Q>(&mut self, key: &Q) -> usize
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.erase_all(key)
+    }
+    /// Unlinks the last element with key equivalent to key, returning a handle that owns its node
+    pub fn extract<Q>(&mut self, key: &Q) -> Option<NodeHandle<MapPair<K, V>, A>>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.extract_key(key)
+    }
+    /// Links the handle's node into the map after any elements with an equivalent key
+    pub fn insert_node(&mut self, handle: NodeHandle<MapPair<K, V>, A>) -> &mut V {
+        self._impl.insert_handle_multi(handle).get_value_mut()
+    }
+    /// Finds an element with key equivalent to key
+    pub fn find<Q>(&self, key: &Q) -> Option<&MapPair<K, V>>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.find(key)
+    }
+    /// Checks if there is an element with key equivalent to key in the container
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.contains(key)
+    }
+    /// Returns the number of elements with key equivalent to key
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.count(key)
+    }
+    /// Iterates over all elements with key equivalent to key, in insertion order
+    pub fn equal_range<Q>(&self, key: &Q) -> PairIterator<'_, K, V, A>
+    where H: HashParam<Function: HashFunction<Q>>,
+          E: KeyEqual<Q>,
+          K: KeyBorrow<Q>,
+          Q: ?Sized
+    {
+        self._impl.equal_range(key)
+    }
+    pub fn iter(&self) -> PairIterator<'_, K, V, A> { self._impl.iter() }
+    pub fn iter_mut(&mut self) -> PairIteratorMut<'_, K, V, A> { self._impl.iter_mut() }
+}
+
+impl<'a, H, K, V, A, E> IntoIterator for &'a MultiMap<H, K, V, A, E>
+where H: HashParam<Function: HashFunction<K>>,
+      E: KeyEqual<K>,
+      A: Allocator + Clone
+{
+    type Item = &'a MapPair<K, V>;
+    type IntoIter = ListIterator<'a, ListNode<MapPair<K, V>, A>, MapPair<K, V>, A>;
+    fn into_iter(self) -> Self::IntoIter { self._impl.iter() }
+}
+
+
 #[cfg(test)]
 pub mod tests {
     use allocator_api2::alloc::{ Allocator, Global };
@@ -546,7 +1284,10 @@ pub mod tests {
         fmt::{ Debug, Display },
         hash::{ Hash, Hasher }
     };
-    use super::{ HasherInit, HashTable };
+    use crate::msvc::hash::{
+        hash_combine, CppHash, FnvAppend, HashFunction, HashParam, HasherFunction, HasherInit, StdHash, FNV_OFFSET_BASIS
+    };
+    use super::{ Entry, EqualTo, HashTable, KeyEqual, Map, MultiMap, MultiSet, NodeHandle, Set };
     use std::error::Error;
     type TestReturn = Result<(), Box<dyn Error>>;
 
@@ -563,9 +1304,10 @@ impl HashTable<FNV1A, String<u8, Global>, String<u8, Global>, Global>
         assert!(new_str == new, "Expected latest entry in bucket to be \"{}\", got {} instead", new, new_str);
     }
 
+    #[allow(clippy::unnecessary_mut_passed)]
     unsafe fn check_string_hash_table_existing(&mut self, item: &str, check_bucket: usize) {
         let cpp_str = String::from_str_in(item, Global);
-        match self.find_node_by_key(&cpp_str) {
+        match self.find_node(&cpp_str) {
             Some(v) => {
                 let mut node = self.get_bucket_node_last(check_bucket);
                 while let Some(n) = node {
@@ -579,6 +1321,7 @@ impl HashTable<FNV1A, String<u8, Global>, String<u8, Global>, Global>
     }
 }
 
+    #[allow(clippy::len_zero)]
     #[test]
     pub fn create_blank_hash_table() -> TestReturn {
         let new: HashTable<FNV1A, u32, u32, Global> = HashTable::new_inner(Global);
@@ -670,4 +1413,263 @@ impl HashTable<FNV1A, String<u8, Global>, String<u8, Global>, Global>
         assert!(door_str == "Door", "Couldn't find the hash table entry for Door");
         Ok(())
     }
+
+    #[test]
+    pub fn cpp_hash_map() -> TestReturn {
+        let mut map: Map<StdHash, u32, u32, Global> = Map::new_in(Global);
+        // std::hash<unsigned int>(0) & 7 == 5, std::hash<unsigned int>(8) & 7 == 5
+        assert!(0u32.cpp_hash() & 7 == 5 && 8u32.cpp_hash() & 7 == 5, "0 and 8 should share a bucket");
+        assert!(map.insert(0, 10), "0 should be inserted");
+        assert!(map.insert(8, 18), "8 should be inserted");
+        assert!(!map.insert(0, 20), "0 should not be inserted twice");
+        unsafe {
+            assert!(*map._impl.get_bucket_first(5).unwrap().get_key() == 8, "Newest entry should begin the bucket");
+            assert!(*map._impl.get_bucket_last(5).unwrap().get_key() == 0, "Oldest entry should end the bucket");
+        }
+        assert!(map.find(&0).unwrap().get_value() == &10, "Value for 0 should be 10");
+        assert!(!map.contains(&2), "Empty bucket should not contain 2");
+        map.erase(&0);
+        unsafe { assert!(*map._impl.get_bucket_last(5).unwrap().get_key() == 8, "Erasing the bucket end should move it back"); }
+        map.erase(&8);
+        assert!(map.empty(), "Map should be empty");
+        unsafe { assert!(map._impl.get_bucket_first(5).is_none(), "Bucket 5 should be empty"); }
+        for i in 0..9 { map.insert(i, i + 10); }
+        assert!(map.bucket_count() == 64, "Ninth insertion should grow the table to 64 buckets");
+        for i in 0..9 {
+            let bucket = i.cpp_hash() as usize & 63;
+            unsafe { assert!(map._impl.get_count_in_bucket(bucket) > 0, "Bucket {} for {} should not be empty", bucket, i); }
+            assert!(map[&i].get_value() == &(i + 10), "Value for {} should be {}", i, i + 10);
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn heterogeneous_lookup() -> TestReturn {
+        let mut fnv: Map<FNV1A, String, u32> = Map::new();
+        let mut std: Map<StdHash, String, u32> = Map::new_in(Global);
+        for (i, name) in ["Player", "Enemy0", "Enemy1", "Door", "Chest"].into_iter().enumerate() {
+            fnv.insert(String::from_str(name), i as u32);
+            std.insert(String::from_str(name), i as u32);
+        }
+        assert!(FNV1A::get_hash(&String::from_str("Player")) == HasherFunction::<FNV1A>::default().hash_value("Player"),
+            "&str should hash the same as String with FNV1A");
+        assert!(String::from_str("Player").cpp_hash() == "Player".cpp_hash(), "&str should hash the same as String with std::hash");
+        assert!(fnv.find("Player").unwrap().get_value() == &0, "Player should be found with a &str");
+        assert!(fnv.contains("Door") && !fnv.contains("Gimmick"), "Door should be in the map but not Gimmick");
+        assert!(std.find("Player").unwrap().get_value() == &0, "Player should be found with a &str");
+        assert!(std.contains("Door") && !std.contains("Gimmick"), "Door should be in the map but not Gimmick");
+        let mut set: Set<FNV1A, String> = Set::new();
+        set.insert(String::from_str("Chest"));
+        assert!(set.contains("Chest") && set.erase("Chest") && set.empty(), "Set should erase with a &str");
+        let mut wide: Map<StdHash, String<u16>, u32> = Map::new_in(Global);
+        wide.insert(String::from_str_wide("Player"), 1);
+        let key: Vec<u16> = "Player".encode_utf16().collect();
+        assert!(wide.find(key.as_slice()).unwrap().get_value() == &1, "Wide string key should be found with a &[u16]");
+        Ok(())
+    }
+
+    #[test]
+    pub fn remove_and_entry() -> TestReturn {
+        let mut map: Map<FNV1A, String, u32> = Map::new();
+        for (i, name) in ["Player", "Enemy0", "Enemy1", "Enemy2", "Enemy3", "Enemy4", "Chest", "Door"].into_iter().enumerate() {
+            map.insert(String::from_str(name), i as u32);
+        }
+        assert!(map.remove("Enemy3") == Some(4), "Removing Enemy3 should return its value");
+        assert!(map.remove("Enemy3").is_none() && !map.erase("Enemy3"), "Enemy3 should already be removed");
+        // Player shared bucket 0 with Enemy3
+        unsafe { assert!(<&str>::from(map._impl.get_bucket_first(0).unwrap().get_key()) == "Player", "Player should now begin bucket 0"); }
+        *map.entry("Player").and_modify(|v| *v += 10).or_insert(100) += 1;
+        assert!(map.find("Player").unwrap().get_value() == &11, "Existing entry should be modified");
+        assert!(*map.entry("Enemy3").or_insert(4) == 4, "Enemy3 should be reinserted through a vacant entry");
+        // Filling the table past its load factor rehashes inside the vacant entry insertion
+        *map.entry("Door2").or_default() += 5;
+        assert!(map.bucket_count() == 64, "Vacant entry insertion should grow the table to 64 buckets");
+        assert!(map.find("Door2").unwrap().get_value() == &5, "Vacant entry should be inserted");
+        match map.entry("Chest") {
+            Entry::Occupied(e) => assert!(e.remove() == 6, "Removing Chest entry should return its value"),
+            Entry::Vacant(_) => panic!("Chest should be occupied")
+        };
+        assert!(!map.contains("Chest") && map.size() == 8, "Chest should be removed through its entry");
+        for name in ["Player", "Enemy0", "Enemy1", "Enemy2", "Enemy3", "Enemy4", "Door", "Door2"] {
+            assert!(map.contains(name), "{} should still be in the map after rehashing", name);
+        }
+        Ok(())
+    }
+
+    // Seeded hasher, like a hash function object holding a per-process salt
+    struct SeededHash { seed: u64 }
+    impl HashParam for SeededHash { type Function = Self; }
+    impl HashFunction<u32> for SeededHash {
+        fn hash_value(&self, value: &u32) -> u64 { hash_combine(self.seed, value.cpp_hash()) }
+    }
+
+    // Case insensitive equality through a case folding table, paired with a folding hasher
+    struct FoldedHash { table: *const [u8; 256] }
+    struct FoldedEqual { table: *const [u8; 256] }
+    impl HashParam for FoldedHash { type Function = Self; }
+    impl HashFunction<String> for FoldedHash {
+        fn hash_value(&self, value: &String) -> u64 { self.hash_value(<&str>::from(value)) }
+    }
+    impl HashFunction<str> for FoldedHash {
+        fn hash_value(&self, value: &str) -> u64 {
+            let table = unsafe { &*self.table };
+            value.bytes().fold(FNV_OFFSET_BASIS, |v, c| table[c as usize].fnv1a_append(v))
+        }
+    }
+    impl KeyEqual<String> for FoldedEqual {
+        fn key_eq(&self, d0: &String, d1: &String) -> bool { self.key_eq(<&str>::from(d0), <&str>::from(d1)) }
+    }
+    impl KeyEqual<str> for FoldedEqual {
+        fn key_eq(&self, d0: &str, d1: &str) -> bool {
+            let table = unsafe { &*self.table };
+            d0.len() == d1.len() && d0.bytes().zip(d1.bytes()).all(|(a, b)| table[a as usize] == table[b as usize])
+        }
+    }
+
+    #[test]
+    pub fn stateful_hash_functions() -> TestReturn {
+        assert!(std::mem::size_of::<Map<StdHash, u32, u32>>() == 64, "Empty function objects should take no space");
+        let mut map: Map<SeededHash, u32, u32> = Map::with_hasher_in(SeededHash { seed: 0x1234 }, EqualTo, Global);
+        assert!(std::mem::size_of_val(&map) == 72, "Hasher should be stored before the max load factor");
+        let load_offset = &raw const map._impl._traits_obj.max_load_factor as usize - &raw const map as usize;
+        assert!(load_offset == 8, "Max load factor should be after the hasher instead of at {}", load_offset);
+        assert!(map.max_load_factor() == 1.0, "Max load factor should be 1.0");
+        for i in 0..20 { map.insert(i, i * 2); }
+        for i in 0..20 {
+            let bucket = hash_combine(0x1234, i.cpp_hash()) as usize & (map.bucket_count() - 1);
+            unsafe { assert!(map._impl.get_count_in_bucket(bucket) > 0, "{} should be hashed with the seed", i); }
+            assert!(map.find(&i).unwrap().get_value() == &(i * 2), "Value for {} should be {}", i, i * 2);
+        }
+
+        let mut table = [0u8; 256];
+        for (i, c) in table.iter_mut().enumerate() { *c = (i as u8).to_ascii_lowercase(); }
+        let mut names: Set<FoldedHash, String, Global, FoldedEqual> = Set::with_hasher_in(FoldedHash { table: &raw const table }, FoldedEqual { table: &raw const table }, Global);
+        assert!(names.insert(String::from_str("Player")), "Player should be inserted");
+        assert!(!names.insert(String::from_str("PLAYER")), "PLAYER should be a duplicate of Player");
+        assert!(names.contains("player") && names.find("pLaYeR").is_some(), "Lookups should use the stored functors");
+        assert!(names.erase("PLAYER") && names.empty(), "Erase should use the stored functors");
+        Ok(())
+    }
+
+    // Equal keys must be adjacent, and every bucket must be a contiguous range of the list
+    fn check_groups<I>(keys: I, bucket_of: impl Fn(u32) -> usize)
+    where I: Iterator<Item = u32>
+    {
+        let mut seen_keys = std::collections::HashSet::new();
+        let mut seen_buckets = std::collections::HashSet::new();
+        let mut last: Option<u32> = None;
+        for key in keys {
+            if last != Some(key) {
+                assert!(seen_keys.insert(key), "Elements with key {} should be adjacent", key);
+                let bucket = bucket_of(key);
+                if last.map(&bucket_of) != Some(bucket) {
+                    assert!(seen_buckets.insert(bucket), "Bucket {} should be contiguous", bucket);
+                }
+            }
+            last = Some(key);
+        }
+    }
+
+    #[test]
+    pub fn event_listener_registry() -> TestReturn {
+        let mut listeners = MultiMap::<StdHash, u32, u32>::new_in(Global);
+        for (event, callback) in [(1, 100), (2, 200), (1, 101), (3, 300), (1, 102), (2, 201)] {
+            assert!(*listeners.insert(event, callback) == callback, "Insert should return the new callback");
+        }
+        assert!(listeners.size() == 6, "Registry should have 6 listeners");
+        assert!(listeners.count(&1) == 3 && listeners.count(&2) == 2 && listeners.count(&4) == 0, "Counts should match");
+        let callbacks: Vec<u32> = listeners.equal_range(&1).map(|p| *p.get_value()).collect();
+        assert!(callbacks == [100, 101, 102], "Callbacks should be in registration order, got {:?}", callbacks);
+        assert!(listeners.find(&1).unwrap().get_value() == &102, "Find should return the last equivalent element");
+        assert!(listeners.equal_range(&4).next().is_none(), "Missing event should have an empty range");
+        let mask = listeners.bucket_count() - 1;
+        check_groups(listeners.iter().map(|p| *p.get_key()), |k| k.cpp_hash() as usize & mask);
+
+        assert!(listeners.erase(&1) == 3, "Erasing event 1 should remove 3 listeners");
+        assert!(listeners.erase(&1) == 0, "Event 1 should have no listeners left");
+        assert!(listeners.size() == 3 && !listeners.contains(&1), "Registry should have 3 listeners");
+        let callbacks: Vec<u32> = listeners.equal_range(&2).map(|p| *p.get_value()).collect();
+        assert!(callbacks == [200, 201], "Event 2 listeners should be untouched, got {:?}", callbacks);
+        Ok(())
+    }
+
+    #[test]
+    pub fn multi_containers_rehash() -> TestReturn {
+        let mut map = MultiMap::<StdHash, u32, u32>::new_in(Global);
+        for i in 0..200 { map.insert(i % 37, i); }
+        assert!(map.size() == 200 && map.bucket_count() >= 200, "Map should have grown past 200 buckets");
+        let mask = map.bucket_count() - 1;
+        check_groups(map.iter().map(|p| *p.get_key()), |k| k.cpp_hash() as usize & mask);
+        for k in 0..37 {
+            let values: Vec<u32> = map.equal_range(&k).map(|p| *p.get_value()).collect();
+            let expected: Vec<u32> = (0..200).filter(|i| i % 37 == k).collect();
+            assert!(values == expected, "Rehashing should keep key {} in insertion order, got {:?}", k, values);
+        }
+
+        // few buckets relative to the keys, so that distinct keys collide
+        let mut set = MultiSet::<FNV1A, u32>::new();
+        for i in 0..24 { set.insert(i % 5); }
+        assert!(set.size() == 24 && set.count(&0) == 5 && set.count(&4) == 4, "Set counts should match");
+        let mask = set.bucket_count() - 1;
+        check_groups(set.iter().copied(), |k| FNV1A::get_hash(&k) as usize & mask);
+        assert!(set.erase(&3) == 5 && set.find(&3).is_none(), "All 3s should be erased");
+        check_groups(set.iter().copied(), |k| FNV1A::get_hash(&k) as usize & mask);
+        assert!(set.equal_range(&2).count() == 5 && set.size() == 19, "Other elements should remain");
+        Ok(())
+    }
+
+    #[test]
+    pub fn node_handles() -> TestReturn {
+        let mut from = Map::<StdHash, u32, u32>::new_in(Global);
+        let mut to = Map::<StdHash, u32, u32>::new_in(Global);
+        for i in 0..40 { from.insert(i, i * 10); }
+        for i in [5, 10, 15] { to.insert(i, 0); }
+        let addresses: Vec<*const u32> = (0..40).map(|i| &raw const *from.find(&i).unwrap().get_value()).collect();
+
+        let mut handle = from.extract(&7).unwrap();
+        assert!(handle.key() == &7 && handle.mapped() == &70, "Handle should own the extracted pair");
+        assert!(!from.contains(&7) && from.size() == 39, "Extracted key should be removed");
+        *handle.key_mut() = 100;
+        *handle.mapped_mut() = 1000;
+        let value = to.insert_node(handle).ok().unwrap();
+        assert!(std::ptr::eq(value, addresses[7]), "Node should be linked without being reallocated");
+        assert!(to.find(&100).unwrap().get_value() == &1000 && to.size() == 4, "Handle should be hashed with its new key");
+
+        // a duplicate key gives the handle back
+        let handle = from.extract(&5).unwrap();
+        let handle = to.insert_node(handle).err().unwrap();
+        assert!(handle.key() == &5 && handle.mapped() == &50, "Duplicate key should return the handle");
+        assert!(from.insert_node(handle).is_ok() && from.contains(&5), "Handle should go back where it came from");
+
+        to.merge(&mut from);
+        assert!(to.size() == 40 && from.size() == 3, "Merge should move all non duplicate keys");
+        for i in [5, 10, 15] {
+            assert!(from.contains(&i) && to.find(&i).unwrap().get_value() == &0, "Duplicate key {} should be left in the source", i);
+        }
+        for i in (0..40).filter(|i| ![5, 7, 10, 15].contains(i)) {
+            let value = to.find(&i).unwrap().get_value();
+            assert!(std::ptr::eq(value, addresses[i as usize]) && *value == i * 10, "{} should be moved without reallocating", i);
+        }
+        let mask = to.bucket_count() - 1;
+        check_groups(to.iter().map(|p| *p.get_key()), |k| k.cpp_hash() as usize & mask);
+
+        // merging across hash functions rehashes each node with the destination's hasher
+        let mut fnv = Set::<FNV1A, u32>::new();
+        let mut std = Set::<StdHash, u32>::new_in(Global);
+        for i in 0..12 { std.insert(i); }
+        fnv.merge(&mut std);
+        assert!(std.empty() && (0..12).all(|i| fnv.contains(&i)), "All values should be moved");
+        let mut handle = fnv.extract(&3).unwrap();
+        *handle.value_mut() = 30;
+        assert!(handle.into_value() == 30 && fnv.size() == 11, "Handle should give up its value");
+
+        let mut multi = MultiMap::<StdHash, u32, u32>::new_in(Global);
+        multi.insert(1, 11);
+        let mut single = Map::<StdHash, u32, u32>::new_in(Global);
+        single.insert(1, 10);
+        let handle: NodeHandle<super::MapPair<u32, u32>> = single.extract(&1).unwrap();
+        multi.insert_node(handle);
+        assert!(multi.equal_range(&1).map(|p| *p.get_value()).eq([11, 10]), "Handle should go after equivalent keys");
+        Ok(())
+    }
 }
@@ -26,6 +26,7 @@ where A: Allocator
 }
 
 impl<T> Vector<T, Global> {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self { Self::new_in(Global) }
     pub fn from_vec(vec: Vec<T>) -> Self { Self::from_vec_in(vec, Global) }
 }
@@ -51,6 +52,7 @@ where A: Allocator
     unsafe fn get_nonnull(&self) -> NonNull<u8> {
         NonNull::new_unchecked(self.first as *mut u8)
     }
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         (self.last as usize - self.first as usize) / std::mem::size_of::<T>()
     }
@@ -109,12 +111,14 @@ where A: Allocator
     }
 
     pub fn as_slice(&self) -> &[T] {
+        if self.first.is_null() { return &[] }
         unsafe {std::slice::from_raw_parts(
             self.first, self.len() 
         )}
     }
 
     pub fn as_slice_mut(&mut self) -> &mut [T] {
+        if self.first.is_null() { return &mut [] }
         unsafe {std::slice::from_raw_parts_mut(
             self.first, self.len() 
         )}
@@ -306,6 +310,7 @@ where A: Allocator
 {
     type Item = &'a T;
     type IntoIter = Iter<'a, T>;
+    #[allow(clippy::into_iter_on_ref)]
     fn into_iter(self) -> Self::IntoIter {
         self.as_slice().into_iter()
     }
@@ -316,6 +321,7 @@ where A: Allocator
 {
     type Item = &'a mut T;
     type IntoIter = IterMut<'a, T>;
+    #[allow(clippy::into_iter_on_ref)]
     fn into_iter(self) -> Self::IntoIter {
         self.as_slice_mut().into_iter()
     }
@@ -426,6 +432,18 @@ where A: Allocator
     }
 }
 
+impl<T, A> AsRef<[T]> for Vector<T, A>
+where A: Allocator
+{
+    fn as_ref(&self) -> &[T] { self.as_slice() }
+}
+
+impl<T, A> AsMut<[T]> for Vector<T, A>
+where A: Allocator
+{
+    fn as_mut(&mut self) -> &mut [T] { self.as_slice_mut() }
+}
+
 impl<T, A> Display for Vector<T, A>
 where T: Display,
       A: Allocator
@@ -488,6 +506,7 @@ where T: Debug,
     }
 }
 
+    #[allow(clippy::partialeq_to_none)]
     #[test]
     pub fn create_vector() -> TestReturn {
         let mut v: Vector<u32> = Vector::new();
@@ -538,6 +557,7 @@ where T: Debug,
         Ok(())
     }
 
+    #[allow(clippy::partialeq_to_none)]
     #[test]
     pub fn list_find() -> TestReturn {
         let list = Vector::from_vec(vec![20, 30, 15, 5, 40, 25]);